@@ -23,6 +23,8 @@ fi
 
 # Build the WASM module
 echo "📦 Compiling Rust to WASM..."
+# Stamp the build with its commit so version_info() can report it
+export STELLARIS_GIT_COMMIT="$(git rev-parse --short HEAD 2>/dev/null || true)"
 wasm-pack build --target web --out-dir pkg --release
 
 echo ""
@@ -3,13 +3,44 @@
  * Handles pool communication and coordinates the WASM mining module
  */
 
+/** User input that ends idle time when the Idle Detection API isn't available */
+const IDLE_INPUT_EVENTS = ['pointermove', 'pointerdown', 'keydown', 'wheel', 'touchstart'];
+
 class StellarisMiner {
     constructor() {
         this.wasmModule = null;
+        this.logSink = null;
+        this.scheduling = 'continuous';
+        this.yieldInterval = 50000;
+        this.gpu = null;
+        this.concurrency = 'Single';
+        this.workers = [];
+        this.workJson = new Map();
+        this.workerStats = null;
+        this.tipWatcher = null;
+        this.session = null;
+        this.http = null;
+        this.shareBatcher = null;
+        this.shareBatchPath = null;
+        this.outbox = null;
+        this.bestHashes = null;
+        this.triedFilter = null;
+        this.shareSigner = null;
+        this.progressCommitments = null;
+        this.telemetry = null;
+        this.telemetryExporter = null;
+        this.telemetryTimer = null;
+        this.idleTimer = null;
+        this.idleAbort = null;
+        this.idleInput = null;
+        this.lastProgressCommitment = 0;
+        this.jobSegment = null;
+        this.hourSegment = null;
         this.mining = false;
         this.poolUrl = '';
         this.walletAddress = '';
         this.workerName = '';
+        this.userAgent = '';
         this.minerId = '';
         this.stats = {
             hashrate: 0,
@@ -19,54 +50,632 @@ class StellarisMiner {
             startTime: null,
             lastUpdate: null,
             currentBlock: null,
+            difficulty: null,
             workUnits: 0
         };
         this.hashrateSamples = [];
         this.maxSamples = 10;
+        this.connected = false;
+        this.maxReconnectDelay = 30000;
+        this.keepaliveInterval = 15000;
+        this.keepalivePath = '/api/ping';
+        this.maxMissedKeepalives = 2;
+        this.keepaliveTimer = null;
+        this.missedKeepalives = 0;
+        this.lastPoolContact = 0;
+        this.connectionLost = false;
+        this.difficultySuggestInterval = 5 * 60 * 1000;
+        this.lastDifficultySuggestion = 0;
+        this.loopPromise = null;
+        this.shutdownPromise = null;
     }
 
-    async init(wasmPath = './pkg/stellaris_wasm_miner.js') {
+    /**
+     * Load the WASM module and pick the fastest hashing backend for this device.
+     * options.backend: 'Scalar' | 'HasherClone' | 'Midstate' | 'Specialized' | 'Unrolled' to skip the benchmark.
+     * options.memoryLimit: cap on the module's heap in bytes, for memory-constrained devices.
+     * options.profiling: record performance.measure entries for the devtools Performance panel.
+     * options.shareBatch: { maxBatch = 1, maxPerSecond = 2, maxPending = 100, path } to rate-limit
+     *   share submissions; with `path`, each batch is posted there as one `{ shares: [...] }` request.
+     * options.triedFilter: a SharedArrayBuffer shared by every worker; ranges already searched by
+     *   any of them (after a restart or an overlapping assignment) are skipped. Nonces are tracked in
+     *   blocks of `yieldInterval`, so every miner sharing a buffer needs the same yieldInterval.
+     * options.logSink: callback(level, target, message) receiving miner and WASM logs instead of the console.
+     * options.progressCommitments: { path, intervalMs = 60000 } to periodically commit to the best
+     *   recent results; entries the pool lists in `reveal` are opened to `${path}/reveal`.
+     * options.background: { policy: 'Continue' | 'Throttle' | 'Pause' = 'Throttle', dutyCycle = 0.25 }
+     *   controls hashing while the tab is hidden.
+     * options.idle: { activeDutyCycle = 0.5, rampMs = 30000, thresholdMs = 60000, detect = true } to hash
+     *   at `activeDutyCycle` while the user is active and ramp to full speed over `rampMs` once idle. With
+     *   `detect`, idleness comes from the Idle Detection API if permission was granted, else from input on
+     *   the page; pass `detect: false` and call `setUserIdle()` to supply your own signal.
+     * options.thermalThrottle: false to keep full speed even when a sustained hashrate drop suggests
+     *   the device is overheating.
+     * options.yieldInterval: hashes between yields to the event loop (default 50000); lower keeps the
+     *   page snappier, higher mines faster. `MiningJob.mine_range_async` takes the same knob.
+     * options.scheduling: 'continuous' (default) or 'idle' to hash only in requestIdleCallback
+     *   deadlines, for pages where the miner must never compete with the site's own work.
+     * options.threadedWasmPath: a `parallel` build to load instead of `wasmPath` when the page is
+     *   cross-origin isolated; elsewhere the plain build is loaded and mining falls back to workers.
+     * options.workers: Web Workers to spread mining over when threads are unavailable (default 1, meaning
+     *   none); they load `worker.js` and talk to this coordinator with `WorkerMessage`s.
+     * options.workerEncoding: 'object' (default, structured clone) or 'msgpack' to exchange worker
+     *   messages as MessagePack bytes, as when relaying them through a broker.
+     * options.cbor: true to exchange pool messages as CBOR (needs a build with the `cbor` feature).
+     * options.hashrateWindows: { sampleIntervalMs = 10000, shortWindowMs = 60000, longWindowMs = 900000 }
+     *   for the instantaneous, rolling and long-run hashrates in `getStats().hashrateWindows`.
+     * options.maxJobAgeMs: abandon a job this long after it was received and fetch fresh work, bounding
+     *   stale shares when a new-block notification is missed. Jobs with a `ttl_ms` from the pool expire then.
+     * options.difficultyLimits: { min = 1, max = 64 }, the job difficulties accepted from the pool. Jobs
+     *   outside them fail with DifficultyOutOfRange instead of having every hash (or none) accepted.
+     * options.checkpointInterval: nonces searched between saved checkpoints of a job (default 100000). After
+     *   a crash or reload the job resumes from its checkpoint, re-searching at most this many nonces.
+     * options.bestHashes: keep the N lowest hashes of each job (up to 256) with their nonces, sent with
+     *   work proofs as `best_hashes` and shown in `getStats().bestHashes`. Applies to single-threaded CPU mining.
+     * options.outbox: { capacity = 64, maxAgeMs = 120000 } to hold shares and blocks whose submission
+     *   failed at the network level and send them once the pool is back, or false to lose them instead.
+     *   Held submissions are dropped once older than maxAgeMs or once the chain reaches their height.
+     * options.telemetry: { url, intervalMs = 60000, maxBatch = 10 } to post batches of stats snapshots
+     *   to a collector while mining, backing off and holding them while it is unreachable.
+     * options.gpu: 'webgl2' to hash in a fragment shader (experimental; needs webgl.js loaded). Jobs
+     *   whose acceptance rule a shader can't test, or a failure to set up WebGL2, fall back to the CPU.
+     */
+    async init(wasmPath = './pkg/stellaris_wasm_miner.js', options = {}) {
+        this.logSink = options.logSink || null;
+        this.scheduling = options.scheduling || 'continuous';
+        this.yieldInterval = options.yieldInterval || 50000;
+        this.workerEncoding = options.workerEncoding || 'object';
         try {
-            const wasm = await import(wasmPath);
+            // Shared-memory builds can't even instantiate on a page without cross-origin isolation
+            const threaded = !!options.threadedWasmPath && globalThis.crossOriginIsolated === true;
+            const wasm = await import(threaded ? options.threadedWasmPath : wasmPath);
             await wasm.default();
+            const selfTest = wasm.self_test_result();
+            if (!selfTest.passed) {
+                throw new Error(`WASM self-test failed: ${selfTest.failures.join('; ')}`);
+            }
             this.wasmModule = wasm;
-            console.log('✅ WASM module loaded successfully');
+            if (this.logSink) {
+                wasm.set_log_sink(this.logSink);
+            }
+            if (options.memoryLimit) {
+                wasm.set_memory_limit(options.memoryLimit);
+            }
+            wasm.set_profiling(!!options.profiling);
+            if (options.difficultyLimits) {
+                const limits = options.difficultyLimits;
+                wasm.set_difficulty_limits(limits.min ?? wasm.min_difficulty(), limits.max ?? wasm.max_difficulty());
+            }
+            this.session = new wasm.MinerSession();
+            const background = options.background || {};
+            this.session.set_background_policy(
+                wasm.BackgroundPolicy[background.policy || 'Throttle'],
+                background.dutyCycle
+            );
+            this.session.thermal_throttle = options.thermalThrottle !== false;
+            if (options.maxJobAgeMs) {
+                this.session.max_job_age_ms = options.maxJobAgeMs;
+            }
+            if (options.checkpointInterval) {
+                this.session.checkpoint_interval = options.checkpointInterval;
+            }
+            if (options.hashrateWindows) {
+                const windows = options.hashrateWindows;
+                this.session.configure_hashrate(
+                    windows.sampleIntervalMs || 10000,
+                    windows.shortWindowMs || 60000,
+                    windows.longWindowMs || 900000
+                );
+            }
+            this.watchVisibility();
+            if (options.idle) {
+                const idle = options.idle;
+                this.session.set_idle_ramp(true, idle.activeDutyCycle, idle.rampMs);
+                if (idle.detect !== false) {
+                    await this.watchIdle(idle.thresholdMs || 60000);
+                }
+            }
+            this.http = new wasm.HttpClient();
+            if (options.cbor) {
+                if ('cbor' in this.http) {
+                    this.http.cbor = true;
+                } else {
+                    this.log('warn', '⚠️ CBOR requested but this build lacks the cbor feature; using JSON');
+                }
+            }
+            const batching = options.shareBatch || {};
+            this.shareBatcher = new wasm.ShareBatcher(
+                batching.maxBatch || 1,
+                batching.maxPerSecond || 2,
+                batching.maxPending || 100
+            );
+            this.shareBatchPath = batching.path || null;
+            if (options.bestHashes) {
+                this.bestHashes = new wasm.BestHashes(options.bestHashes);
+            }
+            if (options.outbox !== false) {
+                const outbox = options.outbox || {};
+                this.outbox = new wasm.SubmissionQueue(outbox.capacity, outbox.maxAgeMs);
+            }
+            if (options.progressCommitments && options.progressCommitments.path) {
+                this.progressCommitments = { intervalMs: 60000, ...options.progressCommitments };
+            }
+            if (options.telemetry && options.telemetry.url) {
+                this.telemetry = { intervalMs: 60000, maxBatch: 10, ...options.telemetry };
+            }
+            if (options.triedFilter) {
+                // Blocks as large as a chunk, so a searched chunk's worth of nonces fills one
+                this.triedFilter = new wasm.TriedNonceFilter(options.triedFilter, 4, this.yieldInterval);
+            }
+            this.userAgent = this.userAgent || wasm.user_agent();
+            this.restoreBestShare();
+            this.restoreCheckpoint();
+            this.log('info', '✅ WASM module loaded successfully');
+
+            if (options.threadedWasmPath && !threaded) {
+                this.log('warn', '⚠️ Page is not cross-origin isolated, loading the single-threaded build');
+            }
+            this.concurrency = wasm.ConcurrencyMode[wasm.choose_concurrency_mode(options.workers || 1)];
+            if (this.concurrency === 'Threads') {
+                await wasm.start_thread_pool(navigator.hardwareConcurrency);
+            } else if (this.concurrency === 'Workers') {
+                this.startWorkers(wasmPath, options.workers);
+            }
+            this.log('info', `🧵 Concurrency: ${this.concurrency}`);
+
+            if (options.gpu === 'webgl2') {
+                this.initGpu();
+            }
+
+            const preferred = options.backend ? wasm.HashBackend[options.backend] : undefined;
+            const report = wasm.auto_select_backend(100, preferred);
+            this.log('info', `⚙️ Hash backend: ${report.selected}`);
             return true;
         } catch (error) {
-            console.error('❌ Failed to load WASM module:', error);
+            this.log('error', '❌ Failed to load WASM module:', error);
             throw error;
         }
     }
 
+    // Spawn the workers used in Workers mode, each loading the same build as this page
+    startWorkers(wasmPath, count) {
+        const wasmUrl = encodeURIComponent(new URL(wasmPath, location.href).href);
+        for (let i = 0; i < count; i++) {
+            this.workers.push(new Worker(`worker.js?wasm=${wasmUrl}`, { type: 'module', name: String(i) }));
+        }
+        this.workerStats = new this.wasmModule.WorkerStats(count);
+    }
+
+    // Search part of a job on every worker at once, combining their results into one
+    async mineOnWorkers(job, nonceStart, nonceEnd) {
+        const wasm = this.wasmModule;
+        const jobId = job.job_id;
+        const share = Math.ceil((nonceEnd - nonceStart) / this.workers.length);
+
+        const results = await Promise.all(this.workers.map((worker, i) => {
+            const start = nonceStart + i * share;
+            const end = Math.min(nonceEnd, start + share);
+            if (start >= end) {
+                return null;
+            }
+            return new Promise((resolve, reject) => {
+                const done = () => {
+                    worker.removeEventListener('message', onMessage);
+                    worker.removeEventListener('error', onError);
+                };
+                const onMessage = (event) => {
+                    done();
+                    try {
+                        resolve(wasm.worker_message_result(event.data));
+                    } catch (error) {
+                        this.workerStats.record_error(i, error.message || String(error));
+                        reject(error);
+                    }
+                };
+                const onError = (event) => {
+                    done();
+                    this.workerStats.record_error(i, event.message);
+                    reject(new Error(`Worker ${i} failed: ${event.message}`));
+                };
+                worker.addEventListener('message', onMessage);
+                worker.addEventListener('error', onError);
+                if (this.workJson.has(jobId)) {
+                    this.postToWorker(worker, { type: 'assign', work: this.workJson.get(jobId) });
+                }
+                this.postToWorker(worker, wasm.worker_range_message(jobId, start, end));
+            });
+        }));
+
+        // Every worker has the job now
+        this.workJson.delete(jobId);
+        const shareDifficulty = this.session.share_difficulty;
+        results.forEach((result, i) => {
+            if (result === null) return;
+            const share = shareDifficulty !== undefined &&
+                wasm.hash_meets_difficulty(result.best_hash, job.previous_hash, shareDifficulty);
+            this.workerStats.record_result(i, result, share);
+        });
+        return wasm.combine_worker_results(results.filter((result) => result !== null));
+    }
+
+    // Free queued shares and worker copies of every job the session expired
+    releaseExpiredJobs() {
+        for (const jobId of this.session.take_expired_jobs()) {
+            this.shareBatcher.discard_job(jobId);
+            this.releaseJob(jobId);
+        }
+    }
+
+    // Tell the workers a job is finished so they can free it
+    releaseJob(jobId) {
+        this.workJson.delete(jobId);
+        for (const worker of this.workers) {
+            this.postToWorker(worker, { type: 'cancel', job_id: jobId });
+        }
+    }
+
+    // Send a WorkerMessage as an object, or as transferred MessagePack bytes when configured
+    postToWorker(worker, message) {
+        if (this.workerEncoding === 'msgpack') {
+            const bytes = this.wasmModule.encode_worker_message(message);
+            worker.postMessage(bytes, [bytes.buffer]);
+        } else {
+            worker.postMessage(message);
+        }
+    }
+
+    // Set up the experimental WebGL2 hasher, staying on the CPU if it isn't usable here
+    initGpu() {
+        if (typeof WebGLHasher === 'undefined' || !WebGLHasher.isSupported()) {
+            this.log('warn', '⚠️ WebGL2 hashing unavailable, mining on the CPU');
+            return;
+        }
+        try {
+            this.gpu = new WebGLHasher();
+            const batch = this.gpu.batchSize.toLocaleString();
+            this.log('info', `🖥️ WebGL2 hashing enabled (${batch} nonces per batch)`);
+        } catch (error) {
+            this.log('warn', '⚠️ WebGL2 hashing failed to start, mining on the CPU:', error);
+        }
+    }
+
+    // Hash a chunk on the GPU; the WASM module re-checks everything the shader reports
+    mineGpuChunk(job, nonceStart, nonceEnd) {
+        const started = performance.now();
+        const { candidates, bestNonce } = this.gpu.hashBatch(nonceStart, nonceEnd - nonceStart);
+        return job.confirm_kernel_batch(
+            nonceStart,
+            nonceEnd,
+            Uint32Array.from(candidates),
+            bestNonce,
+            performance.now() - started
+        );
+    }
+
+    // Keep the session told whether the tab is hidden, so background mining follows its policy
+    watchVisibility() {
+        if (typeof document === 'undefined') return;
+
+        const update = () => {
+            const hidden = document.visibilityState === 'hidden';
+            if (this.session.page_hidden !== hidden) {
+                this.log('info', hidden
+                    ? '🌙 Tab hidden, applying background policy'
+                    : '☀️ Tab visible, mining at full speed');
+            }
+            this.session.page_hidden = hidden;
+        };
+        document.addEventListener('visibilitychange', update);
+        update();
+    }
+
+    // Feed the idle ramp from the Idle Detection API when already permitted, else from input on this page
+    async watchIdle(thresholdMs) {
+        if (typeof document === 'undefined') return;
+        this.unwatchIdle();
+
+        if (typeof IdleDetector !== 'undefined') {
+            try {
+                const permission = await navigator.permissions.query({ name: 'idle-detection' });
+                if (permission.state === 'granted') {
+                    const detector = new IdleDetector();
+                    detector.addEventListener('change', () => {
+                        this.setUserIdle(detector.userState === 'idle' || detector.screenState === 'locked');
+                    });
+                    this.idleAbort = new AbortController();
+                    // The API refuses thresholds under a minute
+                    await detector.start({ threshold: Math.max(60000, thresholdMs), signal: this.idleAbort.signal });
+                    return;
+                }
+            } catch (error) {
+                this.log('warn', '⚠️ Idle Detection API unavailable, watching page input instead:', error);
+            }
+        }
+
+        let lastInput = Date.now();
+        const onInput = () => {
+            lastInput = Date.now();
+            this.setUserIdle(false);
+        };
+        // Kept so unwatchIdle can remove exactly these listeners
+        this.idleInput = onInput;
+        for (const type of IDLE_INPUT_EVENTS) {
+            document.addEventListener(type, onInput, { passive: true });
+        }
+        this.idleTimer = setInterval(() => {
+            if (Date.now() - lastInput >= thresholdMs) this.setUserIdle(true);
+        }, Math.min(thresholdMs, 5000));
+    }
+
+    unwatchIdle() {
+        if (this.idleAbort) {
+            this.idleAbort.abort();
+            this.idleAbort = null;
+        }
+        if (this.idleTimer) {
+            clearInterval(this.idleTimer);
+            this.idleTimer = null;
+        }
+        if (this.idleInput) {
+            for (const type of IDLE_INPUT_EVENTS) {
+                document.removeEventListener(type, this.idleInput, { passive: true });
+            }
+            this.idleInput = null;
+        }
+    }
+
+    /**
+     * Report whether the user is idle, for embedders with their own activity signal.
+     * Only affects hashing when `options.idle` enabled the idle ramp.
+     */
+    setUserIdle(idle) {
+        if (!this.session || this.session.user_idle === idle) return;
+        this.session.user_idle = idle;
+        if (this.session.idle_ramp) {
+            this.log('info', idle ? '💤 User idle, ramping up to full speed' : '🖐️ User active, throttling');
+        }
+    }
+
+    // Rest after a chunk so hashing keeps to the session's duty cycle; wait out a pause entirely
+    async restAfter(busyMs) {
+        while (this.mining && this.session.paused) {
+            await this.sleep(500);
+        }
+        await this.sleep(Math.max(1, this.session.rest_ms(busyMs)));
+    }
+
+    // Resolve with an idle deadline; where requestIdleCallback is missing (Safari, workers), a short timer stands in
+    waitForIdle() {
+        return new Promise(resolve => {
+            if (typeof requestIdleCallback === 'function') {
+                requestIdleCallback(resolve, { timeout: 1000 });
+            } else {
+                setTimeout(() => resolve({ didTimeout: false, timeRemaining: () => 10 }), 50);
+            }
+        });
+    }
+
+    restoreBestShare() {
+        const saved = localStorage.getItem('bestShare');
+        if (!saved) return;
+
+        try {
+            this.session.restore_best_share(this.wasmModule.BestShare.from_json(saved));
+        } catch (error) {
+            this.log('error', '⚠️ Ignoring corrupt best share record:', error);
+            localStorage.removeItem('bestShare');
+        }
+    }
+
+    // Record a searched range, saving the job's checkpoint whenever the session takes a new one
+    markSearched(job, start, end) {
+        if (this.session.mark_searched(job.job_id, start, end)) {
+            const saved = { jobId: job.job_id, nonce: this.session.checkpoint(job.job_id) };
+            localStorage.setItem('nonceCheckpoint', JSON.stringify(saved));
+        }
+    }
+
+    restoreCheckpoint() {
+        const saved = localStorage.getItem('nonceCheckpoint');
+        if (!saved) return;
+
+        try {
+            const { jobId, nonce } = JSON.parse(saved);
+            this.session.restore_checkpoint(jobId, nonce);
+        } catch (error) {
+            this.log('error', '⚠️ Ignoring corrupt nonce checkpoint:', error);
+            localStorage.removeItem('nonceCheckpoint');
+        }
+    }
+
+    // Log to the configured sink as one line, or to the console with details intact
+    log(level, message, ...details) {
+        const describe = () => [message, ...details.map(detail =>
+            detail instanceof Error ? detail.message : typeof detail === 'string' ? detail : JSON.stringify(detail)
+        )].join(' ');
+
+        // Errors also go to the session's event log, which survives without devtools open
+        if (level === 'error' && this.session) {
+            this.session.log_event(this.wasmModule.EventKind.Error, describe());
+        }
+
+        if (!this.logSink) {
+            console[level](message, ...details);
+            return;
+        }
+
+        const text = describe();
+        try {
+            this.logSink(level, 'miner', text);
+        } catch (error) {
+            // A broken sink must not take the miner down with it
+        }
+    }
+
     generateWorkerName() {
         return `wasm-${Math.random().toString(36).substr(2, 8)}`;
     }
 
-    async register() {
+    // All pool calls go through the WASM HttpClient for timeouts and retries. Only
+    // `idempotent` calls are retried after a timeout; a submit fails once and goes
+    // to the outbox rather than risk reaching the pool twice
+    async postPool(path, body, { idempotent = false } = {}) {
+        // Tag every request so pool dashboards can identify this worker
+        const tagged = {
+            ...body,
+            worker_name: this.workerName,
+            user_agent: this.userAgent
+        };
+        const sentAt = Date.now();
+        const result = await this.http.post_json(`${this.poolUrl}${path}`, JSON.stringify(tagged), idempotent);
+        this.lastPoolContact = Date.now();
+
+        // Track clock skew against the pool so header timestamps stay inside its window
+        if (result && typeof result.server_time === 'number') {
+            this.session.observe_pool_time(result.server_time, sentAt, this.lastPoolContact);
+        }
+
+        // Pools may push a new share difficulty on any response, as a number or decimal string
+        if (result && typeof result.share_difficulty === 'number') {
+            this.applyShareDifficulty(result.share_difficulty);
+        } else if (result && typeof result.share_difficulty === 'string') {
+            this.applyShareDifficulty(this.wasmModule.parse_difficulty(result.share_difficulty));
+        }
+        return result;
+    }
+
+    applyShareDifficulty(difficulty) {
+        if (this.session.share_difficulty !== difficulty) {
+            this.log('info', `🎚️ Share difficulty set to ${difficulty}`);
+        }
+        this.session.share_difficulty = difficulty;
+    }
+
+    // Ask the pool for a share difficulty matching our measured hashrate
+    async suggestDifficulty() {
+        if (this.stats.hashrate <= 0) return;
+
+        const difficulty = this.session.suggest_difficulty(this.stats.hashrate);
+        this.lastDifficultySuggestion = Date.now();
+
         try {
-            const response = await fetch(`${this.poolUrl}/api/register`, {
-                method: 'POST',
-                headers: { 'Content-Type': 'application/json' },
-                body: JSON.stringify({
-                    miner_id: this.minerId,
-                    wallet_address: this.walletAddress,
-                    worker_name: this.workerName
-                })
+            await this.postPool('/api/suggest_difficulty', {
+                miner_id: this.minerId,
+                difficulty
             });
+        } catch (error) {
+            this.log('error', '⚠️ Difficulty suggestion failed:', error);
+        }
+    }
+
+    // Commit to recent best results and open whichever entries the pool spot-checks
+    async submitProgressCommitment() {
+        const { path } = this.progressCommitments;
+        this.lastProgressCommitment = Date.now();
+        const commitment = this.session.commit_progress();
+        if (commitment.leaf_count === 0) return;
+
+        try {
+            const response = await this.postPool(path, { miner_id: this.minerId, ...commitment });
+            if (response && Array.isArray(response.reveal) && response.reveal.length > 0) {
+                await this.postPool(`${path}/reveal`, {
+                    miner_id: this.minerId,
+                    sequence: commitment.sequence,
+                    openings: response.reveal.map(index => this.session.open_progress(index))
+                });
+            }
+        } catch (error) {
+            this.log('error', '⚠️ Progress commitment failed:', error);
+        }
+    }
 
-            const result = await response.json();
+    startKeepalive() {
+        this.stopKeepalive();
+        this.missedKeepalives = 0;
+        this.keepaliveTimer = setInterval(() => this.keepalive(), this.keepaliveInterval);
+    }
+
+    stopKeepalive() {
+        if (this.keepaliveTimer) {
+            clearInterval(this.keepaliveTimer);
+            this.keepaliveTimer = null;
+        }
+    }
+
+    // Snapshot stats every interval and post whatever the exporter has ready; it handles backoff
+    startTelemetry() {
+        this.stopTelemetry();
+        if (!this.telemetry) return;
+
+        const { url, intervalMs, maxBatch } = this.telemetry;
+        if (!this.telemetryExporter) {
+            this.telemetryExporter = new this.wasmModule.TelemetryExporter(url, this.workerName, intervalMs, maxBatch);
+        }
+        this.telemetryTimer = setInterval(() => {
+            this.telemetryExporter.record(this.session);
+            if (this.telemetryExporter.due) {
+                this.telemetryExporter.flush();
+            }
+        }, intervalMs);
+    }
+
+    stopTelemetry() {
+        if (this.telemetryTimer) {
+            clearInterval(this.telemetryTimer);
+            this.telemetryTimer = null;
+        }
+    }
+
+    // Ping the pool if it has been quiet; flag the connection dead after repeated misses
+    async keepalive() {
+        if (!this.connected || Date.now() - this.lastPoolContact < this.keepaliveInterval) {
+            return;
+        }
+
+        try {
+            await this.http.ping(
+                `${this.poolUrl}${this.keepalivePath}`,
+                JSON.stringify({ miner_id: this.minerId })
+            );
+            this.lastPoolContact = Date.now();
+            this.missedKeepalives = 0;
+        } catch (error) {
+            this.missedKeepalives++;
+            this.log('warn', `⚠️ Pool keepalive missed (${this.missedKeepalives}/${this.maxMissedKeepalives})`);
+            if (this.missedKeepalives >= this.maxMissedKeepalives) {
+                this.connectionLost = true;
+            }
+        }
+    }
+
+    async register() {
+        try {
+            const result = await this.postPool('/api/register', {
+                miner_id: this.minerId,
+                wallet_address: this.walletAddress,
+                miner_build: this.wasmModule.version_info()
+            });
             
             if (result.success) {
-                console.log(`✅ Registered with pool: ${this.minerId}`);
+                this.connected = true;
+                // Pools with authenticated endpoints hand out a session token on registration
+                if (result.token) {
+                    this.http.set_bearer_token(result.token);
+                }
+                // Pools that want signed shares issue an HMAC session key; receipts are made in WASM
+                if (result.share_key) {
+                    this.shareSigner = new this.wasmModule.ShareSigner(result.share_key, this.minerId);
+                }
+                this.log('info', `✅ Registered with pool: ${this.minerId}`);
                 this.updateStatus('Registered with pool');
                 return true;
             } else {
-                console.error('❌ Registration failed:', result);
+                this.log('error', '❌ Registration failed:', result);
                 this.updateStatus('Registration failed: ' + (result.error || 'Unknown error'));
                 return false;
             }
         } catch (error) {
-            console.error('❌ Registration error:', error);
+            this.log('error', '❌ Registration error:', error);
             this.updateStatus('Registration error: ' + error.message);
             return false;
         }
@@ -74,67 +683,232 @@ class StellarisMiner {
 
     async getWork() {
         try {
-            const response = await fetch(`${this.poolUrl}/api/work`, {
-                method: 'POST',
-                headers: { 'Content-Type': 'application/json' },
-                body: JSON.stringify({ miner_id: this.minerId })
-            });
-
-            return await response.json();
+            return await this.postPool('/api/work', { miner_id: this.minerId }, { idempotent: true });
         } catch (error) {
-            console.error('❌ Error getting work:', error);
+            this.log('error', '❌ Error getting work:', error);
             return null;
         }
     }
 
+    // Receipt fields for a share, when the pool issued a session key
+    signShare(blockHeight, nonce, blockHash) {
+        return this.shareSigner ? { receipt: this.shareSigner.sign(blockHeight, nonce, blockHash) } : {};
+    }
+
     async submitShare(blockHeight, nonce, blockContentHex, blockHash, isValidBlock = false) {
         try {
-            const response = await fetch(`${this.poolUrl}/api/share`, {
-                method: 'POST',
-                headers: { 'Content-Type': 'application/json' },
-                body: JSON.stringify({
-                    miner_id: this.minerId,
-                    block_height: blockHeight,
-                    nonce: nonce,
-                    block_content_hex: blockContentHex,
-                    block_hash: blockHash,
-                    is_valid_block: isValidBlock
-                })
+            return await this.postPool('/api/share', {
+                miner_id: this.minerId,
+                block_height: blockHeight,
+                nonce: nonce,
+                block_content_hex: blockContentHex,
+                block_hash: blockHash,
+                is_valid_block: isValidBlock,
+                ...this.signShare(blockHeight, nonce, blockHash)
             });
-
-            return await response.json();
         } catch (error) {
-            console.error('❌ Error submitting share:', error);
+            this.log('error', '❌ Error submitting share:', error);
             return null;
         }
     }
 
-    async submitWorkProof(blockHeight, nonceStart, nonceEnd, bestNonce, bestHash, hashesComputed) {
-        try {
-            const response = await fetch(`${this.poolUrl}/api/work_proof`, {
-                method: 'POST',
-                headers: { 'Content-Type': 'application/json' },
-                body: JSON.stringify({
+    // Submit whatever the share batcher releases; `force` ignores the rate limit
+    async flushShares(force = false) {
+        if (!this.shareBatcher) return;
+
+        const batch = force ? this.shareBatcher.drain() : this.shareBatcher.take_batch();
+        if (batch.length === 0) return;
+
+        if (this.shareBatchPath) {
+            let response = null;
+            try {
+                response = await this.postPool(this.shareBatchPath, {
                     miner_id: this.minerId,
-                    block_height: blockHeight,
-                    nonce_start: nonceStart,
-                    nonce_end: nonceEnd,
-                    best_nonce: bestNonce,
-                    best_hash: bestHash,
-                    hashes_computed: hashesComputed
-                })
+                    shares: batch.map(share => ({
+                        ...share,
+                        ...this.signShare(share.block_height, share.nonce, share.block_hash)
+                    }))
+                });
+            } catch (error) {
+                this.log('error', '❌ Error submitting share batch:', error);
+            }
+            // Pools may answer per share in `results`, or once for the whole batch
+            for (let i = 0; i < batch.length; i++) {
+                const shareResponse = response && Array.isArray(response.results) ? response.results[i] : response;
+                if (response === null) {
+                    this.holdSubmission(batch[i].job_id, batch[i].block_height, batch[i].nonce,
+                        batch[i].block_content_hex, batch[i].block_hash, false);
+                }
+                await this.recordShareResponse(batch[i].job_id, batch[i].nonce, batch[i].block_hash, shareResponse);
+            }
+            return;
+        }
+
+        for (const share of batch) {
+            const response = await this.submitShare(
+                share.block_height,
+                share.nonce,
+                share.block_content_hex,
+                share.block_hash,
+                false
+            );
+            if (response === null) {
+                this.holdSubmission(share.job_id, share.block_height, share.nonce,
+                    share.block_content_hex, share.block_hash, false);
+            }
+            await this.recordShareResponse(share.job_id, share.nonce, share.block_hash, response);
+        }
+    }
+
+    // Keep a submission that never reached the pool, to send once it is back
+    holdSubmission(jobId, blockHeight, nonce, blockContentHex, blockHash, isValidBlock) {
+        if (!this.outbox) return;
+        if (this.outbox.hold(jobId, BigInt(blockHeight), nonce, blockHash, blockContentHex, isValidBlock)) {
+            const what = isValidBlock ? 'block' : 'share';
+            this.log('warn', `⚠️ Could not send ${what} ${nonce}, holding it until the pool is back`);
+            this.session.log_event(this.wasmModule.EventKind.Outbox, `Held ${what} ${nonce} on job ${jobId}`);
+            this.emitEvent('miner-submission-queued', { jobId, blockHeight: Number(blockHeight), nonce, isValidBlock });
+        }
+        this.reportDroppedSubmissions();
+    }
+
+    // Latest chain height known to the miner: the watched tip, or just below the job being mined
+    knownTipHeight() {
+        let tip = this.session.tip_height;
+        const job = this.session.current_job;
+        if (job && job.height > 0n && (tip === undefined || job.height - 1n > tip)) {
+            tip = job.height - 1n;
+        }
+        return tip;
+    }
+
+    // Send held submissions, oldest block first, stopping at the first that still can't be sent
+    async flushOutbox() {
+        if (!this.outbox) return;
+
+        let held;
+        while (this.mining && (held = this.outbox.next(this.knownTipHeight())) !== undefined) {
+            const response = await this.submitShare(
+                held.block_height,
+                held.nonce,
+                held.block_content_hex,
+                held.block_hash,
+                held.is_valid_block
+            );
+            if (response === null) break;
+
+            this.outbox.remove(held.job_id, held.nonce);
+            const what = held.is_valid_block ? 'block' : 'share';
+            this.log('info', `📤 Sent held ${what} ${held.nonce} after ${Math.round(held.age_ms / 1000)}s`);
+            this.session.log_event(
+                this.wasmModule.EventKind.Outbox,
+                `Sent held ${what} ${held.nonce} on job ${held.job_id}`
+            );
+            this.emitEvent('miner-submission-flushed', {
+                jobId: held.job_id, blockHeight: held.block_height, nonce: held.nonce,
+                isValidBlock: held.is_valid_block, ageMs: held.age_ms
             });
+            await this.recordShareResponse(held.job_id, held.nonce, held.block_hash, response);
+            if (held.is_valid_block && response.block_found) {
+                this.stats.blocksFound++;
+            }
+        }
+        this.reportDroppedSubmissions();
+    }
 
-            return await response.json();
+    reportDroppedSubmissions() {
+        for (const dropped of this.outbox.take_dropped()) {
+            const what = dropped.is_valid_block ? 'block' : 'share';
+            this.log('warn', `⚠️ Dropped held ${what} ${dropped.nonce} (${dropped.reason})`);
+            this.session.log_event(
+                this.wasmModule.EventKind.Outbox,
+                `Dropped held ${what} ${dropped.nonce} on job ${dropped.job_id}: ${dropped.reason}`
+            );
+            this.emitEvent('miner-submission-dropped', {
+                jobId: dropped.job_id, blockHeight: dropped.block_height, nonce: dropped.nonce,
+                isValidBlock: dropped.is_valid_block, reason: dropped.reason
+            });
+        }
+    }
+
+    async submitWorkProof(blockHeight, nonceStart, nonceEnd, bestNonce, bestHash, hashesComputed) {
+        try {
+            return await this.postPool('/api/work_proof', {
+                miner_id: this.minerId,
+                block_height: blockHeight,
+                nonce_start: nonceStart,
+                nonce_end: nonceEnd,
+                best_nonce: bestNonce,
+                best_hash: bestHash,
+                hashes_computed: hashesComputed,
+                ...(this.bestHashes && this.bestHashes.length > 0 ? { best_hashes: this.bestHashes.to_array() } : {})
+            });
         } catch (error) {
-            console.error('❌ Error submitting work proof:', error);
+            this.log('error', '❌ Error submitting work proof:', error);
             return null;
         }
     }
 
-    async startMining(poolUrl, walletAddress, workerName = null) {
+    /**
+     * Configure pool credentials.
+     * options: { token, apiKey, apiKeyHeader = 'X-API-Key', refreshToken: async () => newToken }
+     */
+    configureAuth(options = {}) {
+        if (options.token) {
+            this.http.set_bearer_token(options.token);
+        } else if (options.apiKey) {
+            this.http.set_api_key(options.apiKeyHeader || 'X-API-Key', options.apiKey);
+        } else {
+            this.http.clear_auth();
+        }
+
+        this.http.set_reauth_callback(options.refreshToken || null);
+    }
+
+    /**
+     * Confirmed balance of the mining address (or `address`) from a Stellaris node.
+     * Resolves with { address, balance }; rejects with the usual error codes.
+     */
+    async getBalance(nodeUrl, address = this.walletAddress) {
+        const result = await this.wasmModule.get_balance(nodeUrl, address);
+        return { address: result.address, balance: result.balance };
+    }
+
+    /**
+     * Poll a Stellaris node for new blocks. Each new tip is recorded on the session and
+     * emitted as `miner-tip` with { hash, height, stale }; `stale` means the current job
+     * builds on an old block, so a solo miner should fetch a fresh template. Replaces any
+     * earlier watcher.
+     */
+    watchTip(nodeUrl, intervalMs = 10000) {
+        this.unwatchTip();
+        this.tipWatcher = this.wasmModule.watch_tip(nodeUrl, intervalMs, (hash, height) => {
+            const stale = this.session.observe_tip(hash, BigInt(height));
+            if (stale) {
+                this.log('info', `🧱 New block ${height} at the tip; current job is stale`);
+            }
+            this.emitEvent('miner-tip', { hash, height, stale });
+        });
+    }
+
+    unwatchTip() {
+        if (this.tipWatcher) {
+            this.tipWatcher.stop();
+            this.tipWatcher = null;
+        }
+    }
+
+    /**
+     * Override the user agent reported to the pool (defaults to the crate name and version).
+     * Call after init().
+     */
+    setUserAgent(userAgent) {
+        this.userAgent = userAgent || this.wasmModule.user_agent();
+    }
+
+    async startMining(poolUrl, walletAddress, workerName = null, auth = null) {
         if (this.mining) {
-            console.log('⚠️ Mining already in progress');
+            this.log('warn', '⚠️ Mining already in progress');
             return;
         }
 
@@ -146,6 +920,9 @@ class StellarisMiner {
         this.walletAddress = walletAddress;
         this.workerName = workerName || this.generateWorkerName();
         this.minerId = `${walletAddress.substring(0, 12)}_${this.workerName}`;
+        if (auth) {
+            this.configureAuth(auth);
+        }
         this.mining = true;
         this.stats.startTime = Date.now();
         this.stats.totalHashes = 0;
@@ -153,10 +930,11 @@ class StellarisMiner {
         this.stats.blocksFound = 0;
         this.stats.workUnits = 0;
 
-        console.log(`🚀 Starting mining`);
-        console.log(`   Pool: ${this.poolUrl}`);
-        console.log(`   Wallet: ${this.walletAddress}`);
-        console.log(`   Worker: ${this.workerName}`);
+        this.log('info', `🚀 Starting mining`);
+        this.log('info', `   Pool: ${this.poolUrl}`);
+        this.log('info', `   Wallet: ${this.walletAddress}`);
+        this.log('info', `   Worker: ${this.workerName}`);
+        this.log('info', `   Agent: ${this.userAgent}`);
 
         // Register with pool
         if (!await this.register()) {
@@ -165,69 +943,184 @@ class StellarisMiner {
         }
 
         this.updateStatus('Mining...');
-        this.mineLoop();
+        this.startKeepalive();
+        this.startTelemetry();
+        this.loopPromise = this.mineLoop();
+    }
+
+    /**
+     * Queue a pool work assignment. Clean jobs (new block) preempt current work
+     * at the next chunk boundary; others wait until the current range is done.
+     * The header is decoded here, so malformed work throws before it is queued.
+     */
+    pushJob(work, clean = true) {
+        const json = JSON.stringify(work);
+        const job = this.wasmModule.MiningJob.from_pool_work(json);
+        if (this.workers.length > 0) {
+            this.workJson.set(job.job_id, json);
+        }
+        this.session.prefetch_job(job, clean);
     }
 
     stopMining() {
         this.mining = false;
+        this.stopKeepalive();
+        this.stopTelemetry();
+        if (this.session) {
+            this.session.clear_jobs();
+        }
         this.updateStatus('Stopped');
-        console.log('⚠️ Mining stopped');
+        this.log('warn', '⚠️ Mining stopped');
+    }
+
+    /**
+     * Stop mining for good, e.g. from a `pagehide` handler. Hashing stops at the
+     * next chunk boundary, share submissions already in flight are awaited, and
+     * a final stats snapshot is emitted as `miner-shutdown`. Resolves with the
+     * snapshot once the mining loop has fully exited; repeated calls share it.
+     */
+    shutdown() {
+        if (!this.shutdownPromise) {
+            this.shutdownPromise = (async () => {
+                if (!this.session) return null;
+
+                this.session.shutdown();
+                this.stopMining();
+                if (this.loopPromise) {
+                    await this.loopPromise;
+                }
+                await this.flushShares(true);
+                this.unwatchTip();
+                this.unwatchIdle();
+                if (this.gpu) {
+                    this.gpu.dispose();
+                    this.gpu = null;
+                }
+                for (const worker of this.workers) {
+                    worker.terminate();
+                }
+                this.workers = [];
+
+                const snapshot = this.session.stats_snapshot();
+                this.emitEvent('miner-shutdown', { snapshot });
+                this.log('info', '👋 Miner shut down');
+                return snapshot;
+            })();
+        }
+        return this.shutdownPromise;
     }
 
     async mineLoop() {
-        const chunkSize = 50000; // Process 50k hashes at a time to keep UI responsive
+        const chunkSize = this.yieldInterval; // Hashes between yields to keep the UI responsive
 
         while (this.mining) {
             try {
-                // Get work from pool
-                const work = await this.getWork();
-                
-                if (!work || !work.block_height) {
-                    console.log('⚠️ No work available, waiting...');
-                    this.updateStatus('No work available, waiting...');
-                    await this.sleep(5000);
-                    continue;
+                // Send anything held from an earlier network failure first
+                if (this.connected) {
+                    await this.flushOutbox();
+                }
+
+                // Take the next queued job that hasn't expired, or ask the pool for one
+                this.session.expire_jobs();
+                this.releaseExpiredJobs();
+                let job = this.session.current_job;
+                if (!job) {
+                    const work = await this.getWork();
+
+                    // getWork only returns null when the pool couldn't be reached
+                    if (work === null) {
+                        await this.reconnect();
+                        continue;
+                    }
+                    
+                    if (!work.block_height) {
+                        this.log('warn', '⚠️ No work available, waiting...');
+                        this.updateStatus('No work available, waiting...');
+                        await this.sleep(5000);
+                        continue;
+                    }
+
+                    this.pushJob(work);
+                    job = this.session.current_job;
                 }
 
-                const {
-                    block_height,
-                    difficulty,
-                    previous_hash,
-                    merkle_root,
-                    timestamp,
-                    nonce_start,
-                    nonce_end,
-                    pool_address
-                } = work;
+                const block_height = Number(job.height);
+                const { difficulty, nonce_start, nonce_end } = job;
 
                 this.stats.currentBlock = block_height;
-                console.log(`⛏️ Mining block #${block_height}, difficulty ${difficulty}`);
-                console.log(`   Nonce range: ${nonce_start.toLocaleString()} - ${nonce_end.toLocaleString()}`);
+                this.stats.difficulty = difficulty;
+                this.log('info', `⛏️ Mining block #${block_height}, difficulty ${difficulty}`);
+                this.log('info', `   Nonce range: ${nonce_start.toLocaleString()} - ${nonce_end.toLocaleString()}`);
                 
                 this.updateStatus(`Mining block #${block_height}`);
+                this.updateSegments(job.job_id);
+                if (this.bestHashes && this.bestHashes.job_id !== job.job_id) {
+                    this.bestHashes.clear();
+                }
+
+                // Use the shader for this job if its acceptance rule is one the shader can test
+                let onGpu = false;
+                if (this.gpu) {
+                    try {
+                        this.gpu.setLayout(job.kernel_layout());
+                        onGpu = true;
+                    } catch (error) {
+                        this.log('warn', '⚠️ Job not supported on WebGL2, mining it on the CPU:', error);
+                    }
+                }
 
-                // Mine the range in chunks
+                // Mine the range in chunks, resuming past what an earlier run already searched
                 let currentNonce = nonce_start;
+                const checkpoint = this.session.checkpoint(job.job_id);
+                if (checkpoint !== undefined && checkpoint > nonce_start && checkpoint < nonce_end) {
+                    currentNonce = checkpoint;
+                    this.log('info', `   Resuming from checkpoint at nonce ${checkpoint.toLocaleString()}`);
+                }
+                // Start of the run searched in this pass; the filter only records whole blocks, and
+                // chunks needn't line up with them
+                const searchedFrom = currentNonce;
                 let totalHashes = 0;
                 let bestNonce = nonce_start;
                 let bestHash = 'f'.repeat(64);
+                let preempted = false;
                 const startTime = Date.now();
 
-                while (currentNonce < nonce_end && this.mining) {
-                    const chunkEnd = Math.min(currentNonce + chunkSize, nonce_end);
+                while (currentNonce < nonce_end && this.mining && !this.connectionLost) {
+                    // In idle mode, size each chunk to fit the browser's idle deadline
+                    const parallelism = this.workers.length || 1;
+                    let budget = onGpu ? this.gpu.batchSize : chunkSize * parallelism;
+                    if (this.scheduling === 'idle' && !onGpu) {
+                        const deadline = await this.waitForIdle();
+                        budget = Math.min(budget, this.session.hashes_within(deadline.timeRemaining()) * parallelism);
+                    }
+                    const chunkEnd = Math.min(currentNonce + budget, nonce_end);
                     
                     try {
-                        // Mine chunk using WASM
-                        const result = this.wasmModule.mine_range(
-                            previous_hash,
-                            pool_address,
-                            merkle_root,
-                            timestamp,
-                            difficulty,
-                            currentNonce,
-                            chunkEnd,
-                            chunkSize
-                        );
+                        // Another worker (or an earlier run) already searched this chunk
+                        if (this.triedFilter && this.triedFilter.is_range_tried(job.job_id, currentNonce, chunkEnd)) {
+                            this.markSearched(job, currentNonce, chunkEnd);
+                            currentNonce = chunkEnd;
+                            continue;
+                        }
+
+                        // Mine chunk using WASM, or the shader with WASM confirming its finds
+                        let result;
+                        if (onGpu) {
+                            result = this.mineGpuChunk(job, currentNonce, chunkEnd);
+                        } else if (this.workers.length > 0) {
+                            result = await this.mineOnWorkers(job, currentNonce, chunkEnd);
+                        } else if (this.bestHashes) {
+                            result = job.mine_range_ranked(currentNonce, chunkEnd, budget, this.bestHashes);
+                        } else {
+                            result = job.mine_range(currentNonce, chunkEnd, budget);
+                        }
+                        if (this.triedFilter && !result.found) {
+                            // Earlier chunks recorded every block before the one this chunk started in
+                            const blockStart = currentNonce - currentNonce % this.triedFilter.block_size;
+                            const markFrom = Math.max(searchedFrom, blockStart);
+                            this.triedFilter.mark_range(job.job_id, markFrom, result.next_nonce);
+                        }
+                        this.markSearched(job, currentNonce, result.next_nonce);
 
                         totalHashes += result.hashes_computed;
                         this.stats.totalHashes += result.hashes_computed;
@@ -238,21 +1131,25 @@ class StellarisMiner {
                             bestNonce = result.best_nonce;
                         }
 
+                        // Persist all-time best share across jobs and reloads
+                        if (this.session.record_result(job.job_id, result)) {
+                            localStorage.setItem('bestShare', this.session.best_share.to_json());
+                        }
+
                         // Check if block found
                         if (result.found) {
-                            console.log('🎉🎉🎉 VALID BLOCK FOUND! 🎉🎉🎉');
-                            console.log(`   Nonce: ${result.nonce.toLocaleString()}`);
-                            console.log(`   Hash: ${result.hash}`);
+                            this.log('info', '🎉🎉🎉 VALID BLOCK FOUND! 🎉🎉🎉');
+                            this.log('info', `   Nonce: ${result.nonce.toLocaleString()}`);
+                            this.log('info', `   Hash: ${result.hash}`);
                             
+                            // Never resubmit a block the pool already has, e.g. after a reconnect
+                            if (!this.shareBatcher.mark_submitted(job.job_id, result.nonce)) {
+                                this.log('warn', `⚠️ Block nonce ${result.nonce} was already submitted, skipping`);
+                                break;
+                            }
+
                             // Build block content
-                            const blockContentHex = this.wasmModule.build_block_content(
-                                previous_hash,
-                                pool_address,
-                                merkle_root,
-                                timestamp,
-                                difficulty,
-                                result.nonce
-                            );
+                            const blockContentHex = job.build_block_content(result.nonce);
 
                             // Submit block
                             const response = await this.submitShare(
@@ -262,6 +1159,12 @@ class StellarisMiner {
                                 result.hash,
                                 true
                             );
+                            if (response === null) {
+                                this.holdSubmission(
+                                    job.job_id, job.height, result.nonce, blockContentHex, result.hash, true
+                                );
+                            }
+                            await this.recordShareResponse(job.job_id, result.nonce, result.hash, response);
 
                             if (response && response.block_found) {
                                 this.stats.blocksFound++;
@@ -271,6 +1174,21 @@ class StellarisMiner {
                             break; // Move to next work
                         }
 
+                        // Queue intermediate shares once the pool has set a share difficulty
+                        const shareDifficulty = this.session.share_difficulty;
+                        if (shareDifficulty !== undefined &&
+                            this.wasmModule.hash_meets_difficulty(result.best_hash, job.previous_hash, shareDifficulty)) {
+                            // Refused if this nonce was already queued or submitted
+                            this.shareBatcher.push(
+                                job.job_id,
+                                job.height,
+                                result.best_nonce,
+                                result.best_hash,
+                                job.build_block_content(result.best_nonce)
+                            );
+                        }
+                        await this.flushShares();
+
                         // Update hashrate
                         const elapsed = (Date.now() - startTime) / 1000;
                         if (elapsed > 0) {
@@ -278,17 +1196,50 @@ class StellarisMiner {
                             this.updateHashrate(hashrate);
                         }
 
-                        currentNonce = chunkEnd;
+                        currentNonce = result.next_nonce;
+
+                        // A clean job (new block) makes the rest of this range worthless;
+                        // in threaded builds a network worker may have published one via publish_job
+                        this.session.adopt_published_job();
+                        if (this.session.take_preempted()) {
+                            this.log('info', '🔄 New job received, abandoning current range');
+                            this.shareBatcher.discard_job(job.job_id);
+                            if (this.outbox) this.outbox.discard_job(job.job_id);
+                            this.releaseJob(job.job_id);
+                            preempted = true;
+                            break;
+                        }
+
+                        // Past its TTL or the maximum job age; likely a missed new-block notification
+                        if (this.session.expire_jobs()) {
+                            this.log('warn', `⌛ Job ${job.job_id} expired, fetching fresh work`);
+                            // Held blocks stay in the outbox: a missed notification doesn't mean
+                            // the chain moved, and the outbox drops them itself once the tip does
+                            this.releaseExpiredJobs();
+                            preempted = true;
+                            break;
+                        }
 
-                        // Small delay to keep UI responsive
-                        await this.sleep(1);
+                        // Yield to keep the UI responsive, longer when throttled in the background
+                        await this.restAfter(result.elapsed_ms);
 
                     } catch (error) {
-                        console.error('❌ Mining error:', error);
+                        this.log('error', '❌ Mining error:', error);
                         break;
                     }
                 }
 
+                // The session's current job is already the replacement
+                if (preempted) {
+                    continue;
+                }
+
+                // Keepalives detected a dead connection mid-job
+                if (this.connectionLost) {
+                    await this.reconnect();
+                    continue;
+                }
+
                 // Submit work proof if we didn't find a block
                 if (this.mining && currentNonce >= nonce_end) {
                     const response = await this.submitWorkProof(
@@ -299,24 +1250,66 @@ class StellarisMiner {
                         bestHash,
                         totalHashes
                     );
+                    await this.recordShareResponse(job.job_id, bestNonce, bestHash, response);
 
                     if (response && response.success) {
                         this.stats.sharesSubmitted++;
                         const workUnits = response.work_units || 0;
                         this.stats.workUnits += workUnits;
-                        console.log(`✅ Work proof accepted (${workUnits} work units this round)`);
+                        this.log('info', `✅ Work proof accepted (${workUnits} work units this round)`);
                         this.updateStatus(`Work accepted - ${this.stats.sharesSubmitted} shares submitted`);
+                    } else if (response === null) {
+                        await this.reconnect();
                     }
                 }
 
+                if (this.mining && Date.now() - this.lastDifficultySuggestion > this.difficultySuggestInterval) {
+                    await this.suggestDifficulty();
+                }
+
+                if (this.mining && this.progressCommitments &&
+                    Date.now() - this.lastProgressCommitment > this.progressCommitments.intervalMs) {
+                    await this.submitProgressCommitment();
+                }
+
+                // Done with this job; queued work (if any) goes next
+                this.releaseJob(job.job_id);
+                this.session.advance_job();
+
             } catch (error) {
-                console.error('❌ Mining loop error:', error);
+                this.log('error', '❌ Mining loop error:', error);
                 this.updateStatus('Error: ' + error.message);
                 await this.sleep(5000);
             }
         }
     }
 
+    // Log the pool's verdict in the session ledger and react to rejections
+    async recordShareResponse(jobId, nonce, hash, response) {
+        if (!response) return; // Never reached the pool; nothing to audit
+
+        const { RejectAction } = this.wasmModule;
+        const action = this.session.record_pool_response(
+            jobId, nonce, hash, JSON.stringify(response)
+        );
+
+        switch (action) {
+            case RejectAction.RefreshJob:
+                // The loop fetches new work after every submission, so just note it
+                this.log('warn', '⚠️ Share was stale, fetching fresh work');
+                break;
+            case RejectAction.Reauthenticate:
+                this.log('warn', '⚠️ Pool rejected credentials, re-registering');
+                await this.register();
+                break;
+            case RejectAction.Halt:
+                this.log('error', '❌ Pool rejected the block header, stopping:', response);
+                this.stopMining();
+                this.updateStatus('Stopped: pool rejected header (' + (response.error || 'bad header') + ')');
+                break;
+        }
+    }
+
     updateHashrate(hashrate) {
         this.hashrateSamples.push(hashrate);
         if (this.hashrateSamples.length > this.maxSamples) {
@@ -329,6 +1322,45 @@ class StellarisMiner {
         this.stats.lastUpdate = Date.now();
     }
 
+    /**
+     * Re-register with the pool after a dropped connection, backing off between attempts.
+     * The caller resumes its loop afterwards, which fetches a fresh job.
+     */
+    async reconnect() {
+        this.connected = false;
+        this.session.log_event(this.wasmModule.EventKind.Disconnected, 'Lost connection to pool');
+        this.emitEvent('miner-disconnected', {});
+        this.log('warn', '⚠️ Lost connection to pool, reconnecting...');
+
+        let attempt = 0;
+        while (this.mining) {
+            const delay = Math.min(this.maxReconnectDelay, 1000 * 2 ** attempt);
+            this.updateStatus(`Disconnected, retrying in ${Math.round(delay / 1000)}s...`);
+            await this.sleep(delay);
+            attempt++;
+
+            if (!this.mining) break;
+
+            if (await this.register()) {
+                this.connectionLost = false;
+                this.missedKeepalives = 0;
+                this.log('info', `✅ Reconnected to pool after ${attempt} attempt(s)`);
+                this.session.log_event(this.wasmModule.EventKind.Reconnected, `Reconnected after ${attempt} attempt(s)`);
+                this.emitEvent('miner-reconnected', { attempts: attempt });
+                await this.flushOutbox();
+                return true;
+            }
+        }
+
+        return false;
+    }
+
+    emitEvent(name, detail) {
+        document.dispatchEvent(new CustomEvent(name, {
+            detail: { ...detail, stats: this.getStats() }
+        }));
+    }
+
     updateStatus(message) {
         const event = new CustomEvent('miner-status', { 
             detail: { message, stats: this.getStats() }
@@ -336,15 +1368,88 @@ class StellarisMiner {
         document.dispatchEvent(event);
     }
 
+    // Keep one stats segment open for the current job and one for the current UTC hour
+    updateSegments(jobId) {
+        const jobSegment = `job:${jobId}`;
+        if (jobSegment !== this.jobSegment) {
+            if (this.jobSegment) this.session.end_segment(this.jobSegment);
+            this.session.start_segment(jobSegment);
+            this.jobSegment = jobSegment;
+        }
+
+        const hourSegment = `hour:${new Date().toISOString().slice(0, 13)}`;
+        if (hourSegment !== this.hourSegment) {
+            if (this.hourSegment) this.session.end_segment(this.hourSegment);
+            this.session.start_segment(hourSegment);
+            this.hourSegment = hourSegment;
+        }
+    }
+
+    /**
+     * Recent session events (job changes, shares, errors, reconnects, ...) as
+     * [{ timestamp_ms, kind, message }], oldest first, e.g. to attach to a support request.
+     */
+    recentEvents(limit = 100, kind = undefined) {
+        if (!this.session) return [];
+        return this.session.recent_events(limit, kind === undefined ? undefined : this.wasmModule.EventKind[kind]);
+    }
+
+    // Session history as a CSV string: 'Shares' (submissions and verdicts), 'Segments' (per job/hour) or 'Events'
+    exportCsv(kind = 'Shares') {
+        return this.session ? this.session.export_csv(this.wasmModule.CsvKind[kind]) : '';
+    }
+
+    // Zero the displayed and session-wide stats without stopping; segments keep running
+    resetStats() {
+        if (this.session) this.session.reset_stats();
+        this.stats = {
+            ...this.stats,
+            hashrate: 0,
+            totalHashes: 0,
+            sharesSubmitted: 0,
+            blocksFound: 0,
+            workUnits: 0,
+            startTime: this.mining ? Date.now() : null
+        };
+        this.hashrateSamples = [];
+    }
+
     getStats() {
         const uptime = this.stats.startTime ? 
             Math.floor((Date.now() - this.stats.startTime) / 1000) : 0;
         
+        const bestShare = this.session && this.session.best_share;
+
+        // Expected time to a block at the network difficulty, from the session's rolling hashrate
+        const etaSeconds = this.session && this.stats.difficulty !== null
+            ? this.session.eta_seconds(this.stats.difficulty)
+            : null;
+
         return {
             ...this.stats,
+            bestShare: bestShare ? bestShare.hash : null,
+            bestShareHeight: bestShare && bestShare.height ? Number(bestShare.height) : null,
+            tipHeight: this.session && this.session.tip_height !== undefined ? Number(this.session.tip_height) : null,
+            acceptedShares: this.session ? this.session.accepted_shares : 0,
+            rejectedShares: this.session ? this.session.rejected_shares : 0,
+            staleShares: this.session ? this.session.stale_shares : 0,
             uptime,
             hashrate: Math.round(this.stats.hashrate),
-            mining: this.mining
+            hashrateWindows: this.session ? this.session.hashrate_windows() : null,
+            etaSeconds: Number.isFinite(etaSeconds) ? etaSeconds : null,
+            luck: this.session ? this.session.luck() : null,
+            memory: this.wasmModule ? this.wasmModule.memory_usage() : null,
+            difficultyHistogram: this.session ? Array.from(this.session.difficulty_histogram()) : [],
+            segments: this.session ? this.session.segments() : [],
+            dutyCycle: this.session ? this.session.duty_cycle : 1,
+            idleLevel: this.session ? this.session.idle_level : 1,
+            throttleLevel: this.session ? this.session.throttle_level : 1,
+            concurrency: this.concurrency,
+            workers: this.workerStats ? this.workerStats.report() : null,
+            heldSubmissions: this.outbox ? this.outbox.len : 0,
+            bestHashes: this.bestHashes ? this.bestHashes.to_array() : null,
+            mining: this.mining,
+            connected: this.connected
         };
     }
 
@@ -0,0 +1,110 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::error::{self, ErrorCode};
+
+use crate::params::{
+    ChainParams, COMPRESSED_ADDRESS_LEN, COMPRESSED_ADDRESS_PREFIXES, SEC1_UNCOMPRESSED_ADDRESS_LEN,
+    UNCOMPRESSED_ADDRESS_LEN,
+};
+
+/// Text encoding an address was given in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum AddressEncoding {
+    Hex,
+    Base58,
+}
+
+/// Public-key form implied by the decoded length and first byte
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum AddressKind {
+    /// 33 bytes starting 0x2a/0x2b (Stellaris) or 0x02/0x03 (SEC1)
+    Compressed,
+    /// 64 bytes, x || y
+    Uncompressed,
+    /// 65 bytes starting 0x04
+    Sec1Uncompressed,
+    Unknown,
+}
+
+/// Decode an address, trying hex first and then base58, as the header builder does
+pub fn decode_address(address: &str) -> Result<(Vec<u8>, AddressEncoding), String> {
+    if let Ok(bytes) = hex::decode(address) {
+        return Ok((bytes, AddressEncoding::Hex));
+    }
+
+    bs58::decode(address)
+        .into_vec()
+        .map(|bytes| (bytes, AddressEncoding::Base58))
+        .map_err(|_| "Invalid address format".to_string())
+}
+
+fn address_kind(bytes: &[u8]) -> AddressKind {
+    match (bytes.len(), bytes.first()) {
+        (COMPRESSED_ADDRESS_LEN, Some(first)) if COMPRESSED_ADDRESS_PREFIXES.contains(first) => AddressKind::Compressed,
+        (UNCOMPRESSED_ADDRESS_LEN, _) => AddressKind::Uncompressed,
+        (SEC1_UNCOMPRESSED_ADDRESS_LEN, Some(0x04)) => AddressKind::Sec1Uncompressed,
+        _ => AddressKind::Unknown,
+    }
+}
+
+#[derive(Serialize)]
+struct AddressInfo {
+    encoding: AddressEncoding,
+    byte_length: usize,
+    kind: AddressKind,
+    compressed: bool,
+    /// Decoded bytes re-encoded as lowercase hex
+    hex: String,
+    /// Decoded bytes re-encoded as base58
+    base58: String,
+    /// Bytes that go into the header under the given params, as hex; absent if rejected
+    header_hex: Option<String>,
+    /// Why the address would be rejected, if it would be
+    error: Option<String>,
+}
+
+/// Describe how an address is interpreted: `{ encoding, byte_length, kind,
+/// compressed, hex, base58, header_hex, error }`
+///
+/// `header_hex` is what ends up in the header under `params` (the default
+/// params if omitted); when the address would be rejected it is absent and
+/// `error` says why. Throws only if the string decodes as neither hex nor
+/// base58.
+#[wasm_bindgen]
+pub fn address_info(address: &str, params: Option<ChainParams>) -> Result<JsValue, JsValue> {
+    let (bytes, encoding) = decode_address(address).map_err(|e| error::js_error(ErrorCode::InvalidAddress, e))?;
+    let kind = address_kind(&bytes);
+
+    let (header_hex, error) = match params.unwrap_or_default().normalize_address(bytes.clone()) {
+        Ok(header_bytes) => (Some(hex::encode(header_bytes)), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    let info = AddressInfo {
+        encoding,
+        byte_length: bytes.len(),
+        kind,
+        compressed: kind == AddressKind::Compressed,
+        hex: hex::encode(&bytes),
+        base58: bs58::encode(&bytes).into_string(),
+        header_hex,
+        error,
+    };
+
+    serde_wasm_bindgen::to_value(&info).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+}
+
+/// Re-encode an address (hex or base58) as lowercase hex
+#[wasm_bindgen]
+pub fn address_to_hex(address: &str) -> Result<String, JsValue> {
+    let (bytes, _encoding) = decode_address(address).map_err(|e| error::js_error(ErrorCode::InvalidAddress, e))?;
+    Ok(hex::encode(bytes))
+}
+
+/// Re-encode an address (hex or base58) as base58
+#[wasm_bindgen]
+pub fn address_to_base58(address: &str) -> Result<String, JsValue> {
+    let (bytes, _encoding) = decode_address(address).map_err(|e| error::js_error(ErrorCode::InvalidAddress, e))?;
+    Ok(bs58::encode(bytes).into_string())
+}
@@ -0,0 +1,213 @@
+use std::cell::Cell;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use wasm_bindgen::prelude::*;
+
+use crate::clock;
+use crate::error::{self, ErrorCode};
+use crate::logging::{self, LogLevel};
+use crate::params::MAX_HEADER_LEN;
+use crate::sha::{self, HeaderHasher, LayoutHasher, H0};
+use crate::unrolled;
+
+/// Header size used for benchmarking (compressed address, u32 timestamp)
+const BENCH_PREFIX_LEN: usize = 1 + 32 + 33 + 32 + 4 + 2;
+
+/// Midstate tail capacity: under a block of prefix, the nonce, and padding span at most two blocks
+const MIDSTATE_TAIL_LEN: usize = 128;
+
+/// Strategy used to hash `prefix || nonce` in the mining loop
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HashBackend {
+    /// Copy the prefix and hash the full header for every nonce
+    Scalar,
+    /// Absorb the prefix once and clone the hasher state for every nonce
+    HasherClone,
+    /// Compress the prefix's full blocks once and only run the tail blocks per nonce
+    Midstate,
+    /// Midstate with a word-level SHA-256 specialized for fixed-size headers (see `sha`)
+    Specialized,
+    /// Specialized padding with a fully unrolled compression function (see `unrolled`)
+    Unrolled,
+}
+
+pub const ALL_BACKENDS: [HashBackend; 5] = [
+    HashBackend::Scalar,
+    HashBackend::HasherClone,
+    HashBackend::Midstate,
+    HashBackend::Specialized,
+    HashBackend::Unrolled,
+];
+
+thread_local! {
+    static ACTIVE_BACKEND: Cell<HashBackend> = const { Cell::new(HashBackend::Specialized) };
+}
+
+/// Backend used by `mine_range`
+#[wasm_bindgen]
+pub fn hash_backend() -> HashBackend {
+    ACTIVE_BACKEND.with(|b| b.get())
+}
+
+/// Force a backend, bypassing auto-selection
+#[wasm_bindgen]
+pub fn set_hash_backend(backend: HashBackend) {
+    ACTIVE_BACKEND.with(|b| b.set(backend));
+}
+
+fn compress_blocks(state: &mut [u32; 8], blocks: &[[u8; 64]]) {
+    for block in blocks {
+        sha2::compress256(state, &[(*block).into()]);
+    }
+}
+
+/// A hasher primed with a header prefix, producing digests for individual nonces
+///
+/// Every variant keeps its buffers inline, so hashing a nonce never touches the heap.
+pub enum PreparedHasher {
+    Scalar { header: [u8; MAX_HEADER_LEN], len: usize },
+    HasherClone { hasher: Sha256 },
+    Midstate { state: [u32; 8], tail: [u8; MIDSTATE_TAIL_LEN], tail_len: usize, nonce_offset: usize },
+    Specialized { hasher: LayoutHasher },
+    Unrolled { hasher: HeaderHasher },
+}
+
+impl PreparedHasher {
+    /// Prime a hasher with `prefix`, which `ChainParams` bounds to `MAX_HEADER_LEN` with the nonce
+    pub fn new(backend: HashBackend, prefix: &[u8]) -> PreparedHasher {
+        let len = prefix.len() + 4;
+        assert!(len <= MAX_HEADER_LEN, "{}-byte header exceeds the {}-byte layout maximum", len, MAX_HEADER_LEN);
+
+        match backend {
+            HashBackend::Scalar => {
+                let mut header = [0u8; MAX_HEADER_LEN];
+                header[..prefix.len()].copy_from_slice(prefix);
+                PreparedHasher::Scalar { header, len }
+            }
+            HashBackend::HasherClone => {
+                let mut hasher = Sha256::new();
+                hasher.update(prefix);
+                PreparedHasher::HasherClone { hasher }
+            }
+            HashBackend::Midstate => {
+                let (blocks, remainder) = prefix.as_chunks::<64>();
+                let mut state = H0;
+                compress_blocks(&mut state, blocks);
+
+                // Remaining prefix bytes, a nonce placeholder, then standard SHA-256 padding
+                let mut tail = [0u8; MIDSTATE_TAIL_LEN];
+                let nonce_offset = remainder.len();
+                tail[..nonce_offset].copy_from_slice(remainder);
+                tail[nonce_offset + 4] = 0x80;
+                let tail_len = (nonce_offset + 4 + 1 + 8).div_ceil(64) * 64;
+                tail[tail_len - 8..tail_len].copy_from_slice(&((len as u64) * 8).to_be_bytes());
+
+                PreparedHasher::Midstate { state, tail, tail_len, nonce_offset }
+            }
+            HashBackend::Specialized => PreparedHasher::Specialized {
+                hasher: LayoutHasher::new(prefix),
+            },
+            HashBackend::Unrolled => PreparedHasher::Unrolled {
+                hasher: HeaderHasher::new(prefix),
+            },
+        }
+    }
+
+    /// SHA-256 of `prefix || nonce.to_le_bytes()`
+    pub fn hash(&mut self, nonce: u32) -> [u8; 32] {
+        match self {
+            PreparedHasher::Scalar { header, len } => {
+                header[*len - 4..*len].copy_from_slice(&nonce.to_le_bytes());
+                sha::sha256(&header[..*len])
+            }
+            PreparedHasher::HasherClone { hasher } => {
+                let mut hasher = hasher.clone();
+                hasher.update(nonce.to_le_bytes());
+                hasher.finalize().into()
+            }
+            PreparedHasher::Midstate { state, tail, tail_len, nonce_offset } => {
+                tail[*nonce_offset..*nonce_offset + 4].copy_from_slice(&nonce.to_le_bytes());
+                let mut state = *state;
+                compress_blocks(&mut state, tail[..*tail_len].as_chunks::<64>().0);
+
+                let mut digest = [0u8; 32];
+                for (out, word) in digest.chunks_exact_mut(4).zip(state) {
+                    out.copy_from_slice(&word.to_be_bytes());
+                }
+                digest
+            }
+            PreparedHasher::Specialized { hasher } => hasher.hash(nonce),
+            PreparedHasher::Unrolled { hasher } => hasher.hash_with(nonce, unrolled::compress),
+        }
+    }
+}
+
+/// Measured throughput of one backend
+#[derive(Serialize)]
+struct BackendRate {
+    backend: HashBackend,
+    hashes_per_second: f64,
+}
+
+#[derive(Serialize)]
+struct BenchmarkReport {
+    selected: HashBackend,
+    rates: Vec<BackendRate>,
+}
+
+/// Hash for roughly `budget_ms` with `backend` and return its hashrate
+fn measure(backend: HashBackend, budget_ms: f64) -> f64 {
+    let mut hasher = PreparedHasher::new(backend, &[0u8; BENCH_PREFIX_LEN]);
+    let started = clock::monotonic_ms();
+    let mut hashes = 0u32;
+
+    // Check the clock in small batches so timer cost doesn't dominate
+    loop {
+        for _ in 0..256 {
+            std::hint::black_box(hasher.hash(hashes));
+            hashes = hashes.wrapping_add(1);
+        }
+        let elapsed = clock::monotonic_ms() - started;
+        if elapsed >= budget_ms {
+            return hashes as f64 * 1000.0 / elapsed;
+        }
+    }
+}
+
+/// Benchmark every backend for about `budget_ms` in total and activate the fastest
+///
+/// When `preferred` is given it is activated without benchmarking, so a
+/// config override always wins. Returns `{ selected, rates: [{ backend, hashes_per_second }] }`.
+#[wasm_bindgen]
+pub fn auto_select_backend(budget_ms: f64, preferred: Option<HashBackend>) -> Result<JsValue, JsValue> {
+    let report = match preferred {
+        Some(backend) => BenchmarkReport {
+            selected: backend,
+            rates: Vec::new(),
+        },
+        None => {
+            let per_backend = budget_ms / ALL_BACKENDS.len() as f64;
+            let rates: Vec<BackendRate> = ALL_BACKENDS
+                .iter()
+                .map(|&backend| BackendRate {
+                    backend,
+                    hashes_per_second: measure(backend, per_backend),
+                })
+                .collect();
+
+            let selected = rates
+                .iter()
+                .max_by(|a, b| a.hashes_per_second.total_cmp(&b.hashes_per_second))
+                .map(|rate| rate.backend)
+                .unwrap_or(HashBackend::Specialized);
+
+            BenchmarkReport { selected, rates }
+        }
+    };
+
+    set_hash_backend(report.selected);
+    logging::emit(LogLevel::Info, "backend", &format!("Selected the {:?} hash backend", report.selected));
+    serde_wasm_bindgen::to_value(&report).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+}
@@ -0,0 +1,162 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::clock;
+use crate::error::{self, ErrorCode};
+use crate::policy::{Acceptance, DifficultyPolicy};
+use crate::profile;
+use crate::{record_scan, run_scan, StopReason};
+
+/// One hash that met the requested difficulty
+#[derive(Clone, Debug, Serialize)]
+pub struct FoundHash {
+    nonce: u32,
+    hash: String,
+    /// Whether the hash also meets the job's block difficulty
+    block: bool,
+}
+
+/// Every qualifying hash from one call, instead of stopping at the first
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct MinerResultBatch {
+    found: Vec<FoundHash>,
+    hashes_computed: u32,
+    best_nonce: u32,
+    best_hash: String,
+    elapsed_ms: f64,
+    stop_reason: StopReason,
+    next_nonce: u32,
+}
+
+#[wasm_bindgen]
+impl MinerResultBatch {
+    /// Number of qualifying hashes
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.found.len()
+    }
+
+    /// Nonces of the qualifying hashes, in nonce order (a `Uint32Array`)
+    #[wasm_bindgen(getter)]
+    pub fn nonces(&self) -> Vec<u32> {
+        self.found.iter().map(|found| found.nonce).collect()
+    }
+
+    /// Hashes matching `nonces`, as hex
+    #[wasm_bindgen(getter)]
+    pub fn hashes(&self) -> Vec<String> {
+        self.found.iter().map(|found| found.hash.clone()).collect()
+    }
+
+    /// Whether any qualifying hash is a full block
+    #[wasm_bindgen(getter)]
+    pub fn found_block(&self) -> bool {
+        self.found.iter().any(|found| found.block)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hashes_computed(&self) -> u32 {
+        self.hashes_computed
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn best_nonce(&self) -> u32 {
+        self.best_nonce
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn best_hash(&self) -> String {
+        self.best_hash.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn elapsed_ms(&self) -> f64 {
+        self.elapsed_ms
+    }
+
+    /// `Found` when `max_results` was reached, otherwise why hashing stopped
+    #[wasm_bindgen(getter)]
+    pub fn stop_reason(&self) -> StopReason {
+        self.stop_reason
+    }
+
+    /// Nonce to resume from in the next call
+    #[wasm_bindgen(getter)]
+    pub fn next_nonce(&self) -> u32 {
+        self.next_nonce
+    }
+
+    /// Qualifying hashes as an array of `{ nonce, hash, block }` objects
+    pub fn to_array(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.found).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+}
+
+/// Scan `start..end` for every hash `share_policy` accepts, resuming after each one
+///
+/// Stops at `end`, after `max_hashes`, or once `max_results` hashes have
+/// been collected. Each collected hash is also checked against
+/// `block_policy` so a block found while mining shares is not missed.
+/// `previous_hash` and `share_difficulty` are what `share_policy` was built
+/// from, for the replay log.
+#[allow(clippy::too_many_arguments)]
+pub fn collect_range(
+    prefix: &[u8],
+    share_policy: &Acceptance,
+    block_policy: &Acceptance,
+    previous_hash: &str,
+    share_difficulty: f64,
+    start: u32,
+    end: u32,
+    max_hashes: u32,
+    max_results: u32,
+) -> MinerResultBatch {
+    let started_ms = clock::monotonic_ms();
+    let limit = end.min(start.saturating_add(max_hashes));
+    let mut batch = MinerResultBatch {
+        found: Vec::new(),
+        hashes_computed: 0,
+        best_nonce: start,
+        best_hash: "f".repeat(64),
+        elapsed_ms: 0.0,
+        stop_reason: StopReason::RangeEnd,
+        next_nonce: start,
+    };
+
+    while batch.next_nonce < limit {
+        if batch.found.len() as u32 >= max_results {
+            batch.stop_reason = StopReason::Found;
+            break;
+        }
+
+        let scan = {
+            let _span = profile::Span::start("stellaris:hash");
+            run_scan(prefix, share_policy, batch.next_nonce, limit)
+        };
+        record_scan(prefix, share_policy, previous_hash, share_difficulty, batch.next_nonce, limit, &scan);
+        batch.hashes_computed += scan.hashes_computed;
+        if scan.best_hash < batch.best_hash {
+            batch.best_hash = scan.best_hash;
+            batch.best_nonce = scan.best_nonce;
+        }
+
+        match scan.found {
+            Some((nonce, hash)) => {
+                batch.found.push(FoundHash {
+                    nonce,
+                    block: block_policy.accepts(&hash),
+                    hash,
+                });
+                batch.next_nonce = nonce.saturating_add(1);
+            }
+            None => batch.next_nonce = limit,
+        }
+    }
+
+    if batch.stop_reason == StopReason::RangeEnd && limit < end {
+        batch.stop_reason = StopReason::MaxHashes;
+    }
+    batch.elapsed_ms = clock::monotonic_ms() - started_ms;
+    batch
+}
@@ -0,0 +1,200 @@
+use std::collections::{HashSet, VecDeque};
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::clock;
+use crate::error::{self, ErrorCode};
+
+/// How many submitted shares are remembered for duplicate checks
+pub const MAX_RECENT_SHARES: usize = 4096;
+
+/// A share waiting to be submitted, in the pool's `/api/share` field names
+#[derive(Clone, Debug, Serialize)]
+struct PendingShare {
+    job_id: String,
+    block_height: u64,
+    nonce: u32,
+    block_hash: String,
+    block_content_hex: String,
+}
+
+/// Recently submitted `(job_id, nonce)` pairs, forgetting the oldest past `MAX_RECENT_SHARES`
+#[derive(Default)]
+struct RecentShares {
+    keys: HashSet<(String, u32)>,
+    order: VecDeque<(String, u32)>,
+}
+
+impl RecentShares {
+    fn contains(&self, job_id: &str, nonce: u32) -> bool {
+        self.keys.contains(&(job_id.to_string(), nonce))
+    }
+
+    /// Remember a submission; false if it was already remembered
+    fn insert(&mut self, job_id: &str, nonce: u32) -> bool {
+        let key = (job_id.to_string(), nonce);
+        if !self.keys.insert(key.clone()) {
+            return false;
+        }
+        if self.order.len() == MAX_RECENT_SHARES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+        self.order.push_back(key);
+        true
+    }
+}
+
+/// Buffers low-difficulty shares and releases them in rate-limited batches
+///
+/// A fast machine on an easy share target can find several shares a
+/// second; submitting each immediately hammers the pool. Shares queue here
+/// instead and `take_batch` hands out at most `max_batch` of them, no more
+/// than `max_per_second` times a second. When more than `max_pending` are
+/// waiting, the oldest are dropped. Blocks should bypass the batcher and be
+/// submitted straight away, after claiming them with `mark_submitted`.
+///
+/// Pools penalize resubmitted shares, so the batcher remembers the last
+/// 4096 it handed out and refuses to queue the same `(job_id, nonce)`
+/// twice, e.g. when a range is mined again after a reconnect or when a
+/// replayed queue overlaps what already went out.
+#[wasm_bindgen]
+pub struct ShareBatcher {
+    pending: VecDeque<PendingShare>,
+    max_batch: usize,
+    max_pending: usize,
+    min_interval_ms: f64,
+    last_batch_ms: Option<f64>,
+    dropped: u32,
+    submitted: RecentShares,
+    duplicates: u32,
+}
+
+#[wasm_bindgen]
+impl ShareBatcher {
+    #[wasm_bindgen(constructor)]
+    pub fn new(max_batch: usize, max_per_second: f64, max_pending: usize) -> Result<ShareBatcher, JsValue> {
+        if max_batch == 0 || max_pending == 0 {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "max_batch and max_pending must be positive"));
+        }
+        if max_per_second.is_nan() || max_per_second <= 0.0 {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "max_per_second must be positive"));
+        }
+
+        Ok(ShareBatcher {
+            pending: VecDeque::new(),
+            max_batch,
+            max_pending,
+            min_interval_ms: 1000.0 / max_per_second,
+            last_batch_ms: None,
+            dropped: 0,
+            submitted: RecentShares::default(),
+            duplicates: 0,
+        })
+    }
+
+    /// Queue a share, dropping the oldest if the buffer is full; false if it is already queued or was submitted
+    pub fn push(
+        &mut self,
+        job_id: &str,
+        block_height: u64,
+        nonce: u32,
+        block_hash: &str,
+        block_content_hex: &str,
+    ) -> bool {
+        if self.is_duplicate(job_id, nonce) {
+            self.duplicates += 1;
+            return false;
+        }
+
+        if self.pending.len() >= self.max_pending {
+            self.pending.pop_front();
+            self.dropped += 1;
+        }
+
+        self.pending.push_back(PendingShare {
+            job_id: job_id.to_string(),
+            block_height,
+            nonce,
+            block_hash: block_hash.to_string(),
+            block_content_hex: block_content_hex.to_string(),
+        });
+        true
+    }
+
+    /// Claim a share submitted outside the batcher, such as a block; false if it is queued or was submitted
+    pub fn mark_submitted(&mut self, job_id: &str, nonce: u32) -> bool {
+        if self.is_duplicate(job_id, nonce) {
+            self.duplicates += 1;
+            return false;
+        }
+        self.submitted.insert(job_id, nonce)
+    }
+
+    /// Whether a share is queued or was handed out recently
+    pub fn is_duplicate(&self, job_id: &str, nonce: u32) -> bool {
+        self.submitted.contains(job_id, nonce)
+            || self.pending.iter().any(|share| share.job_id == job_id && share.nonce == nonce)
+    }
+
+    /// Whether `take_batch` would return anything right now
+    #[wasm_bindgen(getter)]
+    pub fn ready(&self) -> bool {
+        !self.pending.is_empty()
+            && self
+                .last_batch_ms
+                .is_none_or(|last| clock::monotonic_ms() - last >= self.min_interval_ms)
+    }
+
+    /// Up to `max_batch` queued shares, oldest first, as plain objects; empty if rate-limited
+    pub fn take_batch(&mut self) -> Result<JsValue, JsValue> {
+        let count = if self.ready() {
+            self.last_batch_ms = Some(clock::monotonic_ms());
+            self.pending.len().min(self.max_batch)
+        } else {
+            0
+        };
+        let batch: Vec<PendingShare> = self.pending.drain(..count).collect();
+        self.remember(&batch);
+        serde_wasm_bindgen::to_value(&batch).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Every queued share regardless of the rate limit, e.g. on shutdown
+    pub fn drain(&mut self) -> Result<JsValue, JsValue> {
+        let batch: Vec<PendingShare> = self.pending.drain(..).collect();
+        self.remember(&batch);
+        serde_wasm_bindgen::to_value(&batch).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Forget queued shares for a job, once a new block has made them stale
+    pub fn discard_job(&mut self, job_id: &str) {
+        self.pending.retain(|share| share.job_id != job_id);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Shares dropped because the buffer was full
+    #[wasm_bindgen(getter)]
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Shares refused as duplicates
+    #[wasm_bindgen(getter)]
+    pub fn duplicates(&self) -> u32 {
+        self.duplicates
+    }
+}
+
+impl ShareBatcher {
+    fn remember(&mut self, batch: &[PendingShare]) {
+        for share in batch {
+            self.submitted.insert(&share.job_id, share.nonce);
+        }
+    }
+}
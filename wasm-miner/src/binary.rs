@@ -0,0 +1,46 @@
+//! Compact binary encoding for jobs, results and saved state
+//!
+//! Every record starts with the magic `SW` and a record kind, followed by
+//! the value in postcard form: varint integers, length-prefixed strings and
+//! fields in declaration order with no names. The kind byte keeps one record
+//! from being decoded as another.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+const MAGIC: [u8; 2] = *b"SW";
+
+/// What a record holds, so one kind can't be decoded as another
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordKind {
+    Job = 1,
+    Result = 2,
+    BestShare = 3,
+    State = 4,
+}
+
+/// Write `value` as a record of `kind`
+pub fn encode<T: Serialize>(kind: RecordKind, value: &T) -> Vec<u8> {
+    let out = vec![MAGIC[0], MAGIC[1], kind as u8];
+    // Records hold only fixed fields, strings and sequences of known length, which postcard always accepts
+    postcard::to_extend(value, out).expect("record fields serialize")
+}
+
+/// Read a record of `kind`, rejecting other kinds and trailing bytes
+pub fn decode<T: DeserializeOwned>(bytes: &[u8], kind: RecordKind) -> Result<T, String> {
+    let [m0, m1, found, ref body @ ..] = *bytes else {
+        return Err("Not a binary miner record".to_string());
+    };
+    if [m0, m1] != MAGIC {
+        return Err("Not a binary miner record".to_string());
+    }
+    if found != kind as u8 {
+        return Err(format!("Record holds kind {}, not a {:?}", found, kind));
+    }
+    let (value, rest) =
+        postcard::take_from_bytes(body).map_err(|e| format!("Invalid {:?} record: {}", kind, e))?;
+    if !rest.is_empty() {
+        return Err(format!("{} unexpected bytes after the record", rest.len()));
+    }
+    Ok(value)
+}
@@ -0,0 +1,32 @@
+use std::cell::RefCell;
+
+/// Buffers kept for reuse; more than a handful only happens with re-entrant use
+const MAX_POOLED: usize = 8;
+
+/// Initial capacity, enough for the largest header (64-byte address, u64 timestamp)
+const HEADER_CAPACITY: usize = 160;
+
+thread_local! {
+    static POOL: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Run `f` with an empty header-sized buffer borrowed from the pool
+///
+/// Share generation under vardiff builds a header for every share; reusing
+/// buffers keeps that from allocating and freeing on every call.
+pub fn with_buffer<T>(f: impl FnOnce(&mut Vec<u8>) -> T) -> T {
+    let mut buffer = POOL
+        .with(|pool| pool.borrow_mut().pop())
+        .unwrap_or_else(|| Vec::with_capacity(HEADER_CAPACITY));
+
+    let result = f(&mut buffer);
+
+    buffer.clear();
+    POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        if pool.len() < MAX_POOLED {
+            pool.push(buffer);
+        }
+    });
+    result
+}
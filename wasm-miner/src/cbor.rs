@@ -0,0 +1,78 @@
+//! CBOR (RFC 8949) as an alternative wire encoding for pool messages
+//!
+//! Pool messages are JSON-shaped, so this transcodes between CBOR (via
+//! `ciborium`) and `serde_json::Value` and leaves parsing to the existing
+//! JSON paths. Byte strings become lowercase hex, matching the JSON form of
+//! hashes, and bignum tags become integers when they fit in 64 bits. JSON
+//! has nowhere to put other tags, so those decode as their content.
+
+use ciborium::Value as CborValue;
+use serde_json::{Map, Value};
+use wasm_bindgen::prelude::*;
+
+use crate::error::{self, ErrorCode};
+use crate::transcode;
+
+/// Media type sent and recognized for CBOR bodies
+pub const CONTENT_TYPE: &str = "application/cbor";
+
+/// Encode a JSON pool message as CBOR
+#[wasm_bindgen]
+pub fn pool_json_to_cbor(json: &str) -> Result<Vec<u8>, JsValue> {
+    json_to_cbor(json).map_err(|e| error::js_error(ErrorCode::InvalidArgument, e))
+}
+
+/// Decode a CBOR pool message to JSON text, e.g. a job pushed over a WebSocket
+#[wasm_bindgen]
+pub fn pool_cbor_to_json(bytes: &[u8]) -> Result<String, JsValue> {
+    cbor_to_json(bytes).map_err(|e| error::js_error(ErrorCode::InvalidResponse, e))
+}
+
+pub fn json_to_cbor(json: &str) -> Result<Vec<u8>, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| format!("Invalid JSON: {}", e))?;
+    let mut out = Vec::with_capacity(json.len());
+    ciborium::into_writer(&value, &mut out).map_err(|e| format!("Could not encode CBOR: {}", e))?;
+    Ok(out)
+}
+
+pub fn cbor_to_json(bytes: &[u8]) -> Result<String, String> {
+    let mut rest = bytes;
+    let value: CborValue = ciborium::de::from_reader_with_recursion_limit(&mut rest, transcode::MAX_DEPTH)
+        .map_err(|e| format!("Invalid CBOR: {}", e))?;
+    if !rest.is_empty() {
+        return Err(format!("{} unexpected bytes after the CBOR item", rest.len()));
+    }
+    Ok(to_json(value)?.to_string())
+}
+
+fn to_json(value: CborValue) -> Result<Value, String> {
+    Ok(match value {
+        CborValue::Integer(n) => {
+            let n = i128::from(n);
+            match (u64::try_from(n), i64::try_from(n)) {
+                (Ok(n), _) => Value::from(n),
+                (_, Ok(n)) => Value::from(n),
+                _ => return Err(format!("CBOR integer {} is out of range", n)),
+            }
+        }
+        CborValue::Bytes(bytes) => Value::String(hex::encode(bytes)),
+        CborValue::Float(n) => transcode::finite(n, || "CBOR float".to_string())?,
+        CborValue::Text(text) => Value::String(text),
+        CborValue::Bool(value) => Value::Bool(value),
+        CborValue::Null => Value::Null,
+        CborValue::Tag(_, content) => to_json(*content)?,
+        CborValue::Array(items) => Value::Array(items.into_iter().map(to_json).collect::<Result<_, _>>()?),
+        CborValue::Map(entries) => {
+            let mut fields = Map::new();
+            for (key, item) in entries {
+                let CborValue::Text(key) = key else {
+                    return Err("CBOR map has a non-text key".to_string());
+                };
+                fields.insert(key, to_json(item)?);
+            }
+            Value::Object(fields)
+        }
+        other => return Err(format!("Unsupported CBOR value {:?}", other)),
+    })
+}
+
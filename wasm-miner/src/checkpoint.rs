@@ -0,0 +1,121 @@
+use std::collections::VecDeque;
+
+/// Jobs whose search progress is kept; the oldest is forgotten first
+pub const MAX_CHECKPOINT_JOBS: usize = 64;
+
+/// Default nonces searched between checkpoints
+pub const DEFAULT_CHECKPOINT_INTERVAL: u32 = 100_000;
+
+/// How far one job's nonce space has been searched
+struct JobProgress {
+    job_id: String,
+    /// Every nonce from the job's start up to here has been searched
+    frontier: u32,
+    /// Searched ranges beyond a gap at the frontier, sorted and disjoint
+    ahead: Vec<(u32, u32)>,
+    /// `frontier` as of the last checkpoint
+    checkpoint: u32,
+}
+
+impl JobProgress {
+    /// Add a searched range and move the frontier over everything now contiguous with it
+    fn mark(&mut self, start: u32, end: u32) {
+        let index = self.ahead.partition_point(|(ahead_start, _)| *ahead_start < start);
+        self.ahead.insert(index, (start, end));
+
+        // Sorted by start, so once a range lies past the frontier every later one does too
+        let mut merged: Vec<(u32, u32)> = Vec::with_capacity(self.ahead.len());
+        for (start, end) in self.ahead.drain(..) {
+            if start <= self.frontier {
+                self.frontier = self.frontier.max(end);
+            } else if let Some(last) = merged.last_mut().filter(|last| start <= last.1) {
+                last.1 = last.1.max(end);
+            } else {
+                merged.push((start, end));
+            }
+        }
+        self.ahead = merged;
+    }
+}
+
+/// Highest fully-searched nonce per job, advanced in steps of `interval` nonces
+///
+/// Ranges may be reported out of order (several workers on one job); the
+/// frontier only moves once everything before it has been searched, so a
+/// replacement resuming from a checkpoint never skips nonces. A coarser
+/// interval means fewer checkpoints to persist and more overlap on resume.
+pub struct Checkpoints {
+    jobs: VecDeque<JobProgress>,
+    interval: u32,
+}
+
+impl Default for Checkpoints {
+    fn default() -> Self {
+        Checkpoints {
+            jobs: VecDeque::new(),
+            interval: DEFAULT_CHECKPOINT_INTERVAL,
+        }
+    }
+}
+
+impl Checkpoints {
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    pub fn set_interval(&mut self, interval: u32) -> Result<(), String> {
+        if interval == 0 {
+            return Err("Checkpoint interval must be positive".to_string());
+        }
+        self.interval = interval;
+        Ok(())
+    }
+
+    /// Record that `start..end` of a job starting at `origin` was searched; true if that took a new checkpoint
+    pub fn mark(&mut self, job_id: &str, origin: u32, start: u32, end: u32) -> bool {
+        if end <= start {
+            return false;
+        }
+        let interval = self.interval;
+        let progress = self.progress(job_id, origin);
+        progress.mark(start, end);
+
+        if progress.frontier - progress.checkpoint >= interval {
+            progress.checkpoint = progress.frontier;
+            return true;
+        }
+        false
+    }
+
+    /// Latest checkpoint for a job, if any progress was recorded
+    pub fn get(&self, job_id: &str) -> Option<u32> {
+        self.jobs.iter().find(|progress| progress.job_id == job_id).map(|progress| progress.checkpoint)
+    }
+
+    /// Resume a job from a checkpoint saved elsewhere, e.g. by a worker that has since died
+    pub fn restore(&mut self, job_id: &str, nonce: u32) {
+        let progress = self.progress(job_id, nonce);
+        if nonce > progress.frontier {
+            progress.mark(progress.frontier, nonce);
+        }
+        progress.checkpoint = progress.checkpoint.max(nonce);
+    }
+
+    fn progress(&mut self, job_id: &str, origin: u32) -> &mut JobProgress {
+        match self.jobs.iter().position(|progress| progress.job_id == job_id) {
+            Some(index) => &mut self.jobs[index],
+            None => {
+                if self.jobs.len() == MAX_CHECKPOINT_JOBS {
+                    self.jobs.pop_front();
+                }
+                self.jobs.push_back(JobProgress {
+                    job_id: job_id.to_string(),
+                    frontier: origin,
+                    ahead: Vec::new(),
+                    checkpoint: origin,
+                });
+                self.jobs.back_mut().expect("just pushed")
+            }
+        }
+    }
+}
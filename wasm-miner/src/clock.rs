@@ -0,0 +1,232 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::JsCast;
+
+/// Source of time for timestamps and elapsed-time measurements
+pub trait Clock {
+    /// Wall-clock time in milliseconds since the Unix epoch
+    fn now_ms(&self) -> f64;
+
+    /// Monotonic time in milliseconds for measuring durations; defaults to wall-clock
+    fn monotonic_ms(&self) -> f64 {
+        self.now_ms()
+    }
+}
+
+/// `Date.now()` for both wall-clock and durations
+pub struct DateClock;
+
+impl Clock for DateClock {
+    #[cfg(target_arch = "wasm32")]
+    fn now_ms(&self) -> f64 {
+        js_sys::Date::now()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now_ms(&self) -> f64 {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64() * 1000.0)
+            .unwrap_or(0.0)
+    }
+}
+
+/// `Date.now()` for wall-clock and `performance.now()` for durations
+///
+/// Falls back to `Date.now()` where the Performance API is missing, and to
+/// `std::time::Instant` in native builds.
+pub struct PerformanceClock;
+
+impl Clock for PerformanceClock {
+    fn now_ms(&self) -> f64 {
+        DateClock.now_ms()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn monotonic_ms(&self) -> f64 {
+        performance()
+            .map(|performance| performance.now())
+            .unwrap_or_else(js_sys::Date::now)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn monotonic_ms(&self) -> f64 {
+        use std::sync::OnceLock;
+        use std::time::Instant;
+
+        static START: OnceLock<Instant> = OnceLock::new();
+        START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+    }
+}
+
+/// The global `performance` object of the page or worker, if it has one
+#[cfg(target_arch = "wasm32")]
+pub fn performance() -> Option<web_sys::Performance> {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("performance"))
+        .ok()
+        .and_then(|performance| performance.dyn_into::<web_sys::Performance>().ok())
+}
+
+/// Manually driven clock for tests and deterministic replays
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct MockClock {
+    now: Rc<Cell<f64>>,
+}
+
+#[wasm_bindgen]
+impl MockClock {
+    #[wasm_bindgen(constructor)]
+    pub fn new(start_ms: f64) -> MockClock {
+        MockClock {
+            now: Rc::new(Cell::new(start_ms)),
+        }
+    }
+
+    pub fn advance(&self, ms: f64) {
+        self.now.set(self.now.get() + ms);
+    }
+
+    pub fn set(&self, ms: f64) {
+        self.now.set(ms);
+    }
+
+    pub fn now(&self) -> f64 {
+        self.now.get()
+    }
+}
+
+impl Clock for MockClock {
+    fn now_ms(&self) -> f64 {
+        self.now.get()
+    }
+}
+
+/// Clock backed by a JS `() => number` callback
+struct CallbackClock {
+    callback: js_sys::Function,
+}
+
+impl Clock for CallbackClock {
+    fn now_ms(&self) -> f64 {
+        self.callback
+            .call0(&JsValue::NULL)
+            .ok()
+            .and_then(|value| value.as_f64())
+            .unwrap_or(0.0)
+    }
+}
+
+/// Built-in clocks selectable from JS
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockKind {
+    Date,
+    Performance,
+}
+
+thread_local! {
+    static CLOCK: RefCell<Rc<dyn Clock>> = RefCell::new(Rc::new(PerformanceClock));
+    static NETWORK_OFFSET_MS: Cell<f64> = const { Cell::new(0.0) };
+}
+
+/// Replace the clock used by the whole module
+pub fn install(clock: Rc<dyn Clock>) {
+    CLOCK.with(|c| *c.borrow_mut() = clock);
+}
+
+/// The clock currently installed, e.g. to put it back after a temporary override
+pub fn installed() -> Rc<dyn Clock> {
+    CLOCK.with(|c| c.borrow().clone())
+}
+
+/// Puts back the clock that was installed when it was created, once dropped
+///
+/// Restoring on drop means a failing assertion can't leave an override in
+/// place for whatever runs next. A seed set while the guard is alive is
+/// cleared too.
+#[must_use]
+pub struct ClockGuard {
+    previous: Rc<dyn Clock>,
+    seeded: bool,
+}
+
+impl ClockGuard {
+    /// Remember the installed clock without replacing it, e.g. before `set_seeded_mode`
+    pub fn new() -> ClockGuard {
+        ClockGuard {
+            previous: installed(),
+            seeded: crate::seeded::seeded_mode(),
+        }
+    }
+
+    /// Drive all timing from `clock` until the guard is dropped
+    pub fn mock(clock: &MockClock) -> ClockGuard {
+        let guard = ClockGuard::new();
+        set_mock_clock(clock);
+        guard
+    }
+}
+
+impl Default for ClockGuard {
+    fn default() -> ClockGuard {
+        ClockGuard::new()
+    }
+}
+
+impl Drop for ClockGuard {
+    fn drop(&mut self) {
+        if !self.seeded && crate::seeded::seeded_mode() {
+            crate::seeded::set_seeded_mode(None);
+        }
+        install(self.previous.clone());
+    }
+}
+
+/// Wall-clock time in milliseconds since the Unix epoch, from the installed clock
+pub fn now_ms() -> f64 {
+    CLOCK.with(|c| c.borrow().now_ms())
+}
+
+/// Wall-clock time corrected by the last known offset to the pool/node clock
+///
+/// Use this, not `now_ms`, for anything that ends up in a header timestamp.
+pub fn network_now_ms() -> f64 {
+    now_ms() + NETWORK_OFFSET_MS.with(|o| o.get())
+}
+
+/// Set the correction applied by `network_now_ms`
+pub fn set_network_offset_ms(offset_ms: f64) {
+    NETWORK_OFFSET_MS.with(|o| o.set(offset_ms));
+}
+
+/// Monotonic time in milliseconds, from the installed clock
+pub fn monotonic_ms() -> f64 {
+    CLOCK.with(|c| c.borrow().monotonic_ms())
+}
+
+/// Switch to one of the built-in clocks
+#[wasm_bindgen]
+pub fn set_clock(kind: ClockKind) {
+    match kind {
+        ClockKind::Date => install(Rc::new(DateClock)),
+        ClockKind::Performance => install(Rc::new(PerformanceClock)),
+    }
+}
+
+/// Drive all timing from a `MockClock`; advancing it is visible immediately
+#[wasm_bindgen]
+pub fn set_mock_clock(clock: &MockClock) {
+    install(Rc::new(clock.clone()));
+}
+
+/// Drive all timing from a JS callback returning milliseconds since the epoch
+#[wasm_bindgen]
+pub fn set_clock_callback(callback: js_sys::Function) {
+    install(Rc::new(CallbackClock { callback }));
+}
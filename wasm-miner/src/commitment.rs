@@ -0,0 +1,188 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use wasm_bindgen::prelude::*;
+
+/// Best results kept per commitment
+pub const COMMITMENT_SIZE: usize = 16;
+
+/// One result folded into a commitment
+#[derive(Clone, Debug, Serialize)]
+pub struct ProgressEntry {
+    pub job_id: String,
+    pub nonce: u32,
+    pub hash: String,
+}
+
+/// Summary sent to the pool; the entries stay local until the pool asks for them
+#[derive(Serialize)]
+pub struct ProgressCommitment {
+    pub sequence: u32,
+    pub hashes_computed: u64,
+    pub leaf_count: usize,
+    /// Merkle root over the entries, lowest hash first; empty when there are none
+    pub root: String,
+}
+
+/// One committed entry plus the sibling hashes linking it to the root
+#[derive(Serialize)]
+pub struct ProgressOpening {
+    pub index: usize,
+    pub job_id: String,
+    pub nonce: u32,
+    pub hash: String,
+    pub proof: Vec<String>,
+}
+
+/// Collects the best results between commitments
+///
+/// Browser miners rarely hit a full share, so the pool has little to
+/// credit them by. Instead the session periodically commits to its best
+/// `COMMITMENT_SIZE` results since the last commitment with a Merkle root;
+/// the pool then asks for a few of them, checks each opening against the
+/// root with `verify_progress_opening`, and re-hashes them with
+/// `validate_share`. Lying about effort means committing to hashes that
+/// don't exist, which the spot checks catch.
+#[derive(Default)]
+pub struct ProgressTracker {
+    entries: Vec<ProgressEntry>,
+    hashes_computed: u64,
+    sequence: u32,
+    /// Entries behind the latest commitment, kept so they can be opened
+    committed: Vec<ProgressEntry>,
+}
+
+impl ProgressTracker {
+    pub fn record(&mut self, job_id: &str, nonce: u32, hash: &str, hashes_computed: u32) {
+        self.hashes_computed += hashes_computed as u64;
+
+        let position = self.entries.partition_point(|entry| entry.hash.as_str() <= hash);
+        if position < COMMITMENT_SIZE {
+            self.entries.insert(
+                position,
+                ProgressEntry {
+                    job_id: job_id.to_string(),
+                    nonce,
+                    hash: hash.to_string(),
+                },
+            );
+            self.entries.truncate(COMMITMENT_SIZE);
+        }
+    }
+
+    /// Commit to everything recorded since the last call and start a new window
+    pub fn commit(&mut self) -> ProgressCommitment {
+        self.sequence += 1;
+        self.committed = std::mem::take(&mut self.entries);
+        let leaves: Vec<[u8; 32]> = self.committed.iter().map(leaf_hash).collect();
+
+        ProgressCommitment {
+            sequence: self.sequence,
+            hashes_computed: std::mem::take(&mut self.hashes_computed),
+            leaf_count: leaves.len(),
+            root: merkle_root(&leaves).map(hex::encode).unwrap_or_default(),
+        }
+    }
+
+    /// Reveal entry `index` of the latest commitment
+    pub fn open(&self, index: usize) -> Option<ProgressOpening> {
+        let entry = self.committed.get(index)?.clone();
+        let leaves: Vec<[u8; 32]> = self.committed.iter().map(leaf_hash).collect();
+        Some(ProgressOpening {
+            index,
+            job_id: entry.job_id,
+            nonce: entry.nonce,
+            hash: entry.hash,
+            proof: merkle_proof(&leaves, index).into_iter().map(hex::encode).collect(),
+        })
+    }
+}
+
+fn leaf_hash(entry: &ProgressEntry) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0u8]);
+    hasher.update(format!("{}:{}:{}", entry.job_id, entry.nonce, entry.hash.to_ascii_lowercase()));
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([1u8]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Next tree level; an odd node out is carried up unchanged
+fn parent_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+fn merkle_root(leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = parent_level(&level);
+    }
+    level.first().copied()
+}
+
+fn merkle_proof(leaves: &[[u8; 32]], mut index: usize) -> Vec<[u8; 32]> {
+    let mut proof = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        level = parent_level(&level);
+        index /= 2;
+    }
+    proof
+}
+
+/// Pool-side check that an opened entry belongs to a progress commitment
+///
+/// Only proves membership; re-hash the entry with `validate_share` to
+/// check the work itself.
+#[wasm_bindgen]
+pub fn verify_progress_opening(
+    root_hex: &str,
+    leaf_count: usize,
+    index: usize,
+    job_id: &str,
+    nonce: u32,
+    hash: &str,
+    proof: Vec<String>,
+) -> bool {
+    if index >= leaf_count {
+        return false;
+    }
+
+    let entry = ProgressEntry {
+        job_id: job_id.to_string(),
+        nonce,
+        hash: hash.to_string(),
+    };
+    let mut node = leaf_hash(&entry);
+    let mut proof = proof.iter();
+    let (mut index, mut width) = (index, leaf_count);
+
+    while width > 1 {
+        // The last node of an odd level has no sibling and is carried up as is
+        if index ^ 1 < width {
+            let Some(sibling) = proof.next().and_then(|s| <[u8; 32]>::try_from(hex::decode(s).ok()?).ok()) else {
+                return false;
+            };
+            node = if index % 2 == 0 { node_hash(&node, &sibling) } else { node_hash(&sibling, &node) };
+        }
+        index /= 2;
+        width = width.div_ceil(2);
+    }
+
+    proof.next().is_none() && hex::encode(node).eq_ignore_ascii_case(root_hex)
+}
@@ -0,0 +1,72 @@
+use std::borrow::Cow;
+use std::io::Read;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use wasm_bindgen::prelude::*;
+
+use crate::error::{self, ErrorCode};
+
+/// Largest payload inflated, so a tiny compressed body can't balloon past the heap
+pub const MAX_INFLATED_BYTES: u64 = 32 * 1024 * 1024;
+
+/// How a payload is compressed, told apart by its first bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Compression {
+    Gzip,
+    Zlib,
+}
+
+/// Gzip has a fixed magic; a zlib header is a deflate method byte whose check bits make it a multiple of 31
+///
+/// The zlib test is only a hint: plain text starting `80` (0x3830) passes
+/// it, as do CBOR heads 0x68 and 0x78, so `inflate` treats a failed zlib
+/// stream as uncompressed.
+fn detect(bytes: &[u8]) -> Option<Compression> {
+    match bytes {
+        [0x1f, 0x8b, ..] => Some(Compression::Gzip),
+        [cmf, flg, ..] if cmf & 0x0f == 8 && cmf >> 4 <= 7 && u16::from_be_bytes([*cmf, *flg]).is_multiple_of(31) => {
+            Some(Compression::Zlib)
+        }
+        _ => None,
+    }
+}
+
+/// Whether a `Content-Type` names a compressed body, e.g. `application/gzip` or `application/zlib`
+pub fn is_compressed_content_type(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    ["gzip", "zlib", "deflate"].iter().any(|kind| essence.ends_with(kind))
+}
+
+/// Inflate a gzip or zlib (deflate) payload, returning anything else unchanged
+///
+/// Bytes that only look like a zlib header and don't inflate are returned
+/// as they are; a broken gzip stream, or one inflating past the limit, is
+/// an error.
+pub fn inflate(bytes: &[u8]) -> Result<Cow<'_, [u8]>, String> {
+    let Some(compression) = detect(bytes) else {
+        return Ok(Cow::Borrowed(bytes));
+    };
+
+    let reader: Box<dyn Read + '_> = match compression {
+        Compression::Gzip => Box::new(GzDecoder::new(bytes)),
+        Compression::Zlib => Box::new(ZlibDecoder::new(bytes)),
+    };
+    let mut inflated = Vec::with_capacity(bytes.len() * 4);
+    match reader.take(MAX_INFLATED_BYTES + 1).read_to_end(&mut inflated) {
+        Ok(_) => {}
+        Err(_) if compression == Compression::Zlib => return Ok(Cow::Borrowed(bytes)),
+        Err(e) => return Err(format!("Invalid {:?} payload: {}", compression, e)),
+    }
+    if inflated.len() as u64 > MAX_INFLATED_BYTES {
+        return Err(format!("{:?} payload inflates past {} bytes", compression, MAX_INFLATED_BYTES));
+    }
+    Ok(Cow::Owned(inflated))
+}
+
+/// Text of a payload that may be gzip or zlib compressed, e.g. a job received over a WebSocket
+#[wasm_bindgen]
+pub fn decompress_payload(bytes: &[u8]) -> Result<String, JsValue> {
+    let inflated = inflate(bytes).map_err(|e| error::js_error(ErrorCode::InvalidResponse, e))?;
+    String::from_utf8(inflated.into_owned())
+        .map_err(|_| error::js_error(ErrorCode::InvalidResponse, "Payload is not valid UTF-8 text"))
+}
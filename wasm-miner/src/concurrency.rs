@@ -0,0 +1,131 @@
+use std::cell::Cell;
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::{self, ErrorCode};
+use crate::logging::{self, LogLevel};
+use crate::MinerResult;
+
+/// How mining is spread over the device's cores
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConcurrencyMode {
+    /// Everything on the calling thread
+    Single,
+    /// A coordinator hands nonce ranges to Web Workers over `postMessage` (see `WorkerMessage`)
+    Workers,
+    /// `mine_range` fans out over the rayon pool in shared memory (`parallel` builds only)
+    Threads,
+}
+
+/// Threaded builds only instantiate on isolated pages, so they start out using their threads
+const DEFAULT_MODE: ConcurrencyMode = if cfg!(feature = "parallel") {
+    ConcurrencyMode::Threads
+} else {
+    ConcurrencyMode::Single
+};
+
+thread_local! {
+    static MODE: Cell<ConcurrencyMode> = const { Cell::new(DEFAULT_MODE) };
+}
+
+/// Whether the rayon pool has started; native builds spawn it on demand
+#[cfg(feature = "parallel")]
+static THREAD_POOL_READY: AtomicBool = AtomicBool::new(!cfg!(target_arch = "wasm32"));
+
+/// Whether this build was compiled with the `parallel` feature
+#[wasm_bindgen]
+pub fn threads_compiled() -> bool {
+    cfg!(feature = "parallel")
+}
+
+/// Whether the page is cross-origin isolated, which shared-memory threads need
+#[wasm_bindgen]
+pub fn cross_origin_isolated() -> bool {
+    let global = js_sys::global();
+    let isolated = js_sys::Reflect::get(&global, &JsValue::from_str("crossOriginIsolated"))
+        .ok()
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    let shared_memory = js_sys::Reflect::has(&global, &JsValue::from_str("SharedArrayBuffer")).unwrap_or(false);
+    isolated && shared_memory
+}
+
+/// Mode in use; `Threads` is the only one that changes what `mine_range` does
+#[wasm_bindgen]
+pub fn concurrency_mode() -> ConcurrencyMode {
+    MODE.with(|mode| mode.get())
+}
+
+/// Force a mode; throws `InvalidArgument` for `Threads` when this build or page can't run them
+#[wasm_bindgen]
+pub fn set_concurrency_mode(mode: ConcurrencyMode) -> Result<(), JsValue> {
+    if mode == ConcurrencyMode::Threads && !(threads_compiled() && cross_origin_isolated()) {
+        return Err(error::js_error(
+            ErrorCode::InvalidArgument,
+            "Threads need a parallel build on a cross-origin isolated page",
+        ));
+    }
+    MODE.with(|current| current.set(mode));
+    Ok(())
+}
+
+/// Pick the best mode this build and page support, activate it, and return it
+///
+/// `Threads` when available, else `Workers` if more than one worker is
+/// wanted, else `Single`. Call `start_thread_pool` if it picks `Threads`.
+#[wasm_bindgen]
+pub fn choose_concurrency_mode(workers: u32) -> ConcurrencyMode {
+    let mode = if threads_compiled() && cross_origin_isolated() {
+        ConcurrencyMode::Threads
+    } else if workers > 1 {
+        ConcurrencyMode::Workers
+    } else {
+        ConcurrencyMode::Single
+    };
+
+    if threads_compiled() && mode != ConcurrencyMode::Threads {
+        logging::emit(LogLevel::Warn, "concurrency", "Page isn't cross-origin isolated; falling back from threads");
+    }
+    logging::emit(LogLevel::Info, "concurrency", &format!("Mining in {:?} mode", mode));
+    MODE.with(|current| current.set(mode));
+    mode
+}
+
+/// Start the rayon pool over `num_threads` Web Workers, resolving once `Threads` mode can use it
+///
+/// Wraps wasm-bindgen-rayon's `initThreadPool`. Until the pool is up, and
+/// for good if starting it fails, `mine_range` hashes on the calling thread
+/// even in `Threads` mode.
+#[cfg(all(feature = "parallel", target_arch = "wasm32"))]
+#[wasm_bindgen]
+pub fn start_thread_pool(num_threads: usize) -> js_sys::Promise {
+    wasm_bindgen_futures::future_to_promise(async move {
+        wasm_bindgen_futures::JsFuture::from(wasm_bindgen_rayon::init_thread_pool(num_threads)).await?;
+        THREAD_POOL_READY.store(true, Ordering::Release);
+        Ok(JsValue::UNDEFINED)
+    })
+}
+
+/// Whether `mine_range` should use the rayon pool: `Threads` mode, with the pool started
+#[cfg(feature = "parallel")]
+pub fn use_threads() -> bool {
+    concurrency_mode() == ConcurrencyMode::Threads && THREAD_POOL_READY.load(Ordering::Acquire)
+}
+
+/// Combine the results workers report for consecutive parts of one range, searched side by side
+///
+/// The lowest found nonce wins and the best hash is the best of all; hash
+/// counts add up, and `elapsed_ms` is the slowest worker's. Returns
+/// `undefined` for an empty list.
+#[wasm_bindgen]
+pub fn combine_worker_results(results: Vec<MinerResult>) -> Option<MinerResult> {
+    let mut results = results.into_iter();
+    let mut combined = results.next()?;
+    for result in results {
+        combined.merge(result);
+    }
+    Some(combined)
+}
@@ -0,0 +1,92 @@
+use std::fmt::Write;
+
+use wasm_bindgen::prelude::*;
+
+/// Which history `MinerSession.export_csv` writes
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsvKind {
+    /// Every retained share submission and the pool's verdict
+    Shares,
+    /// Stats per job and per hour (see `start_segment`)
+    Segments,
+    /// The session's recent event log (see `recent_events`)
+    Events,
+}
+
+/// RFC 4180 CSV built up row by row, with CRLF line endings
+pub struct CsvTable {
+    out: String,
+}
+
+impl CsvTable {
+    pub fn new(columns: &[&str]) -> CsvTable {
+        let mut table = CsvTable { out: String::new() };
+        table.row(columns);
+        table
+    }
+
+    pub fn row<S: AsRef<str>>(&mut self, fields: &[S]) {
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            push_field(&mut self.out, field.as_ref());
+        }
+        self.out.push_str("\r\n");
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+/// Quote fields that need it, and defuse text a spreadsheet would run as a formula
+///
+/// Pool reject messages end up in these files, so a leading `=`, `+` or `@`
+/// gets a `'` in front. `-` is left alone so negative numbers stay numbers.
+fn push_field(out: &mut String, field: &str) {
+    let formula = field.starts_with(['=', '+', '@', '\t', '\r']);
+    if !formula && !field.contains([',', '"', '\n', '\r']) {
+        out.push_str(field);
+        return;
+    }
+
+    out.push('"');
+    if formula {
+        out.push('\'');
+    }
+    out.push_str(&field.replace('"', "\"\""));
+    out.push('"');
+}
+
+/// Milliseconds since the Unix epoch as UTC ISO 8601, e.g. `2024-01-02T03:04:05.678Z`
+pub fn iso_timestamp(ms: f64) -> String {
+    let ms = ms.max(0.0) as u64;
+    let (days, ms_of_day) = (ms / 86_400_000, ms % 86_400_000);
+
+    // Civil-from-days: shift the epoch to 0000-03-01 so leap days fall at the end of each year
+    let days = days as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    let mut out = String::with_capacity(24);
+    let _ = write!(
+        out,
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1_000 % 60,
+        ms_of_day % 1_000
+    );
+    out
+}
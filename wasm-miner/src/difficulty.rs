@@ -0,0 +1,382 @@
+use std::cell::Cell;
+
+use serde::{Deserialize, Deserializer};
+use wasm_bindgen::prelude::*;
+
+use crate::error::{self, ErrorCode};
+use crate::parse;
+use crate::policy::{ChunkSuffix, DifficultyPolicy};
+
+/// Difficulties are carried in the header scaled by 10, so one decimal is the finest step
+const DIFFICULTY_STEP: f64 = 0.1;
+
+/// Lowest job difficulty accepted by default; below one hex character nearly every hash would be a block
+pub const DEFAULT_MIN_DIFFICULTY: f64 = 1.0;
+
+/// Highest job difficulty accepted by default, one per hex character of a digest
+pub const DEFAULT_MAX_DIFFICULTY: f64 = 64.0;
+
+thread_local! {
+    static LIMITS: Cell<(f64, f64)> = const { Cell::new((DEFAULT_MIN_DIFFICULTY, DEFAULT_MAX_DIFFICULTY)) };
+}
+
+/// Expected number of hashes needed to meet `difficulty`
+///
+/// Each whole unit requires one more matching hex character (16x harder);
+/// the fractional part restricts the next character to the lowest
+/// `ceil(16 * (1 - fraction))` values.
+pub fn expected_hashes(difficulty: f64) -> f64 {
+    let whole = difficulty.trunc();
+    let fraction = difficulty - whole;
+    let base = 16f64.powf(whole);
+
+    if fraction > 0.0 {
+        let valid_chars = (16.0 * (1.0 - fraction)).ceil();
+        base * 16.0 / valid_chars
+    } else {
+        base
+    }
+}
+
+/// Highest difficulty (in 0.1 steps) whose expected hash count does not exceed `hashes`
+pub fn difficulty_for_expected_hashes(hashes: f64) -> f64 {
+    if hashes.is_nan() || hashes <= 1.0 {
+        return 0.0;
+    }
+
+    let whole = hashes.log(16.0).floor();
+    (0..10)
+        .rev()
+        .map(|tenths| whole + tenths as f64 * DIFFICULTY_STEP)
+        .find(|&difficulty| expected_hashes(difficulty) <= hashes)
+        .map(|difficulty| (difficulty * 10.0).round() / 10.0)
+        .unwrap_or(whole)
+}
+
+/// Share difficulty that a miner at `hashrate` H/s would hit about once every `interval_secs`
+#[wasm_bindgen]
+pub fn suggest_share_difficulty(hashrate: f64, interval_secs: f64) -> f64 {
+    difficulty_for_expected_hashes(hashrate * interval_secs)
+}
+
+/// Reject difficulties that can't be carried in the header's scaled u16 field
+///
+/// In lenient parse mode a finite difficulty out of range is clamped into
+/// it instead; callers must use the returned value.
+pub fn validate_difficulty(difficulty: f64) -> Result<f64, String> {
+    let max = u16::MAX as f64 / 10.0;
+    if difficulty.is_finite() && (0.0..=max).contains(&difficulty) {
+        return Ok(difficulty);
+    }
+    if difficulty.is_finite() && parse::coerce(|| format!("Difficulty {} is out of range; clamping", difficulty)) {
+        return Ok(difficulty.clamp(0.0, max));
+    }
+    Err(format!("Invalid difficulty {}", difficulty))
+}
+
+/// Set the range of job difficulties the module accepts (default 1 to 64)
+///
+/// Jobs from a pool or node outside it are rejected with
+/// `DifficultyOutOfRange`, or clamped into it in lenient parse mode, so a
+/// malformed difficulty such as 0 or 1e9 can't have the miner accept every
+/// hash or none. Lower the minimum for test networks with trivial difficulty.
+#[wasm_bindgen]
+pub fn set_difficulty_limits(min: f64, max: f64) -> Result<(), JsValue> {
+    let min = validate_difficulty(min).map_err(|e| error::js_error(ErrorCode::InvalidDifficulty, e))?;
+    let max = validate_difficulty(max).map_err(|e| error::js_error(ErrorCode::InvalidDifficulty, e))?;
+    if min > max {
+        return Err(error::js_error(
+            ErrorCode::InvalidArgument,
+            format!("Minimum difficulty {} is above the maximum {}", min, max),
+        ));
+    }
+    LIMITS.with(|limits| limits.set((min, max)));
+    Ok(())
+}
+
+/// Lowest job difficulty accepted (see `set_difficulty_limits`)
+#[wasm_bindgen]
+pub fn min_difficulty() -> f64 {
+    LIMITS.with(|limits| limits.get().0)
+}
+
+/// Highest job difficulty accepted (see `set_difficulty_limits`)
+#[wasm_bindgen]
+pub fn max_difficulty() -> f64 {
+    LIMITS.with(|limits| limits.get().1)
+}
+
+/// Reject a job difficulty outside the configured limits, or clamp it into them in lenient parse mode
+pub fn check_difficulty_limits(difficulty: f64) -> Result<f64, String> {
+    let (min, max) = LIMITS.with(|limits| limits.get());
+    if (min..=max).contains(&difficulty) {
+        return Ok(difficulty);
+    }
+    if difficulty.is_finite()
+        && parse::coerce(|| format!("Difficulty {} is outside {} to {}; clamping", difficulty, min, max))
+    {
+        return Ok(difficulty.clamp(min, max));
+    }
+    Err(format!("Difficulty {} is outside the accepted range {} to {}", difficulty, min, max))
+}
+
+/// Parse a decimal difficulty such as `"6.3"` exactly, in tenths
+///
+/// A float like 6.299999999 truncates to the wrong scaled header value and
+/// fraction; going through the string gives the nearest float to the
+/// intended tenth, which always scales back to it exactly. Digits past the
+/// first decimal must be zero, unless parsing is lenient, in which case
+/// they are dropped.
+pub fn difficulty_from_decimal(text: &str) -> Result<f64, String> {
+    let invalid = || format!("Invalid difficulty {:?}", text);
+    let text = text.trim();
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if whole.is_empty() || !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    let tenths_digit = fraction.bytes().next().map_or(0.0, |b| (b - b'0') as f64);
+    if fraction.bytes().skip(1).any(|b| b != b'0')
+        && !parse::coerce(|| format!("Difficulty {} is finer than a tenth; truncating", text))
+    {
+        return Err(invalid());
+    }
+
+    let whole: f64 = whole.parse().map_err(|_| invalid())?;
+    validate_difficulty((whole * 10.0 + tenths_digit) / 10.0)
+}
+
+/// Accept a difficulty as either a JSON number or a decimal string
+pub fn deserialize_difficulty<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Number(f64),
+        Decimal(String),
+    }
+
+    match Raw::deserialize(deserializer)? {
+        Raw::Number(difficulty) => Ok(difficulty),
+        Raw::Decimal(text) => difficulty_from_decimal(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Parse a decimal difficulty string (see `difficulty_from_decimal`)
+#[wasm_bindgen]
+pub fn parse_difficulty(difficulty: &str) -> Result<f64, JsValue> {
+    difficulty_from_decimal(difficulty).map_err(|e| error::js_error(ErrorCode::InvalidDifficulty, e))
+}
+
+/// Suffix of `previous_hash` that a hash must start with to meet `difficulty`
+pub fn difficulty_chunk(previous_hash: &str, difficulty: f64) -> Result<&str, String> {
+    let difficulty = validate_difficulty(difficulty)?;
+
+    let chunk_len = difficulty as usize;
+    previous_hash
+        .get(previous_hash.len().saturating_sub(chunk_len)..)
+        .ok_or_else(|| "Invalid previous_hash".to_string())
+}
+
+/// Whether `hash_hex` would be accepted at `difficulty` on top of `previous_hash`
+#[wasm_bindgen]
+pub fn hash_meets_difficulty(hash_hex: &str, previous_hash: &str, difficulty: f64) -> Result<bool, JsValue> {
+    let policy =
+        ChunkSuffix::new(previous_hash, difficulty).map_err(|e| error::js_error(ErrorCode::InvalidDifficulty, e))?;
+    Ok(policy.accepts(hash_hex))
+}
+
+/// Highest difficulty (in 0.1 steps) that `hash_hex` satisfies on top of `previous_hash`
+///
+/// The whole part is the longest suffix of `previous_hash` the hash starts
+/// with; the tenths come from how low the following character is. Every
+/// hash satisfies difficulty 0.
+#[wasm_bindgen]
+pub fn achieved_difficulty(hash_hex: &str, previous_hash: &str) -> f64 {
+    let max_whole = previous_hash.len().min(hash_hex.len());
+
+    for whole in (1..=max_whole).rev() {
+        let chunk = match previous_hash.get(previous_hash.len() - whole..) {
+            Some(chunk) => chunk,
+            None => continue,
+        };
+        if hash_hex.starts_with(chunk) {
+            return whole as f64 + fractional_difficulty(hash_hex, whole);
+        }
+    }
+
+    fractional_difficulty(hash_hex, 0)
+}
+
+/// Largest fraction (in 0.1 steps) accepted for the character after `whole` matched characters
+fn fractional_difficulty(hash_hex: &str, whole: usize) -> f64 {
+    let value = match hash_hex.chars().nth(whole).and_then(|c| c.to_digit(16)) {
+        Some(value) => value as f64,
+        None => return 0.0,
+    };
+
+    // Fraction t admits the lowest ceil(16 * (1 - t)) characters
+    (0..10)
+        .rev()
+        .map(|tenths| tenths as f64 * DIFFICULTY_STEP)
+        .find(|&fraction| value < (16.0 * (1.0 - fraction)).ceil())
+        .map(|fraction| (fraction * 10.0).round() / 10.0)
+        .unwrap_or(0.0)
+}
+
+/// Expected seconds to find a block at `difficulty` while hashing at `hashrate` H/s
+///
+/// Returns infinity when the hashrate is zero or unknown.
+#[wasm_bindgen]
+pub fn eta_seconds(difficulty: f64, hashrate: f64) -> f64 {
+    if hashrate > 0.0 {
+        expected_hashes(difficulty) / hashrate
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// Number of hex characters accepted after the chunk at `difficulty` (16 for whole difficulties)
+fn valid_char_count(difficulty: f64) -> usize {
+    let fraction = difficulty - difficulty.trunc();
+    (16.0 * (1.0 - fraction)).ceil() as usize
+}
+
+/// Suffix of `previous_hash` a hash must start with at `difficulty`
+#[wasm_bindgen]
+pub fn difficulty_to_chunk(previous_hash: &str, difficulty: f64) -> Result<String, JsValue> {
+    difficulty_chunk(previous_hash, difficulty)
+        .map(str::to_string)
+        .map_err(|e| error::js_error(ErrorCode::InvalidDifficulty, e))
+}
+
+/// Hex characters allowed straight after the chunk at `difficulty`, e.g. `"01234567"` for x.5
+#[wasm_bindgen]
+pub fn difficulty_to_valid_chars(difficulty: f64) -> Result<String, JsValue> {
+    let difficulty = validate_difficulty(difficulty).map_err(|e| error::js_error(ErrorCode::InvalidDifficulty, e))?;
+    Ok("0123456789abcdef"[..valid_char_count(difficulty)].to_string())
+}
+
+/// Expected number of hashes to meet `difficulty` (see `expected_hashes`)
+#[wasm_bindgen]
+pub fn difficulty_to_expected_hashes(difficulty: f64) -> f64 {
+    expected_hashes(difficulty)
+}
+
+/// Highest difficulty (in 0.1 steps) expected within `hashes` hashes
+#[wasm_bindgen]
+pub fn expected_hashes_to_difficulty(hashes: f64) -> f64 {
+    difficulty_for_expected_hashes(hashes)
+}
+
+/// Expected hashes behind one difficulty-1 share, the chain's unit of work
+///
+/// Difficulty 1 means one hex character of the hash is fixed, so such a
+/// share takes 16 hashes on average. Share and payout arithmetic everywhere
+/// should go through this rather than a literal.
+pub const DIFFICULTY_1_HASHES: f64 = 16.0;
+
+/// `DIFFICULTY_1_HASHES`, for JS callers
+#[wasm_bindgen]
+pub fn difficulty_1_hashes() -> f64 {
+    DIFFICULTY_1_HASHES
+}
+
+/// How many difficulty-1 shares one share at `difficulty` is worth
+#[wasm_bindgen]
+pub fn share_value(difficulty: f64) -> f64 {
+    expected_hashes(difficulty) / DIFFICULTY_1_HASHES
+}
+
+/// Expected hashes behind `shares` shares at `difficulty`
+#[wasm_bindgen]
+pub fn shares_to_hashes(shares: f64, difficulty: f64) -> f64 {
+    shares * expected_hashes(difficulty)
+}
+
+/// Shares at `difficulty` that `hashes` hashes find on average
+#[wasm_bindgen]
+pub fn hashes_to_shares(hashes: f64, difficulty: f64) -> f64 {
+    hashes / expected_hashes(difficulty)
+}
+
+/// Equivalent 256-bit target as 64 hex characters
+///
+/// A uniformly random hash is at or below the target with the same
+/// probability it has of meeting `difficulty`, so `2^256 / (target + 1)`
+/// equals `expected_hashes(difficulty)` exactly.
+#[wasm_bindgen]
+pub fn difficulty_to_target(difficulty: f64) -> Result<String, JsValue> {
+    target_for_difficulty(difficulty).map_err(|e| error::js_error(ErrorCode::InvalidDifficulty, e))
+}
+
+/// `difficulty_to_target` for Rust callers
+pub fn target_for_difficulty(difficulty: f64) -> Result<String, String> {
+    let difficulty = validate_difficulty(difficulty)?;
+
+    // target + 1 = valid_chars * 16^(63 - whole)
+    let whole = difficulty as usize;
+    let valid_chars = valid_char_count(difficulty);
+    let significant = if valid_chars == 16 {
+        "f".repeat(64usize.saturating_sub(whole))
+    } else if whole < 64 {
+        format!("{:x}{}", valid_chars - 1, "f".repeat(63 - whole))
+    } else {
+        String::new()
+    };
+
+    Ok(format!("{:0>64}", significant))
+}
+
+/// Difficulty (in 0.1 steps) whose expected work a 256-bit `target` does not exceed
+#[wasm_bindgen]
+pub fn target_to_difficulty(target_hex: &str) -> Result<f64, JsValue> {
+    if target_hex.is_empty() || target_hex.len() > 64 {
+        return Err(error::js_error(ErrorCode::InvalidHash, "Target must be 1 to 64 hex characters"));
+    }
+
+    let target = hex_value(target_hex).ok_or_else(|| error::js_error(ErrorCode::InvalidHash, "Target must be hex"))?;
+
+    Ok(difficulty_for_expected_hashes(2f64.powi(256) / (target + 1.0)))
+}
+
+/// Value of a big-endian hex string as a float, or `None` if it isn't hex
+fn hex_value(value_hex: &str) -> Option<f64> {
+    value_hex
+        .chars()
+        .try_fold(0f64, |acc, c| c.to_digit(16).map(|digit| acc * 16.0 + digit as f64))
+}
+
+/// How far `hash_hex` is from being accepted at `difficulty` on top of `previous_hash`, in bits
+///
+/// Follows the rule `hash_meets_difficulty` checks. The hash's first
+/// `floor(difficulty)` characters are XORed with the chunk of the previous
+/// hash they must match, so matching characters become zeros, and the
+/// result is read as a 256-bit integer against `difficulty_to_target`. The
+/// return value is `log2((value + 1) / (target + 1))`: zero or below means
+/// the hash is accepted, and each unit is a factor of two. Unlike comparing
+/// hex strings, this says by how much one hash beats another.
+#[wasm_bindgen]
+pub fn hash_distance(hash_hex: &str, previous_hash: &str, difficulty: f64) -> Result<f64, JsValue> {
+    if hash_hex.len() != 64 {
+        return Err(error::js_error(ErrorCode::InvalidHash, "Hash must be 64 hex characters"));
+    }
+    let target = target_for_difficulty(difficulty)
+        .ok()
+        .and_then(|target_hex| hex_value(&target_hex))
+        .ok_or_else(|| error::js_error(ErrorCode::InvalidDifficulty, format!("Invalid difficulty {}", difficulty)))?;
+    let chunk = difficulty_chunk(previous_hash, difficulty).map_err(|e| error::js_error(ErrorCode::InvalidHash, e))?;
+
+    let mut chunk_digits = chunk.chars();
+    let value = hash_hex
+        .chars()
+        .try_fold(0f64, |acc, c| {
+            let digit = match chunk_digits.next() {
+                Some(expected) => c.to_digit(16)? ^ expected.to_digit(16)?,
+                None => c.to_digit(16)?,
+            };
+            Some(acc * 16.0 + digit as f64)
+        })
+        .ok_or_else(|| error::js_error(ErrorCode::InvalidHash, "Hash and previous_hash must be hex"))?;
+
+    Ok((value + 1.0).log2() - (target + 1.0).log2())
+}
@@ -0,0 +1,69 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Stable numeric code carried by every error the module throws
+///
+/// Thrown values are ordinary JS `Error`s named `MinerError` with the code
+/// in a `code` property, so hosts can branch or localize on
+/// `error.code === ErrorCode.InvalidAddress` instead of matching messages.
+/// Codes are never renumbered or reused; new ones are only appended within
+/// their range.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// A numeric or size parameter is out of range (zero sizes, negative rates, ...)
+    InvalidArgument = 100,
+    /// The wallet address isn't valid hex or base58, or has the wrong length
+    InvalidAddress = 101,
+    /// A hash or target is not hex, or not the expected length
+    InvalidHash = 102,
+    /// The difficulty is negative, not finite, or can't be encoded in a header
+    InvalidDifficulty = 103,
+    /// Header fields or a serialized header don't fit the chain's layout
+    InvalidHeader = 104,
+    /// A block template or pool work assignment is malformed
+    InvalidJob = 105,
+    /// A pool-issued session key is not valid hex
+    InvalidSessionKey = 106,
+    /// A pool or node response couldn't be understood
+    InvalidResponse = 107,
+    /// A stored record (e.g. a saved best share) couldn't be restored
+    InvalidRecord = 108,
+    /// The requested entry doesn't exist
+    NotFound = 109,
+    /// A job's difficulty is valid but outside the limits set by `set_difficulty_limits`
+    DifficultyOutOfRange = 110,
+
+    /// The request failed before any response arrived
+    Network = 200,
+    /// The request was aborted after the client's timeout
+    Timeout = 201,
+    /// A browser API the module needs (fetch, timers) is missing from this environment
+    Unavailable = 202,
+
+    /// The call would exceed the configured memory limit
+    MemoryLimit = 300,
+
+    /// A value couldn't be converted to or from JS
+    Serialization = 900,
+    /// A found block failed re-serialization or re-hashing; the hash backend or a cached prefix is faulty
+    SelfCheckFailed = 901,
+}
+
+/// A JS `Error` named `MinerError` with `code` set
+pub fn js_error(code: ErrorCode, message: impl AsRef<str>) -> JsValue {
+    let error = js_sys::Error::new(message.as_ref());
+    error.set_name("MinerError");
+    let _ = js_sys::Reflect::set(&error, &JsValue::from_str("code"), &JsValue::from(code as u32));
+    error.into()
+}
+
+/// Tag an error from a browser API, keeping its message; aborted fetches become `Timeout`
+pub fn from_js(code: ErrorCode, error: JsValue) -> JsValue {
+    match error.dyn_ref::<js_sys::Error>() {
+        Some(inner) if inner.name() == "MinerError" => error,
+        Some(inner) if inner.name() == "AbortError" => js_error(ErrorCode::Timeout, String::from(inner.message())),
+        Some(inner) => js_error(code, String::from(inner.message())),
+        None => js_error(code, error.as_string().unwrap_or_else(|| format!("{:?}", error))),
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::clock;
+use crate::csv::{self, CsvTable};
+
+/// Most events kept; older ones are dropped as new ones arrive
+pub const MAX_EVENTS: usize = 500;
+
+/// What happened, for filtering the event log
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum EventKind {
+    /// A job was queued, or mining moved on to another one
+    JobChanged,
+    /// A share verdict came back from the pool
+    Share,
+    /// The chain tip moved past the job being mined
+    Tip,
+    Error,
+    Disconnected,
+    Reconnected,
+    /// A share or block was held while the pool was unreachable, sent late, or dropped
+    Outbox,
+    Shutdown,
+    /// Anything the embedder logs that fits none of the above
+    Other,
+}
+
+/// One entry in the event log, as returned to JS
+#[derive(Clone, Debug, Serialize)]
+pub struct EventRecord {
+    pub timestamp_ms: f64,
+    pub kind: EventKind,
+    pub message: String,
+}
+
+/// Bounded log of recent session events, oldest first
+///
+/// Meant to be pasted into support requests, so it holds short readable
+/// messages rather than full payloads.
+#[derive(Default)]
+pub struct EventLog {
+    events: VecDeque<EventRecord>,
+    /// Events pushed out to stay within `MAX_EVENTS`
+    dropped: u64,
+}
+
+impl EventLog {
+    pub fn push(&mut self, kind: EventKind, message: impl Into<String>) {
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+            self.dropped += 1;
+        }
+        self.events.push_back(EventRecord {
+            timestamp_ms: clock::now_ms(),
+            kind,
+            message: message.into(),
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// The latest `limit` events, optionally of one kind, oldest first
+    pub fn recent(&self, limit: usize, kind: Option<EventKind>) -> Vec<&EventRecord> {
+        let mut recent: Vec<&EventRecord> = self
+            .events
+            .iter()
+            .rev()
+            .filter(|event| kind.is_none_or(|kind| event.kind == kind))
+            .take(limit)
+            .collect();
+        recent.reverse();
+        recent
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.dropped = 0;
+    }
+
+    /// Retained events as CSV, oldest first
+    pub fn to_csv(&self) -> String {
+        let mut table = CsvTable::new(&["time", "timestamp_ms", "kind", "message"]);
+        for event in &self.events {
+            table.row(&[
+                csv::iso_timestamp(event.timestamp_ms),
+                event.timestamp_ms.to_string(),
+                format!("{:?}", event.kind),
+                event.message.clone(),
+            ]);
+        }
+        table.finish()
+    }
+}
@@ -0,0 +1,30 @@
+//! Miner internals for downstream unit tests (`test-fixtures` feature)
+//!
+//! Pool software can check its share validation against exactly what the
+//! miner hashes and accepts. These follow the miner's internals and are
+//! not covered by semver.
+
+use crate::params::ChainParams;
+use crate::policy::{Acceptance, DifficultyPolicy};
+
+pub use crate::difficulty::difficulty_chunk;
+pub use crate::policy::meets_fraction;
+
+/// Header bytes preceding the nonce, exactly as the mining loop hashes them
+pub fn header_prefix(
+    params: &ChainParams,
+    previous_hash: &str,
+    pool_address: &str,
+    merkle_root: &str,
+    timestamp: u64,
+    difficulty: f64,
+) -> Result<Vec<u8>, String> {
+    crate::prepare_scan(params, previous_hash, pool_address, merkle_root, timestamp, difficulty)
+        .map(|(prefix, _)| prefix)
+        .map_err(|(_, e)| e)
+}
+
+/// Whether the mining loop would accept `hash_hex` under `params`' acceptance rule
+pub fn accepts(params: &ChainParams, previous_hash: &str, difficulty: f64, hash_hex: &str) -> Result<bool, String> {
+    Acceptance::new(params, previous_hash, difficulty).map(|policy| policy.accepts(hash_hex))
+}
@@ -0,0 +1,66 @@
+use wasm_bindgen::prelude::*;
+
+const HASHRATE_UNITS: [&str; 6] = ["H/s", "kH/s", "MH/s", "GH/s", "TH/s", "PH/s"];
+
+/// Hashrate in the largest unit that keeps the value at or above 1, e.g. `"12.35 MH/s"`
+///
+/// Plain H/s are shown without decimals, larger units with two. Negative or
+/// non-finite rates show as `"0 H/s"`.
+#[wasm_bindgen]
+pub fn format_hashrate(hashrate: f64) -> String {
+    if !hashrate.is_finite() || hashrate <= 0.0 {
+        return "0 H/s".to_string();
+    }
+
+    let mut value = hashrate;
+    let mut unit = 0;
+    // Compare the rounded value, so 999_999 H/s reads "1.00 MH/s" rather than "1000.00 kH/s"
+    let decimals = |unit: usize| if unit == 0 { 0 } else { 2 };
+    while unit + 1 < HASHRATE_UNITS.len() && round_to(value, decimals(unit)) >= 1000.0 {
+        value /= 1000.0;
+        unit += 1;
+    }
+
+    format!("{:.*} {}", decimals(unit), value, HASHRATE_UNITS[unit])
+}
+
+fn round_to(value: f64, decimals: usize) -> f64 {
+    let scale = 10f64.powi(decimals as i32);
+    (value * scale).round() / scale
+}
+
+/// Duration in its two largest units, e.g. `"45s"`, `"3m 12s"`, `"2h 05m"`, `"3d 4h"`
+///
+/// Fractions of a second are dropped. Infinity (an ETA at zero hashrate)
+/// shows as `"∞"`; negative or NaN durations as `"0s"`.
+#[wasm_bindgen]
+pub fn format_duration(secs: f64) -> String {
+    if secs == f64::INFINITY {
+        return "∞".to_string();
+    }
+    if secs.is_nan() || secs < 1.0 {
+        return "0s".to_string();
+    }
+
+    let total = secs as u64;
+    let (days, hours, minutes, seconds) = (total / 86_400, total / 3_600 % 24, total / 60 % 60, total % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Difficulty to the header's 0.1 precision, e.g. `"6.5"`
+#[wasm_bindgen]
+pub fn format_difficulty(difficulty: f64) -> String {
+    if difficulty.is_finite() {
+        format!("{:.1}", difficulty)
+    } else {
+        "-".to_string()
+    }
+}
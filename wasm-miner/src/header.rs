@@ -0,0 +1,326 @@
+use wasm_bindgen::prelude::*;
+
+use crate::error::{self, ErrorCode};
+use crate::params::{ChainParams, COMPRESSED_ADDRESS_LEN, COMPRESSED_ADDRESS_VERSION};
+use crate::parse;
+use crate::policy::{Acceptance, DifficultyPolicy};
+use crate::buffers;
+use crate::sha;
+use crate::{difficulty, string_to_bytes, write_prefix};
+
+/// A complete block header with typed fields
+///
+/// Built from the same hex/base58 strings the loose functions take, but
+/// validated once up front: hashes must be 32 bytes of hex, the address
+/// must decode, and the difficulty and timestamp must fit the header.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockHeader {
+    params: ChainParams,
+    previous_hash: [u8; 32],
+    address: Vec<u8>,
+    merkle_root: [u8; 32],
+    timestamp: u64,
+    difficulty: f64,
+    nonce: u32,
+}
+
+/// Fluent construction of a `BlockHeader`, field by field
+///
+/// `new BlockHeaderBuilder().previous_hash(...).address(...)` reads better
+/// in tests and pool tooling than six positional arguments that are easy
+/// to swap. Each setter returns the builder; nothing is checked until
+/// `build()`, which reports every missing field at once and then validates
+/// like the `BlockHeader` constructor. The nonce defaults to 0 and the
+/// layout to the default `ChainParams`.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct BlockHeaderBuilder {
+    params: ChainParams,
+    previous_hash: Option<String>,
+    address: Option<String>,
+    merkle_root: Option<String>,
+    timestamp: Option<u64>,
+    difficulty: Option<f64>,
+    nonce: u32,
+}
+
+#[wasm_bindgen]
+impl BlockHeaderBuilder {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> BlockHeaderBuilder {
+        BlockHeaderBuilder::default()
+    }
+
+    pub fn params(mut self, params: &ChainParams) -> BlockHeaderBuilder {
+        self.params = *params;
+        self
+    }
+
+    pub fn previous_hash(mut self, previous_hash: &str) -> BlockHeaderBuilder {
+        self.previous_hash = Some(previous_hash.to_string());
+        self
+    }
+
+    /// Payout address, as hex or base58
+    pub fn address(mut self, address: &str) -> BlockHeaderBuilder {
+        self.address = Some(address.to_string());
+        self
+    }
+
+    pub fn merkle_root(mut self, merkle_root: &str) -> BlockHeaderBuilder {
+        self.merkle_root = Some(merkle_root.to_string());
+        self
+    }
+
+    /// Unix time in seconds
+    pub fn timestamp(mut self, timestamp: u64) -> BlockHeaderBuilder {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn difficulty(mut self, difficulty: f64) -> BlockHeaderBuilder {
+        self.difficulty = Some(difficulty);
+        self
+    }
+
+    pub fn nonce(mut self, nonce: u32) -> BlockHeaderBuilder {
+        self.nonce = nonce;
+        self
+    }
+
+    /// Validate the fields and build the header; the builder can be reused afterwards
+    pub fn build(&self) -> Result<BlockHeader, JsValue> {
+        let missing: Vec<&str> = [
+            ("previous_hash", self.previous_hash.is_none()),
+            ("address", self.address.is_none()),
+            ("merkle_root", self.merkle_root.is_none()),
+            ("timestamp", self.timestamp.is_none()),
+            ("difficulty", self.difficulty.is_none()),
+        ]
+        .into_iter()
+        .filter_map(|(name, unset)| unset.then_some(name))
+        .collect();
+        if !missing.is_empty() {
+            return Err(error::js_error(
+                ErrorCode::InvalidHeader,
+                format!("Block header is missing {}", missing.join(", ")),
+            ));
+        }
+
+        BlockHeader::parse(
+            &self.params,
+            self.previous_hash.as_deref().unwrap_or_default(),
+            self.address.as_deref().unwrap_or_default(),
+            self.merkle_root.as_deref().unwrap_or_default(),
+            self.timestamp.unwrap_or_default(),
+            self.difficulty.unwrap_or_default(),
+            self.nonce,
+        )
+        .map_err(|e| error::js_error(ErrorCode::InvalidHeader, e))
+    }
+}
+
+/// Parse `build_block_content` output back into its fields
+#[wasm_bindgen]
+pub fn decode_block_content(block_content_hex: &str) -> Result<BlockHeader, JsValue> {
+    decode_block_content_with_params(&ChainParams::default(), block_content_hex)
+}
+
+/// Same as `decode_block_content`, for headers built with explicit params
+#[wasm_bindgen]
+pub fn decode_block_content_with_params(params: &ChainParams, block_content_hex: &str) -> Result<BlockHeader, JsValue> {
+    let bytes = hex::decode(block_content_hex)
+        .map_err(|_| error::js_error(ErrorCode::InvalidHeader, "Block content must be hex"))?;
+    BlockHeader::from_bytes(*params, &bytes).map_err(|e| error::js_error(ErrorCode::InvalidHeader, e))
+}
+
+fn decode_hash(name: &str, value: &str) -> Result<[u8; 32], String> {
+    parse::decode_hex(name, value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("{} must be 32 bytes of hex", name))
+}
+
+#[wasm_bindgen]
+impl BlockHeader {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        previous_hash: &str,
+        address: &str,
+        merkle_root: &str,
+        timestamp: u64,
+        difficulty: f64,
+        nonce: u32,
+    ) -> Result<BlockHeader, JsValue> {
+        BlockHeader::parse(&ChainParams::default(), previous_hash, address, merkle_root, timestamp, difficulty, nonce)
+            .map_err(|e| error::js_error(ErrorCode::InvalidHeader, e))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn params(&self) -> ChainParams {
+        self.params
+    }
+
+    /// Use a non-default header layout
+    pub fn set_params(&mut self, params: &ChainParams) -> Result<(), JsValue> {
+        *self = self.clone().with_params(*params).map_err(|e| error::js_error(ErrorCode::InvalidHeader, e))?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn previous_hash(&self) -> String {
+        hex::encode(self.previous_hash)
+    }
+
+    /// Payout address as hex, whichever form it was given in
+    #[wasm_bindgen(getter)]
+    pub fn address(&self) -> String {
+        hex::encode(&self.address)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn address_bytes(&self) -> Vec<u8> {
+        self.address.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn merkle_root(&self) -> String {
+        hex::encode(self.merkle_root)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn difficulty(&self) -> f64 {
+        self.difficulty
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn nonce(&self) -> u32 {
+        self.nonce
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_nonce(&mut self, nonce: u32) {
+        self.nonce = nonce;
+    }
+
+    /// Serialized header bytes, as submitted
+    pub fn to_bytes(&self) -> Result<Vec<u8>, JsValue> {
+        self.with_bytes(<[u8]>::to_vec)
+    }
+
+    /// Serialized header as hex, identical to `build_block_content`
+    pub fn to_hex(&self) -> Result<String, JsValue> {
+        self.with_bytes(|bytes| hex::encode(bytes))
+    }
+
+    /// SHA-256 of the serialized header, as hex
+    pub fn hash(&self) -> Result<String, JsValue> {
+        self.with_bytes(|bytes| hex::encode(sha::sha256(bytes)))
+    }
+
+    /// Whether the header's hash meets its own difficulty under its params' acceptance rule
+    pub fn meets_difficulty(&self) -> Result<bool, JsValue> {
+        let hash = self.hash()?;
+        let previous_hash = self.previous_hash();
+        Ok(Acceptance::new(&self.params, &previous_hash, self.difficulty).is_ok_and(|policy| policy.accepts(&hash)))
+    }
+
+    /// Highest difficulty the header's hash satisfies (see `achieved_difficulty`)
+    pub fn achieved_difficulty(&self) -> Result<f64, JsValue> {
+        Ok(difficulty::achieved_difficulty(&self.hash()?, &self.previous_hash()))
+    }
+}
+
+impl BlockHeader {
+    /// Serialize into a pooled buffer and pass the bytes to `f`
+    fn with_bytes<T>(&self, f: impl FnOnce(&[u8]) -> T) -> Result<T, JsValue> {
+        buffers::with_buffer(|bytes| {
+            write_prefix(
+                bytes,
+                &self.params,
+                &self.previous_hash(),
+                &self.address,
+                &self.merkle_root(),
+                self.timestamp,
+                self.difficulty,
+            )
+            .map_err(|e| error::js_error(ErrorCode::InvalidHeader, e))?;
+            bytes.extend_from_slice(&self.nonce.to_le_bytes());
+            Ok(f(bytes))
+        })
+    }
+
+    /// Validate and decode header fields for the layout described by `params`
+    pub fn parse(
+        params: &ChainParams,
+        previous_hash: &str,
+        address: &str,
+        merkle_root: &str,
+        timestamp: u64,
+        difficulty: f64,
+        nonce: u32,
+    ) -> Result<BlockHeader, String> {
+        let difficulty = difficulty::validate_difficulty(difficulty)?;
+
+        BlockHeader {
+            params: *params,
+            previous_hash: decode_hash("previous_hash", previous_hash)?,
+            address: string_to_bytes(address)?,
+            merkle_root: decode_hash("merkle_root", merkle_root)?,
+            timestamp,
+            difficulty,
+            nonce,
+        }
+        .with_params(*params)
+    }
+
+    /// Parse serialized header bytes laid out according to `params`
+    ///
+    /// The address length is whatever is left once the fixed-size fields
+    /// are accounted for; 34 leftover bytes starting with the version byte
+    /// are read as a versioned 33-byte compressed address.
+    pub fn from_bytes(params: ChainParams, bytes: &[u8]) -> Result<BlockHeader, String> {
+        let fixed = 32 + 32 + params.timestamp_bytes() + 2 + 4;
+        if bytes.len() <= fixed {
+            return Err(format!("Block content is too short ({} bytes)", bytes.len()));
+        }
+
+        let mut rest = bytes;
+        if rest.len() - fixed == 1 + COMPRESSED_ADDRESS_LEN && rest[0] == COMPRESSED_ADDRESS_VERSION {
+            rest = &rest[1..];
+        }
+
+        let (previous_hash, rest) = rest.split_at(32);
+        let (address, rest) = rest.split_at(rest.len() - (fixed - 32));
+        let (merkle_root, rest) = rest.split_at(32);
+        let (timestamp, rest) = rest.split_at(params.timestamp_bytes());
+        let (difficulty, nonce) = rest.split_at(2);
+
+        let mut timestamp_le = [0u8; 8];
+        timestamp_le[..timestamp.len()].copy_from_slice(timestamp);
+
+        Ok(BlockHeader {
+            params,
+            previous_hash: previous_hash.try_into().map_err(|_| "Invalid previous_hash".to_string())?,
+            address: address.to_vec(),
+            merkle_root: merkle_root.try_into().map_err(|_| "Invalid merkle_root".to_string())?,
+            timestamp: u64::from_le_bytes(timestamp_le),
+            difficulty: u16::from_le_bytes([difficulty[0], difficulty[1]]) as f64 / 10.0,
+            nonce: u32::from_le_bytes([nonce[0], nonce[1], nonce[2], nonce[3]]),
+        })
+    }
+
+    /// Switch layout, checking the address and timestamp still fit
+    pub fn with_params(mut self, params: ChainParams) -> Result<BlockHeader, String> {
+        params.encode_timestamp(self.timestamp, &mut Vec::new())?;
+        self.address = params.normalize_address(self.address)?;
+        self.params = params;
+        Ok(self)
+    }
+}
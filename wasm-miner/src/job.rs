@@ -0,0 +1,869 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+
+use crate::backend;
+use crate::batch::{self, MinerResultBatch};
+use crate::binary::{self, RecordKind};
+use crate::clock;
+use crate::difficulty;
+use crate::error::{self, ErrorCode};
+use crate::header::BlockHeader;
+use crate::kernel::{self, KernelLayout};
+use crate::net;
+use crate::params::ChainParams;
+use crate::parse;
+use crate::policy::Acceptance;
+use crate::profile;
+use crate::ranked::{self, BestHashes};
+use crate::replay;
+use crate::{
+    build_block_content_with_params, mine_prepared, prepare_scan, record_scan, run_scan, scan_result, HeaderFields,
+    MinerResult, StopReason,
+};
+
+/// Bytes per record in the `mine_batch_into` hash buffer
+const BATCH_HASH_BYTES: u32 = 32;
+
+/// Words per record in the `mine_batch_into` record buffer:
+/// `[nonce, hashes_computed, found, next_nonce]`
+const BATCH_RECORD_WORDS: u32 = 4;
+
+/// Node response to `get_mining_info`, optionally wrapped in `{ ok, result }`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TemplateResponse {
+    Wrapped { ok: bool, result: Option<BlockTemplate>, error: Option<String> },
+    Bare(BlockTemplate),
+}
+
+#[derive(Deserialize)]
+struct BlockTemplate {
+    #[serde(deserialize_with = "difficulty::deserialize_difficulty")]
+    difficulty: f64,
+    last_block: LastBlock,
+    merkle_root: String,
+    #[serde(default)]
+    pending_transactions: Vec<String>,
+    #[serde(default)]
+    pending_transactions_hashes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct LastBlock {
+    hash: String,
+    /// Height of the tip; genesis templates may omit it
+    #[serde(default)]
+    id: u64,
+}
+
+/// Work assignment returned by the pool's `/api/work` endpoint
+#[derive(Deserialize)]
+struct PoolWork {
+    #[serde(default)]
+    job_id: Option<String>,
+    block_height: u64,
+    #[serde(deserialize_with = "difficulty::deserialize_difficulty")]
+    difficulty: f64,
+    previous_hash: String,
+    merkle_root: String,
+    timestamp: u64,
+    nonce_start: u32,
+    nonce_end: u32,
+    pool_address: String,
+    /// How long the pool considers the job worth mining
+    #[serde(default)]
+    ttl_ms: Option<f64>,
+}
+
+/// Hashes between event-loop yields in `mine_range_async` when the caller doesn't choose
+const DEFAULT_YIELD_INTERVAL: u32 = 50_000;
+
+/// A unit of work: everything needed to build and hash candidate headers
+///
+/// When the nonce range is exhausted the job can roll an extranonce and
+/// search the range again. Stellaris headers have no coinbase or spare
+/// field, so the extranonce is added to the header timestamp; keep
+/// `max_extranonce` within the node's accepted timestamp drift.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MiningJob {
+    job_id: String,
+    height: u64,
+    previous_hash: String,
+    address: String,
+    merkle_root: String,
+    timestamp: u64,
+    difficulty: f64,
+    transactions: Vec<String>,
+    transaction_hashes: Vec<String>,
+    nonce_start: u32,
+    nonce_end: u32,
+    params: ChainParams,
+    cursor: u32,
+    extranonce: u32,
+    max_extranonce: u32,
+    /// Header prefix built by `prepare`, with the timestamp it was built for
+    #[serde(skip)]
+    prepared: Option<(u64, Vec<u8>)>,
+    /// Monotonic time the job was received, serialized as the job's age since monotonic clocks differ between workers
+    #[serde(serialize_with = "serialize_age", deserialize_with = "deserialize_age")]
+    received_ms: f64,
+    ttl_ms: Option<f64>,
+}
+
+#[wasm_bindgen]
+impl MiningJob {
+    /// Build a solo-mining job from a node block-template response, paying out to `address`
+    pub fn from_block_template(json: &str, address: &str) -> Result<MiningJob, JsValue> {
+        MiningJob::parse_block_template(json, address)
+            .map_err(|e| error::js_error(ErrorCode::InvalidJob, e))?
+            .within_difficulty_limits()
+    }
+
+    /// Build a job from a pool work assignment
+    pub fn from_pool_work(json: &str) -> Result<MiningJob, JsValue> {
+        MiningJob::parse_pool_work(json)
+            .map_err(|e| error::js_error(ErrorCode::InvalidJob, e))?
+            .within_difficulty_limits()
+    }
+
+    /// Build a job from a CBOR-encoded pool work assignment (see `pool_cbor_to_json`)
+    #[cfg(feature = "cbor")]
+    pub fn from_pool_work_cbor(bytes: &[u8]) -> Result<MiningJob, JsValue> {
+        let json = crate::cbor::cbor_to_json(bytes).map_err(|e| error::js_error(ErrorCode::InvalidJob, e))?;
+        MiningJob::from_pool_work(&json)
+    }
+
+    /// Build a job from a protobuf `Job` message (see `proto/stellaris_miner.proto`)
+    #[cfg(feature = "protobuf")]
+    pub fn from_pool_work_protobuf(bytes: &[u8]) -> Result<MiningJob, JsValue> {
+        let json = crate::protobuf::job_to_json(bytes).map_err(|e| error::js_error(ErrorCode::InvalidJob, e))?;
+        MiningJob::from_pool_work(&json)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn job_id(&self) -> String {
+        self.job_id.clone()
+    }
+
+    /// Height of the block this job would create
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Milliseconds since the job was received (or restored from bytes)
+    #[wasm_bindgen(getter)]
+    pub fn age_ms(&self) -> f64 {
+        clock::monotonic_ms() - self.received_ms
+    }
+
+    /// Lifetime the pool gave the job, from `ttl_ms` in its work assignment
+    #[wasm_bindgen(getter)]
+    pub fn ttl_ms(&self) -> Option<f64> {
+        self.ttl_ms
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_ttl_ms(&mut self, ttl_ms: Option<f64>) {
+        self.ttl_ms = ttl_ms;
+    }
+
+    /// Whether the job has outlived its TTL, or `max_age_ms` when given
+    pub fn expired(&self, max_age_ms: Option<f64>) -> bool {
+        let age_ms = self.age_ms();
+        self.ttl_ms.is_some_and(|ttl| age_ms > ttl) || max_age_ms.is_some_and(|max_age| age_ms > max_age)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn previous_hash(&self) -> String {
+        self.previous_hash.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn address(&self) -> String {
+        self.address.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn merkle_root(&self) -> String {
+        self.merkle_root.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn difficulty(&self) -> f64 {
+        self.difficulty
+    }
+
+    /// Hex-encoded transactions that must accompany a block built from this job
+    #[wasm_bindgen(getter)]
+    pub fn transactions(&self) -> Vec<String> {
+        self.transactions.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn transaction_hashes(&self) -> Vec<String> {
+        self.transaction_hashes.clone()
+    }
+
+    /// First nonce assigned to this job
+    #[wasm_bindgen(getter)]
+    pub fn nonce_start(&self) -> u32 {
+        self.nonce_start
+    }
+
+    /// End of the assigned nonce range (exclusive)
+    #[wasm_bindgen(getter)]
+    pub fn nonce_end(&self) -> u32 {
+        self.nonce_end
+    }
+
+    /// Highest extranonce the job may roll to (0 disables rolling)
+    #[wasm_bindgen(getter)]
+    pub fn max_extranonce(&self) -> u32 {
+        self.max_extranonce
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_max_extranonce(&mut self, max_extranonce: u32) {
+        self.max_extranonce = max_extranonce;
+    }
+
+    /// Extranonce currently being searched
+    #[wasm_bindgen(getter)]
+    pub fn extranonce(&self) -> u32 {
+        self.extranonce
+    }
+
+    /// Next nonce `mine_next` will try
+    #[wasm_bindgen(getter)]
+    pub fn cursor(&self) -> u32 {
+        self.cursor
+    }
+
+    /// Use a non-default header layout for this job
+    pub fn set_params(&mut self, params: &ChainParams) {
+        self.params = *params;
+        self.prepared = None;
+    }
+
+    /// Validate the header fields and cache the header prefix
+    ///
+    /// Call on a job that is about to be mined (e.g. between batches of the
+    /// current one) so the switch-over skips decoding the hashes and address.
+    /// A job with bad fields fails here instead of on its first `mine_*` call.
+    pub fn prepare(&mut self) -> Result<(), JsValue> {
+        let (prefix, _policy) = prepare_scan(
+            &self.params,
+            &self.previous_hash,
+            &self.address,
+            &self.merkle_root,
+            self.timestamp,
+            self.difficulty,
+        )
+        .map_err(|(code, e)| error::js_error(code, e))?;
+        self.prepared = Some((self.timestamp, prefix));
+        Ok(())
+    }
+
+    /// Whether `prepare` has cached this job's header prefix
+    #[wasm_bindgen(getter)]
+    pub fn prepared(&self) -> bool {
+        self.prepared.is_some()
+    }
+
+    /// Mine part of this job's nonce space
+    pub fn mine_range(&self, nonce_start: u32, nonce_end: u32, max_hashes: u32) -> Result<MinerResult, JsValue> {
+        let (prefix, policy) = self.scan_setup(self.timestamp)?;
+        let mut result = mine_prepared(
+            &prefix,
+            &policy,
+            &self.previous_hash,
+            self.difficulty,
+            nonce_start,
+            nonce_end,
+            max_hashes,
+        );
+        self.header_fields(&self.previous_hash, self.timestamp).verify_result(&prefix, &policy, &result)?;
+        result.height = self.height;
+        Ok(result)
+    }
+
+    /// `mine_range` that also offers every hash to `best`, which keeps the lowest few for this job
+    ///
+    /// The result is the same as `mine_range`'s. Ranking every hash costs a
+    /// little speed and runs on the calling thread even in Threads mode, so
+    /// use it when the extra hashes are wanted (see `BestHashes`).
+    pub fn mine_range_ranked(
+        &self,
+        nonce_start: u32,
+        nonce_end: u32,
+        max_hashes: u32,
+        best: &mut BestHashes,
+    ) -> Result<MinerResult, JsValue> {
+        best.claim(&self.job_id, &self.previous_hash).map_err(|e| error::js_error(ErrorCode::InvalidArgument, e))?;
+        let (prefix, policy) = self.scan_setup(self.timestamp)?;
+
+        let started_ms = clock::monotonic_ms();
+        let end = nonce_end.min(nonce_start.saturating_add(max_hashes));
+        let scan = {
+            let _span = profile::Span::start("stellaris:hash");
+            ranked::scan_range_ranked(&prefix, &policy, nonce_start, end, best)
+        };
+        let elapsed_ms = clock::monotonic_ms() - started_ms;
+        let mut result =
+            scan_result(scan, &self.previous_hash, self.difficulty, nonce_start, nonce_end, end, elapsed_ms);
+        let (previous_hash, difficulty) = (&self.previous_hash, self.difficulty);
+        replay::record(&prefix, &policy, previous_hash, difficulty, nonce_start, nonce_end, max_hashes, &result);
+        self.header_fields(&self.previous_hash, self.timestamp).verify_result(&prefix, &policy, &result)?;
+        result.height = self.height;
+        Ok(result)
+    }
+
+    /// `mine_range` as a promise that yields to the event loop every `yield_every` hashes
+    ///
+    /// Resolves with one `MinerResult` for the whole call. Smaller intervals
+    /// (default 50,000) keep the page more responsive; larger ones lose less
+    /// time to scheduling. `elapsed_ms` counts hashing time only.
+    pub fn mine_range_async(
+        &self,
+        nonce_start: u32,
+        nonce_end: u32,
+        max_hashes: u32,
+        yield_every: Option<u32>,
+    ) -> js_sys::Promise {
+        let job = self.clone();
+        let yield_every = yield_every.unwrap_or(DEFAULT_YIELD_INTERVAL).max(1);
+        future_to_promise(async move {
+            let end = nonce_end.min(nonce_start.saturating_add(max_hashes));
+            let mut result = job.mine_range(nonce_start, end, yield_every)?;
+            while result.stop_reason == StopReason::MaxHashes {
+                net::sleep(0).await?;
+                result.absorb(job.mine_range(result.next_nonce, end, yield_every)?);
+            }
+
+            // Reaching `end` early means the hash budget ran out, not the range
+            if result.stop_reason == StopReason::RangeEnd && end < nonce_end {
+                result.stop_reason = StopReason::MaxHashes;
+            }
+            Ok(JsValue::from(result))
+        })
+    }
+
+    /// Mine up to `max_hashes` from the job's cursor, rolling the extranonce when the range runs out
+    ///
+    /// Returns `StopReason::RangeEnd` only once every extranonce has been searched.
+    pub fn mine_next(&mut self, max_hashes: u32) -> Result<MinerResult, JsValue> {
+        let timestamp = self.rolled_timestamp(self.extranonce)?;
+        let (prefix, policy) = self.scan_setup(timestamp)?;
+        let mut result = mine_prepared(
+            &prefix,
+            &policy,
+            &self.previous_hash,
+            self.difficulty,
+            self.cursor,
+            self.nonce_end,
+            max_hashes,
+        );
+        self.header_fields(&self.previous_hash, timestamp).verify_result(&prefix, &policy, &result)?;
+        result.extranonce = self.extranonce;
+        result.height = self.height;
+        self.cursor = result.next_nonce;
+
+        if result.stop_reason == StopReason::RangeEnd && self.extranonce < self.max_extranonce {
+            self.extranonce += 1;
+            self.cursor = self.nonce_start;
+            result.stop_reason = StopReason::Rolled;
+        }
+
+        Ok(result)
+    }
+
+    /// Mine the same nonce range on top of two competing tips, alternating slices of `batch_size`
+    ///
+    /// Use during an observed tie at the tip: whichever block the network
+    /// settles on, half the work was spent extending it. The result's `tip`
+    /// says which `previous_hash` the found (or best) hash extends; build the
+    /// block with `with_previous_hash(alternate_previous_hash)` when it is 1.
+    /// `max_hashes` covers both tips together; after a find, the other tip's
+    /// share of that slice is left unsearched.
+    pub fn mine_dual_tip(
+        &self,
+        alternate_previous_hash: &str,
+        nonce_start: u32,
+        nonce_end: u32,
+        max_hashes: u32,
+        batch_size: u32,
+    ) -> Result<MinerResult, JsValue> {
+        if batch_size == 0 {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "batch_size must be positive"));
+        }
+
+        let tips = [self.previous_hash.as_str(), alternate_previous_hash];
+        let scans = tips
+            .iter()
+            .map(|previous_hash| {
+                prepare_scan(
+                    &self.params,
+                    previous_hash,
+                    &self.address,
+                    &self.merkle_root,
+                    self.timestamp,
+                    self.difficulty,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|(code, e)| error::js_error(code, e))?;
+
+        let started_ms = clock::monotonic_ms();
+        let mut best: Option<(u32, u32, String, f64)> = None;
+        let mut hashes_computed = 0u32;
+        let mut start = nonce_start;
+        let mut stop_reason = StopReason::RangeEnd;
+        let mut found = None;
+
+        while start < nonce_end && found.is_none() {
+            let mut remaining = max_hashes.saturating_sub(hashes_computed);
+            if remaining == 0 {
+                stop_reason = StopReason::MaxHashes;
+                break;
+            }
+            // An odd budget gives the first tip the extra hash
+            let end = nonce_end.min(start.saturating_add(batch_size.min(remaining.div_ceil(2))));
+
+            for (tip, (prefix, policy)) in scans.iter().enumerate() {
+                let tip_end = end.min(start.saturating_add(remaining));
+                if tip_end == start {
+                    continue;
+                }
+                let scan = {
+                    let _span = profile::Span::start("stellaris:hash");
+                    run_scan(prefix, policy, start, tip_end)
+                };
+                record_scan(prefix, policy, tips[tip], self.difficulty, start, tip_end, &scan);
+                hashes_computed += scan.hashes_computed;
+                remaining -= scan.hashes_computed;
+
+                let achieved = difficulty::achieved_difficulty(&scan.best_hash, tips[tip]);
+                if best.as_ref().is_none_or(|(_, _, _, best_achieved)| achieved > *best_achieved) {
+                    best = Some((tip as u32, scan.best_nonce, scan.best_hash, achieved));
+                }
+                if let Some((nonce, hash)) = scan.found {
+                    self.header_fields(tips[tip], self.timestamp).verify_found(prefix, policy, nonce, &hash)?;
+                    found = Some((tip as u32, nonce, hash));
+                    break;
+                }
+            }
+            start = end;
+        }
+
+        let elapsed_ms = clock::monotonic_ms() - started_ms;
+        let (best_tip, best_nonce, best_hash, best_difficulty) =
+            best.unwrap_or((0, nonce_start, "f".repeat(64), 0.0));
+
+        let result = match found {
+            Some((tip, nonce, hash)) => MinerResult {
+                found: true,
+                nonce,
+                hash,
+                hashes_computed,
+                best_nonce,
+                best_hash,
+                elapsed_ms,
+                stop_reason: StopReason::Found,
+                next_nonce: nonce.saturating_add(1),
+                extranonce: 0,
+                tip,
+                difficulty: self.difficulty,
+                best_difficulty,
+                height: self.height,
+            },
+            None => MinerResult {
+                found: false,
+                nonce: best_nonce,
+                hash: best_hash.clone(),
+                hashes_computed,
+                best_nonce,
+                best_hash,
+                elapsed_ms,
+                stop_reason,
+                next_nonce: start.max(nonce_start),
+                extranonce: 0,
+                tip: best_tip,
+                difficulty: self.difficulty,
+                best_difficulty,
+                height: self.height,
+            },
+        };
+
+        Ok(result)
+    }
+
+    /// Mine part of this job's nonce space, collecting every hash that meets `share_difficulty`
+    ///
+    /// For pools that want all shares rather than the first, or vardiff
+    /// setups with a share target below the block difficulty. Pass the job
+    /// difficulty (or `None`) to collect every block in the range. Stops
+    /// early once `max_results` hashes are collected.
+    pub fn mine_collect(
+        &self,
+        nonce_start: u32,
+        nonce_end: u32,
+        max_hashes: u32,
+        share_difficulty: Option<f64>,
+        max_results: u32,
+    ) -> Result<MinerResultBatch, JsValue> {
+        let (prefix, block_policy) = self.scan_setup(self.timestamp)?;
+        let share_difficulty = share_difficulty.unwrap_or(self.difficulty);
+        let share_policy = Acceptance::new(&self.params, &self.previous_hash, share_difficulty)
+            .map_err(|e| error::js_error(ErrorCode::InvalidDifficulty, e))?;
+
+        let batch = batch::collect_range(
+            &prefix,
+            &share_policy,
+            &block_policy,
+            &self.previous_hash,
+            share_difficulty,
+            nonce_start,
+            nonce_end,
+            max_hashes,
+            max_results,
+        );
+        let fields = self.header_fields(&self.previous_hash, self.timestamp);
+        for (nonce, hash) in batch.nonces().into_iter().zip(batch.hashes()) {
+            fields.verify_found(&prefix, &share_policy, nonce, &hash)?;
+        }
+        Ok(batch)
+    }
+
+    /// Copy of this job building on a different tip at the same height (see `mine_dual_tip`)
+    pub fn with_previous_hash(&self, previous_hash: &str) -> MiningJob {
+        MiningJob {
+            previous_hash: previous_hash.to_string(),
+            cursor: self.nonce_start,
+            extranonce: 0,
+            prepared: None,
+            ..self.clone()
+        }
+    }
+
+    /// Mine consecutive slices of `batch_size` nonces, writing one record per slice into caller-owned arrays
+    ///
+    /// Slice `i` writes its hash (the found hash, else the best one) as raw
+    /// bytes to `hashes_out[i * 32..]` and `[nonce, hashes_computed, found,
+    /// next_nonce]` to `records_out[i * 4..]`. Nothing is allocated on the JS
+    /// side, so the same arrays can be reused for every call. Stops after a
+    /// found block, at `nonce_end`, or when either array is full, and returns
+    /// the number of records written.
+    pub fn mine_batch_into(
+        &self,
+        nonce_start: u32,
+        nonce_end: u32,
+        batch_size: u32,
+        hashes_out: &js_sys::Uint8Array,
+        records_out: &js_sys::Uint32Array,
+    ) -> Result<u32, JsValue> {
+        if batch_size == 0 {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "batch_size must be positive"));
+        }
+
+        let capacity = (hashes_out.length() / BATCH_HASH_BYTES).min(records_out.length() / BATCH_RECORD_WORDS);
+        let (prefix, policy) = self.scan_setup(self.timestamp)?;
+
+        let fields = self.header_fields(&self.previous_hash, self.timestamp);
+
+        let mut start = nonce_start;
+        let mut written = 0;
+        let mut hash = [0u8; BATCH_HASH_BYTES as usize];
+
+        while written < capacity && start < nonce_end {
+            let end = nonce_end.min(start.saturating_add(batch_size));
+            let scan = {
+                let _span = profile::Span::start("stellaris:hash");
+                run_scan(&prefix, &policy, start, end)
+            };
+            record_scan(&prefix, &policy, &self.previous_hash, self.difficulty, start, end, &scan);
+
+            let found = scan.found.is_some();
+            let (nonce, hash_hex) = scan.found.unwrap_or((scan.best_nonce, scan.best_hash));
+            if found {
+                fields.verify_found(&prefix, &policy, nonce, &hash_hex)?;
+            }
+            let next_nonce = if found { nonce.saturating_add(1) } else { end };
+
+            hex::decode_to_slice(&hash_hex, &mut hash)
+                .map_err(|e| error::js_error(ErrorCode::InvalidHash, e.to_string()))?;
+            let offset = written * BATCH_HASH_BYTES;
+            hashes_out.subarray(offset, offset + BATCH_HASH_BYTES).copy_from(&hash);
+
+            let offset = written * BATCH_RECORD_WORDS;
+            records_out
+                .subarray(offset, offset + BATCH_RECORD_WORDS)
+                .copy_from(&[nonce, scan.hashes_computed, found as u32, next_nonce]);
+
+            written += 1;
+            if found {
+                break;
+            }
+            start = end;
+        }
+
+        Ok(written)
+    }
+
+    /// Midstate, padded tail, and match pattern for hashing this job on a GPU (see `webgl.js`)
+    ///
+    /// Returns `{ midstate, tail, tail_blocks, nonce_word, nonce_shift,
+    /// match_value, match_mask, fraction_nibble, fraction_limit }` at the
+    /// job's base timestamp. Throws `InvalidArgument` under the `Target`
+    /// acceptance rule, which a prefix match can't express.
+    pub fn kernel_layout(&self) -> Result<JsValue, JsValue> {
+        let (prefix, policy) = self.scan_setup(self.timestamp)?;
+        let layout = KernelLayout::new(&prefix, &policy).map_err(|e| error::js_error(ErrorCode::InvalidArgument, e))?;
+        serde_wasm_bindgen::to_value(&layout).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Verify a GPU kernel's pass over `nonce_start..nonce_end` and report it like `mine_range`
+    ///
+    /// `candidates` are the nonces the kernel flagged, in order, and
+    /// `best_nonce` the one with the lowest digest it saw. All are re-hashed
+    /// on the CPU; the first that really meets the difficulty is the find.
+    pub fn confirm_kernel_batch(
+        &self,
+        nonce_start: u32,
+        nonce_end: u32,
+        candidates: &[u32],
+        best_nonce: u32,
+        elapsed_ms: f64,
+    ) -> Result<MinerResult, JsValue> {
+        let (prefix, policy) = self.scan_setup(self.timestamp)?;
+        let mut result = kernel::confirm(
+            &prefix,
+            &policy,
+            &self.previous_hash,
+            self.difficulty,
+            nonce_start,
+            nonce_end,
+            candidates,
+            best_nonce,
+            elapsed_ms,
+        );
+        self.header_fields(&self.previous_hash, self.timestamp).verify_result(&prefix, &policy, &result)?;
+        result.height = self.height;
+        Ok(result)
+    }
+
+    /// The full header this job produces for `nonce` at a given extranonce
+    pub fn header(&self, nonce: u32, extranonce: u32) -> Result<BlockHeader, JsValue> {
+        let timestamp = self.rolled_timestamp(extranonce)?;
+        BlockHeader::parse(
+            &self.params,
+            &self.previous_hash,
+            &self.address,
+            &self.merkle_root,
+            timestamp,
+            self.difficulty,
+            nonce,
+        )
+        .map_err(|e| error::js_error(ErrorCode::InvalidHeader, e))
+    }
+
+    /// Serialize the header for `nonce` as hex, ready for submission
+    pub fn build_block_content(&self, nonce: u32) -> Result<String, JsValue> {
+        self.build_block_content_rolled(nonce, 0)
+    }
+
+    /// Serialize the header for `nonce` at a given extranonce (see `MinerResult.extranonce`)
+    pub fn build_block_content_rolled(&self, nonce: u32, extranonce: u32) -> Result<String, JsValue> {
+        build_block_content_with_params(
+            &self.params,
+            &self.previous_hash,
+            &self.address,
+            &self.merkle_root,
+            self.rolled_timestamp(extranonce)?,
+            self.difficulty,
+            nonce,
+        )
+    }
+
+    /// Serialize to the compact binary form, e.g. to hand the job to another worker
+    ///
+    /// Keeps the mining progress (cursor and extranonce) but not the header
+    /// prefix cached by `prepare`, which the receiver rebuilds on first use.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary::encode(RecordKind::Job, self)
+    }
+
+    /// Restore a job previously produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<MiningJob, JsValue> {
+        binary::decode(bytes, RecordKind::Job).map_err(|e| error::js_error(ErrorCode::InvalidRecord, e))
+    }
+}
+
+fn serialize_age<S: Serializer>(received_ms: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(clock::monotonic_ms() - received_ms)
+}
+
+fn deserialize_age<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    f64::deserialize(deserializer).map(|age_ms| clock::monotonic_ms() - age_ms)
+}
+
+impl MiningJob {
+    /// Header prefix and acceptance policy at `timestamp`, reusing the prefix from `prepare` when it matches
+    fn scan_setup(&self, timestamp: u64) -> Result<(Cow<'_, [u8]>, Acceptance<'_>), JsValue> {
+        match &self.prepared {
+            Some((prepared_timestamp, prefix)) if *prepared_timestamp == timestamp => Ok((
+                Cow::Borrowed(prefix.as_slice()),
+                Acceptance::new(&self.params, &self.previous_hash, self.difficulty)
+                    .map_err(|e| error::js_error(ErrorCode::InvalidDifficulty, e))?,
+            )),
+            _ => prepare_scan(
+                &self.params,
+                &self.previous_hash,
+                &self.address,
+                &self.merkle_root,
+                timestamp,
+                self.difficulty,
+            )
+            .map(|(prefix, policy)| (Cow::Owned(prefix), policy))
+            .map_err(|(code, e)| error::js_error(code, e)),
+        }
+    }
+
+    /// This job's header fields on top of `previous_hash` at `timestamp`, for checking a find
+    fn header_fields<'a>(&'a self, previous_hash: &'a str, timestamp: u64) -> HeaderFields<'a> {
+        HeaderFields {
+            params: &self.params,
+            previous_hash,
+            address: &self.address,
+            merkle_root: &self.merkle_root,
+            timestamp,
+            difficulty: self.difficulty,
+        }
+    }
+
+    /// Header timestamp for a given extranonce
+    fn rolled_timestamp(&self, extranonce: u32) -> Result<u64, JsValue> {
+        self.timestamp
+            .checked_add(extranonce as u64)
+            .ok_or_else(|| error::js_error(ErrorCode::InvalidHeader, "Extranonce overflows the timestamp"))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "parse_block_template", level = "debug", skip_all))]
+    fn parse_block_template(json: &str, address: &str) -> Result<MiningJob, String> {
+        let _span = profile::Span::start("stellaris:parse-job");
+        let response: TemplateResponse = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid block template: {}", e))?;
+
+        let template = match response {
+            TemplateResponse::Bare(template) => template,
+            TemplateResponse::Wrapped { ok: true, result: Some(template), .. } => template,
+            TemplateResponse::Wrapped { error, .. } => {
+                return Err(format!(
+                    "Node returned error: {}",
+                    error.unwrap_or_else(|| "missing result".to_string())
+                ));
+            }
+        };
+
+        // Every transaction needs its hash so the merkle root can be re-derived on submission
+        if template.pending_transactions.len() != template.pending_transactions_hashes.len() {
+            return Err(format!(
+                "Block template lists {} transactions but {} hashes",
+                template.pending_transactions.len(),
+                template.pending_transactions_hashes.len()
+            ));
+        }
+
+        if template.pending_transactions.iter().any(|tx| parse::decode_hex("transaction", tx).is_err()) {
+            return Err("Block template contains a non-hex transaction".to_string());
+        }
+
+        let merkle_prefix = template.merkle_root.get(..16).unwrap_or(&template.merkle_root);
+        let height = template.last_block.id.checked_add(1)
+            .ok_or_else(|| "Block template tip height is out of range".to_string())?;
+
+        Ok(MiningJob {
+            job_id: format!("{}-{}", height, merkle_prefix),
+            height,
+            previous_hash: template.last_block.hash,
+            address: address.to_string(),
+            merkle_root: template.merkle_root,
+            timestamp: (clock::network_now_ms() / 1000.0) as u64,
+            difficulty: template.difficulty,
+            transactions: template.pending_transactions,
+            transaction_hashes: template.pending_transactions_hashes,
+            nonce_start: 0,
+            nonce_end: u32::MAX,
+            params: ChainParams::default(),
+            cursor: 0,
+            extranonce: 0,
+            max_extranonce: 0,
+            prepared: None,
+            received_ms: clock::monotonic_ms(),
+            ttl_ms: None,
+        })
+    }
+
+    /// Hold a freshly parsed job to `set_difficulty_limits`
+    fn within_difficulty_limits(mut self) -> Result<MiningJob, JsValue> {
+        self.difficulty = difficulty::check_difficulty_limits(self.difficulty)
+            .map_err(|e| error::js_error(ErrorCode::DifficultyOutOfRange, e))?;
+        Ok(self)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "parse_pool_work", level = "debug", skip_all))]
+    fn parse_pool_work(json: &str) -> Result<MiningJob, String> {
+        let _span = profile::Span::start("stellaris:parse-job");
+        let work: PoolWork = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid pool work: {}", e))?;
+
+        Ok(MiningJob {
+            job_id: work.job_id.unwrap_or_else(|| {
+                format!("{}-{}-{}", work.block_height, work.nonce_start, work.nonce_end)
+            }),
+            height: work.block_height,
+            previous_hash: work.previous_hash,
+            address: work.pool_address,
+            merkle_root: work.merkle_root,
+            timestamp: work.timestamp,
+            difficulty: work.difficulty,
+            transactions: Vec::new(),
+            transaction_hashes: Vec::new(),
+            nonce_start: work.nonce_start,
+            nonce_end: work.nonce_end,
+            params: ChainParams::default(),
+            cursor: work.nonce_start,
+            extranonce: 0,
+            max_extranonce: 0,
+            prepared: None,
+            received_ms: clock::monotonic_ms(),
+            ttl_ms: work.ttl_ms,
+        })
+    }
+}
+
+/// SHA-256 digests of `job`'s header for each of `nonces`, concatenated 32 bytes apiece
+///
+/// Hashes an arbitrary list in one call, e.g. to re-check nonces reported
+/// by another worker or a GPU kernel. Uses the job's base timestamp
+/// (extranonce 0) and the active hash backend.
+#[wasm_bindgen]
+pub fn hash_headers(job: &MiningJob, nonces: &[u32]) -> Result<Vec<u8>, JsValue> {
+    let (prefix, _policy) = job.scan_setup(job.timestamp)?;
+    let mut hasher = backend::PreparedHasher::new(backend::hash_backend(), &prefix);
+
+    let mut digests = Vec::with_capacity(nonces.len() * BATCH_HASH_BYTES as usize);
+    for &nonce in nonces {
+        digests.extend_from_slice(&hasher.hash(nonce));
+    }
+    Ok(digests)
+}
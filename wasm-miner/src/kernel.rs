@@ -0,0 +1,128 @@
+use serde::Serialize;
+
+use crate::backend::{HashBackend, PreparedHasher};
+use crate::logging::{self, LogLevel};
+use crate::policy::{self, Acceptance, DifficultyPolicy};
+use crate::sha::HeaderHasher;
+use crate::{difficulty, MinerResult, StopReason};
+
+/// Leading hex characters a kernel can test: the first four digest words
+const MATCH_NIBBLES: usize = 32;
+
+/// Everything a GPU kernel needs to hash one job's headers and flag candidates
+///
+/// Words are big-endian SHA-256 words. A kernel ORs the nonce's
+/// byte-swapped value into `tail[nonce_word]` (shifted right by
+/// `nonce_shift`, spilling into the next word when that is non-zero),
+/// compresses `tail_blocks` blocks from `midstate`, and flags the nonce when
+/// `digest[0..4] & match_mask == match_value` and hex digit `fraction_nibble`
+/// is below `fraction_limit`. Flagged nonces are only candidates until
+/// `MiningJob.confirm_kernel_batch` re-hashes them.
+#[derive(Serialize)]
+pub struct KernelLayout {
+    midstate: [u32; 8],
+    tail: [u32; 32],
+    tail_blocks: u32,
+    nonce_word: u32,
+    nonce_shift: u32,
+    match_value: [u32; 4],
+    match_mask: [u32; 4],
+    fraction_nibble: u32,
+    fraction_limit: u32,
+}
+
+impl KernelLayout {
+    /// Layout for `prefix` under `policy`; fails for rules that aren't a hash prefix
+    pub fn new(prefix: &[u8], policy: &Acceptance) -> Result<KernelLayout, String> {
+        let (required, difficulty) = policy
+            .required_prefix()
+            .ok_or_else(|| "Kernels only support prefix acceptance rules, not Target".to_string())?;
+        if required.len() >= MATCH_NIBBLES {
+            return Err(format!("Kernels match at most {} hex digits", MATCH_NIBBLES - 1));
+        }
+
+        let mut match_value = [0u32; 4];
+        let mut match_mask = [0u32; 4];
+        for (i, digit) in required.chars().enumerate() {
+            let digit = digit.to_digit(16).ok_or_else(|| format!("Required prefix {:?} isn't hex", required))?;
+            let shift = 28 - 4 * (i % 8) as u32;
+            match_value[i / 8] |= digit << shift;
+            match_mask[i / 8] |= 0xf << shift;
+        }
+
+        let hasher = HeaderHasher::new(prefix);
+        Ok(KernelLayout {
+            midstate: hasher.midstate(),
+            tail: hasher.tail(),
+            tail_blocks: hasher.tail_blocks() as u32,
+            nonce_word: hasher.nonce_word() as u32,
+            nonce_shift: hasher.nonce_shift(),
+            match_value,
+            match_mask,
+            fraction_nibble: required.len() as u32,
+            fraction_limit: policy::fraction_limit(difficulty) as u32,
+        })
+    }
+}
+
+/// Turn a kernel's report for `nonce_start..nonce_end` into a `MinerResult`
+///
+/// Every candidate and `best_nonce` is re-hashed with the reference backend,
+/// so a miscompiled shader can cost hashrate but never a bad submission.
+/// Candidates that don't verify are logged and dropped.
+#[allow(clippy::too_many_arguments)]
+pub fn confirm(
+    prefix: &[u8],
+    policy: &Acceptance,
+    previous_hash: &str,
+    difficulty: f64,
+    nonce_start: u32,
+    nonce_end: u32,
+    candidates: &[u32],
+    best_nonce: u32,
+    elapsed_ms: f64,
+) -> MinerResult {
+    let mut hasher = PreparedHasher::new(HashBackend::Scalar, prefix);
+    let mut best = (hasher.hash(best_nonce), best_nonce);
+    let mut found = None;
+
+    for &nonce in candidates.iter().filter(|nonce| (nonce_start..nonce_end).contains(nonce)) {
+        let hash = hasher.hash(nonce);
+        best = best.min((hash, nonce));
+
+        let hash_hex = hex::encode(hash);
+        if policy.accepts(&hash_hex) {
+            found = Some((nonce, hash_hex));
+            break;
+        }
+        logging::emit(
+            LogLevel::Warn,
+            "kernel",
+            &format!("Kernel flagged nonce {} but its hash {} doesn't meet the difficulty", nonce, hash_hex),
+        );
+    }
+
+    let best_hash = hex::encode(best.0);
+    let best_difficulty = difficulty::achieved_difficulty(&best_hash, previous_hash);
+    let (found, nonce, hash, stop_reason, next_nonce) = match found {
+        Some((nonce, hash)) => (true, nonce, hash, StopReason::Found, nonce.saturating_add(1)),
+        None => (false, best.1, best_hash.clone(), StopReason::RangeEnd, nonce_end.max(nonce_start)),
+    };
+
+    MinerResult {
+        found,
+        nonce,
+        hash,
+        hashes_computed: next_nonce - nonce_start,
+        best_nonce: best.1,
+        best_hash,
+        elapsed_ms,
+        stop_reason,
+        next_nonce,
+        extranonce: 0,
+        tip: 0,
+        difficulty,
+        best_difficulty,
+        height: 0,
+    }
+}
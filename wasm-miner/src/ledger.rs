@@ -0,0 +1,98 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::csv::{self, CsvTable};
+use crate::reject::RejectReason;
+
+/// Most share records kept for querying; older ones only survive in the counters
+pub const MAX_LEDGER_RECORDS: usize = 10_000;
+
+/// How the pool credited a submitted share
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ShareStatus {
+    Accepted,
+    Rejected,
+    Stale,
+}
+
+/// One submitted share and the pool's verdict on it
+#[derive(Clone, Debug, Serialize)]
+pub struct ShareRecord {
+    pub job_id: String,
+    /// Height of the block the share's job built, when the session still knows the job
+    pub height: Option<u64>,
+    pub nonce: u32,
+    pub hash: String,
+    pub status: ShareStatus,
+    pub reject_reason: Option<RejectReason>,
+    pub reason: Option<String>,
+    pub timestamp_ms: f64,
+}
+
+/// Session-wide record of submitted shares
+#[derive(Default)]
+pub struct ShareLedger {
+    records: VecDeque<ShareRecord>,
+    accepted: u32,
+    rejected: u32,
+    stale: u32,
+}
+
+impl ShareLedger {
+    pub fn record(&mut self, record: ShareRecord) {
+        match record.status {
+            ShareStatus::Accepted => self.accepted += 1,
+            ShareStatus::Rejected => self.rejected += 1,
+            ShareStatus::Stale => self.stale += 1,
+        }
+
+        if self.records.len() == MAX_LEDGER_RECORDS {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    pub fn accepted(&self) -> u32 {
+        self.accepted
+    }
+
+    pub fn rejected(&self) -> u32 {
+        self.rejected
+    }
+
+    pub fn stale(&self) -> u32 {
+        self.stale
+    }
+
+    /// Number of records currently retained
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Retained records in submission order, skipping `offset` and returning at most `limit`
+    pub fn page(&self, offset: usize, limit: usize) -> Vec<&ShareRecord> {
+        self.records.iter().skip(offset).take(limit).collect()
+    }
+
+    /// Retained records as CSV, oldest first
+    pub fn to_csv(&self) -> String {
+        let mut table =
+            CsvTable::new(&["time", "timestamp_ms", "job_id", "nonce", "hash", "status", "reject_reason", "reason"]);
+        for record in &self.records {
+            table.row(&[
+                csv::iso_timestamp(record.timestamp_ms),
+                record.timestamp_ms.to_string(),
+                record.job_id.clone(),
+                record.nonce.to_string(),
+                record.hash.clone(),
+                format!("{:?}", record.status),
+                record.reject_reason.map(|reason| format!("{:?}", reason)).unwrap_or_default(),
+                record.reason.clone().unwrap_or_default(),
+            ]);
+        }
+        table.finish()
+    }
+}
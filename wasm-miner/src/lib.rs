@@ -1,57 +1,169 @@
 use wasm_bindgen::prelude::*;
-use sha2::{Sha256, Digest};
 use std::cmp::min;
+use serde::{Deserialize, Serialize};
 
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-}
+use binary::RecordKind;
+use params::{COMPRESSED_ADDRESS_LEN, COMPRESSED_ADDRESS_VERSION};
+use policy::{Acceptance, DifficultyPolicy};
+
+mod address;
+mod backend;
+mod batch;
+mod batcher;
+mod binary;
+mod buffers;
+#[cfg(feature = "cbor")]
+mod cbor;
+mod checkpoint;
+mod clock;
+mod commitment;
+mod compress;
+mod concurrency;
+mod csv;
+mod difficulty;
+mod error;
+mod events;
+mod format;
+#[cfg(feature = "test-fixtures")]
+pub mod fixtures;
+mod header;
+mod job;
+mod kernel;
+mod ledger;
+mod logging;
+mod luck;
+mod mailbox;
+mod memory;
+mod mock;
+mod net;
+mod node;
+mod outbox;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod params;
+mod parse;
+mod payout;
+mod policy;
+mod profile;
+#[cfg(feature = "protobuf")]
+mod protobuf;
+mod protocol;
+mod queue;
+mod ranked;
+mod receipt;
+mod reject;
+mod replay;
+mod seeded;
+mod segment;
+mod selftest;
+mod session;
+mod sha;
+mod share;
+mod state;
+mod stats;
+mod swap;
+mod telemetry;
+mod throttle;
+mod timesync;
+mod transcode;
+mod tried;
+mod unrolled;
+mod validate;
+mod version;
+mod workers;
+
+pub use address::{address_info, address_to_base58, address_to_hex};
+pub use backend::{auto_select_backend, hash_backend, set_hash_backend, HashBackend};
+pub use batch::MinerResultBatch;
+pub use batcher::ShareBatcher;
+#[cfg(feature = "cbor")]
+pub use cbor::{pool_cbor_to_json, pool_json_to_cbor};
+pub use clock::{set_clock, set_clock_callback, set_mock_clock, ClockGuard, ClockKind, MockClock};
+pub use commitment::verify_progress_opening;
+pub use compress::decompress_payload;
+pub use concurrency::{
+    choose_concurrency_mode, combine_worker_results, concurrency_mode, cross_origin_isolated, set_concurrency_mode,
+    threads_compiled, ConcurrencyMode,
+};
+#[cfg(all(feature = "parallel", target_arch = "wasm32"))]
+pub use concurrency::start_thread_pool;
+pub use csv::CsvKind;
+pub use difficulty::{
+    achieved_difficulty, difficulty_1_hashes, difficulty_to_chunk, difficulty_to_expected_hashes, difficulty_to_target,
+    difficulty_to_valid_chars, eta_seconds, expected_hashes_to_difficulty, hash_distance, hash_meets_difficulty,
+    hashes_to_shares, max_difficulty, min_difficulty, parse_difficulty, set_difficulty_limits, share_value,
+    shares_to_hashes, suggest_share_difficulty, target_to_difficulty,
+};
+pub use error::ErrorCode;
+pub use events::EventKind;
+pub use format::{format_difficulty, format_duration, format_hashrate};
+pub use header::{decode_block_content, decode_block_content_with_params, BlockHeader, BlockHeaderBuilder};
+pub use job::{hash_headers, MiningJob};
+pub use ledger::ShareStatus;
+pub use logging::{set_log_level, set_log_sink, LogLevel};
+pub use mailbox::{MailboxRecord, ResultMailbox};
+pub use memory::{memory_usage, reset_memory_peak, set_memory_limit};
+pub use mock::{generate_mock_job, MockJob};
+pub use net::HttpClient;
+pub use node::{
+    get_balance, parse_address_info, submit_block, watch_tip, AddressBalance, SubmitOutcome, SubmitResult, TipWatcher,
+};
+pub use outbox::SubmissionQueue;
+#[cfg(all(feature = "parallel", target_arch = "wasm32"))]
+pub use wasm_bindgen_rayon::init_thread_pool;
+pub use params::{AcceptanceRule, AddressPolicy, ChainParams, TimestampWidth};
+pub use parse::{parse_mode, set_parse_mode, ParseMode};
+pub use payout::{estimate_earnings, PayoutScheme};
+pub use profile::{profiling_enabled, set_profiling};
+#[cfg(feature = "protobuf")]
+pub use protobuf::{pool_work_from_protobuf, pool_work_to_protobuf, share_from_protobuf, share_to_protobuf};
+pub use protocol::{
+    benchmark_worker_encoding, encode_worker_message, parse_worker_message, worker_message_result,
+    worker_range_message, worker_result_message, WorkerMessage, WorkerMessageValue,
+};
+pub use ranked::BestHashes;
+pub use receipt::{verify_share_receipt, ShareSigner};
+pub use reject::{parse_reject_reason, reject_action, RejectAction, RejectReason};
+pub use replay::{is_recording, replay, start_recording, stop_recording};
+pub use seeded::{seeded_mode, set_seeded_mode};
+pub use selftest::{self_test, self_test_result, SelfTestReport};
+pub use session::MinerSession;
+pub use share::BestShare;
+pub use state::MinerState;
+pub use swap::{publish_job, published_job_generation, take_published_job};
+pub use telemetry::TelemetryExporter;
+pub use throttle::BackgroundPolicy;
+pub use tried::TriedNonceFilter;
+pub use validate::{validate_share, ShareValidation};
+pub use version::version_info;
+pub use workers::WorkerStats;
 
 /// Convert address string to bytes, supporting both hex and base58 formats
 fn string_to_bytes(address: &str) -> Result<Vec<u8>, String> {
-    // Try hex first
-    if let Ok(bytes) = hex::decode(address) {
-        return Ok(bytes);
-    }
-    
-    // Try base58
-    match bs58::decode(address).into_vec() {
-        Ok(bytes) => Ok(bytes),
-        Err(_) => Err("Invalid address format".to_string())
-    }
+    address::decode_address(address).map(|(bytes, _encoding)| bytes)
 }
 
-/// Calculate SHA256 hash of data
-fn sha256(data: &[u8]) -> Vec<u8> {
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    hasher.finalize().to_vec()
-}
-
-/// Check if a block hash meets the difficulty requirement
-fn check_difficulty(hash_hex: &str, chunk: &str, difficulty: f64) -> bool {
-    if !hash_hex.starts_with(chunk) {
-        return false;
-    }
-    
-    let decimal = difficulty % 1.0;
-    if decimal > 0.0 {
-        let charset = "0123456789abcdef";
-        let count = (16.0 * (1.0 - decimal)).ceil() as usize;
-        let valid_chars = &charset[..count];
-        let idifficulty = difficulty as usize;
-        
-        if let Some(char_at_pos) = hash_hex.chars().nth(idifficulty) {
-            return valid_chars.contains(char_at_pos);
-        }
-        return false;
-    }
-    
-    true
+/// Why a `mine_range` call stopped hashing
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StopReason {
+    /// A hash meeting the difficulty was found
+    Found,
+    /// The end of the nonce range was reached
+    RangeEnd,
+    /// The `max_hashes` budget for this call was used up
+    MaxHashes,
+    /// The nonce range ran out and the job rolled to its next extranonce; call again to continue
+    Rolled,
 }
 
+/// Outcome of one mining call
+///
+/// Results are plain values: they can be compared, round-tripped through
+/// `to_json`/`from_json` to hand them between workers or store them, and
+/// built directly with the constructor in tests.
 #[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MinerResult {
     found: bool,
     nonce: u32,
@@ -59,10 +171,55 @@ pub struct MinerResult {
     hashes_computed: u32,
     best_nonce: u32,
     best_hash: String,
+    elapsed_ms: f64,
+    stop_reason: StopReason,
+    next_nonce: u32,
+    extranonce: u32,
+    tip: u32,
+    difficulty: f64,
+    best_difficulty: f64,
+    /// Height of the block the job builds, or 0 when mined without a job
+    #[serde(default)]
+    height: u64,
 }
 
 #[wasm_bindgen]
 impl MinerResult {
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        found: bool,
+        nonce: u32,
+        hash: String,
+        hashes_computed: u32,
+        best_nonce: u32,
+        best_hash: String,
+        elapsed_ms: f64,
+        stop_reason: StopReason,
+        next_nonce: u32,
+        extranonce: u32,
+        tip: u32,
+        difficulty: f64,
+        best_difficulty: f64,
+    ) -> MinerResult {
+        MinerResult {
+            found,
+            nonce,
+            hash,
+            hashes_computed,
+            best_nonce,
+            best_hash,
+            elapsed_ms,
+            stop_reason,
+            next_nonce,
+            extranonce,
+            tip,
+            difficulty,
+            best_difficulty,
+            height: 0,
+        }
+    }
+
     #[wasm_bindgen(getter)]
     pub fn found(&self) -> bool {
         self.found
@@ -93,13 +250,214 @@ impl MinerResult {
         self.best_hash.clone()
     }
     
+    #[wasm_bindgen(getter)]
+    pub fn stop_reason(&self) -> StopReason {
+        self.stop_reason
+    }
+    
+    /// True when the whole nonce range has been searched
+    #[wasm_bindgen(getter)]
+    pub fn exhausted(&self) -> bool {
+        self.stop_reason == StopReason::RangeEnd
+    }
+    
+    /// Nonce to resume from in the next call
+    #[wasm_bindgen(getter)]
+    pub fn next_nonce(&self) -> u32 {
+        self.next_nonce
+    }
+    
+    /// Extranonce the header was built with (0 unless the job rolled)
+    #[wasm_bindgen(getter)]
+    pub fn extranonce(&self) -> u32 {
+        self.extranonce
+    }
+    
+    /// Which tip the header extends: 0 for the job's `previous_hash`, 1 for the
+    /// alternate passed to `mine_dual_tip`
+    #[wasm_bindgen(getter)]
+    pub fn tip(&self) -> u32 {
+        self.tip
+    }
+    
+    /// Difficulty the range was mined against
+    #[wasm_bindgen(getter)]
+    pub fn difficulty(&self) -> f64 {
+        self.difficulty
+    }
+    
+    /// Difficulty actually achieved by `best_hash` (see `achieved_difficulty`)
+    #[wasm_bindgen(getter)]
+    pub fn best_difficulty(&self) -> f64 {
+        self.best_difficulty
+    }
+    
+    /// Height of the block the mined job builds, so shares can be matched to blocks
+    ///
+    /// Set by `MiningJob`'s mining methods; 0 for results from `mine_range`
+    /// with bare header fields or from a `ResultMailbox` slot.
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_height(&mut self, height: u64) {
+        self.height = height;
+    }
+    
+    /// Wall-clock time spent in the mining loop
+    #[wasm_bindgen(getter)]
+    pub fn elapsed_ms(&self) -> f64 {
+        self.elapsed_ms
+    }
+    
+    /// Hashrate achieved by this call, or 0 if it finished too quickly to measure
+    #[wasm_bindgen(getter)]
+    pub fn hashes_per_second(&self) -> f64 {
+        if self.elapsed_ms > 0.0 {
+            self.hashes_computed as f64 * 1000.0 / self.elapsed_ms
+        } else {
+            0.0
+        }
+    }
+    
     #[wasm_bindgen(getter)]
     pub fn block_content_hex(&self) -> String {
         "".to_string() // Will be computed in JS when needed
     }
+
+    /// Serialize to JSON, e.g. to post to another worker or store
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        serde_json::to_string(self).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Restore a result previously produced by `to_json`
+    pub fn from_json(json: &str) -> Result<MinerResult, JsValue> {
+        serde_json::from_str(json).map_err(|e| error::js_error(ErrorCode::InvalidRecord, e.to_string()))
+    }
+
+    /// Serialize to the compact binary form, cheaper than `to_json` to build, post and parse
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary::encode(RecordKind::Result, self)
+    }
+
+    /// Restore a result previously produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<MinerResult, JsValue> {
+        binary::decode(bytes, RecordKind::Result).map_err(|e| error::js_error(ErrorCode::InvalidRecord, e))
+    }
+}
+
+impl MinerResult {
+    /// Fold in the result of mining the range straight after this one
+    ///
+    /// Hash counts and hashing time add up; the best hash is the better of
+    /// the two, and everything describing where the call ended comes from `next`.
+    fn absorb(&mut self, next: MinerResult) {
+        if next.best_hash < self.best_hash {
+            self.best_nonce = next.best_nonce;
+            self.best_hash = next.best_hash;
+            self.best_difficulty = next.best_difficulty;
+            self.tip = next.tip;
+        }
+        self.hashes_computed = self.hashes_computed.saturating_add(next.hashes_computed);
+        self.elapsed_ms += next.elapsed_ms;
+        self.found = next.found;
+        self.nonce = next.nonce;
+        self.hash = next.hash;
+        self.stop_reason = next.stop_reason;
+        self.next_nonce = next.next_nonce;
+        self.extranonce = next.extranonce;
+        self.height = next.height;
+    }
+
+    /// Fold in the result of mining another part of the same range at the same time
+    ///
+    /// Unlike `absorb`, order doesn't matter: the lower found nonce and the
+    /// later stopping point win, and elapsed time is the longer of the two.
+    fn merge(&mut self, other: MinerResult) {
+        let found_first = other.found && (!self.found || other.nonce < self.nonce);
+        let ends_later = !self.found && !other.found && other.next_nonce > self.next_nonce;
+        if found_first || ends_later {
+            self.found = other.found;
+            self.nonce = other.nonce;
+            self.hash = other.hash;
+            self.stop_reason = other.stop_reason;
+            self.next_nonce = other.next_nonce;
+            self.extranonce = other.extranonce;
+        }
+        if other.best_hash < self.best_hash {
+            self.best_nonce = other.best_nonce;
+            self.best_hash = other.best_hash;
+            self.best_difficulty = other.best_difficulty;
+            self.tip = other.tip;
+        }
+        self.hashes_computed = self.hashes_computed.saturating_add(other.hashes_computed);
+        self.elapsed_ms = self.elapsed_ms.max(other.elapsed_ms);
+        self.height = self.height.max(other.height);
+    }
+}
+
+/// Build the header bytes that precede the nonce
+fn build_prefix(
+    params: &ChainParams,
+    previous_hash: &str,
+    address_bytes: &[u8],
+    merkle_root: &str,
+    timestamp: u64,
+    difficulty: f64,
+) -> Result<Vec<u8>, String> {
+    let mut prefix = Vec::new();
+    write_prefix(&mut prefix, params, previous_hash, address_bytes, merkle_root, timestamp, difficulty)?;
+    Ok(prefix)
+}
+
+/// A 32-byte header hash field from hex; any other length would shift the header layout
+fn decode_hash(name: &str, value: &str) -> Result<[u8; 32], String> {
+    let bytes = parse::decode_hex(name, value).map_err(|_| format!("Invalid {}", name))?;
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| format!("{} must be 32 bytes, got {}", name, len))
+}
+
+/// Append the header bytes that precede the nonce to `prefix`
+fn write_prefix(
+    prefix: &mut Vec<u8>,
+    params: &ChainParams,
+    previous_hash: &str,
+    address_bytes: &[u8],
+    merkle_root: &str,
+    timestamp: u64,
+    difficulty: f64,
+) -> Result<(), String> {
+    // Add version byte if compressed address (33 bytes)
+    if address_bytes.len() == COMPRESSED_ADDRESS_LEN {
+        prefix.push(COMPRESSED_ADDRESS_VERSION);
+    }
+    
+    // Add previous_hash
+    prefix.extend_from_slice(&decode_hash("previous_hash", previous_hash)?);
+    
+    // Add address
+    prefix.extend_from_slice(address_bytes);
+    
+    // Add merkle_root
+    prefix.extend_from_slice(&decode_hash("merkle_root", merkle_root)?);
+    
+    // Add timestamp (4 or 8 bytes depending on params, little endian)
+    params.encode_timestamp(timestamp, prefix)?;
+    
+    // Add difficulty (2 bytes, little endian, scaled by 10)
+    let difficulty = difficulty::validate_difficulty(difficulty)?;
+    let difficulty_scaled = (difficulty * 10.0) as u16;
+    prefix.extend_from_slice(&difficulty_scaled.to_le_bytes());
+    
+    Ok(())
 }
 
 #[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
 pub fn mine_range(
     previous_hash: &str,
     pool_address: &str,
@@ -110,86 +468,306 @@ pub fn mine_range(
     nonce_end: u32,
     max_hashes: u32,
 ) -> Result<MinerResult, JsValue> {
-    // Parse address
-    let address_bytes = string_to_bytes(pool_address)
-        .map_err(|e| JsValue::from_str(&e))?;
+    mine_range_with_params(
+        &ChainParams::default(),
+        previous_hash,
+        pool_address,
+        merkle_root,
+        timestamp as u64,
+        difficulty,
+        nonce_start,
+        nonce_end,
+        max_hashes,
+    )
+}
+
+/// Same as `mine_range`, but with an explicit header layout and a u64 timestamp
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn mine_range_with_params(
+    params: &ChainParams,
+    previous_hash: &str,
+    pool_address: &str,
+    merkle_root: &str,
+    timestamp: u64,
+    difficulty: f64,
+    nonce_start: u32,
+    nonce_end: u32,
+    max_hashes: u32,
+) -> Result<MinerResult, JsValue> {
+    let (prefix, policy) = prepare_scan(params, previous_hash, pool_address, merkle_root, timestamp, difficulty)
+        .map_err(|(code, e)| error::js_error(code, e))?;
     
-    // Calculate difficulty chunk
-    let chunk_len = difficulty as usize;
-    let chunk = &previous_hash[previous_hash.len().saturating_sub(chunk_len)..];
+    let result = mine_prepared(&prefix, &policy, previous_hash, difficulty, nonce_start, nonce_end, max_hashes);
+    let fields = HeaderFields { params, previous_hash, address: pool_address, merkle_root, timestamp, difficulty };
+    fields.verify_result(&prefix, &policy, &result)?;
+    Ok(result)
+}
+
+/// Mine `nonce_start..nonce_end` over an already-built prefix (see `prepare_scan`)
+fn mine_prepared(
+    prefix: &[u8],
+    policy: &Acceptance,
+    previous_hash: &str,
+    difficulty: f64,
+    nonce_start: u32,
+    nonce_end: u32,
+    max_hashes: u32,
+) -> MinerResult {
+    let result = scan_prepared(prefix, policy, previous_hash, difficulty, nonce_start, nonce_end, max_hashes);
+    replay::record(prefix, policy, previous_hash, difficulty, nonce_start, nonce_end, max_hashes, &result);
+    result
+}
+
+/// Record one `run_scan` over `start..end` as the `mine_range` call that repeats it
+///
+/// For entry points that scan in slices (batches, shares, two tips) rather
+/// than going through `mine_prepared`.
+fn record_scan(
+    prefix: &[u8],
+    policy: &Acceptance,
+    previous_hash: &str,
+    difficulty: f64,
+    start: u32,
+    end: u32,
+    scan: &ScanOutcome,
+) {
+    if !replay::is_recording() {
+        return;
+    }
+    let result = scan_result(scan.clone(), previous_hash, difficulty, start, end, end, 0.0);
+    replay::record(prefix, policy, previous_hash, difficulty, start, end, end - start, &result);
+}
+
+/// `mine_prepared` without recording the call
+fn scan_prepared(
+    prefix: &[u8],
+    policy: &Acceptance,
+    previous_hash: &str,
+    difficulty: f64,
+    nonce_start: u32,
+    nonce_end: u32,
+    max_hashes: u32,
+) -> MinerResult {
+    let started_ms = clock::monotonic_ms();
+    let end = min(nonce_end, nonce_start.saturating_add(max_hashes));
+    let scan = {
+        let _span = profile::Span::start("stellaris:hash");
+        run_scan(prefix, policy, nonce_start, end)
+    };
     
-    // Build block prefix (matching Python implementation)
-    let mut prefix = Vec::new();
+    let elapsed_ms = clock::monotonic_ms() - started_ms;
+    #[cfg(feature = "tracing")]
+    tracing::debug!(hashes = scan.hashes_computed, elapsed_ms, found = scan.found.is_some(), "scanned range");
+    scan_result(scan, previous_hash, difficulty, nonce_start, nonce_end, end, elapsed_ms)
+}
+
+/// Report a scan of `nonce_start..end`, where `end` is `nonce_end` unless the hash budget cut it short
+fn scan_result(
+    scan: ScanOutcome,
+    previous_hash: &str,
+    difficulty: f64,
+    nonce_start: u32,
+    nonce_end: u32,
+    end: u32,
+    elapsed_ms: f64,
+) -> MinerResult {
+    let best_difficulty = difficulty::achieved_difficulty(&scan.best_hash, previous_hash);
     
-    // Add version byte if compressed address (33 bytes)
-    if address_bytes.len() == 33 {
-        prefix.push(2u8);
+    if let Some((nonce, hash)) = scan.found {
+        return MinerResult {
+            found: true,
+            nonce,
+            hash,
+            hashes_computed: scan.hashes_computed,
+            best_nonce: scan.best_nonce,
+            best_hash: scan.best_hash,
+            elapsed_ms,
+            stop_reason: StopReason::Found,
+            next_nonce: nonce.saturating_add(1),
+            extranonce: 0,
+            tip: 0,
+            difficulty,
+            best_difficulty,
+            height: 0,
+        };
     }
     
-    // Add previous_hash
-    prefix.extend_from_slice(&hex::decode(previous_hash)
-        .map_err(|_| JsValue::from_str("Invalid previous_hash"))?);
-    
-    // Add address
-    prefix.extend_from_slice(&address_bytes);
+    // No block found
+    let stop_reason = if end >= nonce_end {
+        StopReason::RangeEnd
+    } else {
+        StopReason::MaxHashes
+    };
     
-    // Add merkle_root
-    prefix.extend_from_slice(&hex::decode(merkle_root)
-        .map_err(|_| JsValue::from_str("Invalid merkle_root"))?);
+    MinerResult {
+        found: false,
+        nonce: scan.best_nonce,
+        hash: scan.best_hash.clone(),
+        hashes_computed: scan.hashes_computed,
+        best_nonce: scan.best_nonce,
+        best_hash: scan.best_hash,
+        elapsed_ms,
+        stop_reason,
+        next_nonce: end.max(nonce_start),
+        extranonce: 0,
+        tip: 0,
+        difficulty,
+        best_difficulty,
+        height: 0,
+    }
+}
+
+/// Header prefix and acceptance policy for a set of header fields
+fn prepare_scan<'a>(
+    params: &ChainParams,
+    previous_hash: &'a str,
+    pool_address: &str,
+    merkle_root: &str,
+    timestamp: u64,
+    difficulty: f64,
+) -> Result<(Vec<u8>, Acceptance<'a>), (ErrorCode, String)> {
+    // Parse address
+    let address_bytes = string_to_bytes(pool_address)
+        .and_then(|bytes| params.normalize_address(bytes))
+        .map_err(|e| (ErrorCode::InvalidAddress, e))?;
+    difficulty::validate_difficulty(difficulty).map_err(|e| (ErrorCode::InvalidDifficulty, e))?;
     
-    // Add timestamp (4 bytes, little endian)
-    prefix.extend_from_slice(&timestamp.to_le_bytes());
+    // Build block prefix (matching Python implementation); this also validates previous_hash
+    let prefix = build_prefix(params, previous_hash, &address_bytes, merkle_root, timestamp, difficulty)
+        .map_err(|e| (ErrorCode::InvalidHeader, e))?;
     
-    // Add difficulty (2 bytes, little endian, scaled by 10)
-    let difficulty_scaled = (difficulty * 10.0) as u16;
-    prefix.extend_from_slice(&difficulty_scaled.to_le_bytes());
+    // Select the acceptance rule; the hashes are known good by now
+    let policy = Acceptance::new(params, previous_hash, difficulty).map_err(|e| (ErrorCode::InvalidDifficulty, e))?;
     
-    // Mining loop
-    let mut best_hash = "f".repeat(64);
-    let mut best_nonce = nonce_start;
+    Ok((prefix, policy))
+}
+
+/// Mining loop, fanned out over the rayon pool when threads are available
+#[cfg_attr(feature = "tracing", tracing::instrument(name = "hash", level = "debug", skip(prefix, policy)))]
+fn run_scan(prefix: &[u8], policy: &Acceptance, start: u32, end: u32) -> ScanOutcome {
+    #[cfg(feature = "parallel")]
+    if concurrency::use_threads() {
+        return parallel::scan_range_parallel(prefix, policy, start, end);
+    }
+    scan_range(prefix, policy, start, end)
+}
+
+/// Header fields a scan prefix was built from, kept to re-check what the scan finds
+struct HeaderFields<'a> {
+    params: &'a ChainParams,
+    previous_hash: &'a str,
+    address: &'a str,
+    merkle_root: &'a str,
+    timestamp: u64,
+    difficulty: f64,
+}
+
+impl HeaderFields<'_> {
+    /// Check a found hash against a fresh serialization of these fields and the reference SHA-256
+    ///
+    /// The header is rebuilt with `build_block_content_with_params` rather
+    /// than from the scanned prefix, so a stale cached prefix is caught as
+    /// well as an optimized backend drifting from SHA-256. A mismatch is
+    /// logged and returned as `SelfCheckFailed` instead of reporting a block
+    /// the node would reject.
+    fn verify_found(&self, prefix: &[u8], policy: &Acceptance, nonce: u32, hash: &str) -> Result<(), JsValue> {
+        let block_content = build_block_content_with_params(
+            self.params,
+            self.previous_hash,
+            self.address,
+            self.merkle_root,
+            self.timestamp,
+            self.difficulty,
+            nonce,
+        )?;
+        let block_content =
+            hex::decode(block_content).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))?;
+
+        let expected = hex::encode(sha::sha256(&block_content));
+        let problem = if block_content[..block_content.len() - 4] != *prefix {
+            format!("Scanned header prefix differs from its fields at nonce {}", nonce)
+        } else if expected != hash {
+            format!(
+                "{:?} backend diverged from SHA-256 at nonce {}: got {}, expected {}",
+                backend::hash_backend(),
+                nonce,
+                hash,
+                expected
+            )
+        } else if !policy.accepts(&expected) {
+            format!("Hash {} at nonce {} doesn't meet the difficulty", expected, nonce)
+        } else {
+            return Ok(());
+        };
+        logging::emit(LogLevel::Error, "verify", &problem);
+        Err(error::js_error(ErrorCode::SelfCheckFailed, problem))
+    }
+
+    /// `verify_found` for `result`'s block, if it found one
+    fn verify_result(&self, prefix: &[u8], policy: &Acceptance, result: &MinerResult) -> Result<(), JsValue> {
+        if !result.found {
+            return Ok(());
+        }
+        self.verify_found(prefix, policy, result.nonce, &result.hash)
+    }
+}
+
+/// What a scan over part of the nonce space turned up
+#[derive(Clone)]
+struct ScanOutcome {
+    /// First nonce meeting the difficulty, with its hash
+    found: Option<(u32, String)>,
+    best_nonce: u32,
+    best_hash: String,
+    hashes_computed: u32,
+}
+
+/// Hash `prefix || nonce` for every nonce in `start..end`, stopping at the first valid block
+fn scan_range<P: DifficultyPolicy>(prefix: &[u8], policy: &P, start: u32, end: u32) -> ScanOutcome {
+    // Hex digits sort like the bytes they encode, so the best hash is tracked raw
+    let mut best_hash = [0xffu8; 32];
+    let mut best_nonce = start;
     let mut hashes_computed = 0u32;
+    let mut hash_hex = [0u8; 64];
     
-    let end = min(nonce_end, nonce_start.saturating_add(max_hashes));
+    let mut hasher = backend::PreparedHasher::new(backend::hash_backend(), prefix);
     
-    for nonce in nonce_start..end {
-        // Build block content with nonce (4 bytes, little endian)
-        let mut block_content = prefix.clone();
-        block_content.extend_from_slice(&nonce.to_le_bytes());
-        
-        // Calculate hash
-        let hash_bytes = sha256(&block_content);
-        let hash_hex = hex::encode(&hash_bytes);
+    for nonce in start..end {
+        // Hash block content with nonce (4 bytes, little endian)
+        let hash_bytes = hasher.hash(nonce);
         
         hashes_computed += 1;
         
         // Track best hash
-        if hash_hex < best_hash {
-            best_hash = hash_hex.clone();
+        if hash_bytes < best_hash {
+            best_hash = hash_bytes;
             best_nonce = nonce;
         }
         
         // Check if valid block
-        if check_difficulty(&hash_hex, chunk, difficulty) {
-            return Ok(MinerResult {
-                found: true,
-                nonce,
-                hash: hash_hex,
-                hashes_computed,
+        if policy.accepts(hex_digest(&hash_bytes, &mut hash_hex)) {
+            return ScanOutcome {
+                found: Some((nonce, hex::encode(hash_bytes))),
                 best_nonce,
-                best_hash,
-            });
+                best_hash: hex::encode(best_hash),
+                hashes_computed,
+            };
         }
     }
     
-    // No block found
-    Ok(MinerResult {
-        found: false,
-        nonce: best_nonce,
-        hash: best_hash.clone(),
-        hashes_computed,
+    ScanOutcome {
+        found: None,
         best_nonce,
-        best_hash,
-    })
+        best_hash: hex::encode(best_hash),
+        hashes_computed,
+    }
+}
+
+/// Lowercase hex of `hash`, written into `out` instead of a new `String`
+fn hex_digest<'a>(hash: &[u8; 32], out: &'a mut [u8; 64]) -> &'a str {
+    hex::encode_to_slice(hash, out).expect("64 bytes hold a 32-byte digest");
+    std::str::from_utf8(out).expect("hex is ASCII")
 }
 
 #[wasm_bindgen]
@@ -200,44 +778,65 @@ pub fn build_block_content(
     timestamp: u32,
     difficulty: f64,
     nonce: u32,
+) -> Result<String, JsValue> {
+    build_block_content_with_params(
+        &ChainParams::default(),
+        previous_hash,
+        pool_address,
+        merkle_root,
+        timestamp as u64,
+        difficulty,
+        nonce,
+    )
+}
+
+/// Same as `build_block_content`, but with an explicit header layout and a u64 timestamp
+#[wasm_bindgen]
+pub fn build_block_content_with_params(
+    params: &ChainParams,
+    previous_hash: &str,
+    pool_address: &str,
+    merkle_root: &str,
+    timestamp: u64,
+    difficulty: f64,
+    nonce: u32,
 ) -> Result<String, JsValue> {
     // Parse address
     let address_bytes = string_to_bytes(pool_address)
-        .map_err(|e| JsValue::from_str(&e))?;
-    
-    // Build block content
-    let mut block_content = Vec::new();
-    
-    // Add version byte if compressed address (33 bytes)
-    if address_bytes.len() == 33 {
-        block_content.push(2u8);
-    }
-    
-    // Add previous_hash
-    block_content.extend_from_slice(&hex::decode(previous_hash)
-        .map_err(|_| JsValue::from_str("Invalid previous_hash"))?);
-    
-    // Add address
-    block_content.extend_from_slice(&address_bytes);
-    
-    // Add merkle_root
-    block_content.extend_from_slice(&hex::decode(merkle_root)
-        .map_err(|_| JsValue::from_str("Invalid merkle_root"))?);
-    
-    // Add timestamp (4 bytes, little endian)
-    block_content.extend_from_slice(&timestamp.to_le_bytes());
+        .and_then(|bytes| params.normalize_address(bytes))
+        .map_err(|e| error::js_error(ErrorCode::InvalidAddress, e))?;
     
-    // Add difficulty (2 bytes, little endian, scaled by 10)
-    let difficulty_scaled = (difficulty * 10.0) as u16;
-    block_content.extend_from_slice(&difficulty_scaled.to_le_bytes());
-    
-    // Add nonce (4 bytes, little endian)
-    block_content.extend_from_slice(&nonce.to_le_bytes());
-    
-    Ok(hex::encode(block_content))
+    // Build block content in a pooled buffer
+    buffers::with_buffer(|block_content| {
+        write_prefix(block_content, params, previous_hash, &address_bytes, merkle_root, timestamp, difficulty)
+            .map_err(|e| error::js_error(ErrorCode::InvalidHeader, e))?;
+        
+        // Add nonce (4 bytes, little endian)
+        block_content.extend_from_slice(&nonce.to_le_bytes());
+        
+        Ok(hex::encode(block_content))
+    })
+}
+
+/// Identifier sent to pools so they can tell browser workers apart, e.g. `stellaris-wasm-miner/0.1.0`
+#[wasm_bindgen]
+pub fn user_agent() -> String {
+    format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
 }
 
 #[wasm_bindgen(start)]
-pub fn main() {
-    log("Stellaris WASM Miner initialized");
+pub fn start() {
+    // Exported functions return Err rather than panic; if one slips through,
+    // report it instead of leaving only an "unreachable" trap
+    std::panic::set_hook(Box::new(|info| {
+        logging::emit(LogLevel::Error, "panic", &format!("Stellaris WASM Miner panicked: {}", info))
+    }));
+    
+    #[cfg(feature = "tracing")]
+    profile::init_tracing();
+    
+    logging::emit(LogLevel::Info, "init", "Stellaris WASM Miner initialized");
+
+    // Catch a corrupted download or a miscompiling engine before any real hashing
+    selftest::self_test();
 }
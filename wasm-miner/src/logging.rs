@@ -0,0 +1,97 @@
+use std::cell::{Cell, RefCell};
+
+use wasm_bindgen::prelude::*;
+
+/// Severity of a log message; lower values are more severe
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = error)]
+    fn console_error(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = warn)]
+    fn console_warn(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = info)]
+    fn console_info(s: &str);
+
+    #[wasm_bindgen(js_namespace = console, js_name = debug)]
+    fn console_debug(s: &str);
+}
+
+thread_local! {
+    static SINK: RefCell<Option<js_sys::Function>> = const { RefCell::new(None) };
+    static MAX_LEVEL: Cell<LogLevel> = const { Cell::new(LogLevel::Info) };
+}
+
+/// Send the module's log messages to `callback(level, target, message)` instead of the console
+///
+/// `level` is `"error"`, `"warn"`, `"info"` or `"debug"`; `target` names
+/// the part of the miner that logged. Pass `undefined` to go back to the
+/// console. A callback that throws is ignored.
+#[wasm_bindgen]
+pub fn set_log_sink(callback: Option<js_sys::Function>) {
+    SINK.with(|sink| *sink.borrow_mut() = callback);
+}
+
+/// Drop messages less severe than `level` (default `Info`)
+#[wasm_bindgen]
+pub fn set_log_level(level: LogLevel) {
+    MAX_LEVEL.with(|max| max.set(level));
+}
+
+/// Log through the installed sink, or the console when there is none
+pub fn emit(level: LogLevel, target: &str, message: &str) {
+    if level > MAX_LEVEL.with(|max| max.get()) {
+        return;
+    }
+
+    let delivered = SINK.with(|sink| match &*sink.borrow() {
+        Some(callback) => {
+            let _ = callback.call3(
+                &JsValue::NULL,
+                &JsValue::from_str(level.as_str()),
+                &JsValue::from_str(target),
+                &JsValue::from_str(message),
+            );
+            true
+        }
+        None => false,
+    });
+    if delivered {
+        return;
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let line = format!("[{}] {}", target, message);
+        match level {
+            LogLevel::Error => console_error(&line),
+            LogLevel::Warn => console_warn(&line),
+            LogLevel::Info => console_info(&line),
+            LogLevel::Debug => console_debug(&line),
+        }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    eprintln!("{} [{}] {}", level.as_str(), target, message);
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+
+use crate::difficulty::{expected_hashes, hashes_to_shares};
+
+/// Found-versus-expected counts for one kind of event (blocks or shares)
+#[derive(Clone, Copy, Debug, Default)]
+struct LuckCounter {
+    expected: f64,
+    found: u64,
+}
+
+impl LuckCounter {
+    fn report(&self) -> LuckReport {
+        // Finds are Poisson, so the variance of the count equals its expectation
+        let stddev = self.expected.sqrt();
+
+        LuckReport {
+            found: self.found,
+            expected: self.expected,
+            luck_percent: if self.expected > 0.0 {
+                self.found as f64 / self.expected * 100.0
+            } else {
+                0.0
+            },
+            variance: self.expected,
+            z_score: if stddev > 0.0 {
+                (self.found as f64 - self.expected) / stddev
+            } else {
+                0.0
+            },
+            luck_stddev_percent: if self.expected > 0.0 {
+                100.0 / stddev
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// Luck and variance for one kind of event, as returned to JS
+#[derive(Serialize)]
+pub struct LuckReport {
+    pub found: u64,
+    pub expected: f64,
+    /// `found / expected` as a percentage; 100 is exactly average
+    pub luck_percent: f64,
+    /// Variance of `found` under the expected rate
+    pub variance: f64,
+    /// How many standard deviations `found` is from `expected`
+    pub z_score: f64,
+    /// One standard deviation of `luck_percent` at this sample size
+    pub luck_stddev_percent: f64,
+}
+
+/// Session luck for blocks and shares
+#[derive(Serialize)]
+pub struct SessionLuck {
+    pub blocks: LuckReport,
+    pub shares: LuckReport,
+}
+
+/// Accumulates expected and actual finds from `mine_range` results
+///
+/// Shares are judged the same way the mining loop submits them: at most
+/// one per result, when the result's best hash meets the share difficulty
+/// (`share_found`, from `hash_meets_difficulty`).
+/// The expected share count follows that rule, so it is the chance of at
+/// least one share per result rather than hashes divided by share work.
+#[derive(Debug, Default)]
+pub struct LuckTracker {
+    blocks: LuckCounter,
+    shares: LuckCounter,
+}
+
+impl LuckTracker {
+    pub fn record(
+        &mut self,
+        hashes: u64,
+        block_difficulty: f64,
+        block_found: bool,
+        share_difficulty: Option<f64>,
+        share_found: bool,
+    ) {
+        self.blocks.expected += hashes_to_shares(hashes as f64, block_difficulty);
+        if block_found {
+            self.blocks.found += 1;
+        }
+
+        if let Some(share_difficulty) = share_difficulty {
+            let p = 1.0 / expected_hashes(share_difficulty);
+            self.shares.expected += 1.0 - (1.0 - p).powf(hashes as f64);
+            if share_found {
+                self.shares.found += 1;
+            }
+        }
+    }
+
+    pub fn report(&self) -> SessionLuck {
+        SessionLuck {
+            blocks: self.blocks.report(),
+            shares: self.shares.report(),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use wasm_bindgen::prelude::*;
+
+use crate::error::{self, ErrorCode};
+use crate::{MinerResult, StopReason};
+
+/// 32-bit words per slot
+///
+/// Layout, all little-endian words:
+///
+/// | word   | field                                                  |
+/// |--------|--------------------------------------------------------|
+/// | 0      | sequence (odd while a write is in progress, 0 = empty) |
+/// | 1      | flags: bit 0 found, bits 1-2 stop reason, bit 3 tip    |
+/// | 2      | nonce                                                  |
+/// | 3      | hashes computed                                        |
+/// | 4      | best nonce                                             |
+/// | 5      | next nonce                                             |
+/// | 6      | extranonce                                             |
+/// | 7      | elapsed ms (f32 bits)                                  |
+/// | 8      | difficulty, in tenths                                  |
+/// | 9      | best difficulty, in tenths                             |
+/// | 10..18 | hash (32 raw bytes)                                    |
+/// | 18..26 | best hash (32 raw bytes)                               |
+const SLOT_WORDS: u32 = 26;
+
+const SEQUENCE: u32 = 0;
+const FLAGS: u32 = 1;
+const NONCE: u32 = 2;
+const HASHES: u32 = 3;
+const BEST_NONCE: u32 = 4;
+const NEXT_NONCE: u32 = 5;
+const EXTRANONCE: u32 = 6;
+const ELAPSED: u32 = 7;
+const DIFFICULTY: u32 = 8;
+const BEST_DIFFICULTY: u32 = 9;
+const HASH: u32 = 10;
+const BEST_HASH: u32 = 18;
+
+/// Attempts `read` makes before giving up on a slot that keeps changing under it
+const READ_ATTEMPTS: u32 = 8;
+
+/// Fixed-layout result slots in a `SharedArrayBuffer`, one per worker
+///
+/// Workers `write` their latest `MinerResult` into their own slot and the
+/// main thread polls `sequence` and `read`s only slots that changed, which
+/// avoids a structured clone per progress update. Each slot is a seqlock:
+/// the writer makes the sequence odd, writes the fields, then makes it even
+/// again, and readers retry if the sequence moved while they read. A slot
+/// holds only the latest result; intermediate ones a reader misses are gone.
+#[wasm_bindgen]
+pub struct ResultMailbox {
+    words: js_sys::Int32Array,
+    slots: u32,
+}
+
+/// One result read from a mailbox slot
+#[wasm_bindgen]
+pub struct MailboxRecord {
+    sequence: u32,
+    result: MinerResult,
+}
+
+#[wasm_bindgen]
+impl MailboxRecord {
+    /// Sequence of the write this record came from; grows by 2 per write
+    #[wasm_bindgen(getter)]
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn result(&self) -> MinerResult {
+        self.result.clone()
+    }
+}
+
+#[wasm_bindgen]
+impl ResultMailbox {
+    /// Wrap `buffer` (at least `bytes_for(slots)` bytes) shared by the main thread and its workers
+    #[wasm_bindgen(constructor)]
+    pub fn new(buffer: &JsValue, slots: u32) -> Result<ResultMailbox, JsValue> {
+        let words = js_sys::Int32Array::new(buffer);
+        if slots == 0 || words.length() < slots.saturating_mul(SLOT_WORDS) {
+            return Err(error::js_error(
+                ErrorCode::InvalidArgument,
+                format!("Mailbox buffer must hold {} bytes for {} slots", ResultMailbox::bytes_for(slots), slots),
+            ));
+        }
+        Ok(ResultMailbox { words, slots })
+    }
+
+    /// Buffer size needed for `slots` slots
+    pub fn bytes_for(slots: u32) -> u32 {
+        slots.saturating_mul(SLOT_WORDS * 4)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn slots(&self) -> u32 {
+        self.slots
+    }
+
+    /// Publish `result` in `slot`, returning the new sequence number
+    ///
+    /// Only one thread may write a given slot.
+    pub fn write(&self, slot: u32, result: &MinerResult) -> Result<u32, JsValue> {
+        let base = self.base(slot)?;
+        let hash = hash_words(&result.hash)?;
+        let best_hash = hash_words(&result.best_hash)?;
+
+        let sequence = self.load(base + SEQUENCE)?;
+        self.store(base + SEQUENCE, sequence | 1)?;
+
+        let flags = result.found as u32 | (result.stop_reason as u32) << 1 | (result.tip & 1) << 3;
+        self.store(base + FLAGS, flags)?;
+        self.store(base + NONCE, result.nonce)?;
+        self.store(base + HASHES, result.hashes_computed)?;
+        self.store(base + BEST_NONCE, result.best_nonce)?;
+        self.store(base + NEXT_NONCE, result.next_nonce)?;
+        self.store(base + EXTRANONCE, result.extranonce)?;
+        self.store(base + ELAPSED, (result.elapsed_ms as f32).to_bits())?;
+        self.store(base + DIFFICULTY, (result.difficulty * 10.0).round() as u32)?;
+        self.store(base + BEST_DIFFICULTY, (result.best_difficulty * 10.0).round() as u32)?;
+        for i in 0..8 {
+            self.store(base + HASH + i, hash[i as usize])?;
+            self.store(base + BEST_HASH + i, best_hash[i as usize])?;
+        }
+
+        let sequence = (sequence | 1).wrapping_add(1);
+        self.store(base + SEQUENCE, sequence)?;
+        Ok(sequence)
+    }
+
+    /// Current sequence of `slot`: 0 if never written, odd mid-write; cheap enough to poll
+    pub fn sequence(&self, slot: u32) -> Result<u32, JsValue> {
+        self.load(self.base(slot)? + SEQUENCE)
+    }
+
+    /// Latest result in `slot`, or `undefined` if it is empty or a write kept racing the read
+    pub fn read(&self, slot: u32) -> Result<Option<MailboxRecord>, JsValue> {
+        let base = self.base(slot)?;
+        for _ in 0..READ_ATTEMPTS {
+            let sequence = self.load(base + SEQUENCE)?;
+            if sequence == 0 {
+                return Ok(None);
+            }
+            if sequence & 1 == 1 {
+                continue;
+            }
+
+            let mut words = [0u32; SLOT_WORDS as usize];
+            for (i, word) in words.iter_mut().enumerate().skip(1) {
+                *word = self.load(base + i as u32)?;
+            }
+            if self.load(base + SEQUENCE)? != sequence {
+                continue;
+            }
+
+            return Ok(Some(MailboxRecord {
+                sequence,
+                result: decode(&words),
+            }));
+        }
+        Ok(None)
+    }
+}
+
+impl ResultMailbox {
+    fn base(&self, slot: u32) -> Result<u32, JsValue> {
+        if slot >= self.slots {
+            return Err(error::js_error(
+                ErrorCode::InvalidArgument,
+                format!("Slot {} is out of range (mailbox has {})", slot, self.slots),
+            ));
+        }
+        Ok(slot * SLOT_WORDS)
+    }
+
+    fn load(&self, index: u32) -> Result<u32, JsValue> {
+        Ok(js_sys::Atomics::load(&self.words, index)? as u32)
+    }
+
+    fn store(&self, index: u32, value: u32) -> Result<(), JsValue> {
+        js_sys::Atomics::store(&self.words, index, value as i32)?;
+        Ok(())
+    }
+}
+
+/// A 64-character hex hash as eight little-endian words
+fn hash_words(hash_hex: &str) -> Result<[u32; 8], JsValue> {
+    let mut bytes = [0u8; 32];
+    hex::decode_to_slice(hash_hex, &mut bytes)
+        .map_err(|_| error::js_error(ErrorCode::InvalidHash, "Mailbox hashes must be 32 bytes of hex"))?;
+
+    let mut words = [0u32; 8];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    Ok(words)
+}
+
+fn hash_hex(words: &[u32]) -> String {
+    hex::encode(words.iter().flat_map(|word| word.to_le_bytes()).collect::<Vec<u8>>())
+}
+
+fn decode(words: &[u32; SLOT_WORDS as usize]) -> MinerResult {
+    let flags = words[FLAGS as usize];
+    let stop_reason = match (flags >> 1) & 3 {
+        0 => StopReason::Found,
+        1 => StopReason::RangeEnd,
+        2 => StopReason::MaxHashes,
+        _ => StopReason::Rolled,
+    };
+
+    MinerResult::new(
+        flags & 1 == 1,
+        words[NONCE as usize],
+        hash_hex(&words[HASH as usize..BEST_HASH as usize]),
+        words[HASHES as usize],
+        words[BEST_NONCE as usize],
+        hash_hex(&words[BEST_HASH as usize..]),
+        f32::from_bits(words[ELAPSED as usize]) as f64,
+        stop_reason,
+        words[NEXT_NONCE as usize],
+        words[EXTRANONCE as usize],
+        (flags >> 3) & 1,
+        words[DIFFICULTY as usize] as f64 / 10.0,
+        words[BEST_DIFFICULTY as usize] as f64 / 10.0,
+    )
+}
@@ -0,0 +1,155 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::error::{self, ErrorCode};
+
+/// Live bytes handed out by the allocator
+static HEAP_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_HEAP_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_LINEAR_BYTES: AtomicUsize = AtomicUsize::new(0);
+/// Cap on live heap bytes; 0 means unlimited
+static HEAP_LIMIT: AtomicUsize = AtomicUsize::new(0);
+
+/// System allocator wrapper that tracks usage and enforces `set_memory_limit`
+///
+/// Linear memory never shrinks, so capping live heap bytes is the only
+/// way to bound how far it grows.
+struct CountingAllocator;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Account for `size` more live bytes, refusing if that would pass the limit
+fn reserve(size: usize) -> bool {
+    let limit = HEAP_LIMIT.load(Ordering::Relaxed);
+    let now = HEAP_BYTES.fetch_add(size, Ordering::Relaxed).saturating_add(size);
+
+    if limit != 0 && now > limit {
+        HEAP_BYTES.fetch_sub(size, Ordering::Relaxed);
+        return false;
+    }
+
+    if PEAK_HEAP_BYTES.fetch_max(now, Ordering::Relaxed) < now {
+        PEAK_LINEAR_BYTES.fetch_max(linear_bytes(), Ordering::Relaxed);
+    }
+    true
+}
+
+fn release(size: usize) {
+    HEAP_BYTES.fetch_sub(size, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if !reserve(layout.size()) {
+            return ptr::null_mut();
+        }
+        let ptr = System.alloc(layout);
+        if ptr.is_null() {
+            release(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if !reserve(layout.size()) {
+            return ptr::null_mut();
+        }
+        let ptr = System.alloc_zeroed(layout);
+        if ptr.is_null() {
+            release(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        release(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let old_size = layout.size();
+        if new_size > old_size && !reserve(new_size - old_size) {
+            return ptr::null_mut();
+        }
+
+        let new_ptr = System.realloc(ptr, layout, new_size);
+
+        if new_ptr.is_null() {
+            if new_size > old_size {
+                release(new_size - old_size);
+            }
+        } else if new_size < old_size {
+            release(old_size - new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Size of the module's linear memory (0 in native builds)
+fn linear_bytes() -> usize {
+    #[cfg(target_arch = "wasm32")]
+    return core::arch::wasm32::memory_size(0) * 65536;
+    #[cfg(not(target_arch = "wasm32"))]
+    return 0;
+}
+
+#[derive(Serialize)]
+struct MemoryUsage {
+    heap_bytes: usize,
+    peak_heap_bytes: usize,
+    linear_bytes: usize,
+    peak_linear_bytes: usize,
+    limit_bytes: Option<usize>,
+}
+
+/// Current and peak memory use as `{ heap_bytes, peak_heap_bytes, linear_bytes, peak_linear_bytes, limit_bytes }`
+///
+/// `heap_bytes` counts live Rust allocations; `linear_bytes` is the whole
+/// WASM memory, which only grows.
+#[wasm_bindgen]
+pub fn memory_usage() -> Result<JsValue, JsValue> {
+    let linear = linear_bytes();
+    let limit = HEAP_LIMIT.load(Ordering::Relaxed);
+
+    let usage = MemoryUsage {
+        heap_bytes: HEAP_BYTES.load(Ordering::Relaxed),
+        peak_heap_bytes: PEAK_HEAP_BYTES.load(Ordering::Relaxed),
+        linear_bytes: linear,
+        peak_linear_bytes: PEAK_LINEAR_BYTES.fetch_max(linear, Ordering::Relaxed).max(linear),
+        limit_bytes: (limit != 0).then_some(limit),
+    };
+
+    serde_wasm_bindgen::to_value(&usage).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+}
+
+/// Cap live heap bytes; pass nothing (or 0) to remove the cap
+///
+/// An allocation that would pass the cap fails, which aborts the module,
+/// so leave headroom above the usage reported while mining normally.
+#[wasm_bindgen]
+pub fn set_memory_limit(limit_bytes: Option<usize>) -> Result<(), JsValue> {
+    let limit = limit_bytes.unwrap_or(0);
+    let current = HEAP_BYTES.load(Ordering::Relaxed);
+
+    if limit != 0 && limit < current {
+        return Err(error::js_error(ErrorCode::MemoryLimit, format!(
+            "Memory limit {} is below current heap usage {}",
+            limit, current
+        )));
+    }
+
+    HEAP_LIMIT.store(limit, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Restart peak tracking from current usage
+#[wasm_bindgen]
+pub fn reset_memory_peak() {
+    PEAK_HEAP_BYTES.store(HEAP_BYTES.load(Ordering::Relaxed), Ordering::Relaxed);
+    PEAK_LINEAR_BYTES.store(linear_bytes(), Ordering::Relaxed);
+}
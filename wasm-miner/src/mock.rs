@@ -0,0 +1,101 @@
+use sha2::{Digest, Sha256};
+use wasm_bindgen::prelude::*;
+
+use crate::difficulty;
+use crate::error::{self, ErrorCode};
+use crate::job::MiningJob;
+
+/// Mock jobs must stay cheap to solve: 16^5 hashes is about a second in a browser
+const MAX_MOCK_DIFFICULTY: f64 = 5.0;
+
+/// Nonce range every mock job covers; a solution is all but certain within it
+const MOCK_NONCE_RANGE: u32 = 1 << 24;
+
+/// A fake pool job whose fields agree with each other, plus a known solution
+#[wasm_bindgen]
+pub struct MockJob {
+    work_json: String,
+    job: MiningJob,
+    nonce: u32,
+    hash: String,
+    block_content: String,
+}
+
+#[wasm_bindgen]
+impl MockJob {
+    /// The job as the pool's `/api/work` endpoint would return it
+    #[wasm_bindgen(getter)]
+    pub fn work_json(&self) -> String {
+        self.work_json.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn job(&self) -> MiningJob {
+        self.job.clone()
+    }
+
+    /// Lowest nonce in the range whose header meets the job difficulty
+    #[wasm_bindgen(getter)]
+    pub fn solution_nonce(&self) -> u32 {
+        self.nonce
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn solution_hash(&self) -> String {
+        self.hash.clone()
+    }
+
+    /// Serialized header for the solution, as it would be submitted
+    #[wasm_bindgen(getter)]
+    pub fn block_content_hex(&self) -> String {
+        self.block_content.clone()
+    }
+}
+
+/// Hex digest standing in for a random field, derived from `seed`
+fn mock_hex(label: &str, seed: u32) -> String {
+    hex::encode(Sha256::digest(format!("stellaris-mock:{}:{}", label, seed)))
+}
+
+/// Deterministic fake job for frontend development without a node or pool
+///
+/// The same `seed` always gives the same hashes, address, timestamp and
+/// solution. `difficulty` is capped at 5 so the solution is found quickly,
+/// and can't be below `min_difficulty()`.
+#[wasm_bindgen]
+pub fn generate_mock_job(seed: u32, difficulty: f64) -> Result<MockJob, JsValue> {
+    let min = difficulty::min_difficulty().min(MAX_MOCK_DIFFICULTY);
+    if !(min..=MAX_MOCK_DIFFICULTY).contains(&difficulty) {
+        return Err(error::js_error(
+            ErrorCode::InvalidDifficulty,
+            format!("Mock difficulty must be between {} and {}", min, MAX_MOCK_DIFFICULTY),
+        ));
+    }
+
+    let work_json = serde_json::json!({
+        "job_id": format!("mock-{}", seed),
+        "block_height": seed as u64 + 1,
+        "difficulty": difficulty,
+        "previous_hash": mock_hex("previous_hash", seed),
+        "merkle_root": mock_hex("merkle_root", seed),
+        "timestamp": 1_700_000_000 + seed as u64,
+        "nonce_start": 0,
+        "nonce_end": MOCK_NONCE_RANGE,
+        "pool_address": format!("02{}", mock_hex("address", seed)),
+    })
+    .to_string();
+
+    let job = MiningJob::from_pool_work(&work_json)?;
+    let result = job.mine_range(0, MOCK_NONCE_RANGE, MOCK_NONCE_RANGE)?;
+    if !result.found() {
+        return Err(error::js_error(ErrorCode::NotFound, format!("No solution for mock seed {}", seed)));
+    }
+
+    Ok(MockJob {
+        block_content: job.build_block_content(result.nonce())?,
+        work_json,
+        job,
+        nonce: result.nonce(),
+        hash: result.hash(),
+    })
+}
@@ -0,0 +1,468 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{future_to_promise, JsFuture};
+use web_sys::{AbortController, Request, RequestInit, RequestMode, Response};
+
+#[cfg(feature = "cbor")]
+use crate::cbor;
+use crate::compress;
+use crate::error::{self, ErrorCode};
+use crate::logging::{self, LogLevel};
+use crate::profile;
+use crate::seeded;
+
+/// Status code and body text of a completed HTTP request
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+/// A request body and its media type, as sent on the wire
+struct Payload<'a> {
+    bytes: Cow<'a, [u8]>,
+    content_type: &'static str,
+}
+
+/// A response as received, before its body is decoded to text
+struct WireResponse {
+    status: u16,
+    content_type: Option<String>,
+    bytes: Vec<u8>,
+}
+
+impl WireResponse {
+    /// Server-side failures and rate limiting are worth retrying; other statuses are final
+    fn is_retryable(&self) -> bool {
+        self.status >= 500 || self.status == 429
+    }
+}
+
+/// Credentials attached to every request
+#[derive(Clone, Debug)]
+enum Auth {
+    None,
+    Bearer(String),
+    ApiKey { header: String, key: String },
+}
+
+/// Look up a timer function (`setTimeout`/`clearTimeout`) on the global scope
+fn global_function(name: &str) -> Result<js_sys::Function, JsValue> {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str(name))?
+        .dyn_into::<js_sys::Function>()
+        .map_err(|_| error::js_error(ErrorCode::Unavailable, format!("{} is not available in this environment", name)))
+}
+
+fn set_timeout(callback: &JsValue, ms: u32) -> Result<JsValue, JsValue> {
+    global_function("setTimeout")?.call2(&JsValue::NULL, callback, &JsValue::from(ms))
+}
+
+fn clear_timeout(handle: &JsValue) {
+    if let Ok(clear) = global_function("clearTimeout") {
+        let _ = clear.call1(&JsValue::NULL, handle);
+    }
+}
+
+/// Resolve after `ms` milliseconds
+pub async fn sleep(ms: u32) -> Result<(), JsValue> {
+    let mut scheduled = Ok(JsValue::UNDEFINED);
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        scheduled = set_timeout(&resolve, ms);
+    });
+    scheduled?;
+    JsFuture::from(promise).await?;
+    Ok(())
+}
+
+/// Issue a fetch from whichever global scope we run in (page or worker)
+async fn fetch(request: &Request) -> Result<Response, JsValue> {
+    let global = js_sys::global();
+
+    let promise = if let Some(window) = global.dyn_ref::<web_sys::Window>() {
+        window.fetch_with_request(request)
+    } else if let Some(worker) = global.dyn_ref::<web_sys::WorkerGlobalScope>() {
+        worker.fetch_with_request(request)
+    } else {
+        return Err(error::js_error(ErrorCode::Unavailable, "fetch is not available in this environment"));
+    };
+
+    JsFuture::from(promise).await?.dyn_into::<Response>()
+}
+
+/// Small fetch wrapper used for every pool and node call
+///
+/// Each attempt is bounded by `timeout_ms`. For idempotent requests (GETs,
+/// and POSTs sent with `idempotent`), network errors, timeouts, 5xx and 429
+/// responses are retried up to `max_retries` times with exponential backoff
+/// and jitter; any other response is returned to the caller as-is. Other
+/// POSTs, like share and block submits, are only retried on 429: a timed-out
+/// submit may still have reached the server, and repeating it would submit
+/// twice.
+///
+/// Credentials are shared between clones so a token refreshed by one
+/// in-flight request is used by all later ones. On a 401 the optional
+/// re-auth callback is asked for a new bearer token and the request is
+/// repeated once.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct HttpClient {
+    timeout_ms: u32,
+    max_retries: u32,
+    base_backoff_ms: u32,
+    max_backoff_ms: u32,
+    auth: Rc<RefCell<Auth>>,
+    reauth: Option<js_sys::Function>,
+    #[cfg(feature = "cbor")]
+    cbor: bool,
+}
+
+#[wasm_bindgen]
+impl HttpClient {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> HttpClient {
+        HttpClient::default()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn timeout_ms(&self) -> u32 {
+        self.timeout_ms
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_timeout_ms(&mut self, timeout_ms: u32) {
+        self.timeout_ms = timeout_ms;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_max_retries(&mut self, max_retries: u32) {
+        self.max_retries = max_retries;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn base_backoff_ms(&self) -> u32 {
+        self.base_backoff_ms
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_base_backoff_ms(&mut self, base_backoff_ms: u32) {
+        self.base_backoff_ms = base_backoff_ms;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_backoff_ms(&self) -> u32 {
+        self.max_backoff_ms
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_max_backoff_ms(&mut self, max_backoff_ms: u32) {
+        self.max_backoff_ms = max_backoff_ms;
+    }
+
+    /// Send `Authorization: Bearer <token>` with every request
+    pub fn set_bearer_token(&mut self, token: String) {
+        *self.auth.borrow_mut() = Auth::Bearer(token);
+    }
+
+    /// Send `<header>: <key>` with every request
+    pub fn set_api_key(&mut self, header: String, key: String) {
+        *self.auth.borrow_mut() = Auth::ApiKey { header, key };
+    }
+
+    pub fn clear_auth(&mut self) {
+        *self.auth.borrow_mut() = Auth::None;
+    }
+
+    /// Send request bodies as CBOR and ask for CBOR back (JSON responses are still accepted)
+    ///
+    /// Callers keep passing and receiving JSON; the client transcodes at
+    /// the wire. Only in builds with the `cbor` feature.
+    #[cfg(feature = "cbor")]
+    #[wasm_bindgen(getter)]
+    pub fn cbor(&self) -> bool {
+        self.cbor
+    }
+
+    #[cfg(feature = "cbor")]
+    #[wasm_bindgen(setter)]
+    pub fn set_cbor(&mut self, cbor: bool) {
+        self.cbor = cbor;
+    }
+
+    /// Register `callback() -> Promise<string>` to fetch a fresh bearer token after a 401
+    pub fn set_reauth_callback(&mut self, callback: Option<js_sys::Function>) {
+        self.reauth = callback;
+    }
+
+    /// Single-attempt POST that resolves with the HTTP status, for keepalives
+    ///
+    /// Any response at all proves the server is reachable, so the body is
+    /// ignored and retries are skipped so dead connections surface quickly.
+    pub fn ping(&self, url: String, body: String) -> js_sys::Promise {
+        let client = self.clone();
+        future_to_promise(async move {
+            let payload = client.payload(&body)?;
+            let response = client
+                .send_once("POST", &url, Some(&payload))
+                .await
+                .map_err(|e| error::from_js(ErrorCode::Network, e))?;
+            Ok(JsValue::from(response.status))
+        })
+    }
+
+    /// POST a JSON string and resolve with the parsed JSON response
+    ///
+    /// Pass `idempotent` for requests that are safe to repeat, such as
+    /// fetching work, to retry them like GETs.
+    #[wasm_bindgen(js_name = post_json)]
+    pub fn post_json_js(&self, url: String, body: String, idempotent: Option<bool>) -> js_sys::Promise {
+        let client = self.clone();
+        future_to_promise(async move {
+            let response = if idempotent.unwrap_or(false) {
+                client.post_json_idempotent(&url, &body).await?
+            } else {
+                client.post_json(&url, &body).await?
+            };
+            js_sys::JSON::parse(&response.body).map_err(|_| {
+                error::js_error(ErrorCode::InvalidResponse, format!("Invalid JSON response (HTTP {})", response.status))
+            })
+        })
+    }
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        HttpClient {
+            timeout_ms: 10_000,
+            max_retries: 3,
+            base_backoff_ms: 500,
+            max_backoff_ms: 10_000,
+            auth: Rc::new(RefCell::new(Auth::None)),
+            reauth: None,
+            #[cfg(feature = "cbor")]
+            cbor: false,
+        }
+    }
+}
+
+impl HttpClient {
+    /// POST a JSON body once, re-authenticating once on 401
+    ///
+    /// Only a 429 is retried, since the server hasn't processed the request;
+    /// use `post_json_idempotent` for requests that are safe to repeat.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "post", level = "debug", skip(self, body)))]
+    pub async fn post_json(&self, url: &str, body: &str) -> Result<HttpResponse, JsValue> {
+        let _span = profile::Span::start("stellaris:post");
+        self.send("POST", url, Some(body), false).await
+    }
+
+    /// `post_json` for requests that are safe to repeat, retrying transient failures like `get`
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "post", level = "debug", skip(self, body)))]
+    pub async fn post_json_idempotent(&self, url: &str, body: &str) -> Result<HttpResponse, JsValue> {
+        let _span = profile::Span::start("stellaris:post");
+        self.send("POST", url, Some(body), true).await
+    }
+
+    /// GET a URL, retrying transient failures and re-authenticating once on 401
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "get", level = "debug", skip(self)))]
+    pub async fn get(&self, url: &str) -> Result<HttpResponse, JsValue> {
+        let _span = profile::Span::start("stellaris:get");
+        self.send("GET", url, None, true).await
+    }
+
+    async fn send(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<&str>,
+        idempotent: bool,
+    ) -> Result<HttpResponse, JsValue> {
+        let payload = body.map(|body| self.payload(body)).transpose()?;
+        let mut response = self
+            .send_with_retries(method, url, payload.as_ref(), idempotent)
+            .await
+            .map_err(|e| error::from_js(ErrorCode::Network, e))?;
+
+        if response.status == 401 && self.refresh_auth().await? {
+            response = self
+                .send_with_retries(method, url, payload.as_ref(), idempotent)
+                .await
+                .map_err(|e| error::from_js(ErrorCode::Network, e))?;
+        }
+
+        Self::decode(response)
+    }
+
+    /// Encode a JSON body for the wire
+    fn payload<'a>(&self, body: &'a str) -> Result<Payload<'a>, JsValue> {
+        #[cfg(feature = "cbor")]
+        if self.cbor {
+            let bytes = cbor::json_to_cbor(body).map_err(|e| error::js_error(ErrorCode::InvalidArgument, e))?;
+            return Ok(Payload {
+                bytes: Cow::Owned(bytes),
+                content_type: cbor::CONTENT_TYPE,
+            });
+        }
+
+        Ok(Payload {
+            bytes: Cow::Borrowed(body.as_bytes()),
+            content_type: "application/json",
+        })
+    }
+
+    /// Decode a response body to JSON-or-plain text, whatever encoding it arrived in
+    ///
+    /// Bodies still gzip or zlib compressed after fetch (served as e.g.
+    /// `application/gzip` rather than with `Content-Encoding`) are inflated
+    /// first; other bodies are never sniffed, since plain text can look like zlib.
+    fn decode(response: WireResponse) -> Result<HttpResponse, JsValue> {
+        let compressed = response.content_type.as_deref().is_some_and(compress::is_compressed_content_type);
+        let bytes = if compressed {
+            compress::inflate(&response.bytes).map_err(|e| {
+                error::js_error(ErrorCode::InvalidResponse, format!("{} (HTTP {})", e, response.status))
+            })?
+        } else {
+            Cow::Borrowed(response.bytes.as_slice())
+        };
+
+        #[cfg(feature = "cbor")]
+        if response.content_type.as_deref().is_some_and(|ty| ty.starts_with(cbor::CONTENT_TYPE)) {
+            let body = cbor::cbor_to_json(&bytes).map_err(|e| {
+                let message = format!("Invalid CBOR response (HTTP {}): {}", response.status, e);
+                error::js_error(ErrorCode::InvalidResponse, message)
+            })?;
+            return Ok(HttpResponse {
+                status: response.status,
+                body,
+            });
+        }
+
+        Ok(HttpResponse {
+            status: response.status,
+            body: String::from_utf8_lossy(&bytes).into_owned(),
+        })
+    }
+
+    /// Ask the re-auth callback for a new token, returning whether one was obtained
+    async fn refresh_auth(&self) -> Result<bool, JsValue> {
+        let callback = match &self.reauth {
+            Some(callback) => callback,
+            None => return Ok(false),
+        };
+
+        let token = JsFuture::from(js_sys::Promise::resolve(&callback.call0(&JsValue::NULL)?))
+            .await?
+            .as_string();
+
+        match token {
+            Some(token) if !token.is_empty() => {
+                *self.auth.borrow_mut() = Auth::Bearer(token);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn send_with_retries(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<&Payload<'_>>,
+        idempotent: bool,
+    ) -> Result<WireResponse, JsValue> {
+        let mut attempt = 0;
+
+        loop {
+            let result = self.send_once(method, url, body).await;
+
+            // A rate-limited request wasn't processed, so even a submit can be repeated
+            let retryable = match &result {
+                Ok(response) => response.status == 429 || (idempotent && response.is_retryable()),
+                Err(_) => idempotent,
+            };
+
+            if !retryable || attempt >= self.max_retries {
+                return result;
+            }
+
+            let delay_ms = self.backoff_ms(attempt);
+            let failure = match &result {
+                Ok(response) => format!("HTTP {}", response.status),
+                // Failures are MinerErrors (see `error::js_error`), which `as_string` can't read
+                Err(error) => error
+                    .dyn_ref::<js_sys::Error>()
+                    .map(|inner| String::from(inner.message()))
+                    .or_else(|| error.as_string())
+                    .unwrap_or_else(|| "network error".to_string()),
+            };
+            logging::emit(
+                LogLevel::Warn,
+                "net",
+                &format!("{} {} failed ({}), retrying in {} ms", method, url, failure, delay_ms),
+            );
+            sleep(delay_ms).await?;
+            attempt += 1;
+        }
+    }
+
+    /// Delay before retry number `attempt`, using "equal jitter" (half fixed, half random)
+    fn backoff_ms(&self, attempt: u32) -> u32 {
+        let exponential = self
+            .base_backoff_ms
+            .saturating_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX));
+        let capped = exponential.min(self.max_backoff_ms);
+        let half = capped / 2;
+        half + (seeded::random() * half as f64) as u32
+    }
+
+    async fn send_once(&self, method: &str, url: &str, body: Option<&Payload<'_>>) -> Result<WireResponse, JsValue> {
+        let controller = AbortController::new()?;
+
+        let opts = RequestInit::new();
+        opts.set_method(method);
+        opts.set_mode(RequestMode::Cors);
+        if let Some(body) = body {
+            opts.set_body(&js_sys::Uint8Array::from(&body.bytes[..]));
+        }
+        opts.set_signal(Some(&controller.signal()));
+
+        let request = Request::new_with_str_and_init(url, &opts)?;
+        if let Some(body) = body {
+            request.headers().set("Content-Type", body.content_type)?;
+        }
+        #[cfg(feature = "cbor")]
+        if self.cbor {
+            request.headers().set("Accept", &format!("{}, application/json", cbor::CONTENT_TYPE))?;
+        }
+
+        match &*self.auth.borrow() {
+            Auth::None => {}
+            Auth::Bearer(token) => request.headers().set("Authorization", &format!("Bearer {}", token))?,
+            Auth::ApiKey { header, key } => request.headers().set(header, key)?,
+        }
+
+        let abort = Closure::once_into_js(move || controller.abort());
+        let timer = set_timeout(&abort, self.timeout_ms)?;
+
+        let result = async {
+            let response = fetch(&request).await?;
+            let bytes = JsFuture::from(response.array_buffer()?).await?;
+            Ok(WireResponse {
+                status: response.status(),
+                content_type: response.headers().get("Content-Type")?,
+                bytes: js_sys::Uint8Array::new(&bytes).to_vec(),
+            })
+        }
+        .await;
+
+        clear_timeout(&timer);
+        result
+    }
+}
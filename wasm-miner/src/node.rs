@@ -0,0 +1,322 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::error::{self, ErrorCode};
+use crate::logging::{self, LogLevel};
+use crate::net::{self, HttpClient};
+use crate::profile;
+
+/// Polling a node faster than this only adds load; blocks are minutes apart
+const MIN_TIP_INTERVAL_MS: u32 = 1_000;
+
+/// How the node classified a submitted block
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubmitOutcome {
+    Accepted,
+    /// The block no longer extends the tip (someone else found one first)
+    Orphan,
+    /// The node already has this block
+    Duplicate,
+    /// The node rejected the block as malformed or not meeting difficulty
+    Invalid,
+}
+
+/// Result of a `submit_block` call
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct SubmitResult {
+    outcome: SubmitOutcome,
+    message: String,
+}
+
+#[wasm_bindgen]
+impl SubmitResult {
+    #[wasm_bindgen(getter)]
+    pub fn outcome(&self) -> SubmitOutcome {
+        self.outcome
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn accepted(&self) -> bool {
+        self.outcome == SubmitOutcome::Accepted
+    }
+
+    /// Error text reported by the node, empty when accepted
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+#[derive(Serialize)]
+struct PushBlockRequest<'a> {
+    block_content: &'a str,
+    txs: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct PushBlockResponse {
+    ok: bool,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Map a node rejection message onto a submission outcome
+fn classify_rejection(message: &str) -> SubmitOutcome {
+    let message = message.to_lowercase();
+
+    if message.contains("already") || message.contains("duplicate") {
+        SubmitOutcome::Duplicate
+    } else if message.contains("previous hash") || message.contains("orphan") || message.contains("stale") {
+        SubmitOutcome::Orphan
+    } else {
+        SubmitOutcome::Invalid
+    }
+}
+
+/// Submit a solved block to a node's `push_block` endpoint
+///
+/// Transport failures reject the promise; node-side rejections resolve with
+/// a `SubmitResult` describing why the block was refused.
+#[wasm_bindgen]
+pub async fn submit_block(
+    node_url: String,
+    block_hex: String,
+    transactions: Option<Vec<String>>,
+) -> Result<SubmitResult, JsValue> {
+    push_block(&node_url, &block_hex, transactions.unwrap_or_default()).await
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(block_hex, transactions)))]
+async fn push_block(node_url: &str, block_hex: &str, transactions: Vec<String>) -> Result<SubmitResult, JsValue> {
+    let _span = profile::Span::start("stellaris:submit-block");
+    let url = format!("{}/push_block", node_url.trim_end_matches('/'));
+    let body = serde_json::to_string(&PushBlockRequest {
+        block_content: block_hex,
+        txs: &transactions,
+    })
+    .map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))?;
+
+    let response = HttpClient::default().post_json(&url, &body).await?;
+    let parsed: PushBlockResponse = serde_json::from_str(&response.body).map_err(|_| {
+        error::js_error(ErrorCode::InvalidResponse, format!("Unexpected node response (HTTP {})", response.status))
+    })?;
+
+    if parsed.ok {
+        return Ok(SubmitResult {
+            outcome: SubmitOutcome::Accepted,
+            message: String::new(),
+        });
+    }
+
+    let message = parsed.error.unwrap_or_else(|| "Block rejected".to_string());
+    Ok(SubmitResult {
+        outcome: classify_rejection(&message),
+        message,
+    })
+}
+
+/// Confirmed balance of an address, as reported by a node
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct AddressBalance {
+    address: String,
+    balance: f64,
+}
+
+#[wasm_bindgen]
+impl AddressBalance {
+    #[wasm_bindgen(getter)]
+    pub fn address(&self) -> String {
+        self.address.clone()
+    }
+
+    /// Balance in whole coins
+    #[wasm_bindgen(getter)]
+    pub fn balance(&self) -> f64 {
+        self.balance
+    }
+}
+
+#[derive(Deserialize)]
+struct AddressInfoResponse {
+    ok: bool,
+    #[serde(default)]
+    result: Option<AddressInfoResult>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AddressInfoResult {
+    #[serde(deserialize_with = "deserialize_amount")]
+    balance: f64,
+}
+
+/// Nodes serialize amounts as JSON numbers or, to keep decimals exact, as strings
+fn deserialize_amount<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Amount {
+        Number(f64),
+        Text(String),
+    }
+
+    match Amount::deserialize(deserializer)? {
+        Amount::Number(amount) => Ok(amount),
+        Amount::Text(text) => text.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Fetch an address's balance from a node's `get_address_info` endpoint
+///
+/// Transport failures reject with `Network`, and the reply is read as by
+/// `parse_address_info`.
+#[wasm_bindgen]
+pub async fn get_balance(node_url: String, address: String) -> Result<AddressBalance, JsValue> {
+    let url = format!(
+        "{}/get_address_info?address={}",
+        node_url.trim_end_matches('/'),
+        String::from(js_sys::encode_uri_component(&address))
+    );
+    let response = HttpClient::default().get(&url).await?;
+    read_address_info(address, &response.body)
+        .map_err(|(code, message)| error::js_error(code, format!("{} (HTTP {})", message, response.status)))
+}
+
+/// Read a node's `get_address_info` reply for `address`, e.g. one the page fetched itself
+///
+/// Throws `InvalidAddress` when the node's error is about the address, and
+/// `InvalidResponse` for any other error or an unparseable reply.
+#[wasm_bindgen]
+pub fn parse_address_info(address: String, json: &str) -> Result<AddressBalance, JsValue> {
+    read_address_info(address, json).map_err(|(code, message)| error::js_error(code, message))
+}
+
+fn read_address_info(address: String, json: &str) -> Result<AddressBalance, (ErrorCode, String)> {
+    let parsed: AddressInfoResponse = serde_json::from_str(json)
+        .map_err(|_| (ErrorCode::InvalidResponse, "Unexpected node response".to_string()))?;
+
+    match parsed.result {
+        Some(result) if parsed.ok => Ok(AddressBalance {
+            address,
+            balance: result.balance,
+        }),
+        _ => {
+            let message = parsed.error.unwrap_or_else(|| "Node returned no balance".to_string());
+            let code = if message.to_lowercase().contains("address") {
+                ErrorCode::InvalidAddress
+            } else {
+                ErrorCode::InvalidResponse
+            };
+            Err((code, message))
+        }
+    }
+}
+
+/// Node response to `get_mining_info`, of which `watch_tip` only needs the tip
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MiningInfoResponse {
+    Wrapped { ok: bool, result: Option<MiningInfo>, error: Option<String> },
+    Bare(MiningInfo),
+}
+
+#[derive(Deserialize)]
+struct MiningInfo {
+    last_block: TipBlock,
+}
+
+#[derive(Deserialize)]
+struct TipBlock {
+    hash: String,
+    #[serde(default)]
+    id: u64,
+}
+
+/// Latest block hash and height from a node
+async fn fetch_tip(client: &HttpClient, url: &str) -> Result<(String, u64), String> {
+    let response = client.get(url).await.map_err(|e| match e.dyn_ref::<js_sys::Error>() {
+        Some(inner) => String::from(inner.message()),
+        None => format!("{:?}", e),
+    })?;
+    let parsed: MiningInfoResponse = serde_json::from_str(&response.body)
+        .map_err(|_| format!("Unexpected node response (HTTP {})", response.status))?;
+
+    match parsed {
+        MiningInfoResponse::Bare(info) | MiningInfoResponse::Wrapped { ok: true, result: Some(info), .. } => {
+            Ok((info.last_block.hash, info.last_block.id))
+        }
+        MiningInfoResponse::Wrapped { error, .. } => Err(error.unwrap_or_else(|| "missing result".to_string())),
+    }
+}
+
+/// Handle for a `watch_tip` poller
+#[wasm_bindgen]
+pub struct TipWatcher {
+    stopped: Rc<Cell<bool>>,
+}
+
+#[wasm_bindgen]
+impl TipWatcher {
+    /// Stop polling; a request already in flight finishes but reports nothing
+    pub fn stop(&self) {
+        self.stopped.set(true);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn stopped(&self) -> bool {
+        self.stopped.get()
+    }
+}
+
+/// Poll a node's `get_mining_info` every `interval_ms` and call `callback(hash, height)` when the tip changes
+///
+/// The first successful poll always reports. Pass the tip on to
+/// `MinerSession.observe_tip`, which says whether the current job is now
+/// stale so a solo miner can rebuild it on the new `previous_hash`. Failed
+/// polls are logged and retried on the next tick; a callback that throws is
+/// logged and ignored. Intervals under a second are raised to one.
+#[wasm_bindgen]
+pub fn watch_tip(node_url: String, interval_ms: u32, callback: js_sys::Function) -> TipWatcher {
+    let stopped = Rc::new(Cell::new(false));
+    let watcher = TipWatcher {
+        stopped: stopped.clone(),
+    };
+    let url = format!("{}/get_mining_info", node_url.trim_end_matches('/'));
+    let interval_ms = interval_ms.max(MIN_TIP_INTERVAL_MS);
+
+    wasm_bindgen_futures::spawn_local(async move {
+        // The next poll is the retry
+        let mut client = HttpClient::default();
+        client.set_max_retries(0);
+        let mut last_hash: Option<String> = None;
+
+        while !stopped.get() {
+            match fetch_tip(&client, &url).await {
+                Ok(_) if stopped.get() => break,
+                Ok((hash, height)) if last_hash.as_deref() != Some(hash.as_str()) => {
+                    let height = JsValue::from(height as f64);
+                    if callback.call2(&JsValue::NULL, &JsValue::from_str(&hash), &height).is_err() {
+                        logging::emit(LogLevel::Warn, "node", "Tip callback threw; ignoring");
+                    }
+                    last_hash = Some(hash);
+                }
+                Ok(_) => {}
+                Err(e) => logging::emit(LogLevel::Warn, "node", &format!("Polling the tip failed: {}", e)),
+            }
+
+            if net::sleep(interval_ms).await.is_err() {
+                logging::emit(LogLevel::Error, "node", "No timer available; tip polling stopped");
+                break;
+            }
+        }
+    });
+
+    watcher
+}
@@ -0,0 +1,238 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::clock;
+use crate::error::{self, ErrorCode};
+
+/// Submissions held by default while the pool is unreachable
+pub const DEFAULT_OUTBOX_CAPACITY: usize = 64;
+
+/// Default age past which a held submission is dropped rather than sent
+pub const DEFAULT_OUTBOX_MAX_AGE_MS: f64 = 120_000.0;
+
+/// A share or block that couldn't be sent, in the pool's `/api/share` field names
+#[derive(Clone, Debug, Serialize)]
+struct HeldSubmission {
+    job_id: String,
+    block_height: u64,
+    nonce: u32,
+    block_hash: String,
+    block_content_hex: String,
+    is_valid_block: bool,
+    /// Milliseconds since it was held
+    age_ms: f64,
+    #[serde(skip)]
+    held_ms: f64,
+}
+
+/// Why a held submission was given up on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+enum DropReason {
+    /// The outbox was full and a newer submission took its place
+    Full,
+    /// Held longer than `max_age_ms`
+    Expired,
+    /// The chain reached its height, so the pool would reject it
+    Stale,
+    /// Its job was discarded, e.g. after a clean job
+    Discarded,
+}
+
+/// One entry in `take_dropped`
+#[derive(Serialize)]
+struct DroppedSubmission {
+    job_id: String,
+    block_height: u64,
+    nonce: u32,
+    is_valid_block: bool,
+    reason: DropReason,
+}
+
+/// Holds shares and blocks found while the pool is unreachable, to send once it is back
+///
+/// When a submission fails at the network level, `hold` it here instead of
+/// losing it. After reconnecting, call `next(tip_height)` for the submission
+/// to send, then `remove` it once the pool has answered; blocks go before
+/// shares, oldest first. Freshness is checked on the way out: anything held
+/// past `max_age_ms`, or at a height the chain has already reached, is
+/// dropped instead. When full, the oldest share makes room (a block only if
+/// nothing but blocks is held). Drops are reported by `take_dropped`.
+#[wasm_bindgen]
+pub struct SubmissionQueue {
+    held: VecDeque<HeldSubmission>,
+    capacity: usize,
+    max_age_ms: f64,
+    /// Drops not yet collected by `take_dropped`, at most `capacity` of them
+    dropped: VecDeque<DroppedSubmission>,
+    flushed: u32,
+    dropped_total: u32,
+}
+
+#[wasm_bindgen]
+impl SubmissionQueue {
+    /// Hold up to `capacity` submissions (default 64) for at most `max_age_ms` each (default 2 minutes)
+    #[wasm_bindgen(constructor)]
+    pub fn new(capacity: Option<usize>, max_age_ms: Option<f64>) -> Result<SubmissionQueue, JsValue> {
+        let capacity = capacity.unwrap_or(DEFAULT_OUTBOX_CAPACITY);
+        let max_age_ms = max_age_ms.unwrap_or(DEFAULT_OUTBOX_MAX_AGE_MS);
+        if capacity == 0 {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "capacity must be positive"));
+        }
+        if max_age_ms.is_nan() || max_age_ms <= 0.0 {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "max_age_ms must be positive"));
+        }
+
+        Ok(SubmissionQueue {
+            held: VecDeque::new(),
+            capacity,
+            max_age_ms,
+            dropped: VecDeque::new(),
+            flushed: 0,
+            dropped_total: 0,
+        })
+    }
+
+    /// Hold a submission that couldn't be sent; false if the same `(job_id, nonce)` is already held
+    pub fn hold(
+        &mut self,
+        job_id: &str,
+        block_height: u64,
+        nonce: u32,
+        block_hash: &str,
+        block_content_hex: &str,
+        is_valid_block: bool,
+    ) -> bool {
+        if self.position(job_id, nonce).is_some() {
+            return false;
+        }
+
+        if self.held.len() >= self.capacity {
+            let evicted = self.held.iter().position(|held| !held.is_valid_block).unwrap_or(0);
+            if let Some(held) = self.held.remove(evicted) {
+                self.drop_submission(held, DropReason::Full);
+            }
+        }
+
+        self.held.push_back(HeldSubmission {
+            job_id: job_id.to_string(),
+            block_height,
+            nonce,
+            block_hash: block_hash.to_string(),
+            block_content_hex: block_content_hex.to_string(),
+            is_valid_block,
+            age_ms: 0.0,
+            held_ms: clock::monotonic_ms(),
+        });
+        true
+    }
+
+    /// The next submission to send as a plain object, after dropping any that are no longer fresh
+    ///
+    /// `tip_height` is the latest known chain height; submissions for that
+    /// height or below are stale. It stays held until `remove`d, so a send
+    /// that fails again can simply be retried later.
+    pub fn next(&mut self, tip_height: Option<u64>) -> Result<JsValue, JsValue> {
+        self.expire(tip_height);
+        let now_ms = clock::monotonic_ms();
+        let next = self
+            .held
+            .iter()
+            .find(|held| held.is_valid_block)
+            .or_else(|| self.held.front())
+            .map(|held| HeldSubmission { age_ms: now_ms - held.held_ms, ..held.clone() });
+
+        match next {
+            Some(held) => serde_wasm_bindgen::to_value(&held)
+                .map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string())),
+            None => Ok(JsValue::UNDEFINED),
+        }
+    }
+
+    /// Forget a submission the pool has now answered; false if it wasn't held
+    pub fn remove(&mut self, job_id: &str, nonce: u32) -> bool {
+        match self.position(job_id, nonce) {
+            Some(index) => {
+                self.held.remove(index);
+                self.flushed += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop held submissions for a job, once a new block has made them stale
+    pub fn discard_job(&mut self, job_id: &str) {
+        let (discarded, kept): (VecDeque<_>, VecDeque<_>) =
+            std::mem::take(&mut self.held).into_iter().partition(|held| held.job_id == job_id);
+        self.held = kept;
+        for held in discarded {
+            self.drop_submission(held, DropReason::Discarded);
+        }
+    }
+
+    /// Submissions dropped since the last call, as `[{ job_id, block_height, nonce, is_valid_block, reason }]`
+    pub fn take_dropped(&mut self) -> Result<JsValue, JsValue> {
+        let dropped = std::mem::take(&mut self.dropped);
+        serde_wasm_bindgen::to_value(&dropped).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Submissions currently held
+    #[wasm_bindgen(getter)]
+    pub fn len(&self) -> usize {
+        self.held.len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn is_empty(&self) -> bool {
+        self.held.is_empty()
+    }
+
+    /// Held submissions sent and answered so far
+    #[wasm_bindgen(getter)]
+    pub fn flushed(&self) -> u32 {
+        self.flushed
+    }
+
+    /// Held submissions dropped so far, for any reason
+    #[wasm_bindgen(getter)]
+    pub fn dropped(&self) -> u32 {
+        self.dropped_total
+    }
+}
+
+impl SubmissionQueue {
+    fn position(&self, job_id: &str, nonce: u32) -> Option<usize> {
+        self.held.iter().position(|held| held.job_id == job_id && held.nonce == nonce)
+    }
+
+    fn expire(&mut self, tip_height: Option<u64>) {
+        let now_ms = clock::monotonic_ms();
+        let mut kept = VecDeque::with_capacity(self.held.len());
+        for held in std::mem::take(&mut self.held) {
+            if tip_height.is_some_and(|tip| held.block_height <= tip) {
+                self.drop_submission(held, DropReason::Stale);
+            } else if now_ms - held.held_ms > self.max_age_ms {
+                self.drop_submission(held, DropReason::Expired);
+            } else {
+                kept.push_back(held);
+            }
+        }
+        self.held = kept;
+    }
+
+    fn drop_submission(&mut self, held: HeldSubmission, reason: DropReason) {
+        self.dropped_total += 1;
+        if self.dropped.len() == self.capacity {
+            self.dropped.pop_front();
+        }
+        self.dropped.push_back(DroppedSubmission {
+            job_id: held.job_id,
+            block_height: held.block_height,
+            nonce: held.nonce,
+            is_valid_block: held.is_valid_block,
+            reason,
+        });
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use rayon::prelude::*;
+
+use crate::policy::DifficultyPolicy;
+use crate::{scan_range, ScanOutcome};
+
+/// Nonces handed to a worker at a time; small enough that a found block stops the others quickly
+const PIECE_SIZE: u32 = 4096;
+
+/// Combine two partial scans, keeping the lowest found nonce and the lowest hash
+fn merge(a: ScanOutcome, b: ScanOutcome) -> ScanOutcome {
+    let found = match (a.found, b.found) {
+        (Some(x), Some(y)) => Some(if x.0 <= y.0 { x } else { y }),
+        (x, y) => x.or(y),
+    };
+
+    let (best_nonce, best_hash) = if b.best_hash < a.best_hash {
+        (b.best_nonce, b.best_hash)
+    } else {
+        (a.best_nonce, a.best_hash)
+    };
+
+    ScanOutcome {
+        found,
+        best_nonce,
+        best_hash,
+        hashes_computed: a.hashes_computed + b.hashes_computed,
+    }
+}
+
+/// Parallel equivalent of `scan_range`, splitting `start..end` into pieces across the rayon pool
+///
+/// Once a worker finds a block, pieces past it are skipped but those before
+/// it still run, so the found nonce is the first in the range, as in the
+/// sequential scan. `hashes_computed` then counts up to that nonce only;
+/// the best hash may come from a piece that was underway past it.
+pub fn scan_range_parallel<P: DifficultyPolicy>(prefix: &[u8], policy: &P, start: u32, end: u32) -> ScanOutcome {
+    let first_found = AtomicU32::new(u32::MAX);
+    let pieces = end.saturating_sub(start).div_ceil(PIECE_SIZE);
+
+    let empty = || ScanOutcome {
+        found: None,
+        best_nonce: start,
+        best_hash: "f".repeat(64),
+        hashes_computed: 0,
+    };
+
+    let mut outcome = (0..pieces)
+        .into_par_iter()
+        .map(|piece| {
+            let piece_start = start + piece * PIECE_SIZE;
+            if piece_start > first_found.load(Ordering::Relaxed) {
+                return empty();
+            }
+
+            let piece_end = piece_start.saturating_add(PIECE_SIZE).min(end);
+            let outcome = scan_range(prefix, policy, piece_start, piece_end);
+
+            if let Some((nonce, _)) = &outcome.found {
+                first_found.fetch_min(*nonce, Ordering::Relaxed);
+            }
+            outcome
+        })
+        .reduce(empty, merge);
+
+    // Work past the find would not have been done sequentially
+    if let Some((nonce, _)) = &outcome.found {
+        outcome.hashes_computed = outcome.hashes_computed.min(nonce - start + 1);
+    }
+    outcome
+}
@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::parse;
+
+/// First protocol version whose headers carry an 8-byte timestamp
+pub const U64_TIMESTAMP_PROTOCOL_VERSION: u8 = 2;
+
+/// Length of a compressed public-key address
+pub const COMPRESSED_ADDRESS_LEN: usize = 33;
+
+/// First bytes a compressed address may start with
+///
+/// Stellaris writes the y parity as specifier 42 + (y % 2), i.e. 0x2a/0x2b;
+/// the SEC1 forms 0x02/0x03 are accepted too, for keys exported by other
+/// tools.
+pub const COMPRESSED_ADDRESS_PREFIXES: [u8; 4] = [0x2a, 0x2b, 0x02, 0x03];
+
+/// Byte written before compressed addresses in the header
+pub const COMPRESSED_ADDRESS_VERSION: u8 = 2;
+
+/// Length of an uncompressed public key as Stellaris stores it (x || y, no SEC1 prefix)
+pub const UNCOMPRESSED_ADDRESS_LEN: usize = 64;
+
+/// Length of a SEC1 uncompressed public key (0x04 || x || y)
+pub const SEC1_UNCOMPRESSED_ADDRESS_LEN: usize = 65;
+
+/// Largest header any layout produces, nonce included: the longest address and a u64 timestamp
+///
+/// Buffers on the hashing path are stack arrays of this size, so no address
+/// policy admits an address longer than `UNCOMPRESSED_ADDRESS_LEN`.
+pub const MAX_HEADER_LEN: usize = 32 + UNCOMPRESSED_ADDRESS_LEN + 32 + 8 + 2 + 4;
+
+/// Bytes before the nonce in a header with an `address_len`-byte address
+pub const fn prefix_len(address_len: usize, timestamp_width: TimestampWidth) -> usize {
+    let version = (address_len == COMPRESSED_ADDRESS_LEN) as usize;
+    version + 32 + address_len + 32 + timestamp_width as usize + 2
+}
+
+/// How decoded addresses are checked before going into a header
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AddressPolicy {
+    /// Accept only 33-byte compressed keys (0x2a/0x2b or 0x02/0x03) and 64-byte uncompressed keys
+    Strict,
+    /// Like `Strict`, but also accept 65-byte SEC1 keys by dropping their 0x04 prefix
+    Normalize,
+    /// Embed whatever bytes the address decodes to, unchecked
+    Raw,
+}
+
+/// Which rule decides whether a hash is a valid block (see `policy`)
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AcceptanceRule {
+    /// Hash starts with the tail of the previous hash (Stellaris)
+    ChunkSuffix,
+    /// Hash starts with zeros instead of the tail of the previous hash
+    LeadingZeros,
+    /// Hash is at or below the equivalent 256-bit target
+    Target,
+}
+
+/// Width of the timestamp field in the block header
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimestampWidth {
+    U32 = 4,
+    U64 = 8,
+}
+
+/// Chain-level parameters that control the header layout
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainParams {
+    protocol_version: u8,
+    timestamp_width: TimestampWidth,
+    address_policy: AddressPolicy,
+    acceptance_rule: AcceptanceRule,
+}
+
+#[wasm_bindgen]
+impl ChainParams {
+    /// Check decoded address bytes against the address policy, returning the bytes to embed
+    pub fn normalize_address(&self, address: Vec<u8>) -> Result<Vec<u8>, String> {
+        if self.address_policy == AddressPolicy::Raw {
+            if address.len() > UNCOMPRESSED_ADDRESS_LEN {
+                return Err(format!(
+                    "Address is {} bytes; no header layout holds more than {}",
+                    address.len(),
+                    UNCOMPRESSED_ADDRESS_LEN
+                ));
+            }
+            return Ok(address);
+        }
+
+        match (address.len(), address.first()) {
+            (COMPRESSED_ADDRESS_LEN, Some(first)) if COMPRESSED_ADDRESS_PREFIXES.contains(first) => Ok(address),
+            (COMPRESSED_ADDRESS_LEN, _) => {
+                Err("Compressed address must start with 0x2a or 0x2b (or SEC1 0x02/0x03)".to_string())
+            }
+            (UNCOMPRESSED_ADDRESS_LEN, _) => Ok(address),
+            (SEC1_UNCOMPRESSED_ADDRESS_LEN, Some(0x04)) if self.address_policy == AddressPolicy::Normalize => {
+                Ok(address[1..].to_vec())
+            }
+            (SEC1_UNCOMPRESSED_ADDRESS_LEN, Some(0x04)) => {
+                Err("65-byte uncompressed address needs its 0x04 prefix removed".to_string())
+            }
+            (len, _)
+                if len <= UNCOMPRESSED_ADDRESS_LEN
+                    && parse::coerce(|| format!("Address is {} bytes; embedding it unchecked", len)) =>
+            {
+                Ok(address)
+            }
+            (len, _) => Err(format!(
+                "Address is {} bytes; expected a 33-byte compressed or 64-byte uncompressed key",
+                len
+            )),
+        }
+    }
+
+    /// Create params for a protocol version, deriving the timestamp width from it
+    #[wasm_bindgen(constructor)]
+    pub fn new(protocol_version: u8) -> ChainParams {
+        let timestamp_width = if protocol_version >= U64_TIMESTAMP_PROTOCOL_VERSION {
+            TimestampWidth::U64
+        } else {
+            TimestampWidth::U32
+        };
+
+        ChainParams {
+            protocol_version,
+            timestamp_width,
+            address_policy: AddressPolicy::Normalize,
+            acceptance_rule: AcceptanceRule::ChunkSuffix,
+        }
+    }
+
+    /// Override the timestamp width (e.g. to follow a node upgrade early)
+    pub fn with_timestamp_width(&self, timestamp_width: TimestampWidth) -> ChainParams {
+        ChainParams {
+            timestamp_width,
+            ..*self
+        }
+    }
+
+    /// Change how payout addresses are validated
+    pub fn with_address_policy(&self, address_policy: AddressPolicy) -> ChainParams {
+        ChainParams {
+            address_policy,
+            ..*self
+        }
+    }
+
+    /// Change which hashes count as valid blocks
+    pub fn with_acceptance_rule(&self, acceptance_rule: AcceptanceRule) -> ChainParams {
+        ChainParams {
+            acceptance_rule,
+            ..*self
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn protocol_version(&self) -> u8 {
+        self.protocol_version
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn timestamp_width(&self) -> TimestampWidth {
+        self.timestamp_width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn address_policy(&self) -> AddressPolicy {
+        self.address_policy
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn acceptance_rule(&self) -> AcceptanceRule {
+        self.acceptance_rule
+    }
+
+    /// Number of bytes the timestamp occupies in the header
+    #[wasm_bindgen(getter)]
+    pub fn timestamp_bytes(&self) -> usize {
+        self.timestamp_width as usize
+    }
+}
+
+impl ChainParams {
+    /// Append the timestamp in little endian, rejecting values that don't fit the width
+    pub fn encode_timestamp(&self, timestamp: u64, out: &mut Vec<u8>) -> Result<(), String> {
+        match self.timestamp_width {
+            TimestampWidth::U32 => {
+                let timestamp = u32::try_from(timestamp).map_err(|_| {
+                    format!("Timestamp {} does not fit in 4 bytes", timestamp)
+                })?;
+                out.extend_from_slice(&timestamp.to_le_bytes());
+            }
+            TimestampWidth::U64 => out.extend_from_slice(&timestamp.to_le_bytes()),
+        }
+        Ok(())
+    }
+}
+
+impl Default for ChainParams {
+    fn default() -> Self {
+        ChainParams::new(1)
+    }
+}
@@ -0,0 +1,53 @@
+use std::cell::Cell;
+
+use wasm_bindgen::prelude::*;
+
+use crate::logging::{self, LogLevel};
+
+/// How forgiving input parsing is
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Reject anything malformed (the default); what production pools want
+    Strict,
+    /// Coerce near-misses and log a warning instead: odd-length hex gets a
+    /// leading zero, addresses of unknown length are embedded as-is, and
+    /// difficulties are clamped into the encodable range
+    Lenient,
+}
+
+thread_local! {
+    static MODE: Cell<ParseMode> = const { Cell::new(ParseMode::Strict) };
+}
+
+/// Choose how module-wide input parsing treats malformed values
+#[wasm_bindgen]
+pub fn set_parse_mode(mode: ParseMode) {
+    MODE.with(|m| m.set(mode));
+}
+
+#[wasm_bindgen]
+pub fn parse_mode() -> ParseMode {
+    MODE.with(|m| m.get())
+}
+
+/// Whether to coerce instead of reject; logs `warning` when coercing
+pub fn coerce(warning: impl FnOnce() -> String) -> bool {
+    let lenient = parse_mode() == ParseMode::Lenient;
+    if lenient {
+        logging::emit(LogLevel::Warn, "parse", &warning());
+    }
+    lenient
+}
+
+/// Decode hex, left-padding an odd-length value with a zero in lenient mode
+pub fn decode_hex(name: &str, value: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    match hex::decode(value) {
+        Err(hex::FromHexError::OddLength)
+            if coerce(|| format!("{} has an odd number of hex digits; padding", name)) =>
+        {
+            hex::decode(format!("0{}", value))
+        }
+        result => result,
+    }
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::difficulty::{self, DIFFICULTY_1_HASHES};
+use crate::error::{self, ErrorCode};
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// How a pool turns submitted shares into payouts
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayoutScheme {
+    /// Pay per share: a fixed amount per share whether or not the pool finds blocks
+    Pps,
+    /// Pay per last N shares: each block found is split over the shares that preceded it
+    Pplns,
+}
+
+/// Expected earnings for a miner, as returned to JS
+#[derive(Serialize)]
+pub struct EarningsEstimate {
+    /// Blocks' worth of work this hashrate contributes per day
+    blocks_per_day: f64,
+    /// Difficulty-1 shares per day
+    shares_per_day: f64,
+    /// Paid per difficulty-1 share, after the pool fee
+    reward_per_share: f64,
+    /// Expected payout per day, after the pool fee
+    daily_earnings: f64,
+    /// One standard deviation of a day's payout; 0 under PPS, absent under PPLNS without a pool hashrate
+    daily_stddev: Option<f64>,
+}
+
+/// Estimate a miner's earnings under `scheme`
+///
+/// `pool_fee` is a fraction (0.01 for 1%). Both schemes pay the same on
+/// average; PPLNS payouts follow the pool's luck, so their spread depends on
+/// how many blocks the pool finds a day at `pool_hashrate`.
+pub fn estimate(
+    scheme: PayoutScheme,
+    pool_fee: f64,
+    network_difficulty: f64,
+    hashrate: f64,
+    block_reward: f64,
+    pool_hashrate: Option<f64>,
+) -> Result<EarningsEstimate, String> {
+    if !(0.0..=1.0).contains(&pool_fee) {
+        return Err(format!("Pool fee {} must be a fraction between 0 and 1", pool_fee));
+    }
+    if !hashrate.is_finite() || hashrate < 0.0 {
+        return Err(format!("Hashrate {} must be a non-negative number", hashrate));
+    }
+    if !block_reward.is_finite() || block_reward < 0.0 {
+        return Err(format!("Block reward {} must be a non-negative number", block_reward));
+    }
+    let network_difficulty = difficulty::validate_difficulty(network_difficulty)?;
+
+    let block_hashes = difficulty::expected_hashes(network_difficulty);
+    let net_reward = block_reward * (1.0 - pool_fee);
+    let blocks_per_day = hashrate * SECONDS_PER_DAY / block_hashes;
+    let daily_earnings = blocks_per_day * net_reward;
+
+    let daily_stddev = match (scheme, pool_hashrate) {
+        (PayoutScheme::Pps, _) => Some(0.0),
+        (PayoutScheme::Pplns, Some(pool_hashrate)) if pool_hashrate >= hashrate && pool_hashrate > 0.0 => {
+            // Pool blocks are Poisson, and each pays this miner a fixed slice of the reward
+            let pool_blocks = pool_hashrate * SECONDS_PER_DAY / block_hashes;
+            Some(net_reward * hashrate / pool_hashrate * pool_blocks.sqrt())
+        }
+        (PayoutScheme::Pplns, Some(pool_hashrate)) => {
+            return Err(format!("Pool hashrate {} must be at least the miner's {}", pool_hashrate, hashrate));
+        }
+        (PayoutScheme::Pplns, None) => None,
+    };
+
+    Ok(EarningsEstimate {
+        blocks_per_day,
+        shares_per_day: hashrate * SECONDS_PER_DAY / DIFFICULTY_1_HASHES,
+        reward_per_share: net_reward * DIFFICULTY_1_HASHES / block_hashes,
+        daily_earnings,
+        daily_stddev,
+    })
+}
+
+/// Estimate daily earnings: `{ blocks_per_day, shares_per_day,
+/// reward_per_share, daily_earnings, daily_stddev }`
+///
+/// Hashrates are in H/s and `block_reward` is in whatever unit the result
+/// should use. Throws `InvalidArgument` for a fee outside 0-1 or a negative
+/// hashrate or reward, and `InvalidDifficulty` for an unusable difficulty.
+#[wasm_bindgen]
+pub fn estimate_earnings(
+    scheme: PayoutScheme,
+    pool_fee: f64,
+    network_difficulty: f64,
+    hashrate: f64,
+    block_reward: f64,
+    pool_hashrate: Option<f64>,
+) -> Result<JsValue, JsValue> {
+    difficulty::validate_difficulty(network_difficulty).map_err(|e| error::js_error(ErrorCode::InvalidDifficulty, e))?;
+    let estimate = estimate(scheme, pool_fee, network_difficulty, hashrate, block_reward, pool_hashrate)
+        .map_err(|e| error::js_error(ErrorCode::InvalidArgument, e))?;
+    serde_wasm_bindgen::to_value(&estimate).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+}
@@ -0,0 +1,155 @@
+use crate::difficulty;
+use crate::params::{AcceptanceRule, ChainParams};
+
+/// Decides whether a hash is good enough to be a block
+///
+/// The mining loop is generic over this, so a chain with different
+/// acceptance rules only needs a new implementation, not a new loop.
+pub trait DifficultyPolicy: Sync {
+    /// Whether `hash_hex` (64 lowercase hex characters) is accepted
+    fn accepts(&self, hash_hex: &str) -> bool;
+}
+
+/// How many hex digits, from `0` up, may follow the whole part of `difficulty` (16 when it has no fraction)
+pub fn fraction_limit(difficulty: f64) -> usize {
+    let decimal = difficulty % 1.0;
+    if decimal > 0.0 {
+        (16.0 * (1.0 - decimal)).ceil() as usize
+    } else {
+        16
+    }
+}
+
+/// Whether the character after the first `whole` is low enough for the fractional part of `difficulty`
+pub fn meets_fraction(hash_hex: &str, difficulty: f64) -> bool {
+    let decimal = difficulty % 1.0;
+    if decimal > 0.0 {
+        let charset = "0123456789abcdef";
+        let count = fraction_limit(difficulty);
+        let valid_chars = &charset[..count];
+        let idifficulty = difficulty as usize;
+
+        if let Some(char_at_pos) = hash_hex.chars().nth(idifficulty) {
+            return valid_chars.contains(char_at_pos);
+        }
+        return false;
+    }
+
+    true
+}
+
+/// Stellaris rule: the hash starts with the last `floor(difficulty)`
+/// characters of the previous hash, and the next character is low enough
+/// for the fractional part
+pub struct ChunkSuffix<'a> {
+    chunk: &'a str,
+    difficulty: f64,
+}
+
+impl<'a> ChunkSuffix<'a> {
+    pub fn new(previous_hash: &'a str, difficulty: f64) -> Result<ChunkSuffix<'a>, String> {
+        let difficulty = difficulty::validate_difficulty(difficulty)?;
+        Ok(ChunkSuffix {
+            chunk: difficulty::difficulty_chunk(previous_hash, difficulty)?,
+            difficulty,
+        })
+    }
+}
+
+impl DifficultyPolicy for ChunkSuffix<'_> {
+    fn accepts(&self, hash_hex: &str) -> bool {
+        hash_hex.starts_with(self.chunk) && meets_fraction(hash_hex, self.difficulty)
+    }
+}
+
+/// Like `ChunkSuffix`, but the hash must start with `floor(difficulty)` zeros
+pub struct LeadingZeros {
+    difficulty: f64,
+}
+
+impl LeadingZeros {
+    pub fn new(difficulty: f64) -> Result<LeadingZeros, String> {
+        let difficulty = difficulty::validate_difficulty(difficulty)?;
+        Ok(LeadingZeros { difficulty })
+    }
+}
+
+impl DifficultyPolicy for LeadingZeros {
+    fn accepts(&self, hash_hex: &str) -> bool {
+        let zeros = self.difficulty as usize;
+        hash_hex.len() >= zeros
+            && hash_hex.bytes().take(zeros).all(|b| b == b'0')
+            && meets_fraction(hash_hex, self.difficulty)
+    }
+}
+
+/// The hash, read as a 256-bit big-endian integer, is at or below a target
+pub struct Target {
+    target_hex: String,
+}
+
+impl Target {
+    /// Target with the same odds as `difficulty` (see `difficulty_to_target`)
+    pub fn for_difficulty(difficulty: f64) -> Result<Target, String> {
+        Ok(Target {
+            target_hex: difficulty::target_for_difficulty(difficulty)?,
+        })
+    }
+}
+
+impl DifficultyPolicy for Target {
+    fn accepts(&self, hash_hex: &str) -> bool {
+        // Equal-length lowercase hex compares the same as the numbers it encodes
+        hash_hex.len() == self.target_hex.len() && hash_hex <= self.target_hex.as_str()
+    }
+}
+
+/// The policy a `ChainParams` selects, dispatched without boxing
+pub enum Acceptance<'a> {
+    ChunkSuffix(ChunkSuffix<'a>),
+    LeadingZeros(LeadingZeros),
+    Target(Target),
+}
+
+impl<'a> Acceptance<'a> {
+    /// Build the policy `params` calls for at `difficulty` on top of `previous_hash`
+    pub fn new(params: &ChainParams, previous_hash: &'a str, difficulty: f64) -> Result<Acceptance<'a>, String> {
+        Ok(match params.acceptance_rule() {
+            AcceptanceRule::ChunkSuffix => Acceptance::ChunkSuffix(ChunkSuffix::new(previous_hash, difficulty)?),
+            AcceptanceRule::LeadingZeros => Acceptance::LeadingZeros(LeadingZeros::new(difficulty)?),
+            AcceptanceRule::Target => Acceptance::Target(Target::for_difficulty(difficulty)?),
+        })
+    }
+}
+
+impl Acceptance<'_> {
+    /// The `AcceptanceRule` this policy implements
+    pub fn rule(&self) -> AcceptanceRule {
+        match self {
+            Acceptance::ChunkSuffix(_) => AcceptanceRule::ChunkSuffix,
+            Acceptance::LeadingZeros(_) => AcceptanceRule::LeadingZeros,
+            Acceptance::Target(_) => AcceptanceRule::Target,
+        }
+    }
+
+    /// Hex characters every accepted hash starts with, and the difficulty whose fraction limits the next one
+    ///
+    /// `None` for `Target`, which compares whole hashes rather than a prefix.
+    pub fn required_prefix(&self) -> Option<(String, f64)> {
+        match self {
+            Acceptance::ChunkSuffix(policy) => Some((policy.chunk.to_string(), policy.difficulty)),
+            Acceptance::LeadingZeros(policy) => Some(("0".repeat(policy.difficulty as usize), policy.difficulty)),
+            Acceptance::Target(_) => None,
+        }
+    }
+}
+
+impl DifficultyPolicy for Acceptance<'_> {
+    fn accepts(&self, hash_hex: &str) -> bool {
+        match self {
+            Acceptance::ChunkSuffix(policy) => policy.accepts(hash_hex),
+            Acceptance::LeadingZeros(policy) => policy.accepts(hash_hex),
+            Acceptance::Target(policy) => policy.accepts(hash_hex),
+        }
+    }
+}
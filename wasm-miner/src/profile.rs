@@ -0,0 +1,81 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(target_arch = "wasm32")]
+use std::sync::atomic::AtomicU32;
+
+use wasm_bindgen::prelude::*;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Makes start marks unique so overlapping async spans don't collide
+#[cfg(target_arch = "wasm32")]
+static NEXT_MARK: AtomicU32 = AtomicU32::new(0);
+
+/// A `performance.measure` entry, recorded when the guard is dropped
+///
+/// Does nothing unless profiling is on, and nothing at all in native builds.
+pub struct Span {
+    #[cfg(target_arch = "wasm32")]
+    active: Option<(&'static str, String)>,
+}
+
+impl Span {
+    /// Start a span; it shows up under `name` in the devtools Performance panel
+    #[cfg(target_arch = "wasm32")]
+    pub fn start(name: &'static str) -> Span {
+        if ENABLED.load(Ordering::Relaxed) {
+            if let Some(performance) = crate::clock::performance() {
+                let start_mark = format!("{}:{}", name, NEXT_MARK.fetch_add(1, Ordering::Relaxed));
+                if performance.mark(&start_mark).is_ok() {
+                    return Span { active: Some((name, start_mark)) };
+                }
+            }
+        }
+        Span { active: None }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn start(_name: &'static str) -> Span {
+        Span {}
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some((name, start_mark)) = self.active.take() {
+            if let Some(performance) = crate::clock::performance() {
+                let _ = performance.measure_with_start_mark(name, &start_mark);
+                performance.clear_marks_with_mark_name(&start_mark);
+            }
+        }
+    }
+}
+
+/// Record `performance.measure` entries for hashing, job parsing and submission
+///
+/// Measures are named `stellaris:hash`, `stellaris:parse-job`,
+/// `stellaris:post` and `stellaris:submit-block`. Off by default, since
+/// the browser keeps every entry until they are cleared.
+#[wasm_bindgen]
+pub fn set_profiling(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[wasm_bindgen]
+pub fn profiling_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Install the global `tracing` subscriber: the browser console and
+/// Performance panel under WASM, stdout in native builds
+#[cfg(feature = "tracing")]
+pub fn init_tracing() {
+    #[cfg(target_arch = "wasm32")]
+    let _ = tracing_wasm::try_set_as_global_default();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .try_init();
+}
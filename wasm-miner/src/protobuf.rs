@@ -0,0 +1,149 @@
+//! Protobuf encoding of jobs and shares, per `proto/stellaris_miner.proto`
+//!
+//! The messages are `prost` types written out by hand, which
+//! `tests/protobuf.rs` checks against the `.proto` file. Their serde form is
+//! the JSON the rest of the miner already parses: `bytes` fields map to
+//! lowercase hex, missing fields take their proto3 defaults, and decoded
+//! JSON has every field except those declared `optional`, which stay absent.
+//! Unknown fields are skipped on decode and ignored on encode, as protobuf
+//! readers do.
+
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::difficulty;
+use crate::error::{self, ErrorCode};
+
+/// A work assignment, as returned by `/api/work`
+#[derive(Clone, PartialEq, Message, Serialize, Deserialize)]
+#[serde(default)]
+struct Job {
+    #[prost(string, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    job_id: Option<String>,
+    #[prost(uint64, tag = "2")]
+    block_height: u64,
+    #[prost(double, tag = "3")]
+    #[serde(deserialize_with = "difficulty::deserialize_difficulty")]
+    difficulty: f64,
+    #[prost(bytes = "vec", tag = "4")]
+    #[serde(with = "hex_bytes")]
+    previous_hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "5")]
+    #[serde(with = "hex_bytes")]
+    merkle_root: Vec<u8>,
+    #[prost(uint64, tag = "6")]
+    timestamp: u64,
+    #[prost(uint32, tag = "7")]
+    nonce_start: u32,
+    #[prost(uint32, tag = "8")]
+    nonce_end: u32,
+    #[prost(string, tag = "9")]
+    pool_address: String,
+    #[prost(uint64, optional, tag = "10")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ttl_ms: Option<u64>,
+}
+
+/// A signed receipt for a share (see `ShareSigner`)
+#[derive(Clone, PartialEq, Message, Serialize, Deserialize)]
+#[serde(default)]
+struct ShareReceipt {
+    #[prost(uint64, tag = "1")]
+    sequence: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    #[serde(with = "hex_bytes")]
+    signature: Vec<u8>,
+}
+
+/// A share submission, as posted to `/api/share`
+#[derive(Clone, PartialEq, Message, Serialize, Deserialize)]
+#[serde(default)]
+struct Share {
+    #[prost(string, tag = "1")]
+    miner_id: String,
+    #[prost(uint64, tag = "2")]
+    block_height: u64,
+    #[prost(uint32, tag = "3")]
+    nonce: u32,
+    /// `block_content` in the schema; the JSON body names it after its hex form
+    #[prost(bytes = "vec", tag = "4")]
+    #[serde(rename = "block_content_hex", with = "hex_bytes")]
+    block_content: Vec<u8>,
+    #[prost(bytes = "vec", tag = "5")]
+    #[serde(with = "hex_bytes")]
+    block_hash: Vec<u8>,
+    #[prost(bool, tag = "6")]
+    is_valid_block: bool,
+    #[prost(message, optional, tag = "7")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    receipt: Option<ShareReceipt>,
+    #[prost(string, tag = "8")]
+    worker_name: String,
+    #[prost(string, tag = "9")]
+    user_agent: String,
+}
+
+/// `bytes` fields as lowercase hex in JSON
+mod hex_bytes {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        hex::decode(&text).map_err(|_| D::Error::custom(format!("expected a hex string, got {:?}", text)))
+    }
+}
+
+/// Encode an `/api/work` JSON body as a protobuf `Job`, e.g. for a pool test harness
+#[wasm_bindgen]
+pub fn pool_work_to_protobuf(json: &str) -> Result<Vec<u8>, JsValue> {
+    json_to_protobuf::<Job>(json).map_err(|e| error::js_error(ErrorCode::InvalidArgument, e))
+}
+
+/// Decode a protobuf `Job` to its `/api/work` JSON body
+#[wasm_bindgen]
+pub fn pool_work_from_protobuf(bytes: &[u8]) -> Result<String, JsValue> {
+    job_to_json(bytes).map_err(|e| error::js_error(ErrorCode::InvalidJob, e))
+}
+
+/// Encode an `/api/share` JSON body as a protobuf `Share`
+#[wasm_bindgen]
+pub fn share_to_protobuf(json: &str) -> Result<Vec<u8>, JsValue> {
+    json_to_protobuf::<Share>(json).map_err(|e| error::js_error(ErrorCode::InvalidArgument, e))
+}
+
+/// Decode a protobuf `Share` to its `/api/share` JSON body, e.g. on a Node pool
+#[wasm_bindgen]
+pub fn share_from_protobuf(bytes: &[u8]) -> Result<String, JsValue> {
+    protobuf_to_json(&decode::<Share>(bytes)?).map_err(|e| error::js_error(ErrorCode::InvalidRecord, e))
+}
+
+/// Decode a protobuf `Job` to `/api/work` JSON
+pub fn job_to_json(bytes: &[u8]) -> Result<String, String> {
+    let job: Job = decode(bytes)?;
+    // JSON has no NaN or infinity, and serde_json would quietly write them as null
+    if !job.difficulty.is_finite() {
+        return Err(format!("Job difficulty {} is not finite", job.difficulty));
+    }
+    protobuf_to_json(&job)
+}
+
+fn json_to_protobuf<M: Message + for<'de> Deserialize<'de>>(json: &str) -> Result<Vec<u8>, String> {
+    let message: M = serde_json::from_str(json).map_err(|e| format!("Invalid JSON: {}", e))?;
+    Ok(message.encode_to_vec())
+}
+
+fn decode<M: Message + Default>(bytes: &[u8]) -> Result<M, String> {
+    M::decode(bytes).map_err(|e| format!("Invalid protobuf: {}", e))
+}
+
+/// A message as JSON text, going through `serde_json::Value` so keys come out sorted
+fn protobuf_to_json<M: Serialize>(message: &M) -> Result<String, String> {
+    serde_json::to_value(message).map(|value| value.to_string()).map_err(|e| e.to_string())
+}
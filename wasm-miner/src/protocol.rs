@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::clock;
+use crate::error::{self, ErrorCode};
+use crate::transcode;
+use crate::{MinerResult, StopReason};
+
+/// Messages a coordinator and its mining workers exchange over `postMessage`
+///
+/// Serialized as plain objects tagged by `type`; the TypeScript definitions
+/// below are emitted into the package's `.d.ts` so both sides type-check
+/// against the same schema. Hosts that relay messages as bytes (e.g.
+/// through a WebSocket broker) can use the MessagePack form from
+/// `encode_worker_message` instead, which every function here also accepts.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum WorkerMessage {
+    /// Coordinator to worker: start on a job, given as the pool's `/api/work` JSON
+    Assign { work: String },
+    /// Coordinator to worker: search this part of a job's nonce space
+    Range { job_id: String, nonce_start: u32, nonce_end: u32 },
+    /// Worker to coordinator: progress or a find
+    Result { worker: u32, job_id: String, result: MinerResult },
+    /// Coordinator to worker: stop work on `job_id`, or on everything when it is absent
+    Cancel {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        job_id: Option<String>,
+    },
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const WORKER_MESSAGE_TS: &'static str = r#"
+export type StopReasonName = "Found" | "RangeEnd" | "MaxHashes" | "Rolled";
+
+export interface MinerResultData {
+    found: boolean;
+    nonce: number;
+    hash: string;
+    hashes_computed: number;
+    best_nonce: number;
+    best_hash: string;
+    elapsed_ms: number;
+    stop_reason: StopReasonName;
+    next_nonce: number;
+    extranonce: number;
+    tip: number;
+    difficulty: number;
+    best_difficulty: number;
+    height?: number;
+}
+
+export type WorkerMessage =
+    | { type: "assign"; work: string }
+    | { type: "range"; job_id: string; nonce_start: number; nonce_end: number }
+    | { type: "result"; worker: number; job_id: string; result: MinerResultData }
+    | { type: "cancel"; job_id?: string };
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "WorkerMessage")]
+    pub type WorkerMessageValue;
+}
+
+fn to_js(message: &WorkerMessage) -> Result<WorkerMessageValue, JsValue> {
+    serde_wasm_bindgen::to_value(message)
+        .map(JsCast::unchecked_into)
+        .map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+}
+
+/// A received message, either a plain object or MessagePack bytes in a `Uint8Array`
+fn from_js(message: JsValue) -> Result<WorkerMessage, JsValue> {
+    let parsed = match message.dyn_ref::<js_sys::Uint8Array>() {
+        Some(bytes) => from_msgpack(&bytes.to_vec()),
+        None => serde_wasm_bindgen::from_value(message).map_err(|e| e.to_string()),
+    };
+    parsed.map_err(|e| error::js_error(ErrorCode::InvalidArgument, format!("Invalid worker message: {}", e)))
+}
+
+/// MessagePack with named fields, so the bytes decode to the same objects as the JSON form in any language
+fn to_msgpack(message: &WorkerMessage) -> Result<Vec<u8>, String> {
+    rmp_serde::to_vec_named(message).map_err(|e| e.to_string())
+}
+
+fn from_msgpack(bytes: &[u8]) -> Result<WorkerMessage, String> {
+    let mut rest = bytes;
+    let mut decoder = rmp_serde::Deserializer::new(&mut rest);
+    decoder.set_max_depth(transcode::MAX_DEPTH);
+    let message = WorkerMessage::deserialize(&mut decoder).map_err(|e| e.to_string())?;
+    if !rest.is_empty() {
+        return Err(format!("{} unexpected bytes after the MessagePack value", rest.len()));
+    }
+    Ok(message)
+}
+
+/// Check a received message against the schema, returning it in canonical form
+///
+/// Throws `InvalidArgument` for an unknown `type`, missing or extra fields,
+/// or values of the wrong type.
+#[wasm_bindgen]
+pub fn parse_worker_message(message: JsValue) -> Result<WorkerMessageValue, JsValue> {
+    to_js(&from_js(message)?)
+}
+
+/// Check a message against the schema and encode it as MessagePack, e.g. to send as a binary frame
+#[wasm_bindgen]
+pub fn encode_worker_message(message: JsValue) -> Result<Vec<u8>, JsValue> {
+    to_msgpack(&from_js(message)?).map_err(|e| error::js_error(ErrorCode::Serialization, e))
+}
+
+/// `{ type: "result" }` message reporting `result` for `job_id` from worker `worker`
+#[wasm_bindgen]
+pub fn worker_result_message(worker: u32, job_id: &str, result: &MinerResult) -> Result<WorkerMessageValue, JsValue> {
+    to_js(&WorkerMessage::Result { worker, job_id: job_id.to_string(), result: result.clone() })
+}
+
+/// `{ type: "range" }` message assigning `nonce_start..nonce_end` of `job_id`
+#[wasm_bindgen]
+pub fn worker_range_message(job_id: &str, nonce_start: u32, nonce_end: u32) -> Result<WorkerMessageValue, JsValue> {
+    to_js(&WorkerMessage::Range { job_id: job_id.to_string(), nonce_start, nonce_end })
+}
+
+/// Read the `MinerResult` out of a `{ type: "result" }` message
+#[wasm_bindgen]
+pub fn worker_message_result(message: JsValue) -> Result<MinerResult, JsValue> {
+    match from_js(message)? {
+        WorkerMessage::Result { result, .. } => Ok(result),
+        _ => Err(error::js_error(ErrorCode::InvalidArgument, "Not a result message")),
+    }
+}
+
+/// Timings of one encoding in `benchmark_worker_encoding`
+#[derive(Serialize)]
+struct EncodingCost {
+    encoding: &'static str,
+    /// Average time to encode and decode one message
+    round_trip_us: f64,
+    /// Size of one encoded message, or 0 for JS objects
+    bytes: usize,
+}
+
+/// Time round trips of a typical `result` message through each encoding
+///
+/// Compares the JS object form handed to `postMessage` (conversion only;
+/// the browser's structured clone comes on top), MessagePack, and JSON, over
+/// `iterations` messages each. Returns `[{ encoding, round_trip_us, bytes }]`.
+#[wasm_bindgen]
+pub fn benchmark_worker_encoding(iterations: u32) -> Result<JsValue, JsValue> {
+    let hash = "00000a1b2c3d4e5f60718293a4b5c6d7e8f90112233445566778899aabbccdd3".to_string();
+    let result = MinerResult {
+        found: false,
+        nonce: 0,
+        hash: String::new(),
+        hashes_computed: 50_000,
+        best_nonce: 1_234_567,
+        best_hash: hash,
+        elapsed_ms: 48.25,
+        stop_reason: StopReason::MaxHashes,
+        next_nonce: 1_250_000,
+        extranonce: 0,
+        tip: 0,
+        difficulty: 6.3,
+        best_difficulty: 5.7,
+        height: 1001,
+    };
+    let message = WorkerMessage::Result { worker: 3, job_id: "1001-5f2e8c1a9b3d4f60".to_string(), result };
+    let serialization = |e: String| error::js_error(ErrorCode::Serialization, e);
+    let msgpack = to_msgpack(&message).map_err(serialization)?;
+    let json = serde_json::to_string(&message).map_err(|e| serialization(e.to_string()))?;
+
+    let costs = [
+        EncodingCost {
+            encoding: "object",
+            round_trip_us: average_us(iterations, || from_js(to_js(&message)?.into()).map(drop))?,
+            bytes: 0,
+        },
+        EncodingCost {
+            encoding: "msgpack",
+            round_trip_us: average_us(iterations, || {
+                to_msgpack(&message).and_then(|bytes| from_msgpack(&bytes)).map(drop).map_err(serialization)
+            })?,
+            bytes: msgpack.len(),
+        },
+        EncodingCost {
+            encoding: "json",
+            round_trip_us: average_us(iterations, || {
+                let json = serde_json::to_string(&message).map_err(|e| serialization(e.to_string()))?;
+                serde_json::from_str::<WorkerMessage>(&json).map(drop).map_err(|e| serialization(e.to_string()))
+            })?,
+            bytes: json.len(),
+        },
+    ];
+    serde_wasm_bindgen::to_value(&costs).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+}
+
+/// Average wall time of `round_trip` over `iterations` runs, in microseconds
+fn average_us(iterations: u32, mut round_trip: impl FnMut() -> Result<(), JsValue>) -> Result<f64, JsValue> {
+    let iterations = iterations.max(1);
+    let started = clock::monotonic_ms();
+    for _ in 0..iterations {
+        round_trip()?;
+    }
+    Ok((clock::monotonic_ms() - started) * 1000.0 / iterations as f64)
+}
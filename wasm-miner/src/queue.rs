@@ -0,0 +1,66 @@
+use std::collections::VecDeque;
+
+use crate::job::MiningJob;
+
+/// Jobs waiting to be mined, with clean-job preemption
+///
+/// A clean job (new block at the tip) replaces the current job immediately
+/// and discards anything queued, since all of it is now stale. Other jobs
+/// wait until the current one has been fully searched so no work is wasted.
+#[derive(Default)]
+pub struct JobQueue {
+    current: Option<MiningJob>,
+    pending: VecDeque<MiningJob>,
+    preempted: bool,
+}
+
+impl JobQueue {
+    pub fn push(&mut self, job: MiningJob, clean: bool) {
+        if clean {
+            self.pending.clear();
+            self.preempted = self.current.is_some();
+            self.current = Some(job);
+        } else if self.current.is_none() {
+            self.current = Some(job);
+        } else {
+            self.pending.push_back(job);
+        }
+    }
+
+    pub fn current(&self) -> Option<&MiningJob> {
+        self.current.as_ref()
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether a clean job replaced the current one since the last call
+    pub fn take_preempted(&mut self) -> bool {
+        std::mem::take(&mut self.preempted)
+    }
+
+    /// Mark the current job finished and promote the next queued one
+    pub fn advance(&mut self) -> Option<&MiningJob> {
+        self.current = self.pending.pop_front();
+        self.preempted = false;
+        self.current.as_ref()
+    }
+
+    /// Drop expired jobs, promoting the next live one if the current job expired; returns whether it did
+    pub fn expire(&mut self, mut expired: impl FnMut(&MiningJob) -> bool) -> bool {
+        self.pending.retain(|job| !expired(job));
+        if self.current.as_ref().is_some_and(&mut expired) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.current = None;
+        self.pending.clear();
+        self.preempted = false;
+    }
+}
@@ -0,0 +1,192 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::backend;
+use crate::difficulty;
+use crate::error::{self, ErrorCode};
+use crate::policy::{Acceptance, DifficultyPolicy};
+use crate::{hex_digest, ScanOutcome};
+
+/// Most hashes one `BestHashes` keeps
+pub const MAX_BEST_HASHES: usize = 256;
+
+/// One entry in `BestHashes::to_array`
+#[derive(Serialize)]
+struct RankedHash {
+    nonce: u32,
+    hash: String,
+    /// Difficulty the hash achieves (see `achieved_difficulty`)
+    difficulty: f64,
+}
+
+/// The lowest `capacity` hashes seen on one job, with their nonces
+///
+/// A single `best_hash` per range is one data point; pools that credit
+/// best-effort proofs, and diagnostics comparing backends or devices, can
+/// use more. Pass the same tracker to `MiningJob.mine_range_ranked` for
+/// every range of a job: each call offers every hash it computes, so the
+/// list is the true best `capacity` over all of them, lowest hash first.
+/// A tracker belongs to the first job that feeds it; `clear()` frees it for
+/// another.
+#[wasm_bindgen]
+pub struct BestHashes {
+    capacity: usize,
+    /// Raw digests and nonces, lowest digest first
+    entries: Vec<([u8; 32], u32)>,
+    /// Job id and previous hash of the job feeding this tracker
+    job: Option<(String, String)>,
+    hashes_offered: u64,
+}
+
+#[wasm_bindgen]
+impl BestHashes {
+    /// Keep the best `capacity` hashes, up to 256
+    #[wasm_bindgen(constructor)]
+    pub fn new(capacity: usize) -> Result<BestHashes, JsValue> {
+        if capacity == 0 || capacity > MAX_BEST_HASHES {
+            return Err(error::js_error(
+                ErrorCode::InvalidArgument,
+                format!("capacity must be between 1 and {}", MAX_BEST_HASHES),
+            ));
+        }
+
+        Ok(BestHashes {
+            capacity,
+            entries: Vec::with_capacity(capacity),
+            job: None,
+            hashes_offered: 0,
+        })
+    }
+
+    /// Entries as an array of `{ nonce, hash, difficulty }` objects, lowest hash first
+    pub fn to_array(&self) -> Result<JsValue, JsValue> {
+        let previous_hash = self.job.as_ref().map_or("", |(_, previous_hash)| previous_hash.as_str());
+        let ranked: Vec<RankedHash> = self
+            .entries
+            .iter()
+            .map(|(digest, nonce)| {
+                let hash = hex::encode(digest);
+                RankedHash {
+                    nonce: *nonce,
+                    difficulty: difficulty::achieved_difficulty(&hash, previous_hash),
+                    hash,
+                }
+            })
+            .collect();
+        serde_wasm_bindgen::to_value(&ranked).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Nonces of the entries, lowest hash first (a `Uint32Array`)
+    #[wasm_bindgen(getter)]
+    pub fn nonces(&self) -> Vec<u32> {
+        self.entries.iter().map(|(_, nonce)| *nonce).collect()
+    }
+
+    /// Hashes matching `nonces`, as hex
+    #[wasm_bindgen(getter)]
+    pub fn hashes(&self) -> Vec<String> {
+        self.entries.iter().map(|(digest, _)| hex::encode(digest)).collect()
+    }
+
+    /// Id of the job feeding this tracker, if any yet
+    #[wasm_bindgen(getter)]
+    pub fn job_id(&self) -> Option<String> {
+        self.job.as_ref().map(|(job_id, _)| job_id.clone())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Hashes offered so far, across every range
+    #[wasm_bindgen(getter)]
+    pub fn hashes_offered(&self) -> f64 {
+        self.hashes_offered as f64
+    }
+
+    /// Forget the entries and the job, e.g. to reuse the tracker for the next job
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.job = None;
+        self.hashes_offered = 0;
+    }
+}
+
+impl BestHashes {
+    /// Tie the tracker to a job on first use; an error if another job already feeds it
+    pub fn claim(&mut self, job_id: &str, previous_hash: &str) -> Result<(), String> {
+        match &self.job {
+            Some((claimed, _)) if claimed != job_id => {
+                Err(format!("Tracker holds hashes for job {}; clear() it before mining {}", claimed, job_id))
+            }
+            Some(_) => Ok(()),
+            None => {
+                self.job = Some((job_id.to_string(), previous_hash.to_string()));
+                Ok(())
+            }
+        }
+    }
+
+    /// Insert a digest if it ranks, keeping the list sorted and bounded
+    fn offer(&mut self, digest: [u8; 32], nonce: u32) {
+        let full = self.entries.len() == self.capacity;
+        if full && self.entries.last().is_some_and(|(worst, _)| digest >= *worst) {
+            return;
+        }
+        // The same nonce on a re-mined range ranks where it already is
+        if self.entries.iter().any(|(_, seen)| *seen == nonce) {
+            return;
+        }
+
+        let index = self.entries.partition_point(|(ranked, _)| *ranked <= digest);
+        if full {
+            self.entries.pop();
+        }
+        self.entries.insert(index, (digest, nonce));
+    }
+}
+
+/// `scan_range` that also offers every digest to `best`
+pub fn scan_range_ranked(
+    prefix: &[u8],
+    policy: &Acceptance,
+    start: u32,
+    end: u32,
+    best: &mut BestHashes,
+) -> ScanOutcome {
+    let mut best_hash = [0xffu8; 32];
+    let mut best_nonce = start;
+    let mut hashes_computed = 0u32;
+    let mut hash_hex = [0u8; 64];
+    let mut hasher = backend::PreparedHasher::new(backend::hash_backend(), prefix);
+
+    let mut found = None;
+    for nonce in start..end {
+        let hash_bytes = hasher.hash(nonce);
+        hashes_computed += 1;
+        best.offer(hash_bytes, nonce);
+
+        if hash_bytes < best_hash {
+            best_hash = hash_bytes;
+            best_nonce = nonce;
+        }
+        if policy.accepts(hex_digest(&hash_bytes, &mut hash_hex)) {
+            found = Some((nonce, hex::encode(hash_bytes)));
+            break;
+        }
+    }
+    best.hashes_offered += hashes_computed as u64;
+
+    ScanOutcome {
+        found,
+        best_nonce,
+        best_hash: hex::encode(best_hash),
+        hashes_computed,
+    }
+}
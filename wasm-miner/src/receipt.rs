@@ -0,0 +1,120 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use wasm_bindgen::prelude::*;
+
+use crate::error::{self, ErrorCode};
+
+/// SHA-256 block size, which HMAC pads keys to
+const BLOCK_LEN: usize = 64;
+
+/// HMAC-SHA256 (RFC 2104)
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block_key = [0u8; BLOCK_LEN];
+    if key.len() > BLOCK_LEN {
+        block_key[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block_key.map(|b| b ^ 0x36));
+    inner.update(message);
+
+    let mut outer = Sha256::new();
+    outer.update(block_key.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+/// The bytes a receipt signs; pools rebuild the same string to verify
+fn receipt_message(miner_id: &str, block_height: u64, nonce: u32, block_hash: &str, sequence: u64) -> String {
+    format!(
+        "{}:{}:{}:{}:{}",
+        miner_id,
+        block_height,
+        nonce,
+        block_hash.to_ascii_lowercase(),
+        sequence
+    )
+}
+
+fn decode_key(session_key_hex: &str) -> Result<Vec<u8>, JsValue> {
+    let key = hex::decode(session_key_hex)
+        .map_err(|e| error::js_error(ErrorCode::InvalidSessionKey, format!("Invalid session key: {}", e)))?;
+    if key.is_empty() {
+        return Err(error::js_error(ErrorCode::InvalidSessionKey, "Session key must not be empty"));
+    }
+    Ok(key)
+}
+
+/// Signature attached to a submitted share
+#[derive(Serialize)]
+struct ShareReceipt {
+    sequence: u64,
+    signature: String,
+}
+
+/// Signs outgoing shares with a session key issued by the pool
+///
+/// A receipt is the HMAC-SHA256 of `miner_id:block_height:nonce:block_hash:sequence`
+/// under the key. `sequence` increases with every share, so the pool can
+/// refuse replays by remembering the last one it saw. This only stops
+/// shares forged without the key; anyone who can read the page can read
+/// the key too.
+#[wasm_bindgen]
+pub struct ShareSigner {
+    key: Vec<u8>,
+    miner_id: String,
+    sequence: u64,
+}
+
+#[wasm_bindgen]
+impl ShareSigner {
+    #[wasm_bindgen(constructor)]
+    pub fn new(session_key_hex: &str, miner_id: &str) -> Result<ShareSigner, JsValue> {
+        Ok(ShareSigner {
+            key: decode_key(session_key_hex)?,
+            miner_id: miner_id.to_string(),
+            sequence: 0,
+        })
+    }
+
+    /// Receipt for the next share, as `{ sequence, signature }`
+    pub fn sign(&mut self, block_height: u64, nonce: u32, block_hash: &str) -> Result<JsValue, JsValue> {
+        self.sequence += 1;
+        let message = receipt_message(&self.miner_id, block_height, nonce, block_hash, self.sequence);
+        let receipt = ShareReceipt {
+            sequence: self.sequence,
+            signature: hex::encode(hmac_sha256(&self.key, message.as_bytes())),
+        };
+        serde_wasm_bindgen::to_value(&receipt).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Receipts issued so far
+    #[wasm_bindgen(getter)]
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+}
+
+/// Pool-side check of a share receipt (see `ShareSigner`)
+#[wasm_bindgen]
+pub fn verify_share_receipt(
+    session_key_hex: &str,
+    miner_id: &str,
+    block_height: u64,
+    nonce: u32,
+    block_hash: &str,
+    sequence: u64,
+    signature_hex: &str,
+) -> Result<bool, JsValue> {
+    let key = decode_key(session_key_hex)?;
+    let Ok(signature) = hex::decode(signature_hex) else {
+        return Ok(false);
+    };
+
+    let message = receipt_message(miner_id, block_height, nonce, block_hash, sequence);
+    let expected = hmac_sha256(&key, message.as_bytes());
+    // Compare every byte so timing doesn't reveal how much of a forgery matched
+    Ok(signature.len() == expected.len() && signature.iter().zip(expected).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0)
+}
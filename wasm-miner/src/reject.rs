@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Why a pool refused a share
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum RejectReason {
+    /// The share was for a job the pool has already moved past
+    Stale,
+    /// The pool has already credited this share
+    Duplicate,
+    /// The hash does not meet the pool's share difficulty
+    LowDifficulty,
+    /// The header does not match what the pool expects (wrong address, layout, ...)
+    BadHeader,
+    /// Credentials are missing or expired
+    Unauthorized,
+    /// Anything the parser doesn't recognise
+    Unknown,
+}
+
+/// What the mining loop should do after a share verdict
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RejectAction {
+    /// Keep mining the current job
+    Continue,
+    /// Drop the current job and fetch new work
+    RefreshJob,
+    /// Re-authenticate before submitting again
+    Reauthenticate,
+    /// Stop mining: every further share would be rejected the same way
+    Halt,
+}
+
+impl RejectReason {
+    /// Classify a free-form rejection message
+    pub fn parse(message: &str) -> RejectReason {
+        let message = message.to_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|n| message.contains(n));
+
+        // Credentials first, so "token expired" isn't taken for a stale job
+        if has(&["unauthorized", "forbidden", "not registered", "token"]) {
+            RejectReason::Unauthorized
+        } else if has(&["stale", "old job", "unknown job", "expired", "outdated"]) {
+            RejectReason::Stale
+        } else if has(&["duplicate", "already submitted", "already exists"]) {
+            RejectReason::Duplicate
+        } else if has(&["low difficulty", "difficulty too low", "above target", "does not meet"]) {
+            RejectReason::LowDifficulty
+        } else if has(&[
+            "bad header",
+            "invalid header",
+            "malformed header",
+            "header mismatch",
+            "invalid block content",
+            "address mismatch",
+            "wrong address",
+        ]) {
+            // Only phrases naming the header: this halts the miner, so a
+            // routine "Invalid share" or "invalid nonce" must not land here
+            RejectReason::BadHeader
+        } else {
+            RejectReason::Unknown
+        }
+    }
+
+    pub fn action(self) -> RejectAction {
+        match self {
+            RejectReason::Stale => RejectAction::RefreshJob,
+            RejectReason::Duplicate | RejectReason::LowDifficulty | RejectReason::Unknown => {
+                RejectAction::Continue
+            }
+            RejectReason::Unauthorized => RejectAction::Reauthenticate,
+            RejectReason::BadHeader => RejectAction::Halt,
+        }
+    }
+}
+
+/// The parts of a pool share response that decide acceptance
+#[derive(Deserialize)]
+pub struct PoolVerdict {
+    #[serde(default)]
+    pub success: bool,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl PoolVerdict {
+    pub fn parse(json: &str) -> Result<PoolVerdict, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid pool response: {}", e))
+    }
+
+    /// Human-readable rejection text, preferring the more specific field
+    pub fn message(&self) -> Option<String> {
+        self.reason.clone().or_else(|| self.error.clone())
+    }
+
+    /// `None` when the share was accepted
+    pub fn reject_reason(&self) -> Option<RejectReason> {
+        if self.success {
+            return None;
+        }
+        Some(RejectReason::parse(&self.message().unwrap_or_default()))
+    }
+}
+
+/// Classify a pool rejection message
+#[wasm_bindgen]
+pub fn parse_reject_reason(message: &str) -> RejectReason {
+    RejectReason::parse(message)
+}
+
+/// Recommended reaction to a rejection reason
+#[wasm_bindgen]
+pub fn reject_action(reason: RejectReason) -> RejectAction {
+    reason.action()
+}
@@ -0,0 +1,248 @@
+//! Record-and-replay of mining calls
+//!
+//! While recording, every CPU scan logs what it hashed (the header prefix
+//! and acceptance rule) and what it returned. `replay` re-runs the calls
+//! from such a log and reports any result that differs, so a "found a
+//! block but the pool rejected it" report can be reproduced from the log
+//! alone. Entry points that scan in slices (`mine_batch_into`,
+//! `mine_collect`, `mine_dual_tip`) log one `mine_range` call per slice.
+//! GPU passes confirmed with `confirm_kernel_batch` aren't recorded, as
+//! only the kernel's candidates were hashed on the CPU. Timings are not
+//! recorded; everything else in a result is a pure function of the inputs.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::backend::{self, HashBackend};
+use crate::error::{self, ErrorCode};
+use crate::params::{AcceptanceRule, ChainParams, MAX_HEADER_LEN};
+use crate::policy::Acceptance;
+use crate::{MinerResult, StopReason};
+
+/// Bumped whenever the log layout changes
+const LOG_VERSION: u32 = 1;
+
+/// Most calls kept in one recording; the oldest are dropped first
+pub const MAX_RECORDED_CALLS: usize = 10_000;
+
+thread_local! {
+    static RECORDING: RefCell<Option<Recording>> = const { RefCell::new(None) };
+}
+
+#[derive(Serialize, Deserialize)]
+struct Recording {
+    version: u32,
+    /// Each distinct header prefix and rule, so calls on the same job share one entry
+    jobs: Vec<RecordedJob>,
+    calls: VecDeque<RecordedCall>,
+    /// Calls dropped to stay within `MAX_RECORDED_CALLS`
+    #[serde(default)]
+    dropped: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+struct RecordedJob {
+    prefix: String,
+    rule: AcceptanceRule,
+    previous_hash: String,
+    difficulty: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordedCall {
+    job: usize,
+    nonce_start: u32,
+    nonce_end: u32,
+    max_hashes: u32,
+    backend: HashBackend,
+    result: RecordedResult,
+}
+
+/// The deterministic part of a `MinerResult`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+struct RecordedResult {
+    found: bool,
+    nonce: u32,
+    hash: String,
+    hashes_computed: u32,
+    best_nonce: u32,
+    best_hash: String,
+    stop_reason: StopReason,
+    next_nonce: u32,
+}
+
+impl From<&MinerResult> for RecordedResult {
+    fn from(result: &MinerResult) -> RecordedResult {
+        RecordedResult {
+            found: result.found,
+            nonce: result.nonce,
+            hash: result.hash.clone(),
+            hashes_computed: result.hashes_computed,
+            best_nonce: result.best_nonce,
+            best_hash: result.best_hash.clone(),
+            stop_reason: result.stop_reason,
+            next_nonce: result.next_nonce,
+        }
+    }
+}
+
+/// Start a fresh recording, discarding any in progress
+#[wasm_bindgen]
+pub fn start_recording() {
+    RECORDING.with(|recording| {
+        *recording.borrow_mut() = Some(Recording {
+            version: LOG_VERSION,
+            jobs: Vec::new(),
+            calls: VecDeque::new(),
+            dropped: 0,
+        })
+    });
+}
+
+/// Stop recording and return the log as JSON, or `undefined` if nothing was being recorded
+#[wasm_bindgen]
+pub fn stop_recording() -> Result<Option<String>, JsValue> {
+    RECORDING
+        .with(|recording| recording.borrow_mut().take())
+        .map(|log| serde_json::to_string(&log).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string())))
+        .transpose()
+}
+
+#[wasm_bindgen]
+pub fn is_recording() -> bool {
+    RECORDING.with(|recording| recording.borrow().is_some())
+}
+
+/// Add a finished call to the recording, if one is running
+#[allow(clippy::too_many_arguments)]
+pub fn record(
+    prefix: &[u8],
+    policy: &Acceptance,
+    previous_hash: &str,
+    difficulty: f64,
+    nonce_start: u32,
+    nonce_end: u32,
+    max_hashes: u32,
+    result: &MinerResult,
+) {
+    RECORDING.with(|recording| {
+        let mut recording = recording.borrow_mut();
+        let Some(log) = recording.as_mut() else {
+            return;
+        };
+
+        let job = RecordedJob {
+            prefix: hex::encode(prefix),
+            rule: policy.rule(),
+            previous_hash: previous_hash.to_string(),
+            difficulty,
+        };
+        let job = match log.jobs.iter().rposition(|recorded| *recorded == job) {
+            Some(index) => index,
+            None => {
+                log.jobs.push(job);
+                log.jobs.len() - 1
+            }
+        };
+
+        if log.calls.len() == MAX_RECORDED_CALLS {
+            log.calls.pop_front();
+            log.dropped += 1;
+        }
+        log.calls.push_back(RecordedCall {
+            job,
+            nonce_start,
+            nonce_end,
+            max_hashes,
+            backend: backend::hash_backend(),
+            result: result.into(),
+        });
+    });
+}
+
+/// One replayed call whose result differs from the recording
+#[derive(Serialize)]
+struct ReplayMismatch {
+    index: usize,
+    recorded: RecordedResult,
+    replayed: RecordedResult,
+}
+
+/// Outcome of `replay`, as returned to JS
+#[derive(Serialize)]
+struct ReplayReport {
+    calls: usize,
+    /// Calls the original recording had already dropped
+    dropped: u64,
+    mismatches: Vec<ReplayMismatch>,
+    passed: bool,
+}
+
+/// Re-run every call in a `stop_recording` log: `{ calls, dropped, mismatches, passed }`
+///
+/// Each call runs on the backend it was recorded with, or on `backend` for
+/// all of them when given (to check one backend against another's log).
+/// The active backend is restored afterwards, and replayed calls are not
+/// themselves recorded. Throws `InvalidRecord` for a malformed log.
+#[wasm_bindgen]
+pub fn replay(log: &str, backend: Option<HashBackend>) -> Result<JsValue, JsValue> {
+    let log: Recording = serde_json::from_str(log)
+        .map_err(|e| error::js_error(ErrorCode::InvalidRecord, format!("Invalid replay log: {}", e)))?;
+    if log.version != LOG_VERSION {
+        return Err(error::js_error(
+            ErrorCode::InvalidRecord,
+            format!("Replay log version {} is not supported (expected {})", log.version, LOG_VERSION),
+        ));
+    }
+
+    let active = backend::hash_backend();
+    let outcome = replay_calls(&log, backend);
+    backend::set_hash_backend(active);
+
+    let report = ReplayReport {
+        calls: log.calls.len(),
+        dropped: log.dropped,
+        passed: outcome.as_ref().is_ok_and(Vec::is_empty),
+        mismatches: outcome.map_err(|e| error::js_error(ErrorCode::InvalidRecord, e))?,
+    };
+    serde_wasm_bindgen::to_value(&report).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+}
+
+fn replay_calls(log: &Recording, backend: Option<HashBackend>) -> Result<Vec<ReplayMismatch>, String> {
+    let mut mismatches = Vec::new();
+
+    for (index, call) in log.calls.iter().enumerate() {
+        let job = log.jobs.get(call.job).ok_or_else(|| format!("Call {} refers to missing job {}", index, call.job))?;
+        let prefix = hex::decode(&job.prefix).map_err(|_| format!("Job {} has a non-hex prefix", call.job))?;
+        if prefix.len() + 4 > MAX_HEADER_LEN {
+            return Err(format!("Job {} has a {}-byte prefix, too long for a header", call.job, prefix.len()));
+        }
+        let params = ChainParams::default().with_acceptance_rule(job.rule);
+        let policy = Acceptance::new(&params, &job.previous_hash, job.difficulty)?;
+
+        backend::set_hash_backend(backend.unwrap_or(call.backend));
+        let result = crate::scan_prepared(
+            &prefix,
+            &policy,
+            &job.previous_hash,
+            job.difficulty,
+            call.nonce_start,
+            call.nonce_end,
+            call.max_hashes,
+        );
+
+        let replayed = RecordedResult::from(&result);
+        if replayed != call.result {
+            mismatches.push(ReplayMismatch {
+                index,
+                recorded: call.result.clone(),
+                replayed,
+            });
+        }
+    }
+
+    Ok(mismatches)
+}
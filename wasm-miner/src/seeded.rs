@@ -0,0 +1,98 @@
+//! Deterministic mode for reproducible results in downstream CI
+//!
+//! With a seed set, the module's clock starts at a time derived from the
+//! seed and advances a fixed tick on every reading, and retry jitter comes
+//! from a generator seeded the same way. A given sequence of calls then
+//! produces the same timestamps, `elapsed_ms` and `MinerResult`s on every
+//! run and platform.
+//!
+//! Time advances per reading, not per call, so the values depend on how
+//! many times the code reads the clock: a release that adds or removes a
+//! reading shifts everything after it. Expected values are only stable for
+//! one build; regenerate them when upgrading. Profiling spans time
+//! themselves with `performance.now()` and don't move the seeded clock.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::clock::{self, Clock};
+
+/// Wall-clock time of seed 0: 2023-11-14T22:13:20Z, as for mock jobs
+const SEEDED_EPOCH_MS: f64 = 1_700_000_000_000.0;
+
+/// How far the seeded clock moves on each reading
+pub const SEEDED_TICK_MS: f64 = 1.0;
+
+thread_local! {
+    /// splitmix64 state while a seed is set
+    static RNG: Cell<Option<u64>> = const { Cell::new(None) };
+    /// Clock installed before the seed was set, put back when it is cleared
+    static REAL_CLOCK: RefCell<Option<Rc<dyn Clock>>> = const { RefCell::new(None) };
+}
+
+/// Clock that starts at a seed-derived time and ticks forward once per reading
+///
+/// Wall-clock and monotonic readings share the one counter.
+struct SeededClock {
+    now: Cell<f64>,
+}
+
+impl Clock for SeededClock {
+    fn now_ms(&self) -> f64 {
+        let now = self.now.get();
+        self.now.set(now + SEEDED_TICK_MS);
+        now
+    }
+}
+
+/// Derive timestamps and randomness from `seed`, or pass `None` to go back to real time
+///
+/// Each call with the same seed restarts from the same state, so call it
+/// at the start of every test. Durations are counted in clock readings
+/// rather than time spent: a `mine_range` call reports an `elapsed_ms` of
+/// one tick however long hashing took. Clearing the seed puts back the
+/// clock that was installed before it was set.
+#[wasm_bindgen]
+pub fn set_seeded_mode(seed: Option<u32>) {
+    match seed {
+        Some(seed) => {
+            let start_ms = SEEDED_EPOCH_MS + seed as f64 * 1000.0;
+            if !seeded_mode() {
+                REAL_CLOCK.with(|real| *real.borrow_mut() = Some(clock::installed()));
+            }
+            clock::install(Rc::new(SeededClock { now: Cell::new(start_ms) }));
+            RNG.with(|rng| rng.set(Some(seed as u64)));
+        }
+        None => {
+            if let Some(real) = REAL_CLOCK.with(|real| real.borrow_mut().take()) {
+                clock::install(real);
+            }
+            RNG.with(|rng| rng.set(None));
+        }
+    }
+}
+
+/// Whether a seed is set (see `set_seeded_mode`)
+#[wasm_bindgen]
+pub fn seeded_mode() -> bool {
+    RNG.with(|rng| rng.get().is_some())
+}
+
+/// A value in `[0, 1)`: from the seeded generator if a seed is set, else `Math.random()`
+pub fn random() -> f64 {
+    match RNG.with(|rng| rng.get()) {
+        Some(state) => {
+            let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            RNG.with(|rng| rng.set(Some(state)));
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^= z >> 31;
+            // Top 53 bits, so every value is exactly representable
+            (z >> 11) as f64 / (1u64 << 53) as f64
+        }
+        None => js_sys::Math::random(),
+    }
+}
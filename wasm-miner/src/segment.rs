@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::csv::{self, CsvTable};
+use crate::ledger::ShareStatus;
+use crate::MinerResult;
+
+/// Most segments remembered; the oldest finished ones are dropped first
+pub const MAX_SEGMENTS: usize = 64;
+
+/// Stats for one named window of a session, as returned to JS
+#[derive(Clone, Debug, Serialize)]
+pub struct SegmentStats {
+    pub name: String,
+    /// Wall-clock start and end in milliseconds since the Unix epoch; `ended_ms` is unset while open
+    pub started_ms: f64,
+    pub ended_ms: Option<f64>,
+    pub total_hashes: u64,
+    /// Average hashrate over the segment's wall-clock span, in H/s
+    pub hashrate: f64,
+    pub blocks_found: u32,
+    pub accepted_shares: u32,
+    pub rejected_shares: u32,
+    pub stale_shares: u32,
+    pub best_difficulty: f64,
+}
+
+impl SegmentStats {
+    fn new(name: &str, now_ms: f64) -> SegmentStats {
+        SegmentStats {
+            name: name.to_string(),
+            started_ms: now_ms,
+            ended_ms: None,
+            total_hashes: 0,
+            hashrate: 0.0,
+            blocks_found: 0,
+            accepted_shares: 0,
+            rejected_shares: 0,
+            stale_shares: 0,
+            best_difficulty: 0.0,
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.ended_ms.is_none()
+    }
+
+    /// Copy with `hashrate` filled in as of `now_ms`
+    fn report(&self, now_ms: f64) -> SegmentStats {
+        let span_ms = self.ended_ms.unwrap_or(now_ms) - self.started_ms;
+        SegmentStats {
+            hashrate: if span_ms > 0.0 {
+                self.total_hashes as f64 * 1000.0 / span_ms
+            } else {
+                0.0
+            },
+            ..self.clone()
+        }
+    }
+}
+
+/// Named, possibly overlapping windows of session stats (per job, per hour, ...)
+///
+/// Every open segment accumulates each recorded result and share verdict,
+/// so a dashboard can run a per-job and a per-hour segment side by side.
+#[derive(Default)]
+pub struct Segments {
+    segments: VecDeque<SegmentStats>,
+}
+
+impl Segments {
+    /// Open segment `name`, restarting it if it is already open
+    pub fn start(&mut self, name: &str, now_ms: f64) {
+        self.segments.retain(|segment| !(segment.is_open() && segment.name == name));
+        if self.segments.len() >= MAX_SEGMENTS {
+            let oldest = self.segments.iter().position(|segment| !segment.is_open()).unwrap_or(0);
+            self.segments.remove(oldest);
+        }
+        self.segments.push_back(SegmentStats::new(name, now_ms));
+    }
+
+    /// Close open segment `name`, returning false if there was none
+    pub fn end(&mut self, name: &str, now_ms: f64) -> bool {
+        match self.open_mut().find(|segment| segment.name == name) {
+            Some(segment) => {
+                segment.ended_ms = Some(now_ms);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn record_result(&mut self, result: &MinerResult) {
+        for segment in self.open_mut() {
+            segment.total_hashes += result.hashes_computed as u64;
+            segment.blocks_found += result.found as u32;
+            segment.best_difficulty = segment.best_difficulty.max(result.best_difficulty);
+        }
+    }
+
+    pub fn record_share(&mut self, status: ShareStatus) {
+        for segment in self.open_mut() {
+            match status {
+                ShareStatus::Accepted => segment.accepted_shares += 1,
+                ShareStatus::Rejected => segment.rejected_shares += 1,
+                ShareStatus::Stale => segment.stale_shares += 1,
+            }
+        }
+    }
+
+    /// Latest segment called `name`, open or finished
+    pub fn get(&self, name: &str, now_ms: f64) -> Option<SegmentStats> {
+        self.segments
+            .iter()
+            .rev()
+            .find(|segment| segment.name == name)
+            .map(|segment| segment.report(now_ms))
+    }
+
+    /// Every remembered segment, oldest first
+    pub fn report(&self, now_ms: f64) -> Vec<SegmentStats> {
+        self.segments.iter().map(|segment| segment.report(now_ms)).collect()
+    }
+
+    /// Every remembered segment as CSV, oldest first; `ended` is empty while a segment is open
+    pub fn to_csv(&self, now_ms: f64) -> String {
+        let mut table = CsvTable::new(&[
+            "name",
+            "started",
+            "ended",
+            "total_hashes",
+            "hashrate",
+            "blocks_found",
+            "accepted_shares",
+            "rejected_shares",
+            "stale_shares",
+            "best_difficulty",
+        ]);
+        for segment in self.report(now_ms) {
+            table.row(&[
+                segment.name,
+                csv::iso_timestamp(segment.started_ms),
+                segment.ended_ms.map(csv::iso_timestamp).unwrap_or_default(),
+                segment.total_hashes.to_string(),
+                segment.hashrate.to_string(),
+                segment.blocks_found.to_string(),
+                segment.accepted_shares.to_string(),
+                segment.rejected_shares.to_string(),
+                segment.stale_shares.to_string(),
+                segment.best_difficulty.to_string(),
+            ]);
+        }
+        table.finish()
+    }
+
+    /// Forget finished segments
+    pub fn clear_finished(&mut self) {
+        self.segments.retain(SegmentStats::is_open);
+    }
+
+    fn open_mut(&mut self) -> impl Iterator<Item = &mut SegmentStats> {
+        self.segments.iter_mut().filter(|segment| segment.is_open())
+    }
+}
@@ -0,0 +1,152 @@
+use std::cell::RefCell;
+
+use wasm_bindgen::prelude::*;
+
+use crate::backend::{self, PreparedHasher};
+use crate::logging::{self, LogLevel};
+use crate::params::{ChainParams, TimestampWidth};
+use crate::sha;
+use crate::string_to_bytes;
+
+/// FIPS 180-2 SHA-256 vectors: message and digest
+const SHA256_VECTORS: [(&str, &str); 3] = [
+    ("", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+    ("abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+    (
+        "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+    ),
+];
+
+const PREVIOUS_HASH: &str = "00000a1b2c3d4e5f60718293a4b5c6d7e8f90112233445566778899aabbccdd3";
+const MERKLE_ROOT: &str = "5f2e8c1a9b3d4f6071829304a5b6c7d8e9f00112233445566778899aabbccdde";
+const TIMESTAMP: u64 = 1_700_000_000;
+const DIFFICULTY: f64 = 6.3;
+
+/// A header serialized and hashed independently of this crate
+struct HeaderVector {
+    address: &'static str,
+    timestamp_width: TimestampWidth,
+    nonce: u32,
+    hash: &'static str,
+}
+
+const COMPRESSED_ADDRESS: &str = "02abababababababababababababababababababababababababababababababab";
+const UNCOMPRESSED_ADDRESS: &str = "cdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd\
+                                    cdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd";
+
+/// One vector per fixed layout the specialized backend compiles
+const HEADER_VECTORS: [HeaderVector; 4] = [
+    // Compressed address: a single tail block past the midstate
+    HeaderVector {
+        address: COMPRESSED_ADDRESS,
+        timestamp_width: TimestampWidth::U32,
+        nonce: 31_337,
+        hash: "813e4fa8cc3821d743703135dacf51199a6537e1a6a5460ee258a2ff92b37142",
+    },
+    HeaderVector {
+        address: COMPRESSED_ADDRESS,
+        timestamp_width: TimestampWidth::U64,
+        nonce: 2_718_281,
+        hash: "e1f50945002d0198fa855ad0fe5291db0cc5f6767864a8d28c9aff4ba2950f5e",
+    },
+    // Uncompressed address: the nonce lands in a second tail block
+    HeaderVector {
+        address: UNCOMPRESSED_ADDRESS,
+        timestamp_width: TimestampWidth::U32,
+        nonce: 4_000_000_000,
+        hash: "f397691b80ac4596712a69c6edc1332656dd06c45577280a99cfa6923d8f088b",
+    },
+    HeaderVector {
+        address: UNCOMPRESSED_ADDRESS,
+        timestamp_width: TimestampWidth::U64,
+        nonce: 123_456_789,
+        hash: "1a45e4f89d068187791c332d48031a95e7ea7b28359202b4be08b9796ec2dc77",
+    },
+];
+
+/// Verdict of the known-answer checks run when the module starts
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct SelfTestReport {
+    failures: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl SelfTestReport {
+    /// True when every vector matched; don't mine otherwise
+    #[wasm_bindgen(getter)]
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// One line per vector that didn't match
+    #[wasm_bindgen(getter)]
+    pub fn failures(&self) -> Vec<String> {
+        self.failures.clone()
+    }
+}
+
+thread_local! {
+    static LAST_REPORT: RefCell<Option<SelfTestReport>> = const { RefCell::new(None) };
+}
+
+/// Run the SHA-256 and header-construction vectors through every hash backend
+///
+/// A failure means the wasm was corrupted in delivery or miscompiled by the
+/// engine, and any hashing it does would be wasted.
+#[wasm_bindgen]
+pub fn self_test() -> SelfTestReport {
+    let report = SelfTestReport { failures: failures() };
+    if !report.passed() {
+        for failure in &report.failures {
+            logging::emit(LogLevel::Error, "selftest", failure);
+        }
+    }
+    LAST_REPORT.with(|last| *last.borrow_mut() = Some(report.clone()));
+    report
+}
+
+/// Report from the self-test run at startup, running it now if it hasn't run yet
+#[wasm_bindgen]
+pub fn self_test_result() -> SelfTestReport {
+    LAST_REPORT.with(|last| last.borrow().clone()).unwrap_or_else(self_test)
+}
+
+fn failures() -> Vec<String> {
+    let mut failures = Vec::new();
+
+    for (message, expected) in SHA256_VECTORS {
+        if hex::encode(sha::sha256(message.as_bytes())) != expected {
+            failures.push(format!("SHA-256 of {:?} is wrong", message));
+        }
+    }
+
+    for vector in &HEADER_VECTORS {
+        let params = ChainParams::default().with_timestamp_width(vector.timestamp_width);
+        let prefix = match string_to_bytes(vector.address).and_then(|address| {
+            crate::build_prefix(&params, PREVIOUS_HASH, &address, MERKLE_ROOT, TIMESTAMP, DIFFICULTY)
+        }) {
+            Ok(prefix) => prefix,
+            Err(e) => {
+                failures.push(format!("Header prefix for nonce {} failed: {}", vector.nonce, e));
+                continue;
+            }
+        };
+
+        let mut content = prefix.clone();
+        content.extend_from_slice(&vector.nonce.to_le_bytes());
+        if hex::encode(sha::sha256(&content)) != vector.hash {
+            failures.push(format!("Header for nonce {} is serialized wrong", vector.nonce));
+        }
+
+        for backend in backend::ALL_BACKENDS {
+            let hash = PreparedHasher::new(backend, &prefix).hash(vector.nonce);
+            if hex::encode(hash) != vector.hash {
+                failures.push(format!("{:?} backend hashes the header for nonce {} wrong", backend, vector.nonce));
+            }
+        }
+    }
+
+    failures
+}
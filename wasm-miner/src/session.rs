@@ -0,0 +1,865 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::checkpoint::Checkpoints;
+use crate::clock;
+use crate::commitment::ProgressTracker;
+use crate::csv::CsvKind;
+use crate::difficulty::{eta_seconds, hash_meets_difficulty, suggest_share_difficulty, DIFFICULTY_1_HASHES};
+use crate::error::{self, ErrorCode};
+use crate::events::{EventKind, EventLog};
+use crate::job::MiningJob;
+use crate::ledger::{ShareLedger, ShareRecord, ShareStatus};
+use crate::luck::{LuckTracker, SessionLuck};
+use crate::queue::JobQueue;
+use crate::reject::{PoolVerdict, RejectAction, RejectReason};
+use crate::segment::Segments;
+use crate::share::BestShare;
+use crate::stats::{DifficultyHistogram, HashrateMeter, HashrateWindows};
+use crate::swap;
+use crate::throttle::{
+    BackgroundPolicy, DutyCycle, DEFAULT_ACTIVE_DUTY_CYCLE, DEFAULT_BACKGROUND_DUTY_CYCLE, DEFAULT_IDLE_RAMP_MS,
+};
+use crate::timesync::TimeSync;
+use crate::MinerResult;
+
+/// Default time between shares the session aims for when suggesting a difficulty
+const DEFAULT_SHARE_INTERVAL_SECS: f64 = 30.0;
+
+/// Batch size `hashes_within` falls back to before any rate has been measured
+const MIN_BUDGET_HASHES: u32 = 1_000;
+
+/// Weight of the newest result in the smoothed busy hashrate
+const BUSY_RATE_WEIGHT: f64 = 0.3;
+
+/// Most recent jobs remembered, for results and verdicts that arrive after a job is gone
+const MAX_RECENT_JOBS: usize = 64;
+
+/// Long-lived mining state that outlives individual jobs
+#[wasm_bindgen]
+pub struct MinerSession {
+    best_share: Option<BestShare>,
+    ledger: ShareLedger,
+    jobs: JobQueue,
+    share_difficulty: Option<f64>,
+    share_interval_secs: f64,
+    time_sync: TimeSync,
+    hashrate: HashrateMeter,
+    /// Smoothed H/s while actually hashing, ignoring rests between calls
+    busy_hashrate: f64,
+    total_hashes: u64,
+    luck: LuckTracker,
+    histogram: DifficultyHistogram,
+    progress: ProgressTracker,
+    segments: Segments,
+    duty_cycle: DutyCycle,
+    /// Latest chain tip reported through `observe_tip`, as (hash, height)
+    tip: Option<(String, u64)>,
+    /// The latest jobs pushed, newest last, as (job id, height, previous hash)
+    recent_jobs: VecDeque<(String, u64, String)>,
+    checkpoints: Checkpoints,
+    events: EventLog,
+    /// Oldest a job may get before the loop abandons it, on top of any TTL from the pool
+    max_job_age_ms: Option<f64>,
+    /// Jobs dropped by `expire_jobs` since the last `take_expired_jobs`
+    expired_jobs: Vec<String>,
+    shut_down: bool,
+}
+
+/// Point-in-time view of a session's stats, as returned to JS
+#[derive(Serialize)]
+pub struct StatsSnapshot {
+    timestamp_ms: f64,
+    total_hashes: u64,
+    /// `total_hashes` in difficulty-1 shares, the unit pools pay by
+    difficulty_1_shares: f64,
+    hashrate: f64,
+    hashrate_windows: HashrateWindows,
+    /// Height of the block the current job builds
+    height: Option<u64>,
+    tip_height: Option<u64>,
+    accepted_shares: u32,
+    rejected_shares: u32,
+    stale_shares: u32,
+    best_share: Option<BestShare>,
+    luck: SessionLuck,
+    difficulty_histogram: Vec<u32>,
+    duty_cycle: f64,
+    throttle_level: f64,
+    shut_down: bool,
+}
+
+impl Default for MinerSession {
+    fn default() -> Self {
+        MinerSession {
+            best_share: None,
+            ledger: ShareLedger::default(),
+            jobs: JobQueue::default(),
+            share_difficulty: None,
+            share_interval_secs: DEFAULT_SHARE_INTERVAL_SECS,
+            time_sync: TimeSync::default(),
+            hashrate: HashrateMeter::default(),
+            busy_hashrate: 0.0,
+            total_hashes: 0,
+            luck: LuckTracker::default(),
+            histogram: DifficultyHistogram::default(),
+            progress: ProgressTracker::default(),
+            segments: Segments::default(),
+            duty_cycle: DutyCycle::default(),
+            tip: None,
+            recent_jobs: VecDeque::new(),
+            checkpoints: Checkpoints::default(),
+            events: EventLog::default(),
+            max_job_age_ms: None,
+            expired_jobs: Vec::new(),
+            shut_down: false,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl MinerSession {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> MinerSession {
+        MinerSession::default()
+    }
+
+    /// Fold a `mine_range` result into the session's stats, returning true if it set a new best share
+    pub fn record_result(&mut self, job_id: &str, result: &MinerResult) -> bool {
+        if result.hashes_computed == 0 {
+            return false;
+        }
+
+        self.total_hashes += result.hashes_computed as u64;
+        self.hashrate.record(clock::monotonic_ms(), result.elapsed_ms, result.hashes_computed as u64);
+        let rate = result.hashes_per_second();
+        if rate > 0.0 {
+            self.busy_hashrate = if self.busy_hashrate > 0.0 {
+                self.busy_hashrate + BUSY_RATE_WEIGHT * (rate - self.busy_hashrate)
+            } else {
+                rate
+            };
+            self.duty_cycle.thermal.record(clock::monotonic_ms(), self.busy_hashrate);
+        }
+        // The same check the loop submits shares by; `best_difficulty` counts
+        // the longest suffix match, which needn't contain the share's chunk
+        let share_found = match (self.share_difficulty, self.recent_job(job_id)) {
+            (Some(difficulty), Some((_, _, previous_hash))) => {
+                hash_meets_difficulty(&result.best_hash, previous_hash, difficulty).unwrap_or(false)
+            }
+            _ => false,
+        };
+        self.luck.record(
+            result.hashes_computed as u64,
+            result.difficulty,
+            result.found,
+            self.share_difficulty,
+            share_found,
+        );
+        self.histogram.record(result.best_difficulty);
+        self.progress.record(job_id, result.best_nonce, &result.best_hash, result.hashes_computed);
+        self.segments.record_result(result);
+
+        let improved = match &self.best_share {
+            Some(best) => best.is_beaten_by(&result.best_hash),
+            None => true,
+        };
+
+        if improved {
+            self.best_share = Some(BestShare::new(
+                result.best_hash.clone(),
+                result.best_nonce,
+                job_id.to_string(),
+                result.height,
+                clock::now_ms(),
+            ));
+        }
+
+        improved
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn best_share(&self) -> Option<BestShare> {
+        self.best_share.clone()
+    }
+
+    /// Replace the best share, e.g. with one restored from storage after a reload
+    pub fn restore_best_share(&mut self, best_share: BestShare) {
+        self.best_share = Some(best_share);
+    }
+
+    /// Record the pool's verdict on a submitted share
+    pub fn record_share(
+        &mut self,
+        job_id: &str,
+        nonce: u32,
+        hash: &str,
+        status: ShareStatus,
+        reason: Option<String>,
+    ) {
+        let reject_reason = match status {
+            ShareStatus::Accepted => None,
+            ShareStatus::Stale => Some(RejectReason::Stale),
+            ShareStatus::Rejected => Some(RejectReason::parse(reason.as_deref().unwrap_or(""))),
+        };
+
+        self.log_share(job_id, nonce, status, reason.as_deref());
+        self.segments.record_share(status);
+        self.ledger.record(ShareRecord {
+            job_id: job_id.to_string(),
+            height: self.job_height(job_id),
+            nonce,
+            hash: hash.to_string(),
+            status,
+            reject_reason,
+            reason,
+            timestamp_ms: clock::now_ms(),
+        });
+    }
+
+    /// Record a raw pool share response and return how the mining loop should react
+    pub fn record_pool_response(
+        &mut self,
+        job_id: &str,
+        nonce: u32,
+        hash: &str,
+        response_json: &str,
+    ) -> Result<RejectAction, JsValue> {
+        let verdict = PoolVerdict::parse(response_json).map_err(|e| error::js_error(ErrorCode::InvalidResponse, e))?;
+        let reject_reason = verdict.reject_reason();
+
+        let status = match reject_reason {
+            None => ShareStatus::Accepted,
+            Some(RejectReason::Stale) => ShareStatus::Stale,
+            Some(_) => ShareStatus::Rejected,
+        };
+
+        let reason = verdict.message();
+        self.log_share(job_id, nonce, status, reason.as_deref());
+        self.segments.record_share(status);
+        self.ledger.record(ShareRecord {
+            job_id: job_id.to_string(),
+            height: self.job_height(job_id),
+            nonce,
+            hash: hash.to_string(),
+            status,
+            reject_reason,
+            reason,
+            timestamp_ms: clock::now_ms(),
+        });
+
+        Ok(reject_reason.map_or(RejectAction::Continue, RejectReason::action))
+    }
+
+    /// Commit to the best results since the last commitment, as
+    /// `{ sequence, hashes_computed, leaf_count, root }` (see `verify_progress_opening`)
+    pub fn commit_progress(&mut self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.progress.commit())
+            .map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Reveal entry `index` of the latest commitment as `{ index, job_id, nonce, hash, proof }`
+    pub fn open_progress(&self, index: usize) -> Result<JsValue, JsValue> {
+        let opening = self
+            .progress
+            .open(index)
+            .ok_or_else(|| error::js_error(ErrorCode::NotFound, "No such entry in the latest commitment"))?;
+        serde_wasm_bindgen::to_value(&opening).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn accepted_shares(&self) -> u32 {
+        self.ledger.accepted()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rejected_shares(&self) -> u32 {
+        self.ledger.rejected()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn stale_shares(&self) -> u32 {
+        self.ledger.stale()
+    }
+
+    /// Number of share records available to `query_shares`
+    #[wasm_bindgen(getter)]
+    pub fn share_record_count(&self) -> usize {
+        self.ledger.len()
+    }
+
+    /// Page through share records (oldest first) as an array of plain objects
+    pub fn query_shares(&self, offset: usize, limit: usize) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.ledger.page(offset, limit))
+            .map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Queue a job; `clean` jobs preempt the current one and flush the queue
+    ///
+    /// Ignored once the session has been shut down.
+    pub fn push_job(&mut self, job: MiningJob, clean: bool) {
+        if self.shut_down {
+            return;
+        }
+        let message = if clean {
+            format!("Switched to clean job {} at height {}", job.job_id(), job.height())
+        } else {
+            format!("Queued job {} at height {}", job.job_id(), job.height())
+        };
+        self.events.push(EventKind::JobChanged, message);
+        if self.recent_jobs.len() == MAX_RECENT_JOBS {
+            self.recent_jobs.pop_front();
+        }
+        self.recent_jobs.push_back((job.job_id(), job.height(), job.previous_hash()));
+        self.jobs.push(job, clean);
+    }
+
+    /// Height of the block a recently pushed job builds, even after it was mined out or replaced
+    pub fn job_height(&self, job_id: &str) -> Option<u64> {
+        self.recent_job(job_id).map(|(_, height, _)| *height)
+    }
+
+    /// Nonces searched between checkpoints (default 100,000); smaller means less overlap on resume
+    #[wasm_bindgen(setter)]
+    pub fn set_checkpoint_interval(&mut self, interval: u32) -> Result<(), JsValue> {
+        self.checkpoints.set_interval(interval).map_err(|e| error::js_error(ErrorCode::InvalidArgument, e))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn checkpoint_interval(&self) -> u32 {
+        self.checkpoints.interval()
+    }
+
+    /// Record that `start..end` of a job was fully searched, returning whether a new checkpoint was taken
+    ///
+    /// Ranges can be reported in any order, e.g. by several workers; the
+    /// checkpoint only covers nonces with no unsearched gap before them. The
+    /// search is assumed to start at the job's `nonce_start` when it is the
+    /// current job, otherwise at the first range reported. Persist
+    /// `checkpoint(job_id)` whenever this returns true.
+    pub fn mark_searched(&mut self, job_id: &str, start: u32, end: u32) -> bool {
+        let origin = match self.jobs.current() {
+            Some(job) if job.job_id() == job_id => job.nonce_start(),
+            _ => start,
+        };
+        self.checkpoints.mark(job_id, origin, start, end)
+    }
+
+    /// Highest nonce below which a job has been fully searched, as of its latest checkpoint
+    ///
+    /// A replacement worker resumes the job here, re-searching at most
+    /// `checkpoint_interval` nonces.
+    pub fn checkpoint(&self, job_id: &str) -> Option<u32> {
+        self.checkpoints.get(job_id)
+    }
+
+    /// Adopt a checkpoint persisted by an earlier worker, so `checkpoint` reports at least `nonce`
+    pub fn restore_checkpoint(&mut self, job_id: &str, nonce: u32) {
+        self.checkpoints.restore(job_id, nonce);
+    }
+
+    /// Validate and pre-build a job's header prefix, then queue it like `push_job`
+    ///
+    /// Decoding happens now, while the current job is still being mined, so
+    /// the loop can switch to it without a parse. Invalid jobs are rejected
+    /// here and never reach the queue.
+    pub fn prefetch_job(&mut self, mut job: MiningJob, clean: bool) -> Result<(), JsValue> {
+        job.prepare()?;
+        self.push_job(job, clean);
+        Ok(())
+    }
+
+    /// Queue the job from `publish_job` as a clean job, if one is waiting
+    ///
+    /// Returns whether a job was adopted; `take_preempted` then reports it.
+    pub fn adopt_published_job(&mut self) -> bool {
+        match swap::take_published_job() {
+            Some(job) if !self.shut_down => {
+                self.push_job(job, true);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Record the chain tip (e.g. from `watch_tip`), returning whether the current job is now stale
+    ///
+    /// A job is stale when the tip is at or past the height it builds on but
+    /// isn't its `previous_hash`. Older tips, such as a lagging node's, are
+    /// ignored.
+    pub fn observe_tip(&mut self, hash: &str, height: u64) -> bool {
+        if matches!(&self.tip, Some((_, latest)) if height < *latest) {
+            return false;
+        }
+        self.tip = Some((hash.to_string(), height));
+
+        let stale = match self.jobs.current() {
+            Some(job) => job.height() <= height.saturating_add(1) && job.previous_hash() != hash,
+            None => false,
+        };
+        if stale {
+            self.events.push(EventKind::Tip, format!("New tip {} at height {}; current job is stale", hash, height));
+        }
+        stale
+    }
+
+    /// Hash of the latest tip passed to `observe_tip`
+    #[wasm_bindgen(getter)]
+    pub fn tip_hash(&self) -> Option<String> {
+        self.tip.as_ref().map(|(hash, _)| hash.clone())
+    }
+
+    /// Height of the latest tip passed to `observe_tip`
+    #[wasm_bindgen(getter)]
+    pub fn tip_height(&self) -> Option<u64> {
+        self.tip.as_ref().map(|(_, height)| *height)
+    }
+
+    /// Job the mining loop should be working on, if any
+    #[wasm_bindgen(getter)]
+    pub fn current_job(&self) -> Option<MiningJob> {
+        self.jobs.current().cloned()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn pending_jobs(&self) -> usize {
+        self.jobs.pending_len()
+    }
+
+    /// Check (and clear) whether a clean job arrived; the loop should abandon its range if so
+    pub fn take_preempted(&mut self) -> bool {
+        self.jobs.take_preempted()
+    }
+
+    /// Finish the current job and return the next one to mine
+    pub fn advance_job(&mut self) -> Option<MiningJob> {
+        let next = self.jobs.advance().cloned();
+        if let Some(job) = &next {
+            self.events.push(EventKind::JobChanged, format!("Moved on to job {}", job.job_id()));
+        }
+        next
+    }
+
+    /// Longest a job is mined after it was received, whatever its TTL; unset by default
+    #[wasm_bindgen(setter)]
+    pub fn set_max_job_age_ms(&mut self, max_job_age_ms: Option<f64>) -> Result<(), JsValue> {
+        if max_job_age_ms.is_some_and(|max_age| max_age.is_nan() || max_age <= 0.0) {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "Maximum job age must be positive"));
+        }
+        self.max_job_age_ms = max_job_age_ms;
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_job_age_ms(&self) -> Option<f64> {
+        self.max_job_age_ms
+    }
+
+    /// Drop jobs past their TTL or the maximum job age, returning whether the current one was dropped
+    ///
+    /// Call at each chunk boundary; when this returns true the loop should
+    /// abandon its range and take `current_job` again, fetching fresh work
+    /// if nothing live is queued. Bounds the stale shares mined on a job
+    /// whose replacement notification was missed.
+    pub fn expire_jobs(&mut self) -> bool {
+        let max_age_ms = self.max_job_age_ms;
+        let mut expired_ids = Vec::new();
+        let dropped_current = self.jobs.expire(|job| {
+            let expired = job.expired(max_age_ms);
+            if expired {
+                expired_ids.push((job.job_id(), job.age_ms()));
+            }
+            expired
+        });
+        for (job_id, age_ms) in expired_ids {
+            self.events.push(EventKind::JobChanged, format!("Job {} expired after {:.0} s", job_id, age_ms / 1000.0));
+            self.expired_jobs.push(job_id);
+        }
+        dropped_current
+    }
+
+    /// Ids of the jobs `expire_jobs` has dropped since the last call, so their resources can be freed
+    pub fn take_expired_jobs(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.expired_jobs)
+    }
+
+    pub fn clear_jobs(&mut self) {
+        self.jobs.clear();
+    }
+
+    /// Share difficulty currently in force, as last set by the pool
+    #[wasm_bindgen(getter)]
+    pub fn share_difficulty(&self) -> Option<f64> {
+        self.share_difficulty
+    }
+
+    /// Apply a difficulty pushed (or confirmed) by the pool; takes effect from the next chunk
+    #[wasm_bindgen(setter)]
+    pub fn set_share_difficulty(&mut self, difficulty: Option<f64>) {
+        self.share_difficulty = difficulty.filter(|d| d.is_finite() && *d > 0.0);
+    }
+
+    /// Target seconds between shares used by `suggest_difficulty`
+    #[wasm_bindgen(getter)]
+    pub fn share_interval_secs(&self) -> f64 {
+        self.share_interval_secs
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_share_interval_secs(&mut self, secs: f64) {
+        self.share_interval_secs = secs;
+    }
+
+    /// Share difficulty to request from the pool for the measured `hashrate`
+    pub fn suggest_difficulty(&self, hashrate: f64) -> f64 {
+        suggest_share_difficulty(hashrate, self.share_interval_secs)
+    }
+
+    /// Feed a pool/node timestamp (seconds) seen in a response to a request sent at `sent_ms`
+    ///
+    /// `sent_ms` and `received_ms` are local wall-clock times. The corrected
+    /// offset is applied module-wide to header timestamps generated from now on.
+    pub fn observe_pool_time(&mut self, server_time_secs: f64, sent_ms: f64, received_ms: f64) -> bool {
+        let accepted = self.time_sync.observe(server_time_secs * 1000.0, sent_ms, received_ms);
+        if accepted {
+            clock::set_network_offset_ms(self.time_sync.offset_ms());
+        }
+        accepted
+    }
+
+    /// Estimated pool clock minus local clock, in milliseconds
+    #[wasm_bindgen(getter)]
+    pub fn clock_offset_ms(&self) -> f64 {
+        self.time_sync.offset_ms()
+    }
+
+    /// Current pool-aligned Unix time in seconds, for building header timestamps
+    pub fn header_timestamp(&self) -> u64 {
+        (clock::network_now_ms() / 1000.0) as u64
+    }
+
+    /// Rolling hashrate over the short window (the last minute by default) of recorded results, in H/s
+    #[wasm_bindgen(getter)]
+    pub fn hashrate(&self) -> f64 {
+        self.hashrate.hashrate(clock::monotonic_ms())
+    }
+
+    /// Set the hashrate windows: `sample_interval_ms` (10 s by default) is both the
+    /// instantaneous window and the width of each stored sample, `short_window_ms`
+    /// (1 min) drives `hashrate`, and `long_window_ms` (15 min) bounds the history
+    ///
+    /// Recorded samples are kept, so tightening a window takes effect at once.
+    pub fn configure_hashrate(
+        &mut self,
+        sample_interval_ms: f64,
+        short_window_ms: f64,
+        long_window_ms: f64,
+    ) -> Result<(), JsValue> {
+        self.hashrate
+            .configure(sample_interval_ms, short_window_ms, long_window_ms)
+            .map_err(|e| error::js_error(ErrorCode::InvalidArgument, e))
+    }
+
+    /// Hashrate over each window, as `{ instant, short, long, sample_interval_ms, short_window_ms, long_window_ms }`
+    pub fn hashrate_windows(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.hashrate.windows(clock::monotonic_ms()))
+            .map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Smoothed hashrate while hashing, excluding time spent resting or waiting, in H/s
+    #[wasm_bindgen(getter)]
+    pub fn busy_hashrate(&self) -> f64 {
+        self.busy_hashrate
+    }
+
+    /// Hashes expected to fit in `budget_ms`, for sizing batches to an idle deadline
+    ///
+    /// Uses `busy_hashrate`, so rests between batches don't shrink it. Never
+    /// returns less than a small minimum batch, so a zero deadline still
+    /// makes progress.
+    pub fn hashes_within(&self, budget_ms: f64) -> u32 {
+        let hashes = self.busy_hashrate * budget_ms.max(0.0) / 1000.0;
+        (hashes as u32).max(MIN_BUDGET_HASHES)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn total_hashes(&self) -> f64 {
+        self.total_hashes as f64
+    }
+
+    /// Expected seconds until a block at `difficulty`, at the session's rolling hashrate
+    pub fn eta_seconds(&self, difficulty: f64) -> f64 {
+        eta_seconds(difficulty, self.hashrate())
+    }
+
+    /// Blocks and shares found versus statistically expected, as
+    /// `{ blocks, shares }` each with `found`, `expected`, `luck_percent`,
+    /// `variance`, `z_score` and `luck_stddev_percent`
+    pub fn luck(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.luck.report())
+            .map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Histogram of each result's best-hash quality: element `i` counts
+    /// results whose best hash matched `i` characters of the previous hash
+    pub fn difficulty_histogram(&self) -> Vec<u32> {
+        self.histogram.buckets().to_vec()
+    }
+
+    /// Current stats as a plain object, for UIs and final reports
+    pub fn stats_snapshot(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.snapshot())
+            .map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Zero the session-wide stats without touching the job queue or connection state
+    ///
+    /// Clears hashes, hashrate and busy hashrate, luck, the difficulty
+    /// histogram, share counters and records, the best share, and the results
+    /// behind `commit_progress`. Segments keep running; use them for windows
+    /// that shouldn't be wiped by a reset.
+    pub fn reset_stats(&mut self) {
+        self.best_share = None;
+        self.ledger = ShareLedger::default();
+        self.hashrate.clear();
+        self.busy_hashrate = 0.0;
+        self.progress = ProgressTracker::default();
+        self.total_hashes = 0;
+        self.luck = LuckTracker::default();
+        self.histogram = DifficultyHistogram::default();
+    }
+
+    /// Open a named stats segment (e.g. a job id or `"hour-14"`), restarting it if already open
+    ///
+    /// Segments may overlap; each open one counts every result and share
+    /// recorded until `end_segment`.
+    pub fn start_segment(&mut self, name: &str) {
+        self.segments.start(name, clock::now_ms());
+    }
+
+    /// Close an open segment, freezing its stats; false if none was open under `name`
+    pub fn end_segment(&mut self, name: &str) -> bool {
+        self.segments.end(name, clock::now_ms())
+    }
+
+    /// Stats for the latest segment called `name`, as a plain object
+    pub fn segment_stats(&self, name: &str) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.segments.get(name, clock::now_ms()))
+            .map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Every open and recently finished segment, oldest first
+    pub fn segments(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.segments.report(clock::now_ms()))
+            .map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Forget finished segments
+    pub fn clear_finished_segments(&mut self) {
+        self.segments.clear_finished();
+    }
+
+    /// Session history as a CSV string (header row first, CRLF line endings) for spreadsheets
+    ///
+    /// Times are UTC ISO 8601. Shares are the records `query_shares` pages
+    /// through; segments are those `segments` returns.
+    pub fn export_csv(&self, kind: CsvKind) -> String {
+        match kind {
+            CsvKind::Shares => self.ledger.to_csv(),
+            CsvKind::Segments => self.segments.to_csv(clock::now_ms()),
+            CsvKind::Events => self.events.to_csv(),
+        }
+    }
+
+    /// Add an event from the embedder (errors, reconnects, ...) to the log
+    ///
+    /// Job changes, share verdicts, stale tips and shutdown are logged by
+    /// the session itself. Only the latest 500 events are kept.
+    pub fn log_event(&mut self, kind: EventKind, message: &str) {
+        self.events.push(kind, message);
+    }
+
+    /// The latest `limit` events (only those of `kind`, if given) as plain
+    /// `{ timestamp_ms, kind, message }` objects, oldest first
+    pub fn recent_events(&self, limit: usize, kind: Option<EventKind>) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.events.recent(limit, kind))
+            .map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Events currently in the log
+    #[wasm_bindgen(getter)]
+    pub fn event_count(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Events pushed out of the log to make room since it was last cleared
+    #[wasm_bindgen(getter)]
+    pub fn events_dropped(&self) -> f64 {
+        self.events.dropped() as f64
+    }
+
+    pub fn clear_events(&mut self) {
+        self.events.clear();
+    }
+
+    /// Choose what happens while the page is hidden
+    ///
+    /// `duty_cycle` is the fraction of time spent hashing under
+    /// `BackgroundPolicy::Throttle` (default 0.25); it is ignored otherwise.
+    pub fn set_background_policy(&mut self, policy: BackgroundPolicy, duty_cycle: Option<f64>) -> Result<(), JsValue> {
+        let duty_cycle = duty_cycle.unwrap_or(DEFAULT_BACKGROUND_DUTY_CYCLE);
+        if !(duty_cycle > 0.0 && duty_cycle <= 1.0) {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "Duty cycle must be in (0, 1]"));
+        }
+        self.duty_cycle.set_policy(policy, duty_cycle);
+        Ok(())
+    }
+
+    /// Throttle to `active_duty_cycle` (0.5 by default) while the user is active, ramping
+    /// to full speed over `ramp_ms` (30 s) once `user_idle` reports them idle
+    ///
+    /// Stacks with the background policy and thermal throttle. Off until enabled here.
+    pub fn set_idle_ramp(
+        &mut self,
+        enabled: bool,
+        active_duty_cycle: Option<f64>,
+        ramp_ms: Option<f64>,
+    ) -> Result<(), JsValue> {
+        let active_duty_cycle = active_duty_cycle.unwrap_or(DEFAULT_ACTIVE_DUTY_CYCLE);
+        let ramp_ms = ramp_ms.unwrap_or(DEFAULT_IDLE_RAMP_MS);
+        if !(active_duty_cycle > 0.0 && active_duty_cycle <= 1.0) {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "Active duty cycle must be in (0, 1]"));
+        }
+        if !(ramp_ms >= 0.0 && ramp_ms.is_finite()) {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "Idle ramp must be a non-negative duration"));
+        }
+        self.duty_cycle.idle.configure(enabled, active_duty_cycle, ramp_ms);
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn idle_ramp(&self) -> bool {
+        self.duty_cycle.idle.enabled()
+    }
+
+    /// Tell the session whether the user is idle, from an `IdleDetector` or another idle signal
+    #[wasm_bindgen(setter)]
+    pub fn set_user_idle(&mut self, idle: bool) {
+        self.duty_cycle.idle.set_idle(idle, clock::monotonic_ms());
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn user_idle(&self) -> bool {
+        self.duty_cycle.idle.idle()
+    }
+
+    /// Share of the usual duty cycle the idle ramp currently allows; 1 when it is off
+    #[wasm_bindgen(getter)]
+    pub fn idle_level(&self) -> f64 {
+        self.duty_cycle.idle.level(clock::monotonic_ms())
+    }
+
+    /// Tell the session whether its page is hidden; call from a `visibilitychange` listener
+    #[wasm_bindgen(setter)]
+    pub fn set_page_hidden(&mut self, hidden: bool) {
+        self.duty_cycle.set_hidden(hidden);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn page_hidden(&self) -> bool {
+        self.duty_cycle.hidden()
+    }
+
+    /// Fraction of time the mining loop should currently spend hashing
+    #[wasm_bindgen(getter)]
+    pub fn duty_cycle(&self) -> f64 {
+        self.duty_cycle.duty()
+    }
+
+    /// Back off automatically when the hashrate sags, as it does on a hot phone (on by default)
+    #[wasm_bindgen(setter)]
+    pub fn set_thermal_throttle(&mut self, enabled: bool) {
+        self.duty_cycle.thermal.set_enabled(enabled);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn thermal_throttle(&self) -> bool {
+        self.duty_cycle.thermal.enabled()
+    }
+
+    /// Share of the usual duty cycle the thermal throttle currently allows, from 0.25 to 1
+    #[wasm_bindgen(getter)]
+    pub fn throttle_level(&self) -> f64 {
+        self.duty_cycle.thermal.level()
+    }
+
+    /// Whether the mining loop should stop hashing until the page is visible
+    #[wasm_bindgen(getter)]
+    pub fn paused(&self) -> bool {
+        self.duty_cycle.duty() <= 0.0
+    }
+
+    /// Milliseconds to rest after a chunk that hashed for `busy_ms`; infinite while paused
+    pub fn rest_ms(&self, busy_ms: f64) -> f64 {
+        self.duty_cycle.rest_ms(busy_ms)
+    }
+
+    /// Stop handing out work and return the final stats snapshot
+    ///
+    /// Queued jobs are dropped and `current_job` returns nothing afterwards,
+    /// so a mining loop polling the session ends at its next chunk boundary.
+    /// Share verdicts can still be recorded while in-flight submissions
+    /// finish; take `stats_snapshot()` again once they have.
+    pub fn shutdown(&mut self) -> Result<JsValue, JsValue> {
+        if !self.shut_down {
+            self.events.push(EventKind::Shutdown, "Session shut down");
+        }
+        self.shut_down = true;
+        self.jobs.clear();
+        self.stats_snapshot()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn is_shut_down(&self) -> bool {
+        self.shut_down
+    }
+}
+
+impl MinerSession {
+    /// Current stats, for `stats_snapshot` and telemetry
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            timestamp_ms: clock::now_ms(),
+            total_hashes: self.total_hashes,
+            difficulty_1_shares: self.total_hashes as f64 / DIFFICULTY_1_HASHES,
+            hashrate: self.hashrate(),
+            hashrate_windows: self.hashrate.windows(clock::monotonic_ms()),
+            height: self.jobs.current().map(MiningJob::height),
+            tip_height: self.tip_height(),
+            accepted_shares: self.accepted_shares(),
+            rejected_shares: self.rejected_shares(),
+            stale_shares: self.stale_shares(),
+            best_share: self.best_share.clone(),
+            luck: self.luck.report(),
+            difficulty_histogram: self.histogram.buckets().to_vec(),
+            duty_cycle: self.duty_cycle.duty(),
+            throttle_level: self.throttle_level(),
+            shut_down: self.shut_down,
+        }
+    }
+
+    fn log_share(&mut self, job_id: &str, nonce: u32, status: ShareStatus, reason: Option<&str>) {
+        let message = match reason {
+            Some(reason) => format!("Share {} on job {}: {:?} ({})", nonce, job_id, status, reason),
+            None => format!("Share {} on job {}: {:?}", nonce, job_id, status),
+        };
+        self.events.push(EventKind::Share, message);
+    }
+
+    /// A recently pushed job as (job id, height, previous hash), even after it was mined out or replaced
+    fn recent_job(&self, job_id: &str) -> Option<&(String, u64, String)> {
+        self.recent_jobs.iter().rev().find(|(id, _, _)| id == job_id)
+    }
+}
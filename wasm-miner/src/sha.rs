@@ -0,0 +1,316 @@
+//! SHA-256 specialized for Stellaris block headers
+//!
+//! Headers are 108 to 142 bytes, so after the prefix's leading full block is
+//! absorbed once per job, every nonce costs exactly one or two compressions
+//! of a tail whose padding and length never change. Working on big-endian
+//! words throughout avoids the byte buffering and dispatch of a generic
+//! `Digest`.
+
+use sha2::{Digest, Sha256};
+
+use crate::params::{self, TimestampWidth, COMPRESSED_ADDRESS_LEN, UNCOMPRESSED_ADDRESS_LEN};
+
+/// SHA-256 initial hash values
+pub const H0: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// SHA-256 round constants
+pub const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Under 64 leftover prefix bytes plus the nonce and 9 bytes of padding never need more than two blocks
+pub const MAX_TAIL_BLOCKS: usize = 2;
+
+/// Message schedule for one block of big-endian words
+#[inline(always)]
+fn schedule(block: &[u32]) -> [u32; 64] {
+    let mut w = [0u32; 64];
+    w[..16].copy_from_slice(&block[..16]);
+    for t in 16..64 {
+        let s0 = w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ (w[t - 15] >> 3);
+        let s1 = w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ (w[t - 2] >> 10);
+        w[t] = w[t - 16].wrapping_add(s0).wrapping_add(w[t - 7]).wrapping_add(s1);
+    }
+    w
+}
+
+/// Run rounds `start..end` on the working variables `a..h`
+#[inline(always)]
+fn rounds(vars: &mut [u32; 8], w: &[u32; 64], start: usize, end: usize) {
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *vars;
+    for t in start..end {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(K[t]).wrapping_add(w[t]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+    *vars = [a, b, c, d, e, f, g, h];
+}
+
+/// Add the working variables back into the chaining state
+#[inline(always)]
+fn add_state(state: &mut [u32; 8], vars: &[u32; 8]) {
+    for (word, value) in state.iter_mut().zip(vars) {
+        *word = word.wrapping_add(*value);
+    }
+}
+
+/// Run the compression function over one block of big-endian words
+#[inline(always)]
+pub fn compress(state: &mut [u32; 8], block: &[u32]) {
+    let w = schedule(block);
+    let mut vars = *state;
+    rounds(&mut vars, &w, 0, 64);
+    add_state(state, &vars);
+}
+
+/// Reference SHA-256 of `data`, via `sha2`, returned by value so callers needn't allocate
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// Big-endian words of a 64-byte block
+pub fn block_words(block: &[u8]) -> [u32; 16] {
+    let mut words = [0u32; 16];
+    for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    words
+}
+
+/// Hashes `prefix || nonce` for a fixed prefix, one compression pass per tail block
+///
+/// The padded tail is built once per job. The nonce starts in the first
+/// tail block; when 61 to 63 prefix bytes are left over after the full
+/// blocks, its last bytes spill into the second. The rounds before its
+/// first word only see constant words, so those are run once up front as
+/// well.
+#[derive(Clone)]
+pub struct HeaderHasher {
+    /// State after the prefix's full blocks
+    midstate: [u32; 8],
+    /// Padded tail blocks as one run of words, with the nonce bytes zeroed
+    tail: [u32; 16 * MAX_TAIL_BLOCKS],
+    tail_blocks: usize,
+    /// Word of the tail holding the nonce's first byte
+    nonce_word: usize,
+    /// Bit offset of the nonce within that word; non-zero means it spills into the next word
+    nonce_shift: u32,
+    /// Working variables after the rounds that precede `nonce_word`
+    early_vars: [u32; 8],
+}
+
+impl HeaderHasher {
+    pub fn new(prefix: &[u8]) -> HeaderHasher {
+        let (blocks, remainder) = prefix.as_chunks::<64>();
+        let mut midstate = H0;
+        for block in blocks {
+            compress(&mut midstate, &block_words(block));
+        }
+
+        // Remaining prefix bytes, a nonce placeholder, then standard SHA-256 padding
+        let message_len = prefix.len() + 4;
+        let mut bytes = remainder.to_vec();
+        let nonce_offset = bytes.len();
+        bytes.extend_from_slice(&[0u8; 4]);
+        bytes.push(0x80);
+        while bytes.len() % 64 != 56 {
+            bytes.push(0);
+        }
+        bytes.extend_from_slice(&((message_len as u64) * 8).to_be_bytes());
+
+        let tail_blocks = bytes.len() / 64;
+        let mut tail = [0u32; 16 * MAX_TAIL_BLOCKS];
+        for (words, block) in tail.chunks_exact_mut(16).zip(bytes.chunks_exact(64)) {
+            words.copy_from_slice(&block_words(block));
+        }
+
+        let nonce_word = nonce_offset / 4;
+        let mut early_vars = midstate;
+        rounds(&mut early_vars, &schedule(&tail[..16]), 0, nonce_word);
+
+        HeaderHasher {
+            midstate,
+            tail,
+            tail_blocks,
+            nonce_word,
+            nonce_shift: (nonce_offset % 4) as u32 * 8,
+            early_vars,
+        }
+    }
+
+    /// State after the prefix's full blocks
+    pub fn midstate(&self) -> [u32; 8] {
+        self.midstate
+    }
+
+    /// Padded tail words with the nonce bytes zeroed; only the first `tail_blocks` blocks are used
+    pub fn tail(&self) -> [u32; 16 * MAX_TAIL_BLOCKS] {
+        self.tail
+    }
+
+    pub fn tail_blocks(&self) -> usize {
+        self.tail_blocks
+    }
+
+    /// Tail word holding the nonce's first byte
+    pub fn nonce_word(&self) -> usize {
+        self.nonce_word
+    }
+
+    /// Bits the nonce's big-endian word is shifted right by within `nonce_word`
+    pub fn nonce_shift(&self) -> u32 {
+        self.nonce_shift
+    }
+
+    /// SHA-256 of `prefix || nonce.to_le_bytes()`
+    #[inline]
+    pub fn hash(&self, nonce: u32) -> [u8; 32] {
+        self.hash_at(nonce, self.nonce_word, self.nonce_shift, self.tail_blocks)
+    }
+
+    /// `hash` with the layout passed in, so callers that know it at compile time get it folded
+    #[inline(always)]
+    fn hash_at(&self, nonce: u32, nonce_word: usize, nonce_shift: u32, tail_blocks: usize) -> [u8; 32] {
+        let tail = self.patched_tail(nonce, nonce_word, nonce_shift);
+
+        let mut state = self.midstate;
+        let mut vars = self.early_vars;
+        rounds(&mut vars, &schedule(&tail[..16]), nonce_word, 64);
+        add_state(&mut state, &vars);
+        if tail_blocks == 2 {
+            compress(&mut state, &tail[16..]);
+        }
+        digest(&state)
+    }
+
+    /// The padded tail words with `nonce` written in
+    #[inline(always)]
+    fn patched_tail(&self, nonce: u32, nonce_word: usize, nonce_shift: u32) -> [u32; 16 * MAX_TAIL_BLOCKS] {
+        // The nonce's little-endian bytes read as a big-endian word
+        let nonce_be = nonce.swap_bytes();
+        let mut tail = self.tail;
+        tail[nonce_word] |= nonce_be >> nonce_shift;
+        if nonce_shift > 0 {
+            tail[nonce_word + 1] |= nonce_be << (32 - nonce_shift);
+        }
+        tail
+    }
+
+    /// Run the tail blocks for `nonce` through `compress` instead of the shared round loop
+    ///
+    /// Lets an alternative compression function reuse the midstate and the
+    /// precomputed padding; the early-round shortcut is skipped.
+    #[inline(always)]
+    pub fn hash_with(&self, nonce: u32, compress: impl Fn(&mut [u32; 8], &[u32])) -> [u8; 32] {
+        let tail = self.patched_tail(nonce, self.nonce_word, self.nonce_shift);
+
+        let mut state = self.midstate;
+        for block in tail.chunks_exact(16).take(self.tail_blocks) {
+            compress(&mut state, block);
+        }
+        digest(&state)
+    }
+}
+
+/// Big-endian serialization of a final chaining state
+#[inline(always)]
+pub fn digest(state: &[u32; 8]) -> [u8; 32] {
+    let mut digest = [0u8; 32];
+    for (out, word) in digest.chunks_exact_mut(4).zip(state) {
+        out.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+/// `HeaderHasher` for prefixes of exactly `PREFIX_LEN` bytes
+///
+/// The nonce's word, its shift, and the tail block count become constants,
+/// so nonce patching compiles to fixed-offset stores and the second tail
+/// compression is kept or dropped at compile time.
+#[derive(Clone)]
+pub struct FixedHeaderHasher<const PREFIX_LEN: usize> {
+    inner: HeaderHasher,
+}
+
+impl<const PREFIX_LEN: usize> FixedHeaderHasher<PREFIX_LEN> {
+    const NONCE_OFFSET: usize = PREFIX_LEN % 64;
+    const NONCE_WORD: usize = Self::NONCE_OFFSET / 4;
+    const NONCE_SHIFT: u32 = (Self::NONCE_OFFSET % 4) as u32 * 8;
+    /// Tail bytes, the nonce, the 0x80 marker and the 8-byte length, rounded up to blocks
+    const TAIL_BLOCKS: usize = (Self::NONCE_OFFSET + 4 + 1 + 8).div_ceil(64);
+
+    pub fn new(prefix: &[u8]) -> FixedHeaderHasher<PREFIX_LEN> {
+        assert_eq!(prefix.len(), PREFIX_LEN, "prefix doesn't match the fixed layout");
+        FixedHeaderHasher { inner: HeaderHasher::new(prefix) }
+    }
+
+    /// SHA-256 of `prefix || nonce.to_le_bytes()`
+    #[inline]
+    pub fn hash(&self, nonce: u32) -> [u8; 32] {
+        self.inner.hash_at(nonce, Self::NONCE_WORD, Self::NONCE_SHIFT, Self::TAIL_BLOCKS)
+    }
+}
+
+const COMPRESSED_U32: usize = params::prefix_len(COMPRESSED_ADDRESS_LEN, TimestampWidth::U32);
+const COMPRESSED_U64: usize = params::prefix_len(COMPRESSED_ADDRESS_LEN, TimestampWidth::U64);
+const UNCOMPRESSED_U32: usize = params::prefix_len(UNCOMPRESSED_ADDRESS_LEN, TimestampWidth::U32);
+const UNCOMPRESSED_U64: usize = params::prefix_len(UNCOMPRESSED_ADDRESS_LEN, TimestampWidth::U64);
+
+/// A `FixedHeaderHasher` for each layout `ChainParams` produces from a standard address,
+/// with `HeaderHasher` covering any other prefix length
+///
+/// Tail offsets depend only on the prefix length, so choosing by length is
+/// exact even for a raw address that happens to match a standard size.
+#[derive(Clone)]
+pub enum LayoutHasher {
+    CompressedU32(FixedHeaderHasher<COMPRESSED_U32>),
+    CompressedU64(FixedHeaderHasher<COMPRESSED_U64>),
+    UncompressedU32(FixedHeaderHasher<UNCOMPRESSED_U32>),
+    UncompressedU64(FixedHeaderHasher<UNCOMPRESSED_U64>),
+    Other(HeaderHasher),
+}
+
+impl LayoutHasher {
+    pub fn new(prefix: &[u8]) -> LayoutHasher {
+        match prefix.len() {
+            COMPRESSED_U32 => LayoutHasher::CompressedU32(FixedHeaderHasher::new(prefix)),
+            COMPRESSED_U64 => LayoutHasher::CompressedU64(FixedHeaderHasher::new(prefix)),
+            UNCOMPRESSED_U32 => LayoutHasher::UncompressedU32(FixedHeaderHasher::new(prefix)),
+            UNCOMPRESSED_U64 => LayoutHasher::UncompressedU64(FixedHeaderHasher::new(prefix)),
+            _ => LayoutHasher::Other(HeaderHasher::new(prefix)),
+        }
+    }
+
+    /// SHA-256 of `prefix || nonce.to_le_bytes()`
+    #[inline]
+    pub fn hash(&self, nonce: u32) -> [u8; 32] {
+        match self {
+            LayoutHasher::CompressedU32(hasher) => hasher.hash(nonce),
+            LayoutHasher::CompressedU64(hasher) => hasher.hash(nonce),
+            LayoutHasher::UncompressedU32(hasher) => hasher.hash(nonce),
+            LayoutHasher::UncompressedU64(hasher) => hasher.hash(nonce),
+            LayoutHasher::Other(hasher) => hasher.hash(nonce),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::binary::{self, RecordKind};
+use crate::error::{self, ErrorCode};
+
+/// The lowest hash a session has produced, kept across job changes
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BestShare {
+    hash: String,
+    nonce: u32,
+    job_id: String,
+    /// Height of the block the job built, or 0 if unknown
+    #[serde(default)]
+    height: u64,
+    timestamp_ms: f64,
+}
+
+#[wasm_bindgen]
+impl BestShare {
+    #[wasm_bindgen(getter)]
+    pub fn hash(&self) -> String {
+        self.hash.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn nonce(&self) -> u32 {
+        self.nonce
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn job_id(&self) -> String {
+        self.job_id.clone()
+    }
+
+    /// Height of the block the share's job built, or 0 if unknown
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// When the share was recorded, in milliseconds since the Unix epoch
+    #[wasm_bindgen(getter)]
+    pub fn timestamp_ms(&self) -> f64 {
+        self.timestamp_ms
+    }
+
+    /// Serialize to JSON for storage (e.g. localStorage)
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        serde_json::to_string(self).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    /// Restore a record previously produced by `to_json`
+    pub fn from_json(json: &str) -> Result<BestShare, JsValue> {
+        serde_json::from_str(json).map_err(|e| error::js_error(ErrorCode::InvalidRecord, e.to_string()))
+    }
+
+    /// Serialize to the compact binary form (see `MinerResult.to_bytes`)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary::encode(RecordKind::BestShare, self)
+    }
+
+    /// Restore a record previously produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<BestShare, JsValue> {
+        binary::decode(bytes, RecordKind::BestShare).map_err(|e| error::js_error(ErrorCode::InvalidRecord, e))
+    }
+}
+
+impl BestShare {
+    pub fn new(hash: String, nonce: u32, job_id: String, height: u64, timestamp_ms: f64) -> Self {
+        BestShare {
+            hash,
+            nonce,
+            job_id,
+            height,
+            timestamp_ms,
+        }
+    }
+
+    /// Whether `hash` beats this record
+    pub fn is_beaten_by(&self, hash: &str) -> bool {
+        hash < self.hash.as_str()
+    }
+}
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::binary::{self, RecordKind};
+use crate::clock;
+use crate::error::{self, ErrorCode};
+use crate::job::MiningJob;
+use crate::share::BestShare;
+use crate::MinerResult;
+
+/// A worker's progress through one nonce range, to hand to another worker
+///
+/// The worker records each result as it goes and posts `to_bytes()` to
+/// its coordinator (transferring the buffer). If the worker dies, the
+/// coordinator gives the last snapshot to a replacement, which restores it
+/// with `from_bytes` and carries on from `next_nonce` with the job, hash
+/// count and best share intact.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MinerState {
+    job: MiningJob,
+    next_nonce: u32,
+    nonce_end: u32,
+    hashes_computed: u64,
+    best_share: Option<BestShare>,
+}
+
+#[wasm_bindgen]
+impl MinerState {
+    /// Start tracking `nonce_start..nonce_end` of `job`
+    #[wasm_bindgen(constructor)]
+    pub fn new(job: &MiningJob, nonce_start: u32, nonce_end: u32) -> Result<MinerState, JsValue> {
+        if nonce_start > nonce_end {
+            return Err(error::js_error(
+                ErrorCode::InvalidArgument,
+                format!("Range {}..{} is reversed", nonce_start, nonce_end),
+            ));
+        }
+        Ok(MinerState {
+            job: job.clone(),
+            next_nonce: nonce_start,
+            nonce_end,
+            hashes_computed: 0,
+            best_share: None,
+        })
+    }
+
+    /// Fold in a result mined from `next_nonce`, returning whether it set a new best share
+    pub fn record(&mut self, result: &MinerResult) -> bool {
+        self.next_nonce = result.next_nonce.clamp(self.next_nonce, self.nonce_end);
+        self.hashes_computed += result.hashes_computed as u64;
+        if result.hashes_computed == 0 || result.best_hash.is_empty() {
+            return false;
+        }
+
+        let improved = self.best_share.as_ref().is_none_or(|best| best.is_beaten_by(&result.best_hash));
+        if improved {
+            self.best_share = Some(BestShare::new(
+                result.best_hash.clone(),
+                result.best_nonce,
+                self.job.job_id(),
+                self.job.height(),
+                clock::now_ms(),
+            ));
+        }
+        improved
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn job(&self) -> MiningJob {
+        self.job.clone()
+    }
+
+    /// First nonce not yet searched
+    #[wasm_bindgen(getter)]
+    pub fn next_nonce(&self) -> u32 {
+        self.next_nonce
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn nonce_end(&self) -> u32 {
+        self.nonce_end
+    }
+
+    /// Nonces left in the range
+    #[wasm_bindgen(getter)]
+    pub fn remaining(&self) -> u32 {
+        self.nonce_end - self.next_nonce
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn finished(&self) -> bool {
+        self.next_nonce >= self.nonce_end
+    }
+
+    /// Hashes recorded across every worker that held this state (f64 so JS gets a plain number)
+    #[wasm_bindgen(getter)]
+    pub fn hashes_computed(&self) -> f64 {
+        self.hashes_computed as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn best_share(&self) -> Option<BestShare> {
+        self.best_share.clone()
+    }
+
+    /// Serialize to the compact binary form (see `MinerResult.to_bytes`), embedding the job
+    pub fn to_bytes(&self) -> Vec<u8> {
+        binary::encode(RecordKind::State, self)
+    }
+
+    /// Restore a state previously produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<MinerState, JsValue> {
+        MinerState::decode(bytes).map_err(|e| error::js_error(ErrorCode::InvalidRecord, e))
+    }
+}
+
+impl MinerState {
+    fn decode(bytes: &[u8]) -> Result<MinerState, String> {
+        let state: MinerState = binary::decode(bytes, RecordKind::State)?;
+        if state.next_nonce > state.nonce_end {
+            return Err(format!("State is past the end of its range ({} > {})", state.next_nonce, state.nonce_end));
+        }
+        Ok(state)
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+/// Default span of the rolling hashrate
+pub const DEFAULT_HASHRATE_WINDOW_MS: f64 = 60_000.0;
+
+/// Default span of the instantaneous hashrate, which is also the width of each stored sample
+pub const DEFAULT_HASHRATE_SAMPLE_MS: f64 = 10_000.0;
+
+/// Default span of the long-run hashrate
+pub const DEFAULT_LONG_HASHRATE_WINDOW_MS: f64 = 15.0 * 60_000.0;
+
+/// Most samples the long window may need, so a tiny sample interval can't grow the meter without bound
+pub const MAX_HASHRATE_SAMPLES: f64 = 10_000.0;
+
+/// Hashrate over the instantaneous, rolling and long windows, in H/s
+#[derive(Clone, Debug, Serialize)]
+pub struct HashrateWindows {
+    pub instant: f64,
+    pub short: f64,
+    pub long: f64,
+    pub sample_interval_ms: f64,
+    pub short_window_ms: f64,
+    pub long_window_ms: f64,
+}
+
+/// Rolling hashrate over recent `mine_range` calls
+///
+/// Rate is measured against wall time from the start of the oldest sample,
+/// so pauses between calls (UI yields, network waits) count against it.
+/// That is what matters for "how long until I find a block". Calls are
+/// merged into samples of up to `sample_ms` each, kept for the long window;
+/// a sample straddling the start of a window counts in proportion.
+pub struct HashrateMeter {
+    /// (monotonic start ms, monotonic end ms, hashes)
+    samples: VecDeque<(f64, f64, u64)>,
+    sample_ms: f64,
+    window_ms: f64,
+    long_window_ms: f64,
+}
+
+impl HashrateMeter {
+    pub fn new(sample_ms: f64, window_ms: f64, long_window_ms: f64) -> Self {
+        HashrateMeter {
+            samples: VecDeque::new(),
+            sample_ms,
+            window_ms,
+            long_window_ms,
+        }
+    }
+
+    /// Change the windows, keeping recorded samples; spans must be ordered sample <= short <= long
+    pub fn configure(&mut self, sample_ms: f64, window_ms: f64, long_window_ms: f64) -> Result<(), String> {
+        if !(sample_ms > 0.0 && sample_ms <= window_ms && window_ms <= long_window_ms && long_window_ms.is_finite()) {
+            return Err(format!(
+                "Hashrate windows must satisfy 0 < sample ({}) <= short ({}) <= long ({})",
+                sample_ms, window_ms, long_window_ms
+            ));
+        }
+        if long_window_ms / sample_ms > MAX_HASHRATE_SAMPLES {
+            return Err(format!(
+                "A {} ms long window needs more than {} samples of {} ms",
+                long_window_ms, MAX_HASHRATE_SAMPLES, sample_ms
+            ));
+        }
+
+        self.sample_ms = sample_ms;
+        self.window_ms = window_ms;
+        self.long_window_ms = long_window_ms;
+        Ok(())
+    }
+
+    /// Record `hashes` computed over `elapsed_ms`, ending at monotonic time `now_ms`
+    pub fn record(&mut self, now_ms: f64, elapsed_ms: f64, hashes: u64) {
+        let start_ms = now_ms - elapsed_ms;
+        match self.samples.back_mut() {
+            Some(last) if now_ms - last.0 <= self.sample_ms => {
+                last.0 = last.0.min(start_ms);
+                last.1 = now_ms;
+                last.2 += hashes;
+            }
+            _ => self.samples.push_back((start_ms, now_ms, hashes)),
+        }
+        self.prune(now_ms);
+    }
+
+    /// Hashes per second over the rolling window ending at `now_ms`
+    pub fn hashrate(&self, now_ms: f64) -> f64 {
+        self.hashrate_over(now_ms, self.window_ms)
+    }
+
+    /// Hashes per second over the `window_ms` ending at `now_ms`
+    pub fn hashrate_over(&self, now_ms: f64, window_ms: f64) -> f64 {
+        let cutoff = now_ms - window_ms.min(self.long_window_ms);
+        let mut in_window = self.samples.iter().filter(|(_, end, _)| *end > cutoff).peekable();
+
+        let first_start = match in_window.peek() {
+            Some((start, _, _)) => start.max(cutoff),
+            None => return 0.0,
+        };
+        let hashes: f64 = in_window
+            .map(|&(start, end, hashes)| {
+                if start < cutoff {
+                    hashes as f64 * (end - cutoff) / (end - start)
+                } else {
+                    hashes as f64
+                }
+            })
+            .sum();
+        let span_ms = now_ms - first_start;
+
+        if span_ms > 0.0 {
+            hashes * 1000.0 / span_ms
+        } else {
+            0.0
+        }
+    }
+
+    /// Hashrate over all three windows ending at `now_ms`
+    pub fn windows(&self, now_ms: f64) -> HashrateWindows {
+        HashrateWindows {
+            instant: self.hashrate_over(now_ms, self.sample_ms),
+            short: self.hashrate(now_ms),
+            long: self.hashrate_over(now_ms, self.long_window_ms),
+            sample_interval_ms: self.sample_ms,
+            short_window_ms: self.window_ms,
+            long_window_ms: self.long_window_ms,
+        }
+    }
+
+    /// Forget every sample, keeping the windows
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    fn prune(&mut self, now_ms: f64) {
+        let cutoff = now_ms - self.long_window_ms;
+        while self.samples.front().is_some_and(|(_, end, _)| *end < cutoff) {
+            self.samples.pop_front();
+        }
+    }
+}
+
+impl Default for HashrateMeter {
+    fn default() -> Self {
+        HashrateMeter::new(DEFAULT_HASHRATE_SAMPLE_MS, DEFAULT_HASHRATE_WINDOW_MS, DEFAULT_LONG_HASHRATE_WINDOW_MS)
+    }
+}
+
+/// Count of results by leading-match score (whole part of the achieved difficulty)
+#[derive(Debug, Default)]
+pub struct DifficultyHistogram {
+    buckets: Vec<u32>,
+}
+
+impl DifficultyHistogram {
+    pub fn record(&mut self, achieved_difficulty: f64) {
+        let bucket = achieved_difficulty.max(0.0) as usize;
+        if bucket >= self.buckets.len() {
+            self.buckets.resize(bucket + 1, 0);
+        }
+        self.buckets[bucket] = self.buckets[bucket].saturating_add(1);
+    }
+
+    /// Counts indexed by score, ending at the highest score seen
+    pub fn buckets(&self) -> &[u32] {
+        &self.buckets
+    }
+}
@@ -0,0 +1,51 @@
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
+
+use wasm_bindgen::prelude::*;
+
+use crate::job::MiningJob;
+
+/// Latest job published by the network side, not yet taken by the miner
+///
+/// Together with the job the mining loop is holding this forms a double
+/// buffer: the writer only ever replaces this slot, the reader only ever
+/// empties it, and both sides do so with a single atomic swap. In a
+/// threaded build the slot lives in shared memory, so a network worker and
+/// the mining workers exchange jobs without `postMessage`.
+static PUBLISHED: AtomicPtr<MiningJob> = AtomicPtr::new(ptr::null_mut());
+
+/// Bumped on every publish so readers can poll without taking the job
+static GENERATION: AtomicU32 = AtomicU32::new(0);
+
+/// Hand a fresh job to the mining loop, replacing any job it hasn't picked up yet
+///
+/// Returns the new generation number.
+#[wasm_bindgen]
+pub fn publish_job(job: MiningJob) -> u32 {
+    let previous = PUBLISHED.swap(Box::into_raw(Box::new(job)), Ordering::AcqRel);
+    if !previous.is_null() {
+        // SAFETY: every non-null pointer in the slot came from Box::into_raw, and
+        // the swap gave this thread sole ownership of it
+        drop(unsafe { Box::from_raw(previous) });
+    }
+    GENERATION.fetch_add(1, Ordering::Release).wrapping_add(1)
+}
+
+/// Take the published job, if one arrived since the last call
+///
+/// Call at batch boundaries; the slot is empty again afterwards.
+#[wasm_bindgen]
+pub fn take_published_job() -> Option<MiningJob> {
+    let job = PUBLISHED.swap(ptr::null_mut(), Ordering::AcqRel);
+    if job.is_null() {
+        return None;
+    }
+    // SAFETY: as in `publish_job`, the swap transferred ownership of the box to this thread
+    Some(*unsafe { Box::from_raw(job) })
+}
+
+/// Number of jobs published so far; cheaper to poll than `take_published_job`
+#[wasm_bindgen]
+pub fn published_job_generation() -> u32 {
+    GENERATION.load(Ordering::Acquire)
+}
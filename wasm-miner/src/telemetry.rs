@@ -0,0 +1,223 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::future_to_promise;
+
+use crate::clock;
+use crate::error::{self, ErrorCode};
+use crate::logging::{self, LogLevel};
+use crate::net::HttpClient;
+use crate::seeded;
+use crate::session::MinerSession;
+
+/// Shortest interval between posts
+pub const MIN_TELEMETRY_INTERVAL_MS: f64 = 1_000.0;
+
+/// Longest wait after repeated failures
+const MAX_TELEMETRY_BACKOFF_MS: f64 = 15.0 * 60_000.0;
+
+/// Most snapshots held while the endpoint is unreachable; older ones are dropped first
+pub const MAX_PENDING_SNAPSHOTS: usize = 500;
+
+/// Body of one telemetry post
+#[derive(Serialize)]
+struct TelemetryBatch<'a> {
+    source: &'a str,
+    /// Snapshots dropped since the last successful post
+    dropped: u64,
+    snapshots: Vec<serde_json::Value>,
+}
+
+struct Exporter {
+    url: String,
+    source: String,
+    interval_ms: f64,
+    max_batch: usize,
+    pending: VecDeque<serde_json::Value>,
+    dropped: u64,
+    sent: u64,
+    failures: u32,
+    next_post_ms: f64,
+    in_flight: bool,
+}
+
+impl Exporter {
+    fn push(&mut self, snapshot: serde_json::Value) {
+        if self.pending.len() == MAX_PENDING_SNAPSHOTS {
+            self.pending.pop_front();
+            self.dropped += 1;
+        }
+        self.pending.push_back(snapshot);
+    }
+
+    /// Put a failed batch back ahead of anything queued since, dropping its oldest snapshots if that overflows
+    fn requeue(&mut self, batch: Vec<serde_json::Value>) {
+        for snapshot in batch.into_iter().rev() {
+            if self.pending.len() == MAX_PENDING_SNAPSHOTS {
+                self.dropped += 1;
+                continue;
+            }
+            self.pending.push_front(snapshot);
+        }
+    }
+
+    /// Exponential backoff from the interval, with up to 25% jitter so a fleet doesn't retry in step
+    fn backoff_ms(&self) -> f64 {
+        let exponential = self.interval_ms * 2f64.powi(self.failures.min(16) as i32);
+        exponential.min(MAX_TELEMETRY_BACKOFF_MS) * (1.0 + seeded::random() * 0.25)
+    }
+}
+
+/// Batches stats snapshots and posts them to a collector, for fleets of browser miners
+///
+/// Call `record(session)` on a timer and `flush()` whenever `due` is true.
+/// Each post is `{ source, dropped, snapshots: [...] }` with up to
+/// `max_batch` snapshots, oldest first, in the `stats_snapshot` format. A
+/// failed post keeps its snapshots and backs off exponentially (up to 15
+/// minutes), so snapshots gathered meanwhile go out together once the
+/// collector is back. At most 500 are held; the oldest are dropped first.
+#[wasm_bindgen]
+pub struct TelemetryExporter {
+    exporter: Rc<RefCell<Exporter>>,
+    client: HttpClient,
+}
+
+#[wasm_bindgen]
+impl TelemetryExporter {
+    /// Post to `url` at most every `interval_ms` (at least 1 s), tagging batches with `source` (e.g. the worker name)
+    #[wasm_bindgen(constructor)]
+    pub fn new(url: String, source: String, interval_ms: f64, max_batch: usize) -> Result<TelemetryExporter, JsValue> {
+        if url.is_empty() {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "Telemetry URL must not be empty"));
+        }
+        if max_batch == 0 {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "max_batch must be positive"));
+        }
+
+        // Backoff is handled across flushes, so each post is a single attempt
+        let mut client = HttpClient::default();
+        client.set_max_retries(0);
+
+        let interval_ms = if interval_ms.is_nan() { MIN_TELEMETRY_INTERVAL_MS } else { interval_ms };
+        Ok(TelemetryExporter {
+            exporter: Rc::new(RefCell::new(Exporter {
+                url,
+                source,
+                interval_ms: interval_ms.max(MIN_TELEMETRY_INTERVAL_MS),
+                max_batch,
+                pending: VecDeque::new(),
+                dropped: 0,
+                sent: 0,
+                failures: 0,
+                next_post_ms: 0.0,
+                in_flight: false,
+            })),
+            client,
+        })
+    }
+
+    /// Queue a snapshot of `session`'s stats
+    pub fn record(&self, session: &MinerSession) -> Result<(), JsValue> {
+        let snapshot = serde_json::to_value(session.snapshot())
+            .map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))?;
+        self.exporter.borrow_mut().push(snapshot);
+        Ok(())
+    }
+
+    /// Whether `flush` would post now: snapshots are waiting, none in flight, and the interval or backoff has passed
+    #[wasm_bindgen(getter)]
+    pub fn due(&self) -> bool {
+        let exporter = self.exporter.borrow();
+        !exporter.pending.is_empty() && !exporter.in_flight && clock::monotonic_ms() >= exporter.next_post_ms
+    }
+
+    /// Post the next batch if `due`; resolves with whether a batch was delivered
+    pub fn flush(&self) -> js_sys::Promise {
+        let exporter = self.exporter.clone();
+        let client = self.client.clone();
+        let due = self.due();
+
+        future_to_promise(async move {
+            if !due {
+                return Ok(JsValue::FALSE);
+            }
+
+            let (url, body, batch) = {
+                let mut exporter = exporter.borrow_mut();
+                let count = exporter.pending.len().min(exporter.max_batch);
+                let batch: Vec<serde_json::Value> = exporter.pending.drain(..count).collect();
+                let body = serde_json::to_string(&TelemetryBatch {
+                    source: &exporter.source,
+                    dropped: exporter.dropped,
+                    snapshots: batch.clone(),
+                });
+                exporter.in_flight = true;
+                (exporter.url.clone(), body, batch)
+            };
+
+            let outcome = match body {
+                Ok(body) => match client.post_json(&url, &body).await {
+                    Ok(response) if (200..300).contains(&response.status) => Ok(()),
+                    Ok(response) => Err(format!("HTTP {}", response.status)),
+                    Err(e) => Err(e
+                        .dyn_ref::<js_sys::Error>()
+                        .map(|inner| String::from(inner.message()))
+                        .unwrap_or_else(|| "network error".to_string())),
+                },
+                Err(e) => Err(e.to_string()),
+            };
+
+            let mut exporter = exporter.borrow_mut();
+            exporter.in_flight = false;
+            let delivered = outcome.is_ok();
+            match outcome {
+                Ok(()) => {
+                    exporter.sent += batch.len() as u64;
+                    exporter.dropped = 0;
+                    exporter.failures = 0;
+                    exporter.next_post_ms = clock::monotonic_ms() + exporter.interval_ms;
+                }
+                Err(e) => {
+                    exporter.requeue(batch);
+                    exporter.failures += 1;
+                    let delay_ms = exporter.backoff_ms();
+                    exporter.next_post_ms = clock::monotonic_ms() + delay_ms;
+                    logging::emit(
+                        LogLevel::Warn,
+                        "telemetry",
+                        &format!("Posting telemetry failed ({}), next attempt in {:.0} ms", e, delay_ms),
+                    );
+                }
+            }
+            Ok(JsValue::from_bool(delivered))
+        })
+    }
+
+    /// Snapshots waiting to be posted
+    #[wasm_bindgen(getter)]
+    pub fn pending_len(&self) -> usize {
+        self.exporter.borrow().pending.len()
+    }
+
+    /// Snapshots delivered so far
+    #[wasm_bindgen(getter)]
+    pub fn sent(&self) -> f64 {
+        self.exporter.borrow().sent as f64
+    }
+
+    /// Consecutive failed posts
+    #[wasm_bindgen(getter)]
+    pub fn failures(&self) -> u32 {
+        self.exporter.borrow().failures
+    }
+
+    /// Snapshots dropped since the last delivered batch
+    #[wasm_bindgen(getter)]
+    pub fn dropped(&self) -> f64 {
+        self.exporter.borrow().dropped as f64
+    }
+}
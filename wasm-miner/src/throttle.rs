@@ -0,0 +1,256 @@
+use wasm_bindgen::prelude::*;
+
+use crate::clock;
+
+/// Share of wall time spent hashing in a hidden page under `BackgroundPolicy::Throttle`
+pub const DEFAULT_BACKGROUND_DUTY_CYCLE: f64 = 0.25;
+
+/// Share of wall time spent hashing while the user is active, when the idle ramp is on
+pub const DEFAULT_ACTIVE_DUTY_CYCLE: f64 = 0.5;
+
+/// How long after the user goes idle the idle ramp reaches full speed
+pub const DEFAULT_IDLE_RAMP_MS: f64 = 30_000.0;
+
+/// Busy hashrate below this share of the best seen counts as a slowdown
+const SLOWDOWN_RATIO: f64 = 0.8;
+
+/// Busy hashrate at or above this share of the best seen counts as recovered
+const RECOVERY_RATIO: f64 = 0.95;
+
+/// How long a slowdown must last before the throttle steps down
+const SLOWDOWN_MS: f64 = 60_000.0;
+
+/// How long a recovery must last before the throttle steps back up
+const RECOVERY_MS: f64 = 300_000.0;
+
+/// Factor applied to the throttle level per step
+const THROTTLE_STEP: f64 = 0.75;
+
+/// The throttle never cuts hashing below this share of the time
+const MIN_THROTTLE_LEVEL: f64 = 0.25;
+
+/// What a session does while its page is hidden (`document.visibilityState === "hidden"`)
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackgroundPolicy {
+    /// Keep mining at full speed
+    Continue,
+    /// Hash for only a fraction of the time (the default)
+    Throttle,
+    /// Stop hashing until the page is visible again
+    Pause,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Trend {
+    Slow,
+    Recovered,
+}
+
+/// Backs off when the device's hashrate sags for a sustained period
+///
+/// A phone that heats up throttles its CPU, which shows up as a falling
+/// hashrate while hashing. After a minute below 80% of the best rate seen,
+/// the level drops by a quarter (never below 0.25); after five minutes
+/// back near the best rate, it steps up again.
+pub struct ThermalThrottle {
+    enabled: bool,
+    level: f64,
+    best_rate: f64,
+    /// Current trend and the monotonic time it started
+    trend: Option<(Trend, f64)>,
+}
+
+impl ThermalThrottle {
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.level = 1.0;
+            self.trend = None;
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Share of the usual duty cycle currently allowed, from 0.25 to 1
+    pub fn level(&self) -> f64 {
+        self.level
+    }
+
+    /// Feed the smoothed busy hashrate at monotonic time `now_ms`
+    pub fn record(&mut self, now_ms: f64, busy_rate: f64) {
+        if !self.enabled || busy_rate <= 0.0 {
+            return;
+        }
+        self.best_rate = self.best_rate.max(busy_rate);
+
+        let trend = if busy_rate < self.best_rate * SLOWDOWN_RATIO {
+            Trend::Slow
+        } else if busy_rate >= self.best_rate * RECOVERY_RATIO {
+            Trend::Recovered
+        } else {
+            self.trend = None;
+            return;
+        };
+
+        let since = match self.trend {
+            Some((current, since)) if current == trend => since,
+            _ => {
+                self.trend = Some((trend, now_ms));
+                now_ms
+            }
+        };
+
+        let stepped = match trend {
+            Trend::Slow if now_ms - since >= SLOWDOWN_MS && self.level > MIN_THROTTLE_LEVEL => {
+                self.level = (self.level * THROTTLE_STEP).max(MIN_THROTTLE_LEVEL);
+                true
+            }
+            Trend::Recovered if now_ms - since >= RECOVERY_MS && self.level < 1.0 => {
+                self.level = (self.level / THROTTLE_STEP).min(1.0);
+                true
+            }
+            _ => false,
+        };
+        if stepped {
+            self.trend = Some((trend, now_ms));
+        }
+    }
+}
+
+impl Default for ThermalThrottle {
+    fn default() -> Self {
+        ThermalThrottle {
+            enabled: true,
+            level: 1.0,
+            best_rate: 0.0,
+            trend: None,
+        }
+    }
+}
+
+/// Hashes gently while the user is at the device and ramps to full speed once they leave
+///
+/// Fed by the Idle Detection API or any other idle signal from JS. When
+/// the user becomes active the level drops to `active_duty` at once; once
+/// they go idle it rises linearly to 1 over `ramp_ms`, so a brief pause in
+/// typing doesn't spin the fans up. Off by default.
+pub struct IdleRamp {
+    enabled: bool,
+    active_duty: f64,
+    ramp_ms: f64,
+    /// Monotonic time the user went idle, or `None` while active
+    idle_since: Option<f64>,
+}
+
+impl IdleRamp {
+    pub fn configure(&mut self, enabled: bool, active_duty: f64, ramp_ms: f64) {
+        self.enabled = enabled;
+        self.active_duty = active_duty;
+        self.ramp_ms = ramp_ms;
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Record the user going idle or becoming active at monotonic time `now_ms`
+    pub fn set_idle(&mut self, idle: bool, now_ms: f64) {
+        match (idle, self.idle_since) {
+            (true, None) => self.idle_since = Some(now_ms),
+            (false, _) => self.idle_since = None,
+            (true, Some(_)) => {}
+        }
+    }
+
+    pub fn idle(&self) -> bool {
+        self.idle_since.is_some()
+    }
+
+    /// Share of the usual duty cycle allowed at `now_ms`, from `active_duty` to 1
+    pub fn level(&self, now_ms: f64) -> f64 {
+        if !self.enabled {
+            return 1.0;
+        }
+        match self.idle_since {
+            None => self.active_duty,
+            Some(_) if self.ramp_ms <= 0.0 => 1.0,
+            Some(since) => {
+                let progress = ((now_ms - since) / self.ramp_ms).clamp(0.0, 1.0);
+                self.active_duty + (1.0 - self.active_duty) * progress
+            }
+        }
+    }
+}
+
+impl Default for IdleRamp {
+    fn default() -> Self {
+        IdleRamp {
+            enabled: false,
+            active_duty: DEFAULT_ACTIVE_DUTY_CYCLE,
+            ramp_ms: DEFAULT_IDLE_RAMP_MS,
+            idle_since: None,
+        }
+    }
+}
+
+/// How much of its time the mining loop should spend hashing
+///
+/// The loop hashes a chunk, then rests for `rest_ms` of the time it took,
+/// so slow and fast devices are throttled alike.
+pub struct DutyCycle {
+    policy: BackgroundPolicy,
+    background_duty: f64,
+    hidden: bool,
+    pub thermal: ThermalThrottle,
+    pub idle: IdleRamp,
+}
+
+impl DutyCycle {
+    pub fn set_policy(&mut self, policy: BackgroundPolicy, background_duty: f64) {
+        self.policy = policy;
+        self.background_duty = background_duty;
+    }
+
+    pub fn set_hidden(&mut self, hidden: bool) {
+        self.hidden = hidden;
+    }
+
+    pub fn hidden(&self) -> bool {
+        self.hidden
+    }
+
+    /// Fraction of wall time to spend hashing right now: 1 is flat out, 0 is paused
+    pub fn duty(&self) -> f64 {
+        let visibility = match (self.hidden, self.policy) {
+            (false, _) | (true, BackgroundPolicy::Continue) => 1.0,
+            (true, BackgroundPolicy::Throttle) => self.background_duty,
+            (true, BackgroundPolicy::Pause) => 0.0,
+        };
+        visibility * self.thermal.level() * self.idle.level(clock::monotonic_ms())
+    }
+
+    /// Time to rest after `busy_ms` of hashing to keep to the duty cycle; infinite when paused
+    pub fn rest_ms(&self, busy_ms: f64) -> f64 {
+        let duty = self.duty();
+        if duty <= 0.0 {
+            f64::INFINITY
+        } else {
+            busy_ms.max(0.0) * (1.0 - duty) / duty
+        }
+    }
+}
+
+impl Default for DutyCycle {
+    fn default() -> Self {
+        DutyCycle {
+            policy: BackgroundPolicy::Throttle,
+            background_duty: DEFAULT_BACKGROUND_DUTY_CYCLE,
+            hidden: false,
+            thermal: ThermalThrottle::default(),
+            idle: IdleRamp::default(),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::collections::VecDeque;
+
+/// Offset samples kept; the median of these is applied
+const MAX_SAMPLES: usize = 8;
+
+/// Round trips slower than this give too loose a bound on the server time to be useful
+const MAX_RTT_MS: f64 = 5_000.0;
+
+/// Estimates how far the local clock is from the pool's
+///
+/// Each sample assumes the server stamped its reply halfway through the
+/// round trip. The median of recent samples is used so one delayed response
+/// can't yank the offset around.
+#[derive(Default)]
+pub struct TimeSync {
+    samples: VecDeque<f64>,
+    offset_ms: f64,
+}
+
+impl TimeSync {
+    /// Record a server timestamp observed between `sent_ms` and `received_ms` (local clock)
+    ///
+    /// Returns false if the sample was discarded.
+    pub fn observe(&mut self, server_ms: f64, sent_ms: f64, received_ms: f64) -> bool {
+        let rtt = received_ms - sent_ms;
+        if !server_ms.is_finite() || !(0.0..=MAX_RTT_MS).contains(&rtt) {
+            return false;
+        }
+
+        let local_midpoint = sent_ms + rtt / 2.0;
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(server_ms - local_midpoint);
+
+        let mut sorted: Vec<f64> = self.samples.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let mid = sorted.len() / 2;
+        self.offset_ms = if sorted.len().is_multiple_of(2) {
+            (sorted[mid - 1] + sorted[mid]) / 2.0
+        } else {
+            sorted[mid]
+        };
+
+        true
+    }
+
+    /// Milliseconds to add to local time to get pool time
+    pub fn offset_ms(&self) -> f64 {
+        self.offset_ms
+    }
+}
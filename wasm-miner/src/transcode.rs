@@ -0,0 +1,18 @@
+//! Helpers shared by the codecs for binary wire formats (`cbor` and worker MessagePack)
+
+#[cfg(feature = "cbor")]
+use serde_json::{Number, Value};
+
+/// Deepest nesting accepted when decoding, so hostile input can't exhaust the stack
+pub const MAX_DEPTH: usize = 64;
+
+/// A decoded float as a JSON number
+///
+/// JSON has no NaN or infinity, so those are rejected rather than silently
+/// nulled; `what` names the value in the error.
+#[cfg(feature = "cbor")]
+pub fn finite(n: f64, what: impl FnOnce() -> String) -> Result<Value, String> {
+    Number::from_f64(n)
+        .map(Value::Number)
+        .ok_or_else(|| format!("{} is not finite", what()))
+}
@@ -0,0 +1,103 @@
+use sha2::{Digest, Sha256};
+use wasm_bindgen::prelude::*;
+
+use crate::error::{self, ErrorCode};
+
+/// Bloom filter of nonce blocks that have already been searched
+///
+/// The bit array lives in a caller-supplied `SharedArrayBuffer` (a plain
+/// `ArrayBuffer` works for a single worker), and bits are set with
+/// `Atomics.or`, so every worker that wraps the same buffer sees the same
+/// set. Nonces are tracked in blocks of `block_size`; only blocks searched
+/// in full are recorded. A false positive skips a block that was never
+/// searched, so size the buffer for the work expected: with `m` bits, `k`
+/// hashes and `n` blocks the rate is about `(1 - e^(-kn/m))^k`.
+#[wasm_bindgen]
+pub struct TriedNonceFilter {
+    bits: js_sys::Int32Array,
+    bit_count: u64,
+    hash_count: u32,
+    block_size: u32,
+}
+
+#[wasm_bindgen]
+impl TriedNonceFilter {
+    #[wasm_bindgen(constructor)]
+    pub fn new(buffer: &JsValue, hash_count: u32, block_size: u32) -> Result<TriedNonceFilter, JsValue> {
+        if hash_count == 0 || block_size == 0 {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "hash_count and block_size must be positive"));
+        }
+
+        let bits = js_sys::Int32Array::new(buffer);
+        if bits.length() == 0 {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "Filter buffer must hold at least 4 bytes"));
+        }
+
+        Ok(TriedNonceFilter {
+            bit_count: bits.length() as u64 * 32,
+            bits,
+            hash_count,
+            block_size,
+        })
+    }
+
+    /// Nonces per tracked block
+    #[wasm_bindgen(getter)]
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    /// Record that `nonce_start..nonce_end` of a job has been searched
+    ///
+    /// Blocks only partly inside the range are left unrecorded.
+    pub fn mark_range(&self, job_id: &str, nonce_start: u32, nonce_end: u32) -> Result<(), JsValue> {
+        let first = nonce_start.div_ceil(self.block_size);
+        let last = nonce_end / self.block_size;
+        for block in first..last {
+            for bit in self.bits_for(job_id, block) {
+                js_sys::Atomics::or(&self.bits, (bit / 32) as u32, 1 << (bit % 32))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether every block touching `nonce_start..nonce_end` has (probably) been searched
+    pub fn is_range_tried(&self, job_id: &str, nonce_start: u32, nonce_end: u32) -> Result<bool, JsValue> {
+        if nonce_start >= nonce_end {
+            return Ok(true);
+        }
+
+        let first = nonce_start / self.block_size;
+        let last = (nonce_end - 1) / self.block_size;
+        for block in first..=last {
+            for bit in self.bits_for(job_id, block) {
+                if js_sys::Atomics::load(&self.bits, (bit / 32) as u32)? & (1 << (bit % 32)) == 0 {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Clear every bit, e.g. once all the jobs it covered are stale
+    pub fn clear(&self) -> Result<(), JsValue> {
+        for index in 0..self.bits.length() {
+            js_sys::Atomics::store(&self.bits, index, 0)?;
+        }
+        Ok(())
+    }
+}
+
+impl TriedNonceFilter {
+    /// Bit positions for one (job, block) pair, by double hashing a SHA-256 digest
+    fn bits_for(&self, job_id: &str, block: u32) -> impl Iterator<Item = u64> + '_ {
+        let mut hasher = Sha256::new();
+        hasher.update(job_id.as_bytes());
+        hasher.update(block.to_le_bytes());
+        let digest = hasher.finalize();
+
+        let h1 = u64::from_le_bytes(digest[..8].try_into().unwrap_or_default());
+        let h2 = u64::from_le_bytes(digest[8..16].try_into().unwrap_or_default()) | 1;
+        (0..self.hash_count as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % self.bit_count)
+    }
+}
@@ -0,0 +1,83 @@
+//! SHA-256 compression with every round written out
+//!
+//! The loop in `sha` leaves unrolling to the compiler, which mostly declines
+//! under the size-optimized release profile. Here the 64 rounds are expanded
+//! by macro: round-constant loads become immediates, the working
+//! variables rotate by renaming rather than by seven moves per round, and the
+//! message schedule lives in a 16-word ring indexed by constants. `Ch` and
+//! `Maj` use the forms with fewest operations, since scalar wasm has no
+//! and-not instruction; `rotate_right` lowers to `i32.rotr`.
+
+use crate::sha::K;
+
+#[inline(always)]
+fn ch(e: u32, f: u32, g: u32) -> u32 {
+    g ^ (e & (f ^ g))
+}
+
+#[inline(always)]
+fn maj(a: u32, b: u32, c: u32) -> u32 {
+    (a & b) | (c & (a | b))
+}
+
+/// Schedule word `t`, expanding it in place in the ring for `t >= 16`
+#[inline(always)]
+fn word(w: &mut [u32; 16], t: usize) -> u32 {
+    if t >= 16 {
+        let w15 = w[(t - 15) % 16];
+        let w2 = w[(t - 2) % 16];
+        let s0 = w15.rotate_right(7) ^ w15.rotate_right(18) ^ (w15 >> 3);
+        let s1 = w2.rotate_right(17) ^ w2.rotate_right(19) ^ (w2 >> 10);
+        w[t % 16] = w[t % 16].wrapping_add(s0).wrapping_add(w[(t - 7) % 16]).wrapping_add(s1);
+    }
+    w[t % 16]
+}
+
+/// One round; the caller renames the variables instead of shifting them
+macro_rules! round {
+    ($w:ident, $t:expr, $a:ident, $b:ident, $c:ident, $d:ident, $e:ident, $f:ident, $g:ident, $h:ident) => {{
+        let t1 = $h
+            .wrapping_add($e.rotate_right(6) ^ $e.rotate_right(11) ^ $e.rotate_right(25))
+            .wrapping_add(ch($e, $f, $g))
+            .wrapping_add(K[$t])
+            .wrapping_add(word(&mut $w, $t));
+        let t2 = ($a.rotate_right(2) ^ $a.rotate_right(13) ^ $a.rotate_right(22)).wrapping_add(maj($a, $b, $c));
+        $d = $d.wrapping_add(t1);
+        $h = t1.wrapping_add(t2);
+    }};
+}
+
+/// Eight rounds starting at `t`, after which the names line up again
+macro_rules! eight_rounds {
+    ($w:ident, $t:expr, $a:ident, $b:ident, $c:ident, $d:ident, $e:ident, $f:ident, $g:ident, $h:ident) => {
+        round!($w, $t, $a, $b, $c, $d, $e, $f, $g, $h);
+        round!($w, $t + 1, $h, $a, $b, $c, $d, $e, $f, $g);
+        round!($w, $t + 2, $g, $h, $a, $b, $c, $d, $e, $f);
+        round!($w, $t + 3, $f, $g, $h, $a, $b, $c, $d, $e);
+        round!($w, $t + 4, $e, $f, $g, $h, $a, $b, $c, $d);
+        round!($w, $t + 5, $d, $e, $f, $g, $h, $a, $b, $c);
+        round!($w, $t + 6, $c, $d, $e, $f, $g, $h, $a, $b);
+        round!($w, $t + 7, $b, $c, $d, $e, $f, $g, $h, $a);
+    };
+}
+
+/// Run the compression function over one block of big-endian words
+#[inline(always)]
+pub fn compress(state: &mut [u32; 8], block: &[u32]) {
+    let mut w = [0u32; 16];
+    w.copy_from_slice(&block[..16]);
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+    eight_rounds!(w, 0, a, b, c, d, e, f, g, h);
+    eight_rounds!(w, 8, a, b, c, d, e, f, g, h);
+    eight_rounds!(w, 16, a, b, c, d, e, f, g, h);
+    eight_rounds!(w, 24, a, b, c, d, e, f, g, h);
+    eight_rounds!(w, 32, a, b, c, d, e, f, g, h);
+    eight_rounds!(w, 40, a, b, c, d, e, f, g, h);
+    eight_rounds!(w, 48, a, b, c, d, e, f, g, h);
+    eight_rounds!(w, 56, a, b, c, d, e, f, g, h);
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(value);
+    }
+}
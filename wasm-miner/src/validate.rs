@@ -0,0 +1,87 @@
+use wasm_bindgen::prelude::*;
+
+use crate::error::{self, ErrorCode};
+use crate::job::MiningJob;
+use crate::policy::{Acceptance, DifficultyPolicy};
+use crate::reject::RejectReason;
+
+/// Outcome of checking a submitted share against the job it was mined on
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct ShareValidation {
+    reason: Option<RejectReason>,
+    hash: String,
+    block: bool,
+    difficulty: f64,
+}
+
+#[wasm_bindgen]
+impl ShareValidation {
+    /// Whether the share should be credited
+    #[wasm_bindgen(getter)]
+    pub fn valid(&self) -> bool {
+        self.reason.is_none()
+    }
+
+    /// Why the share was refused; `None` when valid
+    #[wasm_bindgen(getter)]
+    pub fn reason(&self) -> Option<RejectReason> {
+        self.reason
+    }
+
+    /// The hash recomputed from the job and nonce
+    #[wasm_bindgen(getter)]
+    pub fn hash(&self) -> String {
+        self.hash.clone()
+    }
+
+    /// Whether the share also meets the job's block difficulty
+    #[wasm_bindgen(getter)]
+    pub fn block(&self) -> bool {
+        self.block
+    }
+
+    /// Highest difficulty the recomputed hash satisfies
+    #[wasm_bindgen(getter)]
+    pub fn difficulty(&self) -> f64 {
+        self.difficulty
+    }
+}
+
+/// Verify a share the way the miner produced it
+///
+/// `job_json` is the `/api/work` response the share was mined on. The
+/// header is rebuilt for `nonce`, hashed, and compared with `claimed_hash`
+/// and `share_difficulty` under the job's acceptance rule. Stale and
+/// duplicate checks need the pool's own state and are left to the caller.
+/// Build with `wasm-pack build --target nodejs` to call this from a pool.
+#[wasm_bindgen]
+pub fn validate_share(
+    job_json: &str,
+    nonce: u32,
+    claimed_hash: &str,
+    share_difficulty: f64,
+) -> Result<ShareValidation, JsValue> {
+    let job = MiningJob::from_pool_work(job_json)?;
+    let header = job.header(nonce, 0)?;
+    let hash = header.hash()?;
+    let previous_hash = job.previous_hash();
+    let policy = Acceptance::new(&header.params(), &previous_hash, share_difficulty)
+        .map_err(|e| error::js_error(ErrorCode::InvalidDifficulty, e))?;
+
+    let in_range = (job.nonce_start()..job.nonce_end()).contains(&nonce);
+    let reason = if !in_range || !claimed_hash.eq_ignore_ascii_case(&hash) {
+        Some(RejectReason::BadHeader)
+    } else if !policy.accepts(&hash) {
+        Some(RejectReason::LowDifficulty)
+    } else {
+        None
+    };
+
+    Ok(ShareValidation {
+        reason,
+        block: header.meets_difficulty()?,
+        difficulty: header.achieved_difficulty()?,
+        hash,
+    })
+}
@@ -0,0 +1,41 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::error::{self, ErrorCode};
+
+/// What this build of the miner is, for pools to log alongside shares
+#[derive(Serialize)]
+struct VersionInfo {
+    name: &'static str,
+    version: &'static str,
+    /// Set from `STELLARIS_GIT_COMMIT` at build time (`build.sh` does this); `null` otherwise
+    git_commit: Option<&'static str>,
+    features: Vec<&'static str>,
+    target: &'static str,
+    simd: bool,
+    threads: bool,
+}
+
+/// Crate version, git commit, enabled cargo features and target flags of this build
+///
+/// Returns `{ name, version, git_commit, features, target, simd, threads }`.
+#[wasm_bindgen]
+pub fn version_info() -> Result<JsValue, JsValue> {
+    let features = [
+        ("parallel", cfg!(feature = "parallel")),
+        ("tracing", cfg!(feature = "tracing")),
+        ("cbor", cfg!(feature = "cbor")),
+        ("protobuf", cfg!(feature = "protobuf")),
+        ("test-fixtures", cfg!(feature = "test-fixtures")),
+    ];
+    let info = VersionInfo {
+        name: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+        git_commit: option_env!("STELLARIS_GIT_COMMIT").filter(|commit| !commit.is_empty()),
+        features: features.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| *name).collect(),
+        target: if cfg!(target_arch = "wasm32") { "wasm32" } else { std::env::consts::ARCH },
+        simd: cfg!(target_feature = "simd128"),
+        threads: cfg!(target_feature = "atomics"),
+    };
+    serde_wasm_bindgen::to_value(&info).map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+}
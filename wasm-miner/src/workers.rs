@@ -0,0 +1,171 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::clock;
+use crate::error::{self, ErrorCode};
+use crate::stats::HashrateMeter;
+use crate::MinerResult;
+
+/// How long a worker may go without a result, while others keep reporting, before it counts as stalled
+pub const DEFAULT_WORKER_STALL_MS: f64 = 60_000.0;
+
+#[derive(Default)]
+struct WorkerEntry {
+    hashrate: HashrateMeter,
+    total_hashes: u64,
+    results: u32,
+    shares: u32,
+    blocks: u32,
+    errors: u32,
+    last_error: Option<String>,
+    /// Monotonic time of the latest result
+    last_result_ms: Option<f64>,
+}
+
+/// One worker's line in `WorkerStats::report`
+#[derive(Serialize)]
+struct WorkerReport<'a> {
+    worker: u32,
+    hashrate: f64,
+    total_hashes: u64,
+    results: u32,
+    shares: u32,
+    blocks: u32,
+    errors: u32,
+    last_error: Option<&'a str>,
+    /// Milliseconds since the latest result, or `None` before the first
+    idle_ms: Option<f64>,
+    stalled: bool,
+}
+
+/// Totals across every worker
+#[derive(Default, Serialize)]
+struct CombinedReport {
+    hashrate: f64,
+    total_hashes: u64,
+    results: u32,
+    shares: u32,
+    blocks: u32,
+    errors: u32,
+    stalled: u32,
+}
+
+#[derive(Serialize)]
+struct WorkersReport<'a> {
+    combined: CombinedReport,
+    workers: Vec<WorkerReport<'a>>,
+}
+
+/// Per-worker stats kept by the coordinator in Workers mode
+///
+/// The coordinator records each worker's `result` messages and failures
+/// here; `report()` breaks hashrate, shares found, blocks and errors down
+/// per worker alongside the totals. A worker that has gone `stall_ms`
+/// without a result while another has reported in that time is flagged
+/// `stalled`, which is how a hung or crashed worker shows up.
+#[wasm_bindgen]
+pub struct WorkerStats {
+    workers: Vec<WorkerEntry>,
+    stall_ms: f64,
+}
+
+#[wasm_bindgen]
+impl WorkerStats {
+    /// Track `count` workers, numbered from 0; `stall_ms` defaults to a minute
+    #[wasm_bindgen(constructor)]
+    pub fn new(count: u32, stall_ms: Option<f64>) -> Result<WorkerStats, JsValue> {
+        let stall_ms = stall_ms.unwrap_or(DEFAULT_WORKER_STALL_MS);
+        if stall_ms.is_nan() || stall_ms <= 0.0 {
+            return Err(error::js_error(ErrorCode::InvalidArgument, "stall_ms must be positive"));
+        }
+
+        Ok(WorkerStats {
+            workers: (0..count).map(|_| WorkerEntry::default()).collect(),
+            stall_ms,
+        })
+    }
+
+    /// Record a worker's result for a range; `share` says whether its best hash met the share difficulty
+    pub fn record_result(&mut self, worker: u32, result: &MinerResult, share: bool) -> Result<(), JsValue> {
+        let now_ms = clock::monotonic_ms();
+        let entry = self.entry(worker)?;
+        entry.hashrate.record(now_ms, result.elapsed_ms, result.hashes_computed as u64);
+        entry.total_hashes += result.hashes_computed as u64;
+        entry.results += 1;
+        entry.shares += share as u32;
+        entry.blocks += result.found as u32;
+        entry.last_result_ms = Some(now_ms);
+        Ok(())
+    }
+
+    /// Record a worker failure, such as an `error` event or a malformed reply
+    pub fn record_error(&mut self, worker: u32, message: &str) -> Result<(), JsValue> {
+        let entry = self.entry(worker)?;
+        entry.errors += 1;
+        entry.last_error = Some(message.to_string());
+        Ok(())
+    }
+
+    /// Workers currently flagged as stalled
+    pub fn stalled(&self) -> Vec<u32> {
+        let now_ms = clock::monotonic_ms();
+        (0..self.workers.len() as u32).filter(|&worker| self.is_stalled(worker as usize, now_ms)).collect()
+    }
+
+    /// `{ combined, workers: [...] }`, each worker with `worker`, `hashrate`, `total_hashes`,
+    /// `results`, `shares`, `blocks`, `errors`, `last_error`, `idle_ms` and `stalled`
+    pub fn report(&self) -> Result<JsValue, JsValue> {
+        let now_ms = clock::monotonic_ms();
+        let mut combined = CombinedReport::default();
+        let workers: Vec<WorkerReport> = self
+            .workers
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let report = WorkerReport {
+                    worker: index as u32,
+                    hashrate: entry.hashrate.hashrate(now_ms),
+                    total_hashes: entry.total_hashes,
+                    results: entry.results,
+                    shares: entry.shares,
+                    blocks: entry.blocks,
+                    errors: entry.errors,
+                    last_error: entry.last_error.as_deref(),
+                    idle_ms: entry.last_result_ms.map(|last| now_ms - last),
+                    stalled: self.is_stalled(index, now_ms),
+                };
+                combined.hashrate += report.hashrate;
+                combined.total_hashes += report.total_hashes;
+                combined.results += report.results;
+                combined.shares += report.shares;
+                combined.blocks += report.blocks;
+                combined.errors += report.errors;
+                combined.stalled += report.stalled as u32;
+                report
+            })
+            .collect();
+
+        serde_wasm_bindgen::to_value(&WorkersReport { combined, workers })
+            .map_err(|e| error::js_error(ErrorCode::Serialization, e.to_string()))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> u32 {
+        self.workers.len() as u32
+    }
+}
+
+impl WorkerStats {
+    fn entry(&mut self, worker: u32) -> Result<&mut WorkerEntry, JsValue> {
+        let count = self.workers.len();
+        self.workers
+            .get_mut(worker as usize)
+            .ok_or_else(|| error::js_error(ErrorCode::InvalidArgument, format!("No worker {} of {}", worker, count)))
+    }
+
+    fn is_stalled(&self, index: usize, now_ms: f64) -> bool {
+        let recent = |entry: &WorkerEntry| entry.last_result_ms.is_some_and(|last| now_ms - last < self.stall_ms);
+        !recent(&self.workers[index])
+            && self.workers.iter().enumerate().any(|(other, entry)| other != index && recent(entry))
+    }
+}
@@ -0,0 +1,38 @@
+//! CBOR pool messages must transcode losslessly to and from JSON
+//!
+//! Run with `cargo test --features cbor`. As in `roundtrip.rs`, only success
+//! paths are exercised natively.
+
+#![cfg(feature = "cbor")]
+
+use stellaris_wasm_miner::{pool_cbor_to_json, pool_json_to_cbor};
+
+#[test]
+fn pool_work_round_trips_through_cbor() {
+    let work = concat!(
+        r#"{"block_height":1001,"difficulty":6.3,"job_id":"job-1","nonce_end":4294967295,"nonce_start":0,"#,
+        r#""previous_hash":"00000a1b2c","share_difficulty":-1,"timestamp":1700000000,"transactions":[],"weight":6.0}"#
+    );
+    let bytes = pool_json_to_cbor(work).unwrap();
+    assert!(bytes.len() < work.len());
+    assert_eq!(pool_cbor_to_json(&bytes).unwrap(), work);
+}
+
+#[test]
+fn decodes_rfc_8949_examples() {
+    let cases: [(&[u8], &str); 10] = [
+        (&[0xa2, 0x61, 0x61, 0x01, 0x61, 0x62, 0x82, 0x02, 0x03], r#"{"a":1,"b":[2,3]}"#),
+        (&[0x38, 0x63], "-100"),
+        (&[0xf9, 0x3c, 0x00], "1.0"),
+        (&[0xf9, 0xc4, 0x00], "-4.0"),
+        (&[0xfa, 0x47, 0xc3, 0x50, 0x00], "100000.0"),
+        (&[0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0], "1363896240"),
+        (&[0x83, 0x42, 0xab, 0xcd, 0xf5, 0xf6], r#"["abcd",true,null]"#),
+        (&[0x9f, 0x01, 0x82, 0x02, 0x03, 0xff], "[1,[2,3]]"),
+        (&[0x7f, 0x65, 0x73, 0x74, 0x72, 0x65, 0x61, 0x64, 0x6d, 0x69, 0x6e, 0x67, 0xff], r#""streaming""#),
+        (&[0xc2, 0x49, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01], "1"),
+    ];
+    for (bytes, json) in cases {
+        assert_eq!(pool_cbor_to_json(bytes).unwrap(), json, "{:02x?}", bytes);
+    }
+}
@@ -0,0 +1,26 @@
+//! The `test-fixtures` helpers must agree with the public header API
+//!
+//! Run with `cargo test --features test-fixtures`.
+
+#![cfg(feature = "test-fixtures")]
+
+use stellaris_wasm_miner::fixtures::{accepts, difficulty_chunk, header_prefix, meets_fraction};
+use stellaris_wasm_miner::{BlockHeader, ChainParams};
+
+const PREVIOUS_HASH: &str = "00000a1b2c3d4e5f60718293a4b5c6d7e8f90112233445566778899aabbccdd3";
+const ADDRESS: &str = "02abababababababababababababababababababababababababababababababab";
+const MERKLE_ROOT: &str = "5f2e8c1a9b3d4f6071829304a5b6c7d8e9f00112233445566778899aabbccdde";
+const TIMESTAMP: u64 = 1_700_000_000;
+
+#[test]
+fn fixtures_match_the_header_api() {
+    let params = ChainParams::default();
+    let header = BlockHeader::new(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, 2.5, 99).unwrap();
+    let prefix = header_prefix(&params, PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, 2.5).unwrap();
+    assert_eq!(&header.to_bytes().unwrap()[..prefix.len()], &prefix[..]);
+
+    assert_eq!(difficulty_chunk(PREVIOUS_HASH, 2.5).unwrap(), "d3");
+    assert!(meets_fraction("d37", 2.5) && !meets_fraction("d38", 2.5));
+    assert!(accepts(&params, PREVIOUS_HASH, 2.5, &format!("d30{}", "f".repeat(61))).unwrap());
+    assert!(!accepts(&params, PREVIOUS_HASH, 2.5, &"f".repeat(64)).unwrap());
+}
@@ -0,0 +1,142 @@
+//! Protobuf jobs and shares must transcode to and from their JSON bodies
+//!
+//! Run with `cargo test --features protobuf`. As in `roundtrip.rs`, only
+//! success paths are exercised natively.
+
+#![cfg(feature = "protobuf")]
+
+use serde_json::{json, Value};
+use stellaris_wasm_miner::{pool_work_from_protobuf, pool_work_to_protobuf, share_from_protobuf, share_to_protobuf};
+
+const HASH: &str = "00000a1b2c3d4e5f60718293a4b5c6d7e8f90112233445566778899aabbccdd3";
+
+#[test]
+fn shares_round_trip_through_protobuf() {
+    let share = format!(
+        concat!(
+            r#"{{"block_content_hex":"0102","block_hash":"{}","block_height":1001,"is_valid_block":false,"#,
+            r#""miner_id":"m-1","nonce":0,"receipt":{{"sequence":7,"signature":"{}"}},"#,
+            r#""user_agent":"stellaris-wasm","worker_name":"rig"}}"#
+        ),
+        HASH, HASH
+    );
+    let bytes = share_to_protobuf(&share).unwrap();
+    assert!(bytes.len() * 2 < share.len());
+    assert_eq!(share_from_protobuf(&bytes).unwrap(), share);
+}
+
+#[test]
+fn encodes_the_published_wire_format() {
+    // nonce_start 0 is a proto3 default and stays off the wire; job_id is `optional` and absent
+    let work = r#"{"block_height":150,"difficulty":"1.5","nonce_start":0,"nonce_end":1,"pool_address":"a","extra":1}"#;
+    let expected = [
+        [0x10, 0x96, 0x01].as_slice(),
+        &[0x19, 0, 0, 0, 0, 0, 0, 0xf8, 0x3f],
+        &[0x40, 0x01],
+        &[0x4a, 0x01, b'a'],
+    ]
+    .concat();
+    assert_eq!(pool_work_to_protobuf(work).unwrap(), expected);
+
+    // Unknown fields (here 15, a varint) are skipped and missing ones decode as defaults
+    let share = share_from_protobuf(&[0x78, 0x05, 0x18, 0x2a]).unwrap();
+    assert!(share.contains(r#""nonce":42"#) && share.contains(r#""miner_id":"""#));
+    assert!(!share.contains("receipt"));
+}
+
+/// Protobuf-to-JSON decoder for one message
+type Decode = fn(&[u8]) -> String;
+
+/// One field declaration from `proto/stellaris_miner.proto`
+struct ProtoField {
+    number: u64,
+    kind: String,
+    name: String,
+    optional: bool,
+}
+
+/// Messages in the published schema with their fields, in file order
+fn proto_messages() -> Vec<(String, Vec<ProtoField>)> {
+    let schema = include_str!("../proto/stellaris_miner.proto");
+    let mut messages: Vec<(String, Vec<ProtoField>)> = Vec::new();
+    for line in schema.lines() {
+        let line = line.split("//").next().unwrap().trim();
+        if let Some(name) = line.strip_prefix("message ") {
+            messages.push((name.trim_end_matches(" {").to_string(), Vec::new()));
+        } else if let Some(declaration) = line.strip_suffix(';').filter(|_| !messages.is_empty()) {
+            let (declaration, number) = declaration.split_once('=').unwrap();
+            let words: Vec<&str> = declaration.split_whitespace().collect();
+            let (optional, words) = match words.as_slice() {
+                ["optional", rest @ ..] => (true, rest.to_vec()),
+                _ => (false, words),
+            };
+            messages.last_mut().unwrap().1.push(ProtoField {
+                number: number.trim().parse().unwrap(),
+                kind: words[0].to_string(),
+                name: words[1].to_string(),
+                optional,
+            });
+        }
+    }
+    messages
+}
+
+/// The field on the wire with a non-default value, with the JSON pointer and value it decodes to
+///
+/// A nested message carries its first field.
+fn sample(field: &ProtoField, messages: &[(String, Vec<ProtoField>)]) -> (Vec<u8>, String, Value) {
+    let key = |wire_type: u64| vec![(field.number << 3 | wire_type) as u8];
+    let pointer = format!("/{}", json_name(field));
+    match field.kind.as_str() {
+        "string" => ([key(2), vec![1, b'x']].concat(), pointer, json!("x")),
+        "bytes" => ([key(2), vec![1, 0xab]].concat(), pointer, json!("ab")),
+        "uint32" | "uint64" => ([key(0), vec![5]].concat(), pointer, json!(5)),
+        "double" => ([key(1), 1.5f64.to_le_bytes().to_vec()].concat(), pointer, json!(1.5)),
+        "bool" => ([key(0), vec![1]].concat(), pointer, json!(true)),
+        message => {
+            let (_, fields) = messages.iter().find(|(name, _)| name == message).expect("message is declared");
+            let (bytes, nested, value) = sample(&fields[0], messages);
+            ([key(2), vec![bytes.len() as u8], bytes].concat(), pointer + &nested, value)
+        }
+    }
+}
+
+/// Name of a field in the JSON bodies, where it differs from the proto name
+fn json_name(field: &ProtoField) -> &str {
+    match field.name.as_str() {
+        "block_content" => "block_content_hex",
+        name => name,
+    }
+}
+
+#[test]
+fn messages_match_the_proto_schema() {
+    let messages = proto_messages();
+    let decoders: [(&str, Decode); 2] = [
+        ("Job", |bytes| pool_work_from_protobuf(bytes).unwrap()),
+        ("Share", |bytes| share_from_protobuf(bytes).unwrap()),
+    ];
+
+    for (message, decode) in decoders {
+        let (_, fields) = messages.iter().find(|(name, _)| name == message).unwrap();
+
+        // An empty message decodes every plain scalar field to its default, and nothing else
+        let defaults: Value = serde_json::from_str(&decode(&[])).unwrap();
+        let mut expected: Vec<&str> = fields
+            .iter()
+            .filter(|field| !field.optional && !field.kind.starts_with(char::is_uppercase))
+            .map(json_name)
+            .collect();
+        let mut decoded: Vec<&str> = defaults.as_object().unwrap().keys().map(String::as_str).collect();
+        expected.sort_unstable();
+        decoded.sort_unstable();
+        assert_eq!(decoded, expected, "{}", message);
+
+        // Each field decodes from its own number and wire type
+        for field in fields {
+            let (bytes, pointer, value) = sample(field, &messages);
+            let decoded: Value = serde_json::from_str(&decode(&bytes)).unwrap();
+            assert_eq!(decoded.pointer(&pointer), Some(&value), "{}.{}", message, field.name);
+        }
+    }
+}
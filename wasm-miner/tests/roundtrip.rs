@@ -0,0 +1,72 @@
+//! `decode_block_content` must exactly invert `build_block_content`
+//!
+//! Only success paths are exercised here: building a `JsValue` error needs
+//! a JS host, so failure cases live in the browser suite (`tests/web.rs`).
+
+use stellaris_wasm_miner::{
+    build_block_content, build_block_content_with_params, decode_block_content, decode_block_content_with_params,
+    AddressPolicy, BlockHeader, ChainParams,
+};
+
+const PREVIOUS_HASH: &str = "00000a1b2c3d4e5f60718293a4b5c6d7e8f90112233445566778899aabbccdd3";
+const ADDRESS: &str = "02abababababababababababababababababababababababababababababababab";
+const MERKLE_ROOT: &str = "5f2e8c1a9b3d4f6071829304a5b6c7d8e9f00112233445566778899aabbccdde";
+const TIMESTAMP: u32 = 1_700_000_000;
+
+#[test]
+fn decode_inverts_build_for_all_address_sizes() {
+    let compressed = ADDRESS.to_string();
+    let compressed_base58 = bs58::encode(hex::decode(ADDRESS).unwrap()).into_string();
+    let uncompressed = "cd".repeat(64);
+    let sec1 = format!("04{}", uncompressed);
+
+    for address in [&compressed, &compressed_base58, &uncompressed, &sec1] {
+        for difficulty in [0.0, 1.5, 6.0, 8.9] {
+            let built = build_block_content(PREVIOUS_HASH, address, MERKLE_ROOT, TIMESTAMP, difficulty, 31_337).unwrap();
+            let decoded = decode_block_content(&built).unwrap();
+            let expected =
+                BlockHeader::new(PREVIOUS_HASH, address, MERKLE_ROOT, TIMESTAMP as u64, difficulty, 31_337).unwrap();
+
+            assert_eq!(decoded, expected, "address {}", address);
+            assert_eq!(decoded.to_hex().unwrap(), built);
+        }
+    }
+}
+
+#[test]
+fn decode_inverts_build_with_u64_timestamps() {
+    let params = ChainParams::new(2);
+    let timestamp = u32::MAX as u64 + 10;
+    let built =
+        build_block_content_with_params(&params, PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, timestamp, 6.0, 7).unwrap();
+    let decoded = decode_block_content_with_params(&params, &built).unwrap();
+
+    assert_eq!(decoded.timestamp(), timestamp);
+    assert_eq!(decoded.params(), params);
+    assert_eq!(decoded.to_hex().unwrap(), built);
+}
+
+#[test]
+fn sec1_addresses_embed_as_64_bytes() {
+    let uncompressed = "cd".repeat(64);
+    let sec1 = format!("04{}", uncompressed);
+
+    assert_eq!(
+        build_block_content(PREVIOUS_HASH, &sec1, MERKLE_ROOT, TIMESTAMP, 6.0, 1).unwrap(),
+        build_block_content(PREVIOUS_HASH, &uncompressed, MERKLE_ROOT, TIMESTAMP, 6.0, 1).unwrap()
+    );
+}
+
+#[test]
+fn raw_policy_round_trips_other_address_sizes() {
+    let params = ChainParams::default().with_address_policy(AddressPolicy::Raw);
+    let short = "ef".repeat(20);
+
+    let built = build_block_content_with_params(&params, PREVIOUS_HASH, &short, MERKLE_ROOT, TIMESTAMP as u64, 6.0, 9)
+        .unwrap();
+    let decoded = decode_block_content_with_params(&params, &built).unwrap();
+    let expected = BlockHeader::parse(&params, PREVIOUS_HASH, &short, MERKLE_ROOT, TIMESTAMP as u64, 6.0, 9).unwrap();
+
+    assert_eq!(decoded, expected);
+    assert_eq!(decoded.address(), short);
+}
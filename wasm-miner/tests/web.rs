@@ -0,0 +1,1576 @@
+//! Tests that exercise the wasm-bindgen boundary in a headless browser
+//!
+//! Run with `wasm-pack test --headless --chrome` (or `--firefox`).
+
+#![cfg(target_arch = "wasm32")]
+
+use std::io::Write;
+
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use js_sys::{Array, Reflect, Uint32Array, Uint8Array};
+use sha2::{Digest, Sha256};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+
+use stellaris_wasm_miner::{
+    achieved_difficulty, address_info, address_to_base58, address_to_hex, auto_select_backend,
+    benchmark_worker_encoding, build_block_content, build_block_content_with_params, choose_concurrency_mode,
+    combine_worker_results, concurrency_mode, decode_block_content, decompress_payload, difficulty_1_hashes,
+    difficulty_to_chunk, difficulty_to_expected_hashes, difficulty_to_target, difficulty_to_valid_chars,
+    encode_worker_message, estimate_earnings, format_difficulty, format_duration, format_hashrate, generate_mock_job,
+    hash_backend, hash_distance, hash_headers, hash_meets_difficulty, hashes_to_shares, is_recording, max_difficulty,
+    min_difficulty, mine_range, mine_range_with_params, parse_address_info, parse_difficulty, parse_reject_reason,
+    parse_worker_message, publish_job, published_job_generation, reject_action, replay, seeded_mode, self_test,
+    self_test_result, set_concurrency_mode, set_difficulty_limits, set_hash_backend, set_log_level, set_log_sink,
+    set_mock_clock, set_parse_mode, set_seeded_mode, share_value, shares_to_hashes, start_recording,
+    stop_recording, take_published_job, target_to_difficulty, threads_compiled, validate_share, verify_progress_opening,
+    verify_share_receipt, version_info, worker_message_result, worker_range_message, worker_result_message,
+    AcceptanceRule, AddressPolicy, BackgroundPolicy, BestHashes, BestShare, BlockHeader, BlockHeaderBuilder,
+    ChainParams, ClockGuard, ConcurrencyMode, CsvKind, ErrorCode, EventKind, HashBackend, LogLevel,
+    MinerResult, MinerSession, MinerState, MiningJob, MockClock, ParseMode, PayoutScheme, RejectAction, RejectReason,
+    ResultMailbox, ShareBatcher, ShareSigner, ShareStatus, StopReason, SubmissionQueue, TelemetryExporter,
+    TriedNonceFilter, WorkerStats,
+};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const PREVIOUS_HASH: &str = "00000a1b2c3d4e5f60718293a4b5c6d7e8f90112233445566778899aabbccdd3";
+const ADDRESS: &str = "02abababababababababababababababababababababababababababababababab";
+const MERKLE_ROOT: &str = "5f2e8c1a9b3d4f6071829304a5b6c7d8e9f00112233445566778899aabbccdde";
+const TIMESTAMP: u32 = 1_700_000_000;
+
+fn pool_work(nonce_start: u32, nonce_end: u32, difficulty: f64) -> String {
+    format!(
+        r#"{{"job_id":"test-job","block_height":42,"difficulty":{},"previous_hash":"{}","merkle_root":"{}","timestamp":{},"nonce_start":{},"nonce_end":{},"pool_address":"{}"}}"#,
+        difficulty, PREVIOUS_HASH, MERKLE_ROOT, TIMESTAMP, nonce_start, nonce_end, ADDRESS
+    )
+}
+
+fn get(object: &JsValue, key: &str) -> JsValue {
+    Reflect::get(object, &JsValue::from_str(key)).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn mine_range_is_deterministic() {
+    let first = mine_range(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, 6.0, 0, 2_000, 2_000).unwrap();
+    let second = mine_range(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, 6.0, 0, 2_000, 2_000).unwrap();
+
+    assert_eq!(first.best_nonce(), second.best_nonce());
+    assert_eq!(first.best_hash(), second.best_hash());
+    assert_eq!(first.hashes_computed(), 2_000);
+    assert_eq!(first.stop_reason(), StopReason::RangeEnd);
+}
+
+#[wasm_bindgen_test]
+fn best_hash_matches_serialized_header() {
+    let result = mine_range(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, 6.0, 0, 500, 500).unwrap();
+    let header = build_block_content(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, 6.0, result.best_nonce()).unwrap();
+
+    let digest = Sha256::digest(hex::decode(header).unwrap());
+    assert_eq!(hex::encode(digest), result.best_hash());
+}
+
+#[wasm_bindgen_test]
+fn found_block_meets_difficulty() {
+    let result = mine_range(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, 1.0, 0, 100_000, 100_000).unwrap();
+
+    assert!(result.found());
+    assert_eq!(result.stop_reason(), StopReason::Found);
+    assert_eq!(result.next_nonce(), result.nonce() + 1);
+    assert!(hash_meets_difficulty(&result.hash(), PREVIOUS_HASH, 1.0).unwrap());
+}
+
+#[wasm_bindgen_test]
+fn chunked_mining_resumes_from_next_nonce() {
+    let whole = mine_range(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, 6.0, 0, 3_000, 3_000).unwrap();
+
+    let mut job = MiningJob::from_pool_work(&pool_work(0, 3_000, 6.0)).unwrap();
+    let mut best_hash = "f".repeat(64);
+    let mut total = 0;
+    loop {
+        let result = job.mine_next(700).unwrap();
+        total += result.hashes_computed();
+        if result.best_hash() < best_hash {
+            best_hash = result.best_hash();
+        }
+        assert_eq!(job.cursor(), result.next_nonce());
+        if result.stop_reason() == StopReason::RangeEnd {
+            break;
+        }
+        assert_eq!(result.stop_reason(), StopReason::MaxHashes);
+    }
+
+    assert_eq!(total, 3_000);
+    assert_eq!(best_hash, whole.best_hash());
+}
+
+#[wasm_bindgen_test]
+fn shutdown_stops_handing_out_work() {
+    let mut session = MinerSession::new();
+    session.push_job(MiningJob::from_pool_work(&pool_work(0, 1_000, 6.0)).unwrap(), true);
+    assert!(session.current_job().is_some());
+
+    let snapshot = session.shutdown().unwrap();
+    assert_eq!(get(&snapshot, "shut_down"), JsValue::TRUE);
+    assert!(session.is_shut_down());
+    assert!(session.current_job().is_none());
+
+    session.push_job(MiningJob::from_pool_work(&pool_work(0, 1_000, 6.0)).unwrap(), true);
+    assert!(session.current_job().is_none());
+}
+
+#[wasm_bindgen_test]
+fn invalid_input_is_an_error_not_a_trap() {
+    assert!(mine_range("not hex", ADDRESS, MERKLE_ROOT, TIMESTAMP, 6.0, 0, 10, 10).is_err());
+    assert!(mine_range(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, f64::NAN, 0, 10, 10).is_err());
+    // 64-byte hashes would overflow the largest header layout
+    let long = mine_range(&"ab".repeat(64), ADDRESS, &"cd".repeat(64), TIMESTAMP, 1.0, 0, 10, 10).unwrap_err();
+    assert_eq!(get(&long, "code").as_f64(), Some(ErrorCode::InvalidHeader as u32 as f64));
+    assert!(build_block_content(PREVIOUS_HASH, ADDRESS, &"cd".repeat(31), TIMESTAMP, 1.0, 0).is_err());
+    assert!(hash_meets_difficulty(&"0".repeat(64), "ü", 1.0).is_err());
+    assert!(MiningJob::from_pool_work("{}").is_err());
+}
+
+#[wasm_bindgen_test]
+fn errors_carry_stable_codes() {
+    let code = |error: JsValue| get(&error, "code").as_f64().unwrap() as u32;
+
+    assert_eq!(code(MiningJob::from_pool_work("{}").unwrap_err()), ErrorCode::InvalidJob as u32);
+    assert_eq!(code(address_to_hex("0OIl").unwrap_err()), ErrorCode::InvalidAddress as u32);
+    assert_eq!(code(difficulty_to_target(-1.0).unwrap_err()), ErrorCode::InvalidDifficulty as u32);
+    assert_eq!(code(hash_distance("abcd", PREVIOUS_HASH, 2.0).unwrap_err()), ErrorCode::InvalidHash as u32);
+
+    let mine = |previous_hash: &str, address: &str, difficulty: f64| {
+        mine_range(previous_hash, address, MERKLE_ROOT, TIMESTAMP, difficulty, 0, 10, 10).unwrap_err()
+    };
+    assert_eq!(code(mine(PREVIOUS_HASH, "0OIl", 6.0)), ErrorCode::InvalidAddress as u32);
+    assert_eq!(code(mine(PREVIOUS_HASH, ADDRESS, f64::NAN)), ErrorCode::InvalidDifficulty as u32);
+    assert_eq!(code(mine("not hex", ADDRESS, 6.0)), ErrorCode::InvalidHeader as u32);
+
+    let error = decode_block_content("zz").unwrap_err();
+    assert_eq!(get(&error, "name"), JsValue::from_str("MinerError"));
+    assert_eq!(get(&error, "message"), JsValue::from_str("Block content must be hex"));
+}
+
+#[wasm_bindgen_test]
+fn session_stats_marshal_to_plain_objects() {
+    let clock = MockClock::new(1_000.0);
+    let _clock = ClockGuard::mock(&clock);
+
+    let mut session = MinerSession::new();
+    let job = MiningJob::from_pool_work(&pool_work(0, 1_000, 6.0)).unwrap();
+    let result = job.mine_range(0, 1_000, 1_000).unwrap();
+
+    assert!(session.record_result(&job.job_id(), &result));
+    let best = session.best_share().unwrap();
+    assert_eq!(best.hash(), result.best_hash());
+    assert_eq!(best.timestamp_ms(), 1_000.0);
+
+    let restored = BestShare::from_json(&best.to_json().unwrap()).unwrap();
+    assert_eq!(restored.nonce(), best.nonce());
+
+    session.record_share("test-job", result.best_nonce(), &result.best_hash(), ShareStatus::Accepted, None);
+    let records = Array::from(&session.query_shares(0, 10).unwrap());
+    assert_eq!(records.length(), 1);
+    assert_eq!(get(&records.get(0), "status"), JsValue::from_str("Accepted"));
+    assert_eq!(get(&records.get(0), "job_id"), JsValue::from_str("test-job"));
+
+    let luck = session.luck().unwrap();
+    assert!(get(&get(&luck, "blocks"), "expected").as_f64().unwrap() > 0.0);
+}
+
+#[wasm_bindgen_test]
+fn luck_counts_shares_by_the_share_policy() {
+    let mut session = MinerSession::new();
+    session.push_job(MiningJob::from_pool_work(&pool_work(0, 1_000, 6.0)).unwrap(), true);
+    session.set_share_difficulty(Some(1.0));
+    let record = |session: &mut MinerSession, hash: String| {
+        let (achieved, stop) = (achieved_difficulty(&hash, PREVIOUS_HASH), StopReason::RangeEnd);
+        let result = MinerResult::new(false, 0, hash.clone(), 1_000, 0, hash, 1.0, stop, 1_000, 0, 0, 6.0, achieved);
+        session.record_result("test-job", &result);
+        get(&get(&session.luck().unwrap(), "shares"), "found").as_f64().unwrap()
+    };
+
+    // Starts with the last two characters of previous_hash but not the last one
+    let hash = format!("d3{}", "0".repeat(62));
+    assert!(achieved_difficulty(&hash, PREVIOUS_HASH) >= 1.0);
+    assert!(!hash_meets_difficulty(&hash, PREVIOUS_HASH, 1.0).unwrap());
+    assert_eq!(record(&mut session, hash), 0.0);
+    assert_eq!(record(&mut session, format!("3{}", "0".repeat(63))), 1.0);
+}
+
+#[wasm_bindgen_test]
+fn batch_results_fill_typed_arrays() {
+    let job = MiningJob::from_pool_work(&pool_work(0, 1_000, 6.0)).unwrap();
+    let hashes = Uint8Array::new_with_length(32 * 8);
+    let records = Uint32Array::new_with_length(4 * 8);
+
+    let written = job.mine_batch_into(0, 1_000, 250, &hashes, &records).unwrap();
+    assert_eq!(written, 4);
+
+    let single = job.mine_range(250, 500, 250).unwrap();
+    assert_eq!(records.get_index(4), single.best_nonce());
+    assert_eq!(records.get_index(5), 250);
+    assert_eq!(records.get_index(7), 500);
+    assert_eq!(hex::encode(hashes.subarray(32, 64).to_vec()), single.best_hash());
+}
+
+#[wasm_bindgen_test]
+fn hash_headers_matches_block_content() {
+    let job = MiningJob::from_pool_work(&pool_work(0, 1_000, 6.0)).unwrap();
+    let nonces = [7u32, 0, u32::MAX];
+    let digests = hash_headers(&job, &nonces).unwrap();
+    assert_eq!(digests.len(), 32 * nonces.len());
+
+    for (digest, nonce) in digests.chunks(32).zip(nonces) {
+        let content = hex::decode(job.build_block_content(nonce).unwrap()).unwrap();
+        assert_eq!(digest, &Sha256::digest(&content)[..]);
+    }
+}
+
+#[wasm_bindgen_test]
+fn validate_share_recomputes_the_hash() {
+    let work = pool_work(0, 1_000, 6.0);
+    let job = MiningJob::from_pool_work(&work).unwrap();
+    let result = job.mine_range(0, 1_000, 1_000).unwrap();
+    let (nonce, hash) = (result.best_nonce(), result.best_hash());
+    let achieved = job.header(nonce, 0).unwrap().achieved_difficulty().unwrap();
+
+    let share = validate_share(&work, nonce, &hash, achieved).unwrap();
+    assert!(share.valid());
+    assert_eq!(share.hash(), hash);
+    assert_eq!(share.difficulty(), achieved);
+
+    let forged = validate_share(&work, nonce ^ 1, &hash, achieved).unwrap();
+    assert_eq!(forged.reason(), Some(RejectReason::BadHeader));
+    let outside = validate_share(&work, 5_000, &hash, achieved).unwrap();
+    assert_eq!(outside.reason(), Some(RejectReason::BadHeader));
+    let low = validate_share(&work, nonce, &hash, achieved + 1.0).unwrap();
+    assert_eq!(low.reason(), Some(RejectReason::LowDifficulty));
+}
+
+#[wasm_bindgen_test]
+fn address_info_replies_parse_to_balances() {
+    let code = |error: JsValue| get(&error, "code").as_f64().unwrap() as u32;
+    let balance = |json: &str| parse_address_info(ADDRESS.to_string(), json).map(|balance| balance.balance());
+
+    // Amounts come as numbers or, to keep decimals exact, as strings
+    assert_eq!(balance(r#"{"ok":true,"result":{"balance":12.5}}"#).unwrap(), 12.5);
+    assert_eq!(balance(r#"{"ok":true,"result":{"balance":" 0.000001 "}}"#).unwrap(), 0.000001);
+    let parsed = parse_address_info(ADDRESS.to_string(), r#"{"ok":true,"result":{"balance":1}}"#).unwrap();
+    assert_eq!(parsed.address(), ADDRESS);
+    let garbled = balance(r#"{"ok":true,"result":{"balance":"lots"}}"#).unwrap_err();
+    assert_eq!(code(garbled), ErrorCode::InvalidResponse as u32);
+
+    // Only errors about the address blame it
+    let refused = balance(r#"{"ok":false,"error":"Invalid address"}"#).unwrap_err();
+    assert_eq!(code(refused), ErrorCode::InvalidAddress as u32);
+    let failed = balance(r#"{"ok":false,"error":"Database unavailable"}"#).unwrap_err();
+    assert_eq!(code(failed), ErrorCode::InvalidResponse as u32);
+    assert_eq!(code(balance(r#"{"ok":false}"#).unwrap_err()), ErrorCode::InvalidResponse as u32);
+}
+
+#[wasm_bindgen_test]
+fn reject_messages_map_to_reasons() {
+    assert_eq!(parse_reject_reason("Stale job"), RejectReason::Stale);
+    assert_eq!(parse_reject_reason("Token expired"), RejectReason::Unauthorized);
+    assert_eq!(parse_reject_reason("Duplicate share"), RejectReason::Duplicate);
+    assert_eq!(parse_reject_reason("Invalid header: address mismatch"), RejectReason::BadHeader);
+    assert_eq!(reject_action(RejectReason::BadHeader), RejectAction::Halt);
+
+    // Routine rejections keep the miner running
+    for message in ["Invalid share", "invalid nonce", "Nonce mismatch"] {
+        assert_eq!(parse_reject_reason(message), RejectReason::Unknown, "{}", message);
+        assert_eq!(reject_action(parse_reject_reason(message)), RejectAction::Continue);
+    }
+}
+
+#[wasm_bindgen_test]
+fn share_receipts_verify_with_the_session_key() {
+    let key = "00112233445566778899aabbccddeeff";
+    let mut signer = ShareSigner::new(key, "miner-1").unwrap();
+    let receipt = signer.sign(42, 7, PREVIOUS_HASH).unwrap();
+    let sequence = get(&receipt, "sequence").as_f64().unwrap() as u64;
+    let signature = get(&receipt, "signature").as_string().unwrap();
+    assert_eq!(sequence, 1);
+
+    assert!(verify_share_receipt(key, "miner-1", 42, 7, PREVIOUS_HASH, sequence, &signature).unwrap());
+    assert!(!verify_share_receipt(key, "miner-1", 42, 8, PREVIOUS_HASH, sequence, &signature).unwrap());
+    assert!(!verify_share_receipt(key, "miner-1", 42, 7, PREVIOUS_HASH, 2, &signature).unwrap());
+    assert!(!verify_share_receipt("ffee", "miner-1", 42, 7, PREVIOUS_HASH, sequence, &signature).unwrap());
+}
+
+#[wasm_bindgen_test]
+fn progress_commitments_open_against_the_root() {
+    let mut session = MinerSession::new();
+    let job = MiningJob::from_pool_work(&pool_work(0, 5_000, 6.0)).unwrap();
+    for start in (0..5_000).step_by(1_000) {
+        session.record_result("test-job", &job.mine_range(start, start + 1_000, 1_000).unwrap());
+    }
+
+    let commitment = session.commit_progress().unwrap();
+    let root = get(&commitment, "root").as_string().unwrap();
+    let leaf_count = get(&commitment, "leaf_count").as_f64().unwrap() as usize;
+    assert_eq!(leaf_count, 5);
+    assert_eq!(get(&commitment, "hashes_computed").as_f64().unwrap(), 5_000.0);
+
+    for index in 0..leaf_count {
+        let opening = session.open_progress(index).unwrap();
+        let nonce = get(&opening, "nonce").as_f64().unwrap() as u32;
+        let hash = get(&opening, "hash").as_string().unwrap();
+        let proof: Vec<String> = Array::from(&get(&opening, "proof")).iter().map(|p| p.as_string().unwrap()).collect();
+
+        assert!(verify_progress_opening(&root, leaf_count, index, "test-job", nonce, &hash, proof.clone()));
+        assert!(!verify_progress_opening(&root, leaf_count, index, "test-job", nonce ^ 1, &hash, proof));
+    }
+    assert!(session.open_progress(leaf_count).is_err());
+}
+
+#[wasm_bindgen_test]
+fn difficulty_conversions_agree() {
+    assert_eq!(difficulty_to_chunk(PREVIOUS_HASH, 3.5).unwrap(), "dd3");
+    assert_eq!(difficulty_to_valid_chars(3.5).unwrap(), "01234567");
+    assert_eq!(difficulty_to_expected_hashes(2.5), 512.0);
+    assert_eq!(difficulty_to_target(0.0).unwrap(), "f".repeat(64));
+    assert_eq!(difficulty_to_target(2.5).unwrap(), format!("007{}", "f".repeat(61)));
+
+    for tenths in 0..200 {
+        let difficulty = tenths as f64 / 10.0;
+        let target = difficulty_to_target(difficulty).unwrap();
+        assert_eq!(target_to_difficulty(&target).unwrap(), difficulty, "target {}", target);
+    }
+
+    // Distances follow the chunk rule: PREVIOUS_HASH ends in "d3"
+    let distance = |hash: &str, difficulty: f64| hash_distance(hash, PREVIOUS_HASH, difficulty).unwrap();
+    assert_eq!(distance(&format!("d3{}", "f".repeat(62)), 2.0), 0.0);
+    assert!(distance(&format!("d3{}", "0".repeat(62)), 2.0) < -240.0);
+    assert!((distance(&"0".repeat(64), 2.0) - 211f64.log2()).abs() < 1e-9);
+    assert_eq!(distance(&format!("d37{}", "f".repeat(61)), 2.5), 0.0);
+    for hash in ["d37", "d38", "d30", "d4", "03", "ff"].map(|start| format!("{:f<64}", start)) {
+        for difficulty in [1.0, 2.0, 2.5, 3.0] {
+            let accepted = hash_meets_difficulty(&hash, PREVIOUS_HASH, difficulty).unwrap();
+            assert_eq!(distance(&hash, difficulty) <= 0.0, accepted, "{} at {}", hash, difficulty);
+        }
+    }
+    assert!(hash_distance("abcd", PREVIOUS_HASH, 2.0).is_err());
+    assert!(hash_distance(&"f".repeat(64), "zz", 2.0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn formatting_picks_readable_units() {
+    assert_eq!(format_hashrate(0.0), "0 H/s");
+    assert_eq!(format_hashrate(f64::NAN), "0 H/s");
+    assert_eq!(format_hashrate(950.4), "950 H/s");
+    assert_eq!(format_hashrate(999.7), "1.00 kH/s");
+    assert_eq!(format_hashrate(12_345.0), "12.35 kH/s");
+    assert_eq!(format_hashrate(999_999.0), "1.00 MH/s");
+    assert_eq!(format_hashrate(2.5e18), "2500.00 PH/s");
+
+    assert_eq!(format_duration(0.4), "0s");
+    assert_eq!(format_duration(45.9), "45s");
+    assert_eq!(format_duration(192.0), "3m 12s");
+    assert_eq!(format_duration(7_500.0), "2h 05m");
+    assert_eq!(format_duration(273_600.0), "3d 4h");
+    assert_eq!(format_duration(f64::INFINITY), "∞");
+
+    assert_eq!(format_difficulty(6.5), "6.5");
+    assert_eq!(format_difficulty(7.0), "7.0");
+    assert_eq!(format_difficulty(f64::NAN), "-");
+}
+
+#[wasm_bindgen_test]
+fn difficulty_1_shares_convert_consistently() {
+    assert_eq!(difficulty_1_hashes(), difficulty_to_expected_hashes(1.0));
+    assert_eq!(share_value(1.0), 1.0);
+    assert_eq!(share_value(2.0), 16.0);
+    assert_eq!(shares_to_hashes(3.0, 2.5), 3.0 * 512.0);
+    assert_eq!(hashes_to_shares(shares_to_hashes(7.0, 4.5), 4.5), 7.0);
+    assert_eq!(hashes_to_shares(difficulty_1_hashes(), 1.0), 1.0);
+}
+
+#[wasm_bindgen_test]
+fn block_header_matches_loose_functions() {
+    let result = mine_range(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, 1.0, 0, 100_000, 100_000).unwrap();
+    let header = BlockHeader::new(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP as u64, 1.0, result.nonce()).unwrap();
+
+    assert_eq!(
+        header.to_hex().unwrap(),
+        build_block_content(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, 1.0, result.nonce()).unwrap()
+    );
+    assert_eq!(header.hash().unwrap(), result.hash());
+    assert!(header.meets_difficulty().unwrap());
+
+    let job = MiningJob::from_pool_work(&pool_work(0, 1_000, 1.0)).unwrap();
+    assert_eq!(job.header(result.nonce(), 0).unwrap(), header);
+    assert!(BlockHeader::new("abcd", ADDRESS, MERKLE_ROOT, 0, 1.0, 0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn decode_rejects_truncated_content() {
+    let built = build_block_content(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, 6.0, 7).unwrap();
+    assert!(decode_block_content(&built[..40]).is_err());
+    assert!(decode_block_content("zz").is_err());
+}
+
+#[wasm_bindgen_test]
+fn malformed_addresses_are_rejected() {
+    let wrong_prefix = format!("05{}", "ab".repeat(32));
+    let odd_length = "ef".repeat(20);
+    let sec1_strict = ChainParams::default().with_address_policy(AddressPolicy::Strict);
+
+    assert!(build_block_content(PREVIOUS_HASH, &wrong_prefix, MERKLE_ROOT, TIMESTAMP, 6.0, 0).is_err());
+    assert!(mine_range(PREVIOUS_HASH, &odd_length, MERKLE_ROOT, TIMESTAMP, 6.0, 0, 10, 10).is_err());
+    assert!(build_block_content_with_params(
+        &sec1_strict,
+        PREVIOUS_HASH,
+        &format!("04{}", "cd".repeat(64)),
+        MERKLE_ROOT,
+        TIMESTAMP as u64,
+        6.0,
+        0
+    )
+    .is_err());
+
+    // No layout, even a raw one, fits an address longer than 64 bytes
+    let raw = ChainParams::default().with_address_policy(AddressPolicy::Raw);
+    let too_long = "ef".repeat(65);
+    assert!(build_block_content_with_params(&raw, PREVIOUS_HASH, &too_long, MERKLE_ROOT, 0, 6.0, 0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn earnings_estimates_follow_the_payout_scheme() {
+    let pps = estimate_earnings(PayoutScheme::Pps, 0.25, 4.0, 1_000_000.0, 100.0, None).unwrap();
+    assert_eq!(get(&pps, "blocks_per_day").as_f64(), Some(1_000_000.0 * 86_400.0 / 65_536.0));
+    assert_eq!(get(&pps, "shares_per_day").as_f64(), Some(1_000_000.0 * 86_400.0 / 16.0));
+    assert_eq!(get(&pps, "reward_per_share").as_f64(), Some(75.0 * 16.0 / 65_536.0));
+    assert_eq!(get(&pps, "daily_earnings").as_f64(), Some(1_000_000.0 * 86_400.0 / 65_536.0 * 75.0));
+    assert_eq!(get(&pps, "daily_stddev").as_f64(), Some(0.0));
+
+    let pplns = estimate_earnings(PayoutScheme::Pplns, 0.25, 4.0, 1_000_000.0, 100.0, Some(4_000_000.0)).unwrap();
+    assert_eq!(get(&pplns, "daily_earnings"), get(&pps, "daily_earnings"));
+    let pool_blocks = 4_000_000.0 * 86_400.0 / 65_536.0;
+    let stddev = get(&pplns, "daily_stddev").as_f64().unwrap();
+    assert!((stddev - 75.0 / 4.0 * f64::sqrt(pool_blocks)).abs() < 1e-6);
+
+    let unknown_pool = estimate_earnings(PayoutScheme::Pplns, 0.25, 4.0, 1_000_000.0, 100.0, None).unwrap();
+    assert_eq!(get(&unknown_pool, "daily_stddev").as_f64(), None);
+
+    assert!(estimate_earnings(PayoutScheme::Pps, 1.5, 4.0, 1_000.0, 100.0, None).is_err());
+    assert!(estimate_earnings(PayoutScheme::Pps, 0.01, -1.0, 1_000.0, 100.0, None).is_err());
+    assert!(estimate_earnings(PayoutScheme::Pplns, 0.01, 4.0, 1_000.0, 100.0, Some(10.0)).is_err());
+}
+
+#[wasm_bindgen_test]
+fn address_info_reports_interpretation() {
+    let base58 = bs58::encode(hex::decode(ADDRESS).unwrap()).into_string();
+    let info = address_info(&base58, None).unwrap();
+
+    assert_eq!(get(&info, "encoding"), JsValue::from_str("Base58"));
+    assert_eq!(get(&info, "byte_length").as_f64(), Some(33.0));
+    assert_eq!(get(&info, "compressed"), JsValue::TRUE);
+    assert_eq!(get(&info, "hex"), JsValue::from_str(ADDRESS));
+    assert_eq!(get(&info, "header_hex"), JsValue::from_str(ADDRESS));
+
+    let rejected = address_info(&"ef".repeat(20), None).unwrap();
+    assert_eq!(get(&rejected, "kind"), JsValue::from_str("Unknown"));
+    assert!(get(&rejected, "error").is_string());
+    assert!(address_info("0OIl", None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn stellaris_compressed_addresses_are_accepted() {
+    // The compressed address for private key 1: specifier 0x2a (even y), then the generator's x coordinate
+    let address = "DcqYHkaFpdsss2cmWo2WvzvmYj3boepcP9NnxXL8pareT";
+    let key_hex = "2a79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    let info = address_info(address, None).unwrap();
+    assert_eq!(get(&info, "kind"), JsValue::from_str("Compressed"));
+    assert_eq!(get(&info, "header_hex"), JsValue::from_str(key_hex));
+
+    let built = build_block_content(PREVIOUS_HASH, address, MERKLE_ROOT, TIMESTAMP, 6.0, 0).unwrap();
+    assert_eq!((&built[..2], &built[66..132]), ("02", key_hex));
+    let strict = ChainParams::default().with_address_policy(AddressPolicy::Strict);
+    assert!(build_block_content_with_params(&strict, PREVIOUS_HASH, address, MERKLE_ROOT, 0, 6.0, 0).is_ok());
+    assert_eq!(decode_block_content(&built).unwrap().address(), key_hex);
+}
+
+#[wasm_bindgen_test]
+fn address_conversions_round_trip() {
+    let base58 = address_to_base58(ADDRESS).unwrap();
+    assert_eq!(address_to_hex(&base58).unwrap(), ADDRESS);
+    assert_eq!(address_to_base58(&base58).unwrap(), base58);
+    assert_eq!(address_to_hex(&ADDRESS.to_uppercase()).unwrap(), ADDRESS);
+    assert!(address_to_hex("0OIl").is_err());
+}
+
+#[wasm_bindgen_test]
+fn acceptance_rule_comes_from_params() {
+    let zeros = ChainParams::default().with_acceptance_rule(AcceptanceRule::LeadingZeros);
+    let result =
+        mine_range_with_params(&zeros, PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP as u64, 2.0, 0, 100_000, 100_000)
+            .unwrap();
+    assert!(result.found());
+    assert!(result.hash().starts_with("00"));
+
+    let target = ChainParams::default().with_acceptance_rule(AcceptanceRule::Target);
+    let result =
+        mine_range_with_params(&target, PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP as u64, 2.5, 0, 100_000, 100_000)
+            .unwrap();
+    assert!(result.found());
+    assert!(result.hash() <= difficulty_to_target(2.5).unwrap());
+
+    let mut header = BlockHeader::new(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP as u64, 2.5, result.nonce()).unwrap();
+    header.set_params(&target).unwrap();
+    assert!(header.meets_difficulty().unwrap());
+}
+
+#[wasm_bindgen_test]
+fn dual_tip_reports_the_tip_it_extends() {
+    let alternate = format!("{}0", &PREVIOUS_HASH[..63]);
+    let job = MiningJob::from_pool_work(&pool_work(0, 100_000, 1.0)).unwrap();
+
+    let result = job.mine_dual_tip(&alternate, 0, 100_000, 200_000, 64).unwrap();
+    assert!(result.found());
+
+    let tip_job = if result.tip() == 1 { job.with_previous_hash(&alternate) } else { job.clone() };
+    let header = tip_job.header(result.nonce(), 0).unwrap();
+    assert_eq!(header.hash().unwrap(), result.hash());
+    assert!(header.meets_difficulty().unwrap());
+
+    let capped = job.mine_dual_tip(&alternate, 0, 100_000, 10, 64).unwrap();
+    assert!(capped.hashes_computed() <= 10);
+    for max_hashes in [1, 3] {
+        let odd = job.mine_dual_tip(&alternate, 0, 100_000, max_hashes, 64).unwrap();
+        assert_eq!(odd.hashes_computed(), max_hashes);
+        assert_eq!(odd.stop_reason(), StopReason::MaxHashes);
+    }
+    let code = |error: JsValue| get(&error, "code").as_f64().unwrap() as u32;
+    assert_eq!(code(job.mine_dual_tip("zz", 0, 10, 10, 1).unwrap_err()), ErrorCode::InvalidHeader as u32);
+    let bad_address = pool_work(0, 10, 1.0).replace(ADDRESS, "0OIl");
+    let bad_job = MiningJob::from_pool_work(&bad_address).unwrap();
+    assert_eq!(code(bad_job.mine_dual_tip(&alternate, 0, 10, 10, 1).unwrap_err()), ErrorCode::InvalidAddress as u32);
+}
+
+#[wasm_bindgen_test]
+fn collect_mode_returns_every_share() {
+    let job = MiningJob::from_pool_work(&pool_work(0, 20_000, 6.0)).unwrap();
+    let batch = job.mine_collect(0, 20_000, 20_000, Some(2.0), 1_000).unwrap();
+
+    assert_eq!(batch.hashes_computed(), 20_000);
+    assert_eq!(batch.stop_reason(), StopReason::RangeEnd);
+    assert!(batch.length() > 1);
+    assert_eq!(batch.nonces().len(), batch.length());
+    for hash in batch.hashes() {
+        assert!(hash_meets_difficulty(&hash, PREVIOUS_HASH, 2.0).unwrap());
+    }
+    assert_eq!(Array::from(&batch.to_array().unwrap()).length() as usize, batch.length());
+
+    let capped = job.mine_collect(0, 20_000, 20_000, Some(1.0), 2).unwrap();
+    assert_eq!(capped.length(), 2);
+    assert_eq!(capped.stop_reason(), StopReason::Found);
+    assert_eq!(capped.next_nonce(), capped.nonces()[1] + 1);
+}
+
+#[wasm_bindgen_test]
+fn history_exports_as_csv() {
+    let clock = MockClock::new(1_700_000_000_123.0);
+    let _clock = ClockGuard::mock(&clock);
+
+    let mut session = MinerSession::new();
+    session.start_segment("job:test-job");
+    session.record_share("test-job", 7, "00ff", ShareStatus::Accepted, None);
+    session.record_share("test-job", 8, "0aff", ShareStatus::Rejected, Some("=cmd, \"low\"".to_string()));
+
+    let shares = session.export_csv(CsvKind::Shares);
+    let lines: Vec<&str> = shares.split("\r\n").collect();
+    assert_eq!(lines[0], "time,timestamp_ms,job_id,nonce,hash,status,reject_reason,reason");
+    assert_eq!(lines[1], "2023-11-14T22:13:20.123Z,1700000000123,test-job,7,00ff,Accepted,,");
+    assert!(lines[2].ends_with(",test-job,8,0aff,Rejected,Unknown,\"'=cmd, \"\"low\"\"\""), "{}", lines[2]);
+    assert_eq!(lines[3], "");
+
+    let segments = session.export_csv(CsvKind::Segments);
+    let lines: Vec<&str> = segments.split("\r\n").collect();
+    assert!(lines[0].starts_with("name,started,ended,total_hashes"));
+    assert_eq!(lines[1], "job:test-job,2023-11-14T22:13:20.123Z,,0,0,0,1,1,0,0");
+}
+
+#[wasm_bindgen_test]
+fn event_log_keeps_recent_history() {
+    let mut session = MinerSession::new();
+    session.push_job(MiningJob::from_pool_work(&pool_work(0, 1_000, 6.0)).unwrap(), true);
+    session.record_share("test-job", 7, "00ff", ShareStatus::Rejected, Some("low difficulty".to_string()));
+    session.log_event(EventKind::Reconnected, "Reconnected after 2 attempt(s)");
+
+    let events = Array::from(&session.recent_events(10, None).unwrap());
+    assert_eq!(events.length(), 3);
+    assert_eq!(get(&events.get(0), "kind"), JsValue::from_str("JobChanged"));
+    assert_eq!(get(&events.get(1), "message"), JsValue::from_str("Share 7 on job test-job: Rejected (low difficulty)"));
+    assert_eq!(get(&events.get(2), "kind"), JsValue::from_str("Reconnected"));
+
+    let shares = Array::from(&session.recent_events(10, Some(EventKind::Share)).unwrap());
+    assert_eq!(shares.length(), 1);
+    assert!(session.export_csv(CsvKind::Events).starts_with("time,timestamp_ms,kind,message\r\n"));
+
+    for i in 0..600 {
+        session.log_event(EventKind::Other, &format!("event {}", i));
+    }
+    assert_eq!(session.event_count(), 500);
+    assert_eq!(session.events_dropped(), 103.0);
+    let latest = Array::from(&session.recent_events(1, None).unwrap());
+    assert_eq!(get(&latest.get(0), "message"), JsValue::from_str("event 599"));
+
+    session.reset_stats();
+    assert_eq!(session.event_count(), 500, "resetting stats keeps the event log");
+    session.clear_events();
+    assert_eq!(session.event_count(), 0);
+}
+
+#[wasm_bindgen_test]
+fn segments_window_stats_across_resets() {
+    let clock = MockClock::new(0.0);
+    let _clock = ClockGuard::mock(&clock);
+
+    let mut session = MinerSession::new();
+    let job = MiningJob::from_pool_work(&pool_work(0, 2_000, 6.0)).unwrap();
+    session.record_result("test-job", &job.mine_range(0, 1_000, 1_000).unwrap());
+
+    session.start_segment("hour");
+    session.start_segment("test-job");
+    session.record_result("test-job", &job.mine_range(1_000, 2_000, 1_000).unwrap());
+    session.record_share("test-job", 1, "00ff", ShareStatus::Accepted, None);
+    clock.advance(500.0);
+    assert!(session.end_segment("test-job"));
+    assert!(!session.end_segment("test-job"));
+
+    session.reset_stats();
+    assert_eq!(session.total_hashes(), 0.0);
+    assert_eq!(session.accepted_shares(), 0);
+    assert!(session.best_share().is_none());
+
+    clock.advance(500.0);
+    let per_job = session.segment_stats("test-job").unwrap();
+    assert_eq!(get(&per_job, "total_hashes").as_f64(), Some(1_000.0));
+    assert_eq!(get(&per_job, "accepted_shares").as_f64(), Some(1.0));
+    assert_eq!(get(&per_job, "hashrate").as_f64(), Some(2_000.0));
+    let hour = session.segment_stats("hour").unwrap();
+    assert!(get(&hour, "ended_ms").is_null() || get(&hour, "ended_ms").is_undefined());
+    assert_eq!(get(&hour, "hashrate").as_f64(), Some(1_000.0));
+
+    session.clear_finished_segments();
+    assert_eq!(Array::from(&session.segments().unwrap()).length(), 1);
+}
+
+#[wasm_bindgen_test]
+fn share_batcher_rate_limits_and_caps() {
+    let clock = MockClock::new(0.0);
+    let _clock = ClockGuard::mock(&clock);
+
+    let mut batcher = ShareBatcher::new(2, 1.0, 3).unwrap();
+    for nonce in 0..4 {
+        batcher.push("test-job", 42, nonce, "00ff", "abcd");
+    }
+    assert_eq!(batcher.pending_len(), 3);
+    assert_eq!(batcher.dropped(), 1);
+
+    let first = Array::from(&batcher.take_batch().unwrap());
+    assert_eq!(first.length(), 2);
+    assert_eq!(get(&first.get(0), "nonce").as_f64(), Some(1.0));
+    assert_eq!(Array::from(&batcher.take_batch().unwrap()).length(), 0);
+
+    clock.advance(1_000.0);
+    assert!(batcher.ready());
+    batcher.discard_job("test-job");
+    assert!(!batcher.ready());
+    assert!(ShareBatcher::new(1, 0.0, 1).is_err());
+}
+
+#[wasm_bindgen_test]
+fn share_batcher_refuses_duplicates() {
+    let mut batcher = ShareBatcher::new(10, 1_000.0, 10).unwrap();
+    assert!(batcher.push("test-job", 42, 7, "00ff", "abcd"));
+    assert!(!batcher.push("test-job", 42, 7, "00ff", "abcd"));
+    assert_eq!(Array::from(&batcher.drain().unwrap()).length(), 1);
+
+    // Already submitted, e.g. found again after a reconnect
+    assert!(!batcher.push("test-job", 42, 7, "00ff", "abcd"));
+    assert!(batcher.push("other-job", 42, 7, "00ff", "abcd"));
+    assert!(batcher.mark_submitted("test-job", 99));
+    assert!(!batcher.mark_submitted("test-job", 99));
+    assert!(!batcher.mark_submitted("other-job", 7));
+    assert_eq!((batcher.pending_len(), batcher.duplicates()), (1, 4));
+}
+
+#[wasm_bindgen_test]
+fn tried_filter_is_shared_through_the_buffer() {
+    let buffer = js_sys::ArrayBuffer::new(1024);
+    let worker_a = TriedNonceFilter::new(&buffer, 4, 100).unwrap();
+    let worker_b = TriedNonceFilter::new(&buffer, 4, 100).unwrap();
+
+    worker_a.mark_range("test-job", 0, 250).unwrap();
+    assert!(worker_b.is_range_tried("test-job", 0, 200).unwrap());
+    assert!(!worker_b.is_range_tried("test-job", 150, 300).unwrap());
+    assert!(!worker_b.is_range_tried("other-job", 0, 100).unwrap());
+
+    worker_b.clear().unwrap();
+    assert!(!worker_a.is_range_tried("test-job", 0, 100).unwrap());
+    assert!(TriedNonceFilter::new(&js_sys::ArrayBuffer::new(0), 4, 100).is_err());
+}
+
+#[wasm_bindgen_test]
+fn recorded_calls_replay_identically() {
+    let job = MiningJob::from_pool_work(&pool_work(0, 20_000, 1.0)).unwrap();
+    let mut leading_zeros = job.clone();
+    leading_zeros.set_params(&ChainParams::default().with_acceptance_rule(AcceptanceRule::LeadingZeros));
+
+    start_recording();
+    assert!(is_recording());
+    let found = job.mine_range(0, 20_000, 20_000).unwrap();
+    assert!(found.found());
+    job.mine_range(found.next_nonce(), 20_000, 500).unwrap();
+    leading_zeros.mine_range(0, 2_000, 2_000).unwrap();
+    let log = stop_recording().unwrap().unwrap();
+    assert!(!is_recording());
+    assert!(stop_recording().unwrap().is_none());
+
+    set_hash_backend(HashBackend::Scalar);
+    let report = replay(&log, Some(HashBackend::Midstate)).unwrap();
+    assert_eq!(get(&report, "calls").as_f64(), Some(3.0));
+    assert_eq!(get(&report, "passed"), JsValue::TRUE);
+    assert_eq!(hash_backend(), HashBackend::Scalar);
+    set_hash_backend(HashBackend::Specialized);
+
+    let tampered = log.replacen(&format!("\"nonce\":{}", found.nonce()), "\"nonce\":1", 1);
+    let report = replay(&tampered, None).unwrap();
+    assert_eq!(get(&report, "passed"), JsValue::FALSE);
+    let mismatch = Array::from(&get(&report, "mismatches")).get(0);
+    assert_eq!(get(&mismatch, "index").as_f64(), Some(0.0));
+    assert_eq!(get(&get(&mismatch, "replayed"), "nonce").as_f64(), Some(found.nonce() as f64));
+
+    assert!(replay("{}", None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn batched_calls_replay_identically() {
+    let job = MiningJob::from_pool_work(&pool_work(0, 20_000, 6.0)).unwrap();
+    let hashes = Uint8Array::new_with_length(32 * 8);
+    let records = Uint32Array::new_with_length(4 * 8);
+
+    // Each slice of a batch is logged as the mine_range call that repeats it
+    start_recording();
+    let written = job.mine_batch_into(0, 2_000, 500, &hashes, &records).unwrap();
+    let log = stop_recording().unwrap().unwrap();
+    let report = replay(&log, Some(HashBackend::Scalar)).unwrap();
+    assert_eq!(get(&report, "calls").as_f64(), Some(written as f64));
+    assert_eq!(get(&report, "passed"), JsValue::TRUE);
+    let tampered = log.replacen(&format!("\"best_nonce\":{}", records.get_index(4)), "\"best_nonce\":1", 1);
+    assert_eq!(get(&replay(&tampered, None).unwrap(), "passed"), JsValue::FALSE);
+
+    let alternate = format!("{}0", &PREVIOUS_HASH[..63]);
+    let mut best = BestHashes::new(8).unwrap();
+    start_recording();
+    job.mine_range_ranked(0, 1_000, 1_000, &mut best).unwrap();
+    job.mine_collect(0, 2_000, 2_000, Some(1.0), 3).unwrap();
+    job.mine_dual_tip(&alternate, 0, 1_000, 1_000, 250).unwrap();
+    let report = replay(&stop_recording().unwrap().unwrap(), None).unwrap();
+    assert!(get(&report, "calls").as_f64().unwrap() >= 1.0 + 2.0 + 4.0);
+    assert_eq!(get(&report, "passed"), JsValue::TRUE);
+}
+
+#[wasm_bindgen_test]
+fn prefetched_job_mines_identically() {
+    let fresh = MiningJob::from_pool_work(&pool_work(0, 2_000, 6.0)).unwrap();
+    let mut prepared = fresh.clone();
+    prepared.prepare().unwrap();
+    assert!(prepared.prepared());
+
+    let expected = fresh.mine_range(0, 2_000, 2_000).unwrap();
+    let result = prepared.mine_range(0, 2_000, 2_000).unwrap();
+    assert_eq!(result.best_hash(), expected.best_hash());
+    assert_eq!(result.best_nonce(), expected.best_nonce());
+
+    let mut session = MinerSession::new();
+    let broken = pool_work(0, 10, 6.0).replace(MERKLE_ROOT, "not-hex");
+    assert!(session.prefetch_job(MiningJob::from_pool_work(&broken).unwrap(), true).is_err());
+    assert!(session.current_job().is_none());
+
+    session.prefetch_job(fresh, true).unwrap();
+    assert!(session.current_job().unwrap().prepared());
+}
+
+#[wasm_bindgen_test]
+fn observed_tips_flag_stale_jobs() {
+    let mut session = MinerSession::new();
+    assert!(!session.observe_tip(PREVIOUS_HASH, 41));
+    assert_eq!(session.tip_height(), Some(41));
+
+    session.push_job(MiningJob::from_pool_work(&pool_work(0, 1_000, 6.0)).unwrap(), true);
+    assert!(!session.observe_tip(PREVIOUS_HASH, 41));
+
+    let next_tip = "f".repeat(64);
+    assert!(!session.observe_tip(&next_tip, 40), "older tips are ignored");
+    assert_eq!(session.tip_hash().as_deref(), Some(PREVIOUS_HASH));
+    assert!(session.observe_tip(&next_tip, 42));
+    assert_eq!(session.tip_hash(), Some(next_tip));
+    assert!(session.observe_tip(&"e".repeat(64), u64::MAX), "the highest tip doesn't wrap around");
+}
+
+#[wasm_bindgen_test]
+fn published_job_is_picked_up_once() {
+    let mut session = MinerSession::new();
+    session.push_job(MiningJob::from_pool_work(&pool_work(0, 1_000, 6.0)).unwrap(), true);
+    session.take_preempted();
+
+    let generation = published_job_generation();
+    let fresh = pool_work(0, 1_000, 6.0).replace("test-job", "fresh-job");
+    publish_job(MiningJob::from_pool_work(&pool_work(0, 1_000, 6.0)).unwrap());
+    assert_eq!(publish_job(MiningJob::from_pool_work(&fresh).unwrap()), generation + 2);
+
+    assert!(session.adopt_published_job());
+    assert!(session.take_preempted());
+    assert_eq!(session.current_job().unwrap().job_id(), "fresh-job");
+    assert!(!session.adopt_published_job());
+    assert!(take_published_job().is_none());
+}
+
+#[wasm_bindgen_test]
+fn nonces_across_the_tail_block_boundary_hash_correctly() {
+    // 70 fixed bytes plus the address put the nonce at tail offsets 59..=64; from 61 it spills into the second block
+    let raw = ChainParams::new(1).with_address_policy(AddressPolicy::Raw);
+    let backends = [
+        HashBackend::Scalar,
+        HashBackend::HasherClone,
+        HashBackend::Midstate,
+        HashBackend::Specialized,
+        HashBackend::Unrolled,
+    ];
+    for backend in backends {
+        set_hash_backend(backend);
+        for address_len in 53..=58 {
+            let address = "ab".repeat(address_len);
+            let time = TIMESTAMP as u64;
+            let result = mine_range_with_params(&raw, PREVIOUS_HASH, &address, MERKLE_ROOT, time, 6.0, 0, 200, 200)
+                .unwrap();
+            let nonce = result.best_nonce();
+            let header = build_block_content_with_params(&raw, PREVIOUS_HASH, &address, MERKLE_ROOT, time, 6.0, nonce);
+            let digest = Sha256::digest(hex::decode(header.unwrap()).unwrap());
+            assert_eq!(hex::encode(digest), result.best_hash(), "{:?} with a {}-byte address", backend, address_len);
+        }
+    }
+    set_hash_backend(HashBackend::Specialized);
+}
+
+#[wasm_bindgen_test]
+fn hash_backends_agree() {
+    let long_address = "cd".repeat(64);
+    let mut results = Vec::new();
+    let backends = [
+        HashBackend::Scalar,
+        HashBackend::HasherClone,
+        HashBackend::Midstate,
+        HashBackend::Specialized,
+        HashBackend::Unrolled,
+    ];
+    for backend in backends {
+        set_hash_backend(backend);
+        for address in [ADDRESS, long_address.as_str()] {
+            let result = mine_range(PREVIOUS_HASH, address, MERKLE_ROOT, TIMESTAMP, 6.0, 0, 1_000, 1_000).unwrap();
+            results.push((address, result.best_nonce(), result.best_hash()));
+        }
+    }
+    set_hash_backend(HashBackend::Specialized);
+
+    for result in &results[2..] {
+        let expected = if result.0 == ADDRESS { &results[0] } else { &results[1] };
+        assert_eq!(result, expected);
+    }
+}
+
+#[wasm_bindgen_test]
+fn log_sink_receives_level_target_and_message() {
+    let received = Array::new();
+    let sink = js_sys::Function::new_with_args("level, target, message", "this.push([level, target, message])");
+    set_log_sink(Some(sink.bind(&received)));
+
+    auto_select_backend(0.0, Some(HashBackend::Specialized)).unwrap();
+    assert_eq!(received.length(), 1);
+    let entry = Array::from(&received.get(0));
+    assert_eq!(entry.get(0), JsValue::from_str("info"));
+    assert_eq!(entry.get(1), JsValue::from_str("backend"));
+
+    set_log_level(LogLevel::Warn);
+    auto_select_backend(0.0, Some(HashBackend::Specialized)).unwrap();
+    assert_eq!(received.length(), 1);
+
+    set_log_level(LogLevel::Info);
+    set_log_sink(None);
+}
+
+#[wasm_bindgen_test]
+fn lenient_parsing_coerces_near_misses() {
+    let odd_hash = &PREVIOUS_HASH[1..];
+    let short_address = "ab".repeat(40);
+    assert!(BlockHeader::new(odd_hash, ADDRESS, MERKLE_ROOT, 0, 1.0, 0).is_err());
+    assert!(BlockHeader::new(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, 0, -1.0, 0).is_err());
+    assert!(BlockHeader::new(PREVIOUS_HASH, &short_address, MERKLE_ROOT, 0, 1.0, 0).is_err());
+
+    set_parse_mode(ParseMode::Lenient);
+    let header = BlockHeader::new(odd_hash, ADDRESS, MERKLE_ROOT, 0, 1.0, 0).unwrap();
+    assert_eq!(header.previous_hash(), PREVIOUS_HASH);
+    assert_eq!(BlockHeader::new(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, 0, -1.0, 0).unwrap().difficulty(), 0.0);
+    assert!(BlockHeader::new(PREVIOUS_HASH, &short_address, MERKLE_ROOT, 0, 1.0, 0).is_ok());
+    assert!(BlockHeader::new(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, 0, f64::NAN, 0).is_err());
+    set_parse_mode(ParseMode::Strict);
+}
+
+#[wasm_bindgen_test]
+fn decimal_difficulties_parse_exactly() {
+    assert_eq!(parse_difficulty("6.3").unwrap(), 6.3);
+    assert_eq!(parse_difficulty(" 6.30 ").unwrap(), 6.3);
+    assert!(parse_difficulty("6.299999999").is_err());
+    assert!(parse_difficulty("-1").is_err());
+    assert!(parse_difficulty("6.").is_ok() && parse_difficulty(".5").is_err());
+
+    let header = BlockHeader::new(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, 0, parse_difficulty("6.3").unwrap(), 0).unwrap();
+    assert_eq!(decode_block_content(&header.to_hex().unwrap()).unwrap().difficulty(), 6.3);
+
+    let work = pool_work(0, 1_000, 6.3).replace(r#""difficulty":6.3"#, r#""difficulty":"6.3""#);
+    assert_eq!(MiningJob::from_pool_work(&work).unwrap().difficulty(), 6.3);
+}
+
+#[wasm_bindgen_test]
+fn miner_results_round_trip_through_json() {
+    let result = mine_range(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, 6.0, 0, 1_000, 1_000).unwrap();
+    let restored = MinerResult::from_json(&result.to_json().unwrap()).unwrap();
+    assert_eq!(restored, result);
+    assert_eq!(restored.best_hash(), result.best_hash());
+
+    let hash = "ab".repeat(32);
+    let built = MinerResult::new(true, 7, hash.clone(), 8, 7, hash, 1.0, StopReason::Found, 8, 0, 0, 1.0, 1.0);
+    assert!(built.found() && built.nonce() == 7 && built.stop_reason() == StopReason::Found);
+    assert!(MinerResult::from_json("{}").is_err());
+}
+
+#[wasm_bindgen_test]
+fn miner_state_migrates_between_workers() {
+    let job = MiningJob::from_pool_work(&pool_work(0, 4_000, 6.0)).unwrap();
+    let mut state = MinerState::new(&job, 1_000, 4_000).unwrap();
+    let first = job.mine_range(state.next_nonce(), state.nonce_end(), 1_500).unwrap();
+    assert!(state.record(&first));
+    assert_eq!((state.next_nonce(), state.remaining()), (2_500, 1_500));
+
+    let mut moved = MinerState::from_bytes(&state.to_bytes()).unwrap();
+    assert_eq!(moved.job().job_id(), "test-job");
+    assert_eq!(moved.best_share(), state.best_share());
+    let rest = moved.job().mine_range(moved.next_nonce(), moved.nonce_end(), 1_500).unwrap();
+    moved.record(&rest);
+    assert!(moved.finished());
+    assert_eq!(moved.hashes_computed(), 3_000.0);
+    let expected = first.best_hash().min(rest.best_hash());
+    assert_eq!(moved.best_share().unwrap().hash(), expected);
+
+    assert!(MinerState::new(&job, 10, 5).is_err());
+    assert!(MinerState::from_bytes(&job.to_bytes()).is_err());
+}
+
+#[wasm_bindgen_test]
+fn telemetry_exporter_batches_snapshots() {
+    let session = MinerSession::new();
+    let exporter = TelemetryExporter::new("https://collector.example/stats".into(), "rig-1".into(), 0.0, 10).unwrap();
+    assert!(!exporter.due());
+
+    exporter.record(&session).unwrap();
+    exporter.record(&session).unwrap();
+    assert_eq!(exporter.pending_len(), 2);
+    assert!(exporter.due());
+    assert_eq!((exporter.sent(), exporter.failures(), exporter.dropped()), (0.0, 0, 0.0));
+
+    assert!(TelemetryExporter::new(String::new(), "rig-1".into(), 1_000.0, 10).is_err());
+    assert!(TelemetryExporter::new("https://collector.example/stats".into(), "rig-1".into(), 1_000.0, 0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn compressed_payloads_are_inflated() {
+    let work = pool_work(0, 1_000, 6.0);
+    let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+    gzip.write_all(work.as_bytes()).unwrap();
+    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+    zlib.write_all(work.as_bytes()).unwrap();
+
+    for compressed in [gzip.finish().unwrap(), zlib.finish().unwrap()] {
+        let json = decompress_payload(&compressed).unwrap();
+        assert_eq!(MiningJob::from_pool_work(&json).unwrap().job_id(), "test-job");
+    }
+    assert_eq!(decompress_payload(work.as_bytes()).unwrap(), work);
+    // "80" passes the zlib header check but is an ordinary numeric body
+    assert_eq!(decompress_payload(b"80").unwrap(), "80");
+    assert_eq!(decompress_payload(b"8000").unwrap(), "8000");
+    assert!(decompress_payload(&[0x1f, 0x8b, 8, 0, 0]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn records_round_trip_through_binary() {
+    let result = mine_range(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP, 6.0, 0, 1_000, 1_000).unwrap();
+    let bytes = result.to_bytes();
+    assert_eq!(MinerResult::from_bytes(&bytes).unwrap(), result);
+    assert!(bytes.len() * 2 < result.to_json().unwrap().len());
+
+    // A job's bytes carry its age since it was received, so hold the clock still while comparing them
+    let clock = MockClock::new(0.0);
+    let _clock = ClockGuard::mock(&clock);
+    let mut job = MiningJob::from_pool_work(&pool_work(100, 5_000, 6.0)).unwrap();
+    job.set_params(&ChainParams::default().with_acceptance_rule(AcceptanceRule::LeadingZeros));
+    job.mine_next(500).unwrap();
+    let mut restored = MiningJob::from_bytes(&job.to_bytes()).unwrap();
+    assert_eq!(restored.to_bytes(), job.to_bytes());
+    let (expected, resumed) = (job.mine_next(500).unwrap(), restored.mine_next(500).unwrap());
+    assert_eq!((resumed.next_nonce(), resumed.best_hash()), (expected.next_nonce(), expected.best_hash()));
+
+    let best = BestShare::new(result.best_hash(), result.best_nonce(), "job-1".to_string(), 1001, 1_000.0);
+    assert_eq!(BestShare::from_bytes(&best.to_bytes()).unwrap(), best);
+
+    assert!(MiningJob::from_bytes(&bytes).is_err());
+    assert!(MinerResult::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    assert!(MinerResult::from_bytes(&[bytes.as_slice(), &[0]].concat()).is_err());
+    assert!(MinerResult::from_bytes(b"{}").is_err());
+}
+
+#[wasm_bindgen_test]
+fn mock_jobs_are_deterministic_and_solved() {
+    let mock = generate_mock_job(7, 2.0).unwrap();
+    assert_eq!(mock.work_json(), generate_mock_job(7, 2.0).unwrap().work_json());
+    assert_ne!(mock.work_json(), generate_mock_job(8, 2.0).unwrap().work_json());
+
+    let job = MiningJob::from_pool_work(&mock.work_json()).unwrap();
+    let header = job.header(mock.solution_nonce(), 0).unwrap();
+    assert!(header.meets_difficulty().unwrap());
+    assert_eq!(header.hash().unwrap(), mock.solution_hash());
+    assert_eq!(header.to_hex().unwrap(), mock.block_content_hex());
+    assert!(generate_mock_job(7, 9.0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn self_test_passes_on_a_healthy_build() {
+    let report = self_test();
+    assert!(report.passed(), "{:?}", report.failures());
+    assert!(self_test_result().passed());
+}
+
+#[wasm_bindgen_test]
+fn version_info_describes_the_build() {
+    let info = version_info().unwrap();
+    assert_eq!(get(&info, "version"), JsValue::from_str(env!("CARGO_PKG_VERSION")));
+    assert_eq!(get(&info, "target"), JsValue::from_str("wasm32"));
+    assert!(Array::is_array(&get(&info, "features")));
+    let features: Vec<String> = Array::from(&get(&info, "features")).iter().filter_map(|f| f.as_string()).collect();
+    for (feature, enabled) in [("cbor", cfg!(feature = "cbor")), ("protobuf", cfg!(feature = "protobuf"))] {
+        assert_eq!(features.iter().any(|f| f == feature), enabled, "{}", feature);
+    }
+    assert!(get(&info, "simd").as_bool().is_some());
+}
+
+#[wasm_bindgen_test]
+fn hidden_pages_follow_the_background_policy() {
+    let mut session = MinerSession::new();
+    assert_eq!(session.duty_cycle(), 1.0);
+    assert_eq!(session.rest_ms(100.0), 0.0);
+
+    session.set_page_hidden(true);
+    assert_eq!(session.duty_cycle(), 0.25);
+    assert_eq!(session.rest_ms(100.0), 300.0);
+
+    session.set_background_policy(BackgroundPolicy::Pause, None).unwrap();
+    assert!(session.paused() && session.rest_ms(100.0).is_infinite());
+    session.set_background_policy(BackgroundPolicy::Continue, None).unwrap();
+    assert_eq!(session.duty_cycle(), 1.0);
+    assert!(session.set_background_policy(BackgroundPolicy::Throttle, Some(0.0)).is_err());
+}
+
+#[wasm_bindgen_test]
+fn expired_jobs_are_dropped() {
+    let clock = MockClock::new(0.0);
+    let _clock = ClockGuard::mock(&clock);
+    let work = pool_work(0, 1_000, 6.0);
+    let short_lived = MiningJob::from_pool_work(&work.replace(r#""nonce_start""#, r#""ttl_ms":5000,"nonce_start""#)).unwrap();
+    assert_eq!(short_lived.ttl_ms(), Some(5_000.0));
+
+    let mut session = MinerSession::new();
+    session.push_job(short_lived, true);
+    session.push_job(MiningJob::from_pool_work(&pool_work(1_000, 2_000, 6.0)).unwrap(), false);
+    clock.advance(4_000.0);
+    assert!(!session.expire_jobs());
+
+    clock.advance(2_000.0);
+    assert!(session.expire_jobs());
+    assert_eq!(session.current_job().unwrap().nonce_start(), 1_000);
+    assert_eq!(session.take_expired_jobs(), vec!["test-job".to_string()]);
+    assert!(session.take_expired_jobs().is_empty());
+
+    // Age survives a trip through bytes; the maximum age applies without a TTL
+    let moved = MiningJob::from_bytes(&session.current_job().unwrap().to_bytes()).unwrap();
+    assert_eq!(moved.age_ms(), 6_000.0);
+    session.set_max_job_age_ms(Some(10_000.0)).unwrap();
+    clock.advance(5_000.0);
+    assert!(moved.expired(Some(10_000.0)));
+    assert!(session.expire_jobs());
+    assert!(session.current_job().is_none());
+    assert!(session.set_max_job_age_ms(Some(0.0)).is_err());
+}
+
+#[wasm_bindgen_test]
+fn worker_stats_flag_stalled_workers() {
+    let clock = MockClock::new(0.0);
+    let _clock = ClockGuard::mock(&clock);
+    let mut stats = WorkerStats::new(2, Some(10_000.0)).unwrap();
+    let hash = "f".repeat(64);
+    let result = MinerResult::new(false, 0, hash.clone(), 5_000, 0, hash, 1_000.0, StopReason::RangeEnd, 0, 0, 0, 6.0, 2.0);
+
+    clock.advance(1_000.0);
+    stats.record_result(0, &result, true).unwrap();
+    stats.record_result(1, &result, false).unwrap();
+    assert!(stats.stalled().is_empty());
+
+    clock.advance(9_000.0);
+    stats.record_result(0, &result, false).unwrap();
+    stats.record_error(1, "out of memory").unwrap();
+    clock.advance(1_000.0);
+    assert_eq!(stats.stalled(), vec![1]);
+
+    let report = stats.report().unwrap();
+    let combined = get(&report, "combined");
+    assert_eq!(get(&combined, "total_hashes").as_f64(), Some(15_000.0));
+    assert_eq!(get(&combined, "shares").as_f64(), Some(1.0));
+    assert_eq!(get(&combined, "stalled").as_f64(), Some(1.0));
+    let second = Array::from(&get(&report, "workers")).get(1);
+    assert_eq!(get(&second, "errors").as_f64(), Some(1.0));
+    assert_eq!(get(&second, "last_error").as_string().as_deref(), Some("out of memory"));
+    assert_eq!(get(&second, "idle_ms").as_f64(), Some(10_000.0));
+
+    assert!(stats.record_result(2, &result, false).is_err());
+    assert!(WorkerStats::new(2, Some(0.0)).is_err());
+}
+
+#[wasm_bindgen_test]
+fn idle_users_ramp_the_duty_cycle_up() {
+    let clock = MockClock::new(0.0);
+    let _clock = ClockGuard::mock(&clock);
+    let mut session = MinerSession::new();
+    session.set_user_idle(true);
+    assert_eq!(session.duty_cycle(), 1.0);
+
+    session.set_idle_ramp(true, Some(0.2), Some(10_000.0)).unwrap();
+    session.set_user_idle(false);
+    assert_eq!(session.duty_cycle(), 0.2);
+    session.set_user_idle(true);
+    clock.advance(5_000.0);
+    assert!((session.idle_level() - 0.6).abs() < 1e-9);
+    clock.advance(10_000.0);
+    assert_eq!(session.duty_cycle(), 1.0);
+
+    session.set_page_hidden(true);
+    assert_eq!(session.duty_cycle(), 0.25);
+    session.set_user_idle(false);
+    assert!((session.duty_cycle() - 0.05).abs() < 1e-9);
+    assert!(session.set_idle_ramp(true, Some(0.0), None).is_err());
+    assert!(session.set_idle_ramp(true, None, Some(-1.0)).is_err());
+}
+
+#[wasm_bindgen_test]
+fn hashrate_is_reported_over_configurable_windows() {
+    let clock = MockClock::new(0.0);
+    let _clock = ClockGuard::mock(&clock);
+    let mut session = MinerSession::new();
+    session.configure_hashrate(5_000.0, 20_000.0, 60_000.0).unwrap();
+
+    let hash = "f".repeat(64);
+    let second = |hashes| {
+        MinerResult::new(false, 0, hash.clone(), hashes, 0, hash.clone(), 1_000.0, StopReason::MaxHashes, 0, 0, 0, 6.0, 0.0)
+    };
+    for elapsed in 1..=65 {
+        clock.advance(1_000.0);
+        session.record_result("windows", &second(if elapsed > 60 { 20_000 } else { 10_000 }));
+    }
+
+    let windows = session.hashrate_windows().unwrap();
+    let rate = |key| get(&windows, key).as_f64().unwrap();
+    assert_eq!(rate("instant"), 20_000.0);
+    assert_eq!(rate("short"), 12_500.0);
+    assert!((rate("long") - 650_000.0 / 60.0).abs() < 1e-6);
+    assert_eq!(session.hashrate(), 12_500.0);
+    assert_eq!(rate("long_window_ms"), 60_000.0);
+
+    assert!(session.configure_hashrate(0.0, 20_000.0, 60_000.0).is_err());
+    assert!(session.configure_hashrate(30_000.0, 20_000.0, 60_000.0).is_err());
+    assert!(session.configure_hashrate(1.0, 1_000.0, 60_000.0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn idle_budgets_follow_the_busy_hashrate() {
+    let mut session = MinerSession::new();
+    assert_eq!(session.hashes_within(50.0), 1_000);
+
+    let hash = "f".repeat(64);
+    let result = MinerResult::new(false, 0, hash.clone(), 10_000, 0, hash, 100.0, StopReason::MaxHashes, 0, 0, 0, 6.0, 0.0);
+    session.record_result("idle", &result);
+    assert_eq!(session.busy_hashrate(), 100_000.0);
+    assert_eq!(session.hashes_within(50.0), 5_000);
+    assert_eq!(session.hashes_within(0.0), 1_000);
+
+    session.reset_stats();
+    assert_eq!(session.busy_hashrate(), 0.0);
+    assert_eq!(session.hashes_within(50.0), 1_000);
+    let commitment = session.commit_progress().unwrap();
+    assert_eq!(get(&commitment, "hashes_computed").as_f64(), Some(0.0));
+    assert_eq!(get(&commitment, "leaf_count").as_f64(), Some(0.0));
+}
+
+#[wasm_bindgen_test]
+fn sustained_slowdowns_lower_the_throttle_level() {
+    let clock = MockClock::new(0.0);
+    let _clock = ClockGuard::mock(&clock);
+
+    let mut session = MinerSession::new();
+    let hash = "f".repeat(64);
+    let result = |hashes: u32| {
+        MinerResult::new(false, 0, hash.clone(), hashes, 0, hash.clone(), 100.0, StopReason::MaxHashes, 0, 0, 0, 6.0, 0.0)
+    };
+
+    session.record_result("hot", &result(10_000));
+    for _ in 0..100 {
+        clock.advance(1_000.0);
+        session.record_result("hot", &result(5_000));
+    }
+    assert_eq!(session.throttle_level(), 0.75);
+    assert_eq!(session.duty_cycle(), 0.75);
+
+    session.set_thermal_throttle(false);
+    assert_eq!(session.throttle_level(), 1.0);
+}
+
+#[wasm_bindgen_test]
+async fn async_mining_matches_sync_across_yields() {
+    let job = MiningJob::from_pool_work(&pool_work(0, 3_000, 6.0)).unwrap();
+    let sync = job.mine_range(0, 3_000, 2_500).unwrap();
+    let result = JsFuture::from(job.mine_range_async(0, 3_000, 2_500, Some(700))).await.unwrap();
+
+    assert_eq!(get(&result, "hashes_computed"), JsValue::from(sync.hashes_computed()));
+    assert_eq!(get(&result, "best_hash"), JsValue::from(sync.best_hash()));
+    assert_eq!(get(&result, "next_nonce"), JsValue::from(sync.next_nonce()));
+    assert_eq!(get(&result, "stop_reason"), JsValue::from(StopReason::MaxHashes as u32));
+}
+
+#[wasm_bindgen_test]
+fn mailbox_slots_round_trip_results() {
+    let buffer = js_sys::SharedArrayBuffer::new(ResultMailbox::bytes_for(2));
+    let writer = ResultMailbox::new(&buffer, 2).unwrap();
+    let reader = ResultMailbox::new(&buffer, 2).unwrap();
+    assert!(reader.read(1).unwrap().is_none());
+
+    let job = MiningJob::from_pool_work(&pool_work(0, 1_000, 6.3)).unwrap();
+    let result = job.mine_range(0, 1_000, 1_000).unwrap();
+    assert_eq!(writer.write(1, &result).unwrap(), 2);
+
+    let record = reader.read(1).unwrap().unwrap();
+    assert_eq!(record.sequence(), reader.sequence(1).unwrap());
+    let read = record.result();
+    assert_eq!((read.best_hash(), read.best_nonce()), (result.best_hash(), result.best_nonce()));
+    assert_eq!((read.next_nonce(), read.stop_reason()), (result.next_nonce(), result.stop_reason()));
+    assert_eq!(read.difficulty(), 6.3);
+    assert!(reader.read(0).unwrap().is_none() && reader.read(2).is_err());
+}
+
+#[wasm_bindgen_test]
+fn worker_messages_follow_the_schema() {
+    let job = MiningJob::from_pool_work(&pool_work(0, 1_000, 6.0)).unwrap();
+    let result = job.mine_range(0, 1_000, 1_000).unwrap();
+    let message = worker_result_message(3, "test-job", &result).unwrap();
+    assert_eq!(get(&message, "type"), JsValue::from_str("result"));
+
+    let parsed = parse_worker_message(message.into()).unwrap();
+    assert_eq!(worker_message_result(parsed.into()).unwrap(), result);
+
+    let range = worker_range_message("test-job", 0, 500).unwrap();
+    assert!(worker_message_result(range.into()).is_err());
+    let bogus = js_sys::JSON::parse(r#"{"type":"cancel","job_id":"a","extra":1}"#).unwrap();
+    assert!(parse_worker_message(bogus).is_err());
+    assert!(parse_worker_message(js_sys::JSON::parse(r#"{"type":"cancel"}"#).unwrap()).is_ok());
+}
+
+#[wasm_bindgen_test]
+fn worker_messages_round_trip_through_msgpack() {
+    let job = MiningJob::from_pool_work(&pool_work(0, 1_000, 6.0)).unwrap();
+    let result = job.mine_range(0, 1_000, 1_000).unwrap();
+    let message = worker_result_message(3, "test-job", &result).unwrap();
+    let json_len = js_sys::JSON::stringify(&message).unwrap().length() as usize;
+
+    let bytes = encode_worker_message(message.into()).unwrap();
+    let packed: JsValue = Uint8Array::from(&bytes[..]).into();
+    assert_eq!(worker_message_result(packed.clone()).unwrap(), result);
+    assert_eq!(get(&parse_worker_message(packed).unwrap(), "worker").as_f64(), Some(3.0));
+    assert!(bytes.len() < json_len);
+
+    let truncated = Uint8Array::from(&bytes[..bytes.len() - 1]);
+    assert!(parse_worker_message(truncated.into()).is_err());
+    let bogus = js_sys::JSON::parse(r#"{"type":"range","job_id":"a"}"#).unwrap();
+    assert!(encode_worker_message(bogus).is_err());
+
+    let costs = Array::from(&benchmark_worker_encoding(10).unwrap());
+    assert_eq!(costs.length(), 3);
+    let bytes_of = |i: u32| get(&costs.get(i), "bytes").as_f64().unwrap();
+    assert!(bytes_of(1) < bytes_of(2));
+    assert!(get(&costs.get(1), "round_trip_us").as_f64().unwrap() >= 0.0);
+}
+
+#[wasm_bindgen_test]
+fn kernel_layouts_reproduce_the_header_hash() {
+    let mock = generate_mock_job(3, 3.0).unwrap();
+    let job = MiningJob::from_pool_work(&mock.work_json()).unwrap();
+    let layout = job.kernel_layout().unwrap();
+    let words = |name: &str| -> Vec<u32> {
+        Array::from(&get(&layout, name)).iter().map(|word| word.as_f64().unwrap() as u32).collect()
+    };
+    let number = |name: &str| get(&layout, name).as_f64().unwrap() as u32;
+
+    // What a kernel does for one nonce
+    let nonce = mock.solution_nonce();
+    let mut tail = words("tail");
+    let (word, shift) = (number("nonce_word") as usize, number("nonce_shift"));
+    tail[word] |= nonce.swap_bytes() >> shift;
+    if shift > 0 {
+        tail[word + 1] |= nonce.swap_bytes() << (32 - shift);
+    }
+    let mut state: [u32; 8] = words("midstate").try_into().unwrap();
+    for block in tail.chunks(16).take(number("tail_blocks") as usize) {
+        let bytes: [u8; 64] = block.iter().flat_map(|word| word.to_be_bytes()).collect::<Vec<u8>>().try_into().unwrap();
+        sha2::compress256(&mut state, &[bytes.into()]);
+    }
+    let digest: String = state.iter().map(|word| format!("{:08x}", word)).collect();
+    assert_eq!(digest, mock.solution_hash());
+
+    let (value, mask) = (words("match_value"), words("match_mask"));
+    assert!((0..4).all(|i| state[i] & mask[i] == value[i]));
+
+    // Confirmation drops a candidate that doesn't verify and keeps the real find
+    let result = job.confirm_kernel_batch(0, nonce + 10, &[nonce - 1, nonce], nonce, 1.0).unwrap();
+    assert!(result.found());
+    assert_eq!((result.nonce(), result.hash()), (nonce, mock.solution_hash()));
+    assert_eq!(result.hashes_computed(), nonce + 1);
+
+    let mut target = MiningJob::from_pool_work(&mock.work_json()).unwrap();
+    target.set_params(&ChainParams::default().with_acceptance_rule(AcceptanceRule::Target));
+    assert!(target.kernel_layout().is_err());
+}
+
+#[wasm_bindgen_test]
+fn worker_fallback_when_threads_are_unavailable() {
+    assert!(!threads_compiled());
+    assert_eq!(choose_concurrency_mode(4), ConcurrencyMode::Workers);
+    assert_eq!(concurrency_mode(), ConcurrencyMode::Workers);
+    assert!(set_concurrency_mode(ConcurrencyMode::Threads).is_err());
+    assert_eq!(choose_concurrency_mode(1), ConcurrencyMode::Single);
+
+    // Halves mined side by side add up to the whole range
+    let job = MiningJob::from_pool_work(&pool_work(0, 2_000, 6.0)).unwrap();
+    let whole = job.mine_range(0, 2_000, 2_000).unwrap();
+    let halves = vec![job.mine_range(1_000, 2_000, 1_000).unwrap(), job.mine_range(0, 1_000, 1_000).unwrap()];
+    let combined = combine_worker_results(halves).unwrap();
+    assert_eq!((combined.best_nonce(), combined.best_hash()), (whole.best_nonce(), whole.best_hash()));
+    assert_eq!((combined.hashes_computed(), combined.next_nonce()), (2_000, 2_000));
+    assert!(combine_worker_results(Vec::new()).is_none());
+}
+
+#[wasm_bindgen_test]
+fn block_height_follows_results_and_shares() {
+    let job = MiningJob::from_pool_work(&pool_work(0, 500, 6.0)).unwrap();
+    let result = job.mine_range(0, 500, 500).unwrap();
+    assert_eq!(result.height(), 42);
+    assert_eq!(MinerResult::from_bytes(&result.to_bytes()).unwrap().height(), 42);
+    let legacy = result.to_json().unwrap().replace(r#","height":42"#, "");
+    assert_eq!(MinerResult::from_json(&legacy).unwrap().height(), 0);
+
+    let mut session = MinerSession::new();
+    session.push_job(job, true);
+    session.record_result("test-job", &result);
+    assert_eq!(session.best_share().unwrap().height(), 42);
+    assert_eq!(get(&session.stats_snapshot().unwrap(), "height"), JsValue::from(42));
+
+    // A verdict for the previous job still knows its height after a new block replaces it
+    let next = pool_work(0, 500, 6.0).replace(r#""test-job","block_height":42"#, r#""next-job","block_height":43"#);
+    session.push_job(MiningJob::from_pool_work(&next).unwrap(), true);
+    session.record_share("test-job", result.best_nonce(), &result.best_hash(), ShareStatus::Accepted, None);
+    let records = Array::from(&session.query_shares(0, 10).unwrap());
+    assert_eq!(get(&records.get(0), "height"), JsValue::from(42));
+    assert_eq!(session.job_height("next-job"), Some(43));
+    assert_eq!(get(&session.stats_snapshot().unwrap(), "height"), JsValue::from(43));
+}
+
+#[wasm_bindgen_test]
+fn submission_queue_holds_fresh_submissions() {
+    let clock = MockClock::new(0.0);
+    let _clock = ClockGuard::mock(&clock);
+    let mut outbox = SubmissionQueue::new(Some(2), Some(60_000.0)).unwrap();
+    assert!(outbox.hold("job-1", 42, 1, "00aa", "c0", false));
+    assert!(!outbox.hold("job-1", 42, 1, "00aa", "c0", false));
+    assert!(outbox.hold("job-1", 42, 2, "0000", "c1", true));
+
+    // Full: the share makes room, not the block, and the block goes out first
+    clock.advance(1_000.0);
+    assert!(outbox.hold("job-1", 42, 3, "00bb", "c2", false));
+    let dropped = Array::from(&outbox.take_dropped().unwrap());
+    assert_eq!(get(&dropped.get(0), "nonce"), JsValue::from(1));
+    assert_eq!(get(&dropped.get(0), "reason"), JsValue::from_str("Full"));
+    let next = outbox.next(Some(41)).unwrap();
+    assert_eq!(get(&next, "nonce"), JsValue::from(2));
+    assert_eq!(get(&next, "age_ms"), JsValue::from(1_000.0));
+    assert!(outbox.remove("job-1", 2));
+    assert_eq!((outbox.len(), outbox.flushed()), (1, 1));
+
+    // Once the chain reaches the share's height it is stale; past max_age_ms it has expired
+    assert!(outbox.next(Some(42)).unwrap().is_undefined());
+    assert!(outbox.hold("job-2", 43, 4, "00cc", "c3", false));
+    clock.advance(61_000.0);
+    assert!(outbox.next(None).unwrap().is_undefined());
+    let reasons: Vec<JsValue> = Array::from(&outbox.take_dropped().unwrap()).iter().map(|d| get(&d, "reason")).collect();
+    assert_eq!(reasons, vec![JsValue::from_str("Stale"), JsValue::from_str("Expired")]);
+    assert_eq!(outbox.dropped(), 3);
+    assert!(SubmissionQueue::new(Some(0), None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn best_hashes_rank_every_hash_of_a_job() {
+    let job = MiningJob::from_pool_work(&pool_work(0, 2_000, 6.0)).unwrap();
+    let plain = job.mine_range(0, 2_000, 2_000).unwrap();
+    let mut whole = BestHashes::new(8).unwrap();
+    let ranked = job.mine_range_ranked(0, 2_000, 2_000, &mut whole).unwrap();
+    assert_eq!((ranked.best_nonce(), ranked.best_hash()), (plain.best_nonce(), plain.best_hash()));
+    assert_eq!((ranked.hashes_computed(), ranked.next_nonce()), (2_000, 2_000));
+    assert_eq!(ranked.height(), 42);
+
+    let hashes = whole.hashes();
+    assert_eq!(whole.length(), 8);
+    assert_eq!(hashes[0], plain.best_hash());
+    assert!(hashes.windows(2).all(|pair| pair[0] < pair[1]));
+
+    // Ranking across chunks finds the same list as one pass
+    let mut chunked = BestHashes::new(8).unwrap();
+    let first = job.mine_range_ranked(0, 2_000, 700, &mut chunked).unwrap();
+    job.mine_range_ranked(first.next_nonce(), 2_000, 2_000, &mut chunked).unwrap();
+    assert_eq!(chunked.nonces(), whole.nonces());
+    assert_eq!(chunked.hashes_offered(), 2_000.0);
+    let entries = Array::from(&chunked.to_array().unwrap());
+    assert_eq!(get(&entries.get(0), "nonce"), JsValue::from(plain.best_nonce()));
+    assert_eq!(get(&entries.get(0), "difficulty").as_f64(), Some(plain.best_difficulty()));
+
+    // A tracker serves one job until cleared
+    let other = pool_work(0, 2_000, 6.0).replace("test-job", "other-job");
+    let other = MiningJob::from_pool_work(&other).unwrap();
+    assert!(other.mine_range_ranked(0, 100, 100, &mut chunked).is_err());
+    chunked.clear();
+    assert!(other.mine_range_ranked(0, 100, 100, &mut chunked).is_ok());
+    assert_eq!(chunked.job_id().as_deref(), Some("other-job"));
+    assert!(BestHashes::new(0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn checkpoints_track_the_fully_searched_frontier() {
+    let mut session = MinerSession::new();
+    session.push_job(MiningJob::from_pool_work(&pool_work(1_000, 100_000, 6.0)).unwrap(), true);
+    assert!(session.set_checkpoint_interval(0).is_err());
+    session.set_checkpoint_interval(1_000).unwrap();
+
+    // A range past a gap doesn't move the checkpoint until the gap is searched
+    assert!(!session.mark_searched("test-job", 1_500, 3_000));
+    assert_eq!(session.checkpoint("test-job"), Some(1_000));
+    assert!(session.mark_searched("test-job", 1_000, 1_500));
+    assert_eq!(session.checkpoint("test-job"), Some(3_000));
+
+    // Less than an interval past the checkpoint isn't worth saving yet
+    assert!(!session.mark_searched("test-job", 3_000, 3_900));
+    assert_eq!(session.checkpoint("test-job"), Some(3_000));
+    assert!(session.mark_searched("test-job", 3_900, 4_000));
+    assert_eq!(session.checkpoint("test-job"), Some(4_000));
+
+    // A replacement adopts a checkpoint saved by the worker it replaces
+    let mut replacement = MinerSession::new();
+    replacement.set_checkpoint_interval(1_000).unwrap();
+    replacement.restore_checkpoint("test-job", 4_000);
+    replacement.push_job(MiningJob::from_pool_work(&pool_work(1_000, 100_000, 6.0)).unwrap(), true);
+    assert_eq!(replacement.checkpoint("test-job"), Some(4_000));
+    assert!(replacement.mark_searched("test-job", 4_000, 5_000));
+    assert_eq!(replacement.checkpoint("test-job"), Some(5_000));
+    assert_eq!(replacement.checkpoint("other-job"), None);
+}
+
+#[wasm_bindgen_test]
+fn job_difficulty_is_held_to_the_limits() {
+    let code = |error: JsValue| get(&error, "code").as_f64().unwrap() as u32;
+    assert_eq!((min_difficulty(), max_difficulty()), (1.0, 64.0));
+    for difficulty in [0.0, 1e9] {
+        let error = MiningJob::from_pool_work(&pool_work(0, 1_000, difficulty)).unwrap_err();
+        assert_eq!(code(error), ErrorCode::DifficultyOutOfRange as u32);
+    }
+
+    set_parse_mode(ParseMode::Lenient);
+    assert_eq!(MiningJob::from_pool_work(&pool_work(0, 1_000, 0.0)).unwrap().difficulty(), 1.0);
+    assert_eq!(MiningJob::from_pool_work(&pool_work(0, 1_000, 1e9)).unwrap().difficulty(), 64.0);
+    set_parse_mode(ParseMode::Strict);
+
+    assert_eq!(code(set_difficulty_limits(8.0, 2.0).unwrap_err()), ErrorCode::InvalidArgument as u32);
+    assert_eq!(code(set_difficulty_limits(-1.0, 2.0).unwrap_err()), ErrorCode::InvalidDifficulty as u32);
+    set_difficulty_limits(0.0, 8.0).unwrap();
+    assert!(MiningJob::from_pool_work(&pool_work(0, 1_000, 0.0)).is_ok());
+    assert!(MiningJob::from_pool_work(&pool_work(0, 1_000, 9.0)).is_err());
+    set_difficulty_limits(1.0, 64.0).unwrap();
+}
+
+#[wasm_bindgen_test]
+fn header_builder_matches_positional_construction() {
+    let builder = BlockHeaderBuilder::new()
+        .previous_hash(PREVIOUS_HASH)
+        .address(ADDRESS)
+        .merkle_root(MERKLE_ROOT)
+        .timestamp(TIMESTAMP as u64)
+        .difficulty(6.0);
+    let expected = BlockHeader::new(PREVIOUS_HASH, ADDRESS, MERKLE_ROOT, TIMESTAMP as u64, 6.0, 0).unwrap();
+    assert_eq!(builder.build().unwrap(), expected);
+    assert_eq!(builder.clone().nonce(7).build().unwrap().nonce(), 7);
+
+    // Every missing field is named, and present fields are validated as usual
+    let error = BlockHeaderBuilder::new().previous_hash(PREVIOUS_HASH).difficulty(6.0).build().unwrap_err();
+    let message = js_sys::Error::from(error).message();
+    assert_eq!(String::from(message), "Block header is missing address, merkle_root, timestamp");
+    assert!(builder.clone().merkle_root("abcd").build().is_err());
+    assert!(builder.difficulty(f64::NAN).build().is_err());
+}
+
+#[wasm_bindgen_test]
+fn seeded_mode_makes_results_reproducible() {
+    let _clock = ClockGuard::new();
+    let run = |seed: u32| {
+        set_seeded_mode(Some(seed));
+        let job = MiningJob::from_pool_work(&pool_work(0, 2_000, 6.0)).unwrap();
+        let result = job.mine_range(0, 2_000, 2_000).unwrap();
+        let mut session = MinerSession::new();
+        session.push_job(job, true);
+        session.record_result("test-job", &result);
+        let snapshot = session.stats_snapshot().unwrap();
+        (result, get(&snapshot, "timestamp_ms").as_f64().unwrap())
+    };
+
+    let (result, timestamp_ms) = run(7);
+    assert!(seeded_mode());
+    assert_eq!(result.elapsed_ms(), 1.0);
+    assert_eq!(run(7), (result.clone(), timestamp_ms));
+    assert_eq!(result.to_json().unwrap(), run(7).0.to_json().unwrap());
+    assert_ne!(run(8).1, timestamp_ms);
+
+    // Clearing the seed goes back to the clock that was driving time before
+    set_seeded_mode(None);
+    let clock = MockClock::new(5_000.0);
+    set_mock_clock(&clock);
+    set_seeded_mode(Some(7));
+    set_seeded_mode(None);
+    assert!(!seeded_mode());
+    let job = MiningJob::from_pool_work(&pool_work(0, 2_000, 6.0)).unwrap();
+    assert_eq!(job.mine_range(0, 10, 10).unwrap().elapsed_ms(), 0.0);
+}
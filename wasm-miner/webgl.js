@@ -0,0 +1,249 @@
+/**
+ * Stellaris WASM Miner - experimental WebGL2 hashing
+ * Runs SHA-256 in a fragment shader, one nonce per pixel, for GPUs the browser won't expose through WebGPU
+ */
+
+const VERTEX_SHADER = `#version 300 es
+void main() {
+    // One triangle covering the viewport
+    vec2 corner = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+    gl_Position = vec4(corner * 2.0 - 1.0, 0.0, 1.0);
+}`;
+
+const FRAGMENT_SHADER = `#version 300 es
+precision highp float;
+precision highp int;
+
+uniform uint u_midstate[8];
+uniform uint u_tail[32];
+uniform uint u_tail_blocks;
+uniform uint u_nonce_word;
+uniform uint u_nonce_shift;
+uniform uvec4 u_match_value;
+uniform uvec4 u_match_mask;
+uniform uint u_fraction_nibble;
+uniform uint u_fraction_limit;
+uniform uint u_nonce_base;
+uniform uint u_count;
+uniform uint u_width;
+
+out uvec4 out_record;
+
+const uint K[64] = uint[64](
+    0x428a2f98u, 0x71374491u, 0xb5c0fbcfu, 0xe9b5dba5u, 0x3956c25bu, 0x59f111f1u, 0x923f82a4u, 0xab1c5ed5u,
+    0xd807aa98u, 0x12835b01u, 0x243185beu, 0x550c7dc3u, 0x72be5d74u, 0x80deb1feu, 0x9bdc06a7u, 0xc19bf174u,
+    0xe49b69c1u, 0xefbe4786u, 0x0fc19dc6u, 0x240ca1ccu, 0x2de92c6fu, 0x4a7484aau, 0x5cb0a9dcu, 0x76f988dau,
+    0x983e5152u, 0xa831c66du, 0xb00327c8u, 0xbf597fc7u, 0xc6e00bf3u, 0xd5a79147u, 0x06ca6351u, 0x14292967u,
+    0x27b70a85u, 0x2e1b2138u, 0x4d2c6dfcu, 0x53380d13u, 0x650a7354u, 0x766a0abbu, 0x81c2c92eu, 0x92722c85u,
+    0xa2bfe8a1u, 0xa81a664bu, 0xc24b8b70u, 0xc76c51a3u, 0xd192e819u, 0xd6990624u, 0xf40e3585u, 0x106aa070u,
+    0x19a4c116u, 0x1e376c08u, 0x2748774cu, 0x34b0bcb5u, 0x391c0cb3u, 0x4ed8aa4au, 0x5b9cca4fu, 0x682e6ff3u,
+    0x748f82eeu, 0x78a5636fu, 0x84c87814u, 0x8cc70208u, 0x90befffau, 0xa4506cebu, 0xbef9a3f7u, 0xc67178f2u
+);
+
+uint rotr(uint x, uint n) {
+    return (x >> n) | (x << (32u - n));
+}
+
+void compress(inout uint state[8], uint block[16]) {
+    uint w[64];
+    for (int t = 0; t < 16; t++) {
+        w[t] = block[t];
+    }
+    for (int t = 16; t < 64; t++) {
+        uint s0 = rotr(w[t - 15], 7u) ^ rotr(w[t - 15], 18u) ^ (w[t - 15] >> 3u);
+        uint s1 = rotr(w[t - 2], 17u) ^ rotr(w[t - 2], 19u) ^ (w[t - 2] >> 10u);
+        w[t] = w[t - 16] + s0 + w[t - 7] + s1;
+    }
+
+    uint a = state[0], b = state[1], c = state[2], d = state[3];
+    uint e = state[4], f = state[5], g = state[6], h = state[7];
+    for (int t = 0; t < 64; t++) {
+        uint t1 = h + (rotr(e, 6u) ^ rotr(e, 11u) ^ rotr(e, 25u)) + (g ^ (e & (f ^ g))) + K[t] + w[t];
+        uint t2 = (rotr(a, 2u) ^ rotr(a, 13u) ^ rotr(a, 22u)) + ((a & b) | (c & (a | b)));
+        h = g; g = f; f = e; e = d + t1;
+        d = c; c = b; b = a; a = t1 + t2;
+    }
+
+    state[0] += a; state[1] += b; state[2] += c; state[3] += d;
+    state[4] += e; state[5] += f; state[6] += g; state[7] += h;
+}
+
+void main() {
+    uint index = uint(gl_FragCoord.y) * u_width + uint(gl_FragCoord.x);
+    if (index >= u_count) {
+        out_record = uvec4(0u, 0xffffffffu, 0xffffffffu, 0u);
+        return;
+    }
+
+    // Patch the nonce's little-endian bytes into the big-endian tail words
+    uint nonce = u_nonce_base + index;
+    uint nonce_be = (nonce << 24) | ((nonce & 0xff00u) << 8) | ((nonce >> 8) & 0xff00u) | (nonce >> 24);
+    uint tail[32] = u_tail;
+    tail[u_nonce_word] |= nonce_be >> u_nonce_shift;
+    if (u_nonce_shift > 0u) {
+        tail[u_nonce_word + 1u] |= nonce_be << (32u - u_nonce_shift);
+    }
+
+    uint state[8] = u_midstate;
+    uint block[16];
+    for (int i = 0; i < 16; i++) {
+        block[i] = tail[i];
+    }
+    compress(state, block);
+    if (u_tail_blocks == 2u) {
+        for (int i = 0; i < 16; i++) {
+            block[i] = tail[16 + i];
+        }
+        compress(state, block);
+    }
+
+    uvec4 head = uvec4(state[0], state[1], state[2], state[3]);
+    uint digit = (head[u_fraction_nibble / 8u] >> (28u - 4u * (u_fraction_nibble % 8u))) & 0xfu;
+    bool matched = all(equal(head & u_match_mask, u_match_value)) && digit < u_fraction_limit;
+    out_record = uvec4(matched ? 1u : 0u, state[0], state[1], 0u);
+}`;
+
+class WebGLHasher {
+    /**
+     * Whether this browser can create a WebGL2 context at all.
+     */
+    static isSupported() {
+        try {
+            return !!WebGLHasher.createCanvas(1, 1).getContext('webgl2');
+        } catch (error) {
+            return false;
+        }
+    }
+
+    static createCanvas(width, height) {
+        if (typeof OffscreenCanvas !== 'undefined') {
+            return new OffscreenCanvas(width, height);
+        }
+        const canvas = document.createElement('canvas');
+        canvas.width = width;
+        canvas.height = height;
+        return canvas;
+    }
+
+    /**
+     * Each batch hashes up to width * height nonces, one per pixel of an RGBA32UI render target.
+     */
+    constructor(width = 512, height = 256) {
+        this.width = width;
+        this.height = height;
+        this.canvas = WebGLHasher.createCanvas(width, height);
+        const gl = this.canvas.getContext('webgl2', { antialias: false, depth: false, preserveDrawingBuffer: false });
+        if (!gl) {
+            throw new Error('WebGL2 is not available');
+        }
+        this.gl = gl;
+        this.program = this.link(VERTEX_SHADER, FRAGMENT_SHADER);
+        this.uniforms = {};
+        const count = gl.getProgramParameter(this.program, gl.ACTIVE_UNIFORMS);
+        for (let i = 0; i < count; i++) {
+            const name = gl.getActiveUniform(this.program, i).name.replace(/\[0\]$/, '');
+            this.uniforms[name] = gl.getUniformLocation(this.program, name);
+        }
+
+        // Results land in an integer texture: [matched, digest word 0, digest word 1, unused] per nonce
+        this.texture = gl.createTexture();
+        gl.bindTexture(gl.TEXTURE_2D, this.texture);
+        gl.texStorage2D(gl.TEXTURE_2D, 1, gl.RGBA32UI, width, height);
+        this.framebuffer = gl.createFramebuffer();
+        gl.bindFramebuffer(gl.FRAMEBUFFER, this.framebuffer);
+        gl.framebufferTexture2D(gl.FRAMEBUFFER, gl.COLOR_ATTACHMENT0, gl.TEXTURE_2D, this.texture, 0);
+        if (gl.checkFramebufferStatus(gl.FRAMEBUFFER) !== gl.FRAMEBUFFER_COMPLETE) {
+            throw new Error('RGBA32UI render targets are not supported');
+        }
+        this.records = new Uint32Array(width * height * 4);
+        this.vertexArray = gl.createVertexArray();
+    }
+
+    get batchSize() {
+        return this.width * this.height;
+    }
+
+    link(vertexSource, fragmentSource) {
+        const gl = this.gl;
+        const program = gl.createProgram();
+        for (const [type, source] of [[gl.VERTEX_SHADER, vertexSource], [gl.FRAGMENT_SHADER, fragmentSource]]) {
+            const shader = gl.createShader(type);
+            gl.shaderSource(shader, source);
+            gl.compileShader(shader);
+            if (!gl.getShaderParameter(shader, gl.COMPILE_STATUS)) {
+                throw new Error(`Shader failed to compile: ${gl.getShaderInfoLog(shader)}`);
+            }
+            gl.attachShader(program, shader);
+        }
+        gl.linkProgram(program);
+        if (!gl.getProgramParameter(program, gl.LINK_STATUS)) {
+            throw new Error(`Shader program failed to link: ${gl.getProgramInfoLog(program)}`);
+        }
+        return program;
+    }
+
+    /**
+     * Load a job's `MiningJob.kernel_layout()` into the shader.
+     */
+    setLayout(layout) {
+        const gl = this.gl;
+        const u = this.uniforms;
+        gl.useProgram(this.program);
+        gl.uniform1uiv(u.u_midstate, Uint32Array.from(layout.midstate));
+        gl.uniform1uiv(u.u_tail, Uint32Array.from(layout.tail));
+        gl.uniform1ui(u.u_tail_blocks, layout.tail_blocks);
+        gl.uniform1ui(u.u_nonce_word, layout.nonce_word);
+        gl.uniform1ui(u.u_nonce_shift, layout.nonce_shift);
+        gl.uniform4uiv(u.u_match_value, Uint32Array.from(layout.match_value));
+        gl.uniform4uiv(u.u_match_mask, Uint32Array.from(layout.match_mask));
+        gl.uniform1ui(u.u_fraction_nibble, layout.fraction_nibble);
+        gl.uniform1ui(u.u_fraction_limit, layout.fraction_limit);
+        gl.uniform1ui(u.u_width, this.width);
+    }
+
+    /**
+     * Hash `count` nonces from `nonceStart` (at most `batchSize`).
+     * Returns { candidates, bestNonce }: flagged nonces in order, and the nonce with the lowest digest.
+     * Nothing here is trusted; pass both to `MiningJob.confirm_kernel_batch`.
+     */
+    hashBatch(nonceStart, count) {
+        const gl = this.gl;
+        count = Math.min(count, this.batchSize);
+        gl.useProgram(this.program);
+        gl.uniform1ui(this.uniforms.u_nonce_base, nonceStart);
+        gl.uniform1ui(this.uniforms.u_count, count);
+
+        gl.bindFramebuffer(gl.FRAMEBUFFER, this.framebuffer);
+        gl.viewport(0, 0, this.width, this.height);
+        gl.bindVertexArray(this.vertexArray);
+        gl.drawArrays(gl.TRIANGLES, 0, 3);
+
+        // Only the rows that hold nonces need to come back
+        const rows = Math.ceil(count / this.width);
+        gl.readPixels(0, 0, this.width, rows, gl.RGBA_INTEGER, gl.UNSIGNED_INT, this.records);
+
+        const candidates = [];
+        let bestIndex = 0;
+        for (let i = 0; i < count; i++) {
+            const record = i * 4;
+            if (this.records[record] === 1) {
+                candidates.push(nonceStart + i);
+            }
+            const best = bestIndex * 4;
+            if (this.records[record + 1] < this.records[best + 1] ||
+                (this.records[record + 1] === this.records[best + 1] &&
+                 this.records[record + 2] < this.records[best + 2])) {
+                bestIndex = i;
+            }
+        }
+        return { candidates, bestNonce: nonceStart + bestIndex };
+    }
+
+    dispose() {
+        const gl = this.gl;
+        gl.deleteFramebuffer(this.framebuffer);
+        gl.deleteTexture(this.texture);
+        gl.deleteVertexArray(this.vertexArray);
+        gl.deleteProgram(this.program);
+    }
+}
@@ -0,0 +1,58 @@
+/**
+ * Stellaris WASM Miner - mining worker
+ * Used in Workers mode, when the page can't run the threaded build: the coordinator in miner.js
+ * sends `WorkerMessage`s (`assign`, `range`, `cancel`) and gets a `result` back for every range,
+ * as a plain object or, if the message arrived as MessagePack bytes, as MessagePack too
+ */
+
+const wasmPath = new URL(self.location.href).searchParams.get('wasm') || './pkg/stellaris_wasm_miner.js';
+const worker = Number(self.name) || 0;
+const jobs = new Map();
+
+const ready = import(wasmPath).then(async (wasm) => {
+    await wasm.default();
+    return wasm;
+});
+
+function handle(wasm, data) {
+    const message = wasm.parse_worker_message(data);
+    const packed = data instanceof Uint8Array;
+
+    switch (message.type) {
+        case 'assign': {
+            const job = wasm.MiningJob.from_pool_work(message.work);
+            jobs.set(job.job_id, job);
+            break;
+        }
+        case 'range': {
+            const job = jobs.get(message.job_id);
+            const { nonce_start, nonce_end } = message;
+            const result = job.mine_range(nonce_start, nonce_end, nonce_end - nonce_start);
+            const reply = wasm.worker_result_message(worker, message.job_id, result);
+            if (packed) {
+                const bytes = wasm.encode_worker_message(reply);
+                self.postMessage(bytes, [bytes.buffer]);
+            } else {
+                self.postMessage(reply);
+            }
+            break;
+        }
+        case 'cancel':
+            for (const [jobId, job] of jobs) {
+                if (message.job_id === undefined || message.job_id === jobId) {
+                    job.free();
+                    jobs.delete(jobId);
+                }
+            }
+            break;
+    }
+}
+
+self.onmessage = (event) => {
+    ready.then((wasm) => handle(wasm, event.data)).catch((error) => {
+        // Rethrow outside the promise so the coordinator gets an `error` event instead of waiting forever
+        setTimeout(() => {
+            throw error;
+        });
+    });
+};